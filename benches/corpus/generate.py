@@ -0,0 +1,164 @@
+"""Writes the documents the benchmarks extract, next to this script.
+
+The documents are made of random sentences from a fixed list of words, with the random numbers seeded so that running
+this again writes the same files byte for byte:
+
+- letter.pdf is a one page letter in one standard font.
+- report.pdf is forty pages in two standard fonts, with raised footnote markers and an outline.
+- cid.pdf is ten pages in a composite font with a ToUnicode CMap of 600 codes, as subset fonts come with.
+"""
+
+import os
+import random
+import zlib
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+
+class Pdf:
+    """Builds a PDF out of objects written by hand, with no compression other than of streams."""
+
+    def __init__(self):
+        self.objects = {}
+        self.next_id = 1
+        self.pages = []
+        self.catalog_extra = ""
+
+    def reserve(self):
+        oid = self.next_id
+        self.next_id += 1
+        return oid
+
+    def add(self, body, oid=None):
+        if oid is None:
+            oid = self.reserve()
+        self.objects[oid] = body.encode("latin-1") if isinstance(body, str) else body
+        return oid
+
+    def stream(self, data):
+        data = zlib.compress(data.encode("latin-1"))
+        body = b"<< /Length %d  /Filter /FlateDecode >>\nstream\n" % len(data) + data + b"\nendstream"
+        return self.add(body)
+
+    def font(self, base):
+        return self.add(f"<< /Type /Font /Subtype /Type1 /BaseFont /{base}  >>")
+
+    def page(self, content, fonts):
+        content_id = self.stream(content)
+        font_dict = " ".join(f"/{name} {oid} 0 R" for name, oid in fonts.items())
+        resources = f"/Resources << /Font << {font_dict} >>  >>"
+        pid = self.reserve()
+        self.pages.append(
+            (pid, f"<< /Type /Page /Parent PAGES_ID 0 R /MediaBox [0 0 612 792] {resources} /Contents {content_id} 0 R  >>")
+        )
+        return pid
+
+    def save(self, name):
+        pages_id = self.reserve()
+        for pid, body in self.pages:
+            self.add(body.replace("PAGES_ID", str(pages_id)), pid)
+        kids = " ".join(f"{pid} 0 R" for pid, _ in self.pages) + " "
+        self.add(f"<< /Type /Pages /Kids [{kids}] /Count {len(self.pages)}  >>", pages_id)
+        catalog = self.add(f"<< /Type /Catalog /Pages {pages_id} 0 R {self.catalog_extra} >>")
+        out = bytearray(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n")
+        offsets = {}
+        for oid in sorted(self.objects):
+            offsets[oid] = len(out)
+            out += b"%d 0 obj\n" % oid + self.objects[oid] + b"\nendobj\n"
+        xref = len(out)
+        size = max(self.objects) + 1
+        out += b"xref\n0 %d\n0000000000 65535 f \n" % size
+        for oid in range(1, size):
+            if oid in offsets:
+                out += b"%010d 00000 n \n" % offsets[oid]
+            else:
+                out += b"0000000000 65535 f \n"
+        out += b"trailer\n<< /Size %d /Root %d 0 R >>\nstartxref\n%d\n%%%%EOF\n" % (size, catalog, xref)
+        with open(os.path.join(HERE, name), "wb") as file:
+            file.write(out)
+
+
+def text_lines(lines, font="F1", size=12):
+    """Draws each of `lines`, which are (x, y, text), in a text object of its own."""
+    out = []
+    for x, y, text in lines:
+        text = text.replace("\\", "\\\\").replace("(", "\\(").replace(")", "\\)")
+        out.append(f"BT /{font} {size} Tf 1 0 0 1 {x} {y} Tm ({text}) Tj ET")
+    return "\n".join(out)
+
+
+random.seed(224)
+WORDS = (
+    "the of and to in is that for it as with was on be by this are from at or an which have not were their has more "
+    "been one other all also can its new such into after when first than used only these some time two may most over "
+    "any extraction document page font stream content glyph encoding table figure section result method analysis "
+    "measurement value sample energy system process model"
+).split()
+
+
+def sentence(length):
+    return " ".join(random.choice(WORDS) for _ in range(length))
+
+
+# A one page letter.
+pdf = Pdf()
+font = pdf.font("Times-Roman")
+lines = [(72, 720, "Dear reader,")] + [(72, 700 - 14 * i, sentence(12)) for i in range(30)] + [(72, 260, "Yours sincerely,")]
+pdf.page(text_lines(lines), {"F1": font})
+pdf.save("letter.pdf")
+
+# A long report with two fonts, superscripts and an outline.
+pdf = Pdf()
+body = pdf.font("Helvetica")
+bold = pdf.font("Helvetica-Bold")
+pages = []
+for number in range(1, 41):
+    content = [text_lines([(72, 740, f"Section {number}: {sentence(4)}")], "F2", 16)]
+    y = 710
+    for i in range(45):
+        content.append(text_lines([(72, y, sentence(10))]))
+        if i % 9 == 4:
+            content.append(text_lines([(400, y + 4, str(i))], "F1", 8))
+            content.append(text_lines([(410, y, sentence(3))]))
+        y -= 14
+    pages.append(pdf.page("\n".join(content), {"F1": body, "F2": bold}))
+root = pdf.reserve()
+items = [pdf.reserve() for _ in pages[::5]]
+pdf.add(f"<< /Type /Outlines /First {items[0]} 0 R /Last {items[-1]} 0 R /Count {len(items)} >>", root)
+for index, (item, page) in enumerate(zip(items, pages[::5])):
+    links = f"/Parent {root} 0 R"
+    if index > 0:
+        links += f" /Prev {items[index - 1]} 0 R"
+    if index + 1 < len(items):
+        links += f" /Next {items[index + 1]} 0 R"
+    pdf.add(f"<< /Title (Chapter {index + 1}) {links} /Dest [{page} 0 R /Fit] >>", item)
+pdf.catalog_extra = f"/Outlines {root} 0 R"
+pdf.save("report.pdf")
+
+# A composite font with a large ToUnicode CMap, as produced for subset fonts.
+pdf = Pdf()
+codes = list(range(1, 601))
+entries = [f"<{code:04X}> <{0x4E00 + code:04X}>" for code in codes[:100]] + [
+    f"<{code:04X}> <{0x20 + code % 95:04X}>" for code in codes[100:]
+]
+sections = []
+for start in range(0, len(entries), 100):
+    section = entries[start : start + 100]
+    sections.append(f"{len(section)} beginbfchar\n" + "\n".join(section) + "\nendbfchar")
+cmap = (
+    "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n/CMapName /Bench def\n1 begincodespacerange\n"
+    "<0000> <FFFF>\nendcodespacerange\n"
+    + "\n".join(sections)
+    + "\nendcmap\nCMapName currentdict /CMap defineresource pop\nend\nend"
+)
+to_unicode = pdf.stream(cmap)
+font = pdf.add(
+    f"<< /Type /Font /Subtype /Type0 /BaseFont /Bench-Identity-H /Encoding /Identity-H /ToUnicode {to_unicode} 0 R >>"
+)
+for number in range(10):
+    content = []
+    for i in range(40):
+        text = "".join(f"{random.choice(codes):04X}" for _ in range(30))
+        content.append(f"BT /F1 10 Tf 1 0 0 1 72 {740 - 16 * i} Tm <{text}> Tj ET")
+    pdf.page("\n".join(content), {"F1": font})
+pdf.save("cid.pdf")
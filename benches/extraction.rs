@@ -0,0 +1,136 @@
+//! Measures extraction over the documents in `benches/corpus`, both end to end and one stage at a time. The documents
+//! are written by `benches/corpus/generate.py`.
+//!
+//! - `letter.pdf` is a single page of plain text in one of the standard fonts.
+//! - `report.pdf` is forty pages in two fonts, with superscripts, subscripts and an outline.
+//! - `cid.pdf` is ten pages in a composite font with a six hundred entry `ToUnicode` map.
+
+use std::{
+    hint::black_box,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use lopdf::{Document, Object, ObjectId};
+use rust_pdf_test::{
+    content::PageBudget,
+    font::{self, FontCache, UnicodeTable},
+    text::{self, ExtractOptions, ScriptDetector},
+    traversal,
+};
+
+const CORPUS: [&str; 3] = ["letter.pdf", "report.pdf", "cid.pdf"];
+
+fn corpus_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("benches/corpus")
+        .join(name)
+}
+
+fn load(name: &str) -> Document {
+    let path = corpus_path(name);
+    Document::load(&path)
+        .unwrap_or_else(|error| panic!("Couldn't load {}: {}", path.display(), error))
+}
+
+/// Loading and extracting a whole document, as the command line does.
+fn end_to_end(c: &mut Criterion) {
+    let mut group = c.benchmark_group("end to end");
+    for name in CORPUS {
+        let path = corpus_path(name);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let document = Document::load(&path).unwrap();
+                text::extract_text(&document, &ExtractOptions::default()).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Parsing and compiling the `ToUnicode` maps of every font in a document, without the cache that normally hides it.
+fn cmap_parse(c: &mut Criterion) {
+    let document = load("cid.pdf");
+    let unicode_maps: Vec<_> = document
+        .objects
+        .values()
+        .filter_map(|object| object.as_dict().ok())
+        .filter_map(|font| font.get(b"ToUnicode").and_then(Object::as_reference).ok())
+        .map(|id| document.get_object(id).and_then(Object::as_stream).unwrap())
+        .collect();
+    c.bench_function("cmap parse", |b| {
+        b.iter(|| {
+            for unicode_map in &unicode_maps {
+                black_box(UnicodeTable::new(
+                    &font::parse_unicode_map(unicode_map).characters,
+                ));
+            }
+        })
+    });
+}
+
+/// Decoding content streams and walking their operators, with the fonts already cached.
+fn operator_walk(c: &mut Criterion) {
+    let budget = ExtractOptions::default().budget;
+    let mut group = c.benchmark_group("operator walk");
+    for name in CORPUS {
+        let document = load(name);
+        let pages = traversal::pages(&document).unwrap();
+        let mut fonts = FontCache::new(NonZeroUsize::new(64).unwrap(), None);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for (&number, &page_id) in &pages {
+                    black_box(walk(&document, number, page_id, budget, &mut fonts));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn walk(
+    document: &Document,
+    number: u32,
+    page_id: ObjectId,
+    budget: PageBudget,
+    fonts: &mut FontCache,
+) -> Vec<text::TextChunk> {
+    let options = ExtractOptions::default().text_options();
+    text::extract_text_chunks(document, number, page_id, budget, &options, fonts)
+        .unwrap()
+        .chunks
+}
+
+/// Joining chunks into rows and marking up superscripts and subscripts.
+fn layout(c: &mut Criterion) {
+    let options = ExtractOptions::default();
+    let budget = options.budget;
+    let mut group = c.benchmark_group("layout");
+    for name in CORPUS {
+        let document = load(name);
+        let mut fonts = FontCache::new(NonZeroUsize::new(64).unwrap(), None);
+        let pages: Vec<_> = traversal::pages(&document)
+            .unwrap()
+            .into_iter()
+            .map(|(number, page_id)| walk(&document, number, page_id, budget, &mut fonts))
+            .collect();
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || pages.clone(),
+                |mut pages| {
+                    let mut scripts = ScriptDetector::default();
+                    for rows in &mut pages {
+                        text::lay_out_rows(rows, &mut scripts, &options.layout_options());
+                    }
+                    pages
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, end_to_end, cmap_parse, operator_walk, layout);
+criterion_main!(benches);
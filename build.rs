@@ -0,0 +1,9 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    // The proto file is compiled in Rust rather than with protoc, so that nothing needs installing to build it.
+    #[cfg(feature = "grpc")]
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_fds(protox::compile(["proto/extractor.proto"], ["proto"])?)?;
+    Ok(())
+}
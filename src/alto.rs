@@ -0,0 +1,150 @@
+//! Writes the text as ALTO 4, the XML digital libraries and newspaper archives keep the layout of scanned pages in.
+//!
+//! Each page is a `Page` with a `PrintSpace` as big as the part of it that is shown, holding the blocks of text on
+//! it, found as they are for hOCR, as `TextBlock`s. The rows of a block are its `TextLine`s, and the words of each row,
+//! found by `words`, are its `String`s, with an `SP` between each one and the next. Everything is placed by its left
+//! and top edges, going down from the top left corner of the page, and its width and height, in the 1200ths of an inch
+//! ALTO calls `inch1200`.
+
+use std::io::{self, Write};
+
+use crate::{html, json, output::OutputRenderer, text::Page, words};
+
+const HEADER: &str = concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<alto xmlns=\"http://www.loc.gov/standards/alto/ns-v4#\" ",
+    "xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" ",
+    "xsi:schemaLocation=\"http://www.loc.gov/standards/alto/ns-v4# ",
+    "http://www.loc.gov/standards/alto/v4/alto-4-2.xsd\">\n",
+    "<Description>\n",
+    "<MeasurementUnit>inch1200</MeasurementUnit>\n",
+    "<OCRProcessing ID=\"processing\">\n",
+    "<ocrProcessingStep>\n",
+    "<processingSoftware>\n",
+    "<softwareName>rust-pdf-test</softwareName>\n",
+    "<softwareVersion>",
+    env!("CARGO_PKG_VERSION"),
+    "</softwareVersion>\n",
+    "</processingSoftware>\n",
+    "</ocrProcessingStep>\n",
+    "</OCRProcessing>\n",
+    "</Description>\n",
+    "<Layout>\n",
+);
+
+const FOOTER: &str = "</Layout>\n</alto>\n";
+
+/// How many of ALTO's units there are to a point.
+const UNITS_PER_POINT: f32 = 1200.0 / 72.0;
+
+pub struct AltoRenderer<W: Write> {
+    writer: W,
+    word_gap: f32,
+    started: bool,
+}
+
+impl<W: Write> AltoRenderer<W> {
+    pub fn new(writer: W, word_gap: f32) -> Self {
+        Self {
+            writer,
+            word_gap,
+            started: false,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.writer.write_all(HEADER.as_bytes())?;
+            self.started = true;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputRenderer for AltoRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.start()?;
+        let [left, bottom, right, top] = page.bounds;
+        let units = |points: f32| (points * UNITS_PER_POINT).round() as i64;
+        let position = |x: f32, y: f32, width: f32, height: f32| {
+            format!(
+                "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+                units(x - left),
+                units(top - y - height),
+                units(width),
+                units(height)
+            )
+        };
+        write!(
+            self.writer,
+            "<Page ID=\"page_{}\" PHYSICAL_IMG_NR=\"{}\"",
+            page.number, page.number
+        )?;
+        if let Some(label) = &page.label {
+            write!(
+                self.writer,
+                " PRINTED_IMG_NR=\"{}\"",
+                html::escape_plain(label)
+            )?;
+        }
+        writeln!(
+            self.writer,
+            " WIDTH=\"{}\" HEIGHT=\"{}\">",
+            units(right - left),
+            units(top - bottom)
+        )?;
+        writeln!(
+            self.writer,
+            "<PrintSpace {}>",
+            position(left, bottom, right - left, top - bottom)
+        )?;
+        let (mut line_count, mut word_count) = (0, 0);
+        for (index, block) in json::blocks_with_tables(page).iter().enumerate() {
+            let lines = words::lines(block, self.word_gap);
+            if lines.is_empty() {
+                continue;
+            }
+            writeln!(
+                self.writer,
+                "<TextBlock ID=\"block_{}_{}\" {}>",
+                page.number,
+                index + 1,
+                position(block.x, block.y, block.width, block.height)
+            )?;
+            for (line, words) in lines {
+                line_count += 1;
+                write!(
+                    self.writer,
+                    "<TextLine ID=\"line_{}_{}\" {}>",
+                    page.number,
+                    line_count,
+                    position(line.x, line.y, line.width, line.height)
+                )?;
+                for (index, word) in words.iter().enumerate() {
+                    word_count += 1;
+                    if index > 0 {
+                        write!(self.writer, "<SP/>")?;
+                    }
+                    write!(
+                        self.writer,
+                        "<String ID=\"string_{}_{}\" {} CONTENT=\"{}\"/>",
+                        page.number,
+                        word_count,
+                        position(word.x, word.y, word.width, word.height),
+                        html::escape_plain(&word.text)
+                    )?;
+                }
+                writeln!(self.writer, "</TextLine>")?;
+            }
+            writeln!(self.writer, "</TextBlock>")?;
+        }
+        writeln!(self.writer, "</PrintSpace>\n</Page>")?;
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        self.writer.write_all(FOOTER.as_bytes())?;
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,181 @@
+//! Finds the Bates numbers stamped on pages produced for legal discovery, and takes them out of the text.
+//!
+//! A Bates number is a prefix of capital letters followed by a run of at least four digits, such as `ABC0001234`, and
+//! is stamped in a corner of every page. So it is looked for at the start or end of the top and bottom rows of each
+//! page, which is also where it ends up when it shares a line with a footer. Text on the same line is often joined up
+//! without a space, so a stamp at the end of a row doesn't need one before it.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::{
+    output::{OutputRenderer, PlainTextRenderer},
+    text::{self, Page},
+};
+
+/// Writes the text of a document to a file with the Bates numbers taken out, keeping track of each page's number.
+pub struct BatesRenderer {
+    text: PlainTextRenderer<BufWriter<File>>,
+    stamp: Regex,
+    /// Each page's number, along with its Bates number if it has one.
+    pub pages: Vec<(u32, Option<String>)>,
+}
+
+impl BatesRenderer {
+    pub fn new(text_path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            text: PlainTextRenderer::new(BufWriter::new(File::create(text_path)?)),
+            stamp: Regex::new(
+                r"([A-Z][A-Z0-9]*[ _.-]?\d{4,})$|^([A-Z][A-Z0-9]*[ _.-]?\d{4,})(?:\s|$)",
+            )
+            .unwrap(),
+            pages: Vec::new(),
+        })
+    }
+
+    /// The first and last Bates numbers of the document, if any pages have them.
+    pub fn range(&self) -> Option<(&str, &str)> {
+        let mut numbers = self
+            .pages
+            .iter()
+            .filter_map(|(_, number)| number.as_deref());
+        let first = numbers.next()?;
+        Some((first, numbers.next_back().unwrap_or(first)))
+    }
+}
+
+impl OutputRenderer for BatesRenderer {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let top = page
+            .rows
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.y.total_cmp(&b.y));
+        let bottom = page
+            .rows
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y));
+        let mut rows = page.rows.clone();
+        let mut number = None;
+        // Stamps are more often at the bottom of the page, so that is where to look first.
+        for (index, row) in bottom.into_iter().chain(top) {
+            let text = text::plain_text(&row.text);
+            let Some(captures) = self.stamp.captures(text.trim()) else {
+                continue;
+            };
+            let stamp = captures.get(1).or(captures.get(2)).unwrap();
+            number = Some(stamp.as_str().to_owned());
+            let start = text.find(stamp.as_str()).unwrap();
+            // The rest of the row is kept, without its markup, in case the stamp shares its line with a footer.
+            rows[index].text = format!("{}{}", &text[..start], &text[start + stamp.len()..])
+                .trim()
+                .to_owned();
+            break;
+        }
+        rows.retain(|row| !row.text.is_empty());
+        self.pages.push((page.number, number));
+        self.text.render_page(&Page {
+            number: page.number,
+            label: page.label.clone(),
+            rows,
+            tables: page.tables.clone(),
+            bounds: page.bounds,
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.text.get_mut().flush()
+    }
+}
+
+/// Writes the text of each document to a directory, along with CSV load files listing the documents and pages.
+///
+/// `documents.csv` has the first and last Bates numbers of each document, its page count, and where its file and text
+/// are, and `pages.csv` has the Bates number of every page.
+pub struct LoadFile {
+    directory: PathBuf,
+    documents: BufWriter<File>,
+    pages: BufWriter<File>,
+    text_names: HashSet<String>,
+}
+
+impl LoadFile {
+    pub fn new(directory: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+        let mut documents = BufWriter::new(File::create(directory.join("documents.csv"))?);
+        writeln!(documents, "BEGBATES,ENDBATES,PAGES,SOURCE,TEXT")?;
+        let mut pages = BufWriter::new(File::create(directory.join("pages.csv"))?);
+        writeln!(pages, "SOURCE,PAGE,BATES")?;
+        Ok(Self {
+            directory,
+            documents,
+            pages,
+            text_names: HashSet::new(),
+        })
+    }
+
+    /// Picks where to write a document's text, named after the document but never the same as another's.
+    pub fn text_path(&mut self, source: &Path) -> PathBuf {
+        let stem = source
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "document".to_owned());
+        let mut name = stem.clone();
+        let mut suffix = 1;
+        while !self.text_names.insert(name.clone()) {
+            suffix += 1;
+            name = format!("{}-{}", stem, suffix);
+        }
+        self.directory.join(name).with_extension("txt")
+    }
+
+    pub fn add(
+        &mut self,
+        source: &Path,
+        text_path: &Path,
+        renderer: &BatesRenderer,
+    ) -> io::Result<()> {
+        let source = csv_field(&source.display().to_string());
+        let (first, last) = renderer.range().unwrap_or_default();
+        writeln!(
+            self.documents,
+            "{},{},{},{},{}",
+            csv_field(first),
+            csv_field(last),
+            renderer.pages.len(),
+            source,
+            csv_field(&text_path.display().to_string())
+        )?;
+        for (page, number) in &renderer.pages {
+            writeln!(
+                self.pages,
+                "{},{},{}",
+                source,
+                page,
+                csv_field(number.as_deref().unwrap_or_default())
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.documents.flush()?;
+        self.pages.flush()
+    }
+}
+
+/// Quotes a field for a CSV file, if it needs it.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
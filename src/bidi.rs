@@ -0,0 +1,61 @@
+//! Puts text written right to left, such as Arabic and Hebrew, back into the order it is read in.
+//!
+//! Pages draw their glyphs from left to right whatever the script, so the text of a right to left line comes out of
+//! the content in visual order, backwards. Running the Unicode bidirectional algorithm over the visual text reverses
+//! the right to left runs again, which for all but the most tangled mixtures of scripts gives back the logical order,
+//! with numbers and any left to right words inside the line kept the right way round.
+
+use unicode_bidi::BidiInfo;
+
+use crate::text::find_markup;
+
+/// Reorders a row of text from the order it was drawn in into the order it is read in, leaving rows without any right
+/// to left text alone.
+///
+/// The text between the markup, such as that of superscripts and subscripts, is reordered on its own, so that the
+/// tags stay whole and around the text they were around.
+pub fn logical_order(text: &mut String) {
+    if !BidiInfo::new(text, None).has_rtl() {
+        return;
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some((start, tag)) = find_markup(rest) {
+        push_reordered(&mut result, &rest[..start]);
+        result.push_str(tag);
+        rest = &rest[start + tag.len()..];
+    }
+    push_reordered(&mut result, rest);
+    *text = result;
+}
+
+fn push_reordered(result: &mut String, text: &str) {
+    let info = BidiInfo::new(text, None);
+    for paragraph in &info.paragraphs {
+        let (levels, runs) = info.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            if levels[run.start].is_rtl() {
+                // Brackets are drawn mirrored in right to left text, so the glyph that looks like the opening one is
+                // really the closing one.
+                result.extend(text[run].chars().rev().map(mirror));
+            } else {
+                result.push_str(&text[run]);
+            }
+        }
+    }
+}
+
+/// The other half of a pair of brackets.
+fn mirror(character: char) -> char {
+    match character {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '«' => '»',
+        '»' => '«',
+        _ => character,
+    }
+}
@@ -0,0 +1,427 @@
+//! Keeps the text extracted from each file on disk, so that running over the same files again only has to extract the
+//! ones which are new or have changed.
+//!
+//! Entries are keyed by a hash of the file's content together with the extraction options, so renaming or moving a
+//! file keeps its entry, while changing it or extracting it differently makes a new one. Each entry is a small text
+//! file of the extracted rows and tables, written as the pages are extracted and only put in place once the last one
+//! is done.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    output::OutputRenderer,
+    tables::Table,
+    text::{ExtractOptions, Page, TextChunk},
+};
+
+/// Changes whenever the entries change format, so that old ones are never misread.
+const FORMAT_HEADER: &str = "rust-pdf-test cache 8";
+
+pub struct ResultCache {
+    directory: PathBuf,
+}
+
+/// A file's extracted text, as found in the cache.
+pub struct CachedResult {
+    /// How many pages the whole document has, which may be more than were extracted.
+    pub page_count: usize,
+    pub pages: Vec<Page>,
+}
+
+impl ResultCache {
+    pub fn new(directory: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    /// Works out which entry a file's results belong in, which means reading the whole file.
+    pub fn key(&self, path: &Path, options: &ExtractOptions) -> io::Result<String> {
+        let mut hasher = blake3::Hasher::new();
+        io::copy(&mut File::open(path)?, &mut hasher)?;
+        // Debug output covers every option, so new ones are part of the key without anything else having to change.
+        // The version is there because a new version may well extract the same file differently.
+        hasher.update(format!("\0{} {:?}", env!("CARGO_PKG_VERSION"), options).as_bytes());
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Reads an entry, treating one that can't be read or doesn't make sense as missing.
+    pub fn load(&self, key: &str) -> Option<CachedResult> {
+        let file = File::open(self.entry_path(key)).ok()?;
+        let result = read_entry(BufReader::new(file));
+        if result.is_none() {
+            eprintln!("warning: ignoring the unreadable cache entry {}", key);
+        }
+        result
+    }
+
+    /// Starts writing an entry, which only replaces any existing one once the writer is finished.
+    pub fn writer(&self, key: &str, page_count: usize) -> io::Result<CacheWriter> {
+        let path = self.entry_path(key);
+        let partial_path = path.with_extension("partial");
+        let mut writer = BufWriter::new(File::create(&partial_path)?);
+        writeln!(writer, "{}", FORMAT_HEADER)?;
+        writeln!(writer, "pages {}", page_count)?;
+        Ok(CacheWriter {
+            writer,
+            partial_path,
+            path,
+        })
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.directory.join(key).with_extension("txt")
+    }
+}
+
+/// Writes pages to a cache entry as they are extracted.
+pub struct CacheWriter {
+    writer: BufWriter<File>,
+    partial_path: PathBuf,
+    path: PathBuf,
+}
+
+impl OutputRenderer for CacheWriter {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let [left, bottom, right, top] = page.bounds;
+        // The label goes last, since it can have spaces in it.
+        writeln!(
+            self.writer,
+            "page {} {} {} {} {} {} {} {}",
+            page.number,
+            page.rows.len(),
+            page.tables.len(),
+            left,
+            bottom,
+            right,
+            top,
+            page.label
+                .as_deref()
+                .map_or_else(|| "-".to_owned(), |label| format!("+{}", escape(label)))
+        )?;
+        for row in &page.rows {
+            write_row(&mut self.writer, row)?;
+        }
+        // Each table is a line of where it is and how many rows and columns it has, then a line for each cell.
+        for table in &page.tables {
+            writeln!(
+                self.writer,
+                "{} {} {} {} {} {}",
+                table.x,
+                table.y,
+                table.width,
+                table.height,
+                table.cells.len(),
+                table.cells.first().map_or(0, Vec::len)
+            )?;
+            for cell in table.cells.iter().flatten() {
+                writeln!(self.writer, "{}", escape(cell))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        fs::rename(&self.partial_path, &self.path)
+    }
+}
+
+/// Writes a row as a line of where it is, everything else about it that the renderers read and how many chunks it was
+/// joined from, with the font's family last since it can have spaces in it, then a line of its text, then each of its
+/// chunks in the same way. Missing values are written as `-`.
+fn write_row(writer: &mut impl Write, row: &TextChunk) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+        row.x,
+        row.y,
+        row.width,
+        row.height,
+        row.space_width,
+        row.vertical,
+        row.angle,
+        optional(row.marked_content),
+        optional(row.heading),
+        row.font_size,
+        row.bold,
+        row.italic,
+        optional(row.baseline),
+        row.chunks.len(),
+        escape(row.font_family.as_deref().unwrap_or_default())
+    )?;
+    writeln!(writer, "{}", escape(&row.text))?;
+    for chunk in &row.chunks {
+        write_row(writer, chunk)?;
+    }
+    Ok(())
+}
+
+fn read_row(lines: &mut impl Iterator<Item = String>, page: u32) -> Option<TextChunk> {
+    let line = lines.next()?;
+    let mut fields = line.splitn(15, ' ');
+    let (x, y, width, height, space_width, vertical, angle) = (
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+    );
+    let (marked_content, heading) = (
+        parse_optional(fields.next()?)?,
+        parse_optional(fields.next()?)?,
+    );
+    let (font_size, bold, italic) = (
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+    );
+    let baseline = parse_optional(fields.next()?)?;
+    let chunk_count: usize = fields.next()?.parse().ok()?;
+    let font_family = Some(unescape(fields.next()?)?).filter(|family| !family.is_empty());
+    let text = unescape(&lines.next()?)?;
+    let chunks = (0..chunk_count)
+        .map(|_| read_row(lines, page))
+        .collect::<Option<_>>()?;
+    Some(TextChunk {
+        text,
+        page,
+        x,
+        y,
+        width,
+        height,
+        space_width,
+        vertical,
+        angle,
+        marked_content,
+        heading,
+        font_family,
+        font_size,
+        bold,
+        italic,
+        chunks,
+        baseline,
+    })
+}
+
+/// Writes a value which can be missing, as `-` if it is.
+fn optional(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "-".to_owned(), |value| value.to_string())
+}
+
+/// Reads a value written by [`optional`], giving `None` if it isn't one.
+fn parse_optional<T: std::str::FromStr>(field: &str) -> Option<Option<T>> {
+    match field {
+        "-" => Some(None),
+        _ => field.parse().ok().map(Some),
+    }
+}
+
+fn read_entry(reader: impl BufRead) -> Option<CachedResult> {
+    let mut lines = reader.lines().map_while(Result::ok);
+    if lines.next()? != FORMAT_HEADER {
+        return None;
+    }
+    let page_count = lines.next()?.strip_prefix("pages ")?.parse().ok()?;
+    let mut pages = Vec::new();
+    while let Some(line) = lines.next() {
+        let mut counts = line.strip_prefix("page ")?.splitn(8, ' ');
+        let number = counts.next()?.parse().ok()?;
+        let row_count = counts.next()?.parse().ok()?;
+        let table_count = counts.next()?.parse().ok()?;
+        let mut bounds = [0.0; 4];
+        for corner in &mut bounds {
+            *corner = counts.next()?.parse().ok()?;
+        }
+        let label = match counts.next()? {
+            "-" => None,
+            label => Some(unescape(label.strip_prefix('+')?)?),
+        };
+        let rows = (0..row_count)
+            .map(|_| read_row(&mut lines, number))
+            .collect::<Option<_>>()?;
+        // The counts are only pushed up to rather than allocated for, since a corrupt entry could have any in it.
+        let mut tables = Vec::new();
+        for _ in 0..table_count {
+            let line = lines.next()?;
+            let mut fields = line.split(' ');
+            let (x, y, width, height) = (
+                fields.next()?.parse().ok()?,
+                fields.next()?.parse().ok()?,
+                fields.next()?.parse().ok()?,
+                fields.next()?.parse().ok()?,
+            );
+            let row_count: usize = fields.next()?.parse().ok()?;
+            let column_count: usize = fields.next()?.parse().ok()?;
+            let mut cells = Vec::new();
+            for _ in 0..row_count {
+                let row = (0..column_count)
+                    .map(|_| unescape(&lines.next()?))
+                    .collect::<Option<Vec<_>>>()?;
+                cells.push(row);
+            }
+            tables.push(Table {
+                x,
+                y,
+                width,
+                height,
+                cells,
+            });
+        }
+        pages.push(Page {
+            number,
+            label,
+            rows,
+            tables,
+            bounds,
+        });
+    }
+    Some(CachedResult { page_count, pages })
+}
+
+/// Escapes the characters which would break up the lines of an entry.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut characters = text.chars();
+    while let Some(character) = characters.next() {
+        if character == '\\' {
+            result.push(match characters.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                '\\' => '\\',
+                _ => return None,
+            });
+        } else {
+            result.push(character);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_what_it_wrote() {
+        let directory =
+            std::env::temp_dir().join(format!("rust-pdf-test-cache-{}", std::process::id()));
+        let cache = ResultCache::new(directory.clone()).unwrap();
+        let chunk = TextChunk {
+            text: "2".to_owned(),
+            page: 3,
+            x: 112.5,
+            y: 690.25,
+            width: 4.0,
+            height: 8.0,
+            space_width: 2.0,
+            font_size: 8.0,
+            baseline: Some(686.0),
+            ..TextChunk::default()
+        };
+        let row = TextChunk {
+            text: "E = mc<sup>2</sup>\\ back\nslash".to_owned(),
+            page: 3,
+            x: 72.0,
+            y: 686.0,
+            width: 44.125,
+            height: 12.0,
+            space_width: 3.0,
+            vertical: true,
+            angle: -90.0,
+            marked_content: Some(7),
+            heading: Some(2),
+            font_family: Some("Times New Roman".to_owned()),
+            font_size: 12.0,
+            bold: true,
+            italic: true,
+            chunks: vec![chunk],
+            baseline: None,
+        };
+        let page = Page {
+            number: 3,
+            label: Some("iii a".to_owned()),
+            rows: vec![row, TextChunk::default()],
+            tables: vec![Table {
+                x: 72.0,
+                y: 600.0,
+                width: 300.0,
+                height: 40.0,
+                cells: vec![
+                    vec!["a".to_owned(), "b c".to_owned()],
+                    vec!["\n".to_owned(), String::new()],
+                ],
+            }],
+            bounds: [0.0, 0.0, 612.0, 792.0],
+        };
+        let mut writer = cache.writer("entry", 5).unwrap();
+        writer.render_page(&page).unwrap();
+        writer.finish().unwrap();
+        let result = cache.load("entry");
+        fs::remove_dir_all(&directory).unwrap();
+
+        let result = result.unwrap();
+        assert_eq!(result.page_count, 5);
+        let [read] = &result.pages[..] else {
+            panic!("{} pages were read", result.pages.len());
+        };
+        assert_eq!(read.number, page.number);
+        assert_eq!(read.label, page.label);
+        assert_eq!(read.bounds, page.bounds);
+        // The page of each row isn't written, since it is the page it is on.
+        assert!(read.rows[0] == page.rows[0]);
+        assert!(
+            read.rows[1]
+                == TextChunk {
+                    page: 3,
+                    ..TextChunk::default()
+                }
+        );
+        let (table, read_table) = (&page.tables[0], &read.tables[0]);
+        assert_eq!(read_table.cells, table.cells);
+        assert_eq!(
+            [
+                read_table.x,
+                read_table.y,
+                read_table.width,
+                read_table.height
+            ],
+            [72.0, 600.0, 300.0, 40.0]
+        );
+    }
+
+    #[test]
+    fn treats_corrupt_counts_as_missing() {
+        for entry in [
+            format!(
+                "{}\npages 1\npage 1 0 {} 0 0 612 792 -\n",
+                FORMAT_HEADER,
+                usize::MAX
+            ),
+            format!(
+                "{}\npages 1\npage 1 0 1 0 0 612 792 -\n0 0 1 1 {} {}\n",
+                FORMAT_HEADER,
+                usize::MAX,
+                usize::MAX
+            ),
+            format!(
+                "{}\npages 1\npage 1 {} 0 0 0 612 792 -\n",
+                FORMAT_HEADER,
+                usize::MAX
+            ),
+        ] {
+            assert!(read_entry(entry.as_bytes()).is_none());
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Splits documents into chunks of about the same size for retrieval-augmented generation, where each chunk is looked
+//! up and handed to a language model on its own.
+//!
+//! Chunks are made of whole paragraphs where possible, and only paragraphs too long to fit in a chunk are split, at
+//! word boundaries. Each chunk starts with the end of the chunk before it, so that text near the boundary between
+//! them isn't cut off from what it is about. Sizes are measured in tokens, which are estimated rather than counted
+//! since every model splits text into tokens differently.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    mem,
+};
+
+use serde::Serialize;
+
+use crate::{
+    hyphenation::Hyphenation,
+    outline::Bookmark,
+    output::OutputRenderer,
+    speech,
+    text::{Page, TextChunk},
+    toc,
+};
+
+/// Text in English has about this many characters per token.
+const CHARACTERS_PER_TOKEN: usize = 4;
+
+#[derive(Serialize)]
+pub struct Chunk {
+    /// The document the chunk comes from.
+    pub document: String,
+    /// Where the chunk comes in the document, starting from 0.
+    pub index: usize,
+    pub first_page: u32,
+    pub last_page: u32,
+    /// The page numbers printed on the first and last pages, if they have them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_page_label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_page_label: Option<String>,
+    /// The titles of the section the chunk starts in and the sections around that, outermost first.
+    pub headings: Vec<String>,
+    pub text: String,
+}
+
+impl Chunk {
+    /// Turns the chunk into a record for a vector store.
+    ///
+    /// Its ID is a UUID made from a hash of the document and where the chunk is in it, so extracting the same document
+    /// again gives the same IDs, and the records replace the ones from before rather than adding to them.
+    pub fn to_record(&self) -> Record {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(format!("{}\0{}", self.document, self.index).as_bytes());
+        let mut bytes: [u8; 16] = hasher.finalize().as_bytes()[..16].try_into().unwrap();
+        // These mark it as a version 8 UUID, which is the version for UUIDs made in a custom way.
+        bytes[6] = (bytes[6] & 0x0F) | 0x80;
+        bytes[8] = (bytes[8] & 0x3F) | 0x80;
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let id = format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        );
+        #[cfg(feature = "language")]
+        let lang = crate::language::detect(&self.text).map(|detection| detection.language);
+        #[cfg(not(feature = "language"))]
+        let lang = None;
+        Record {
+            id,
+            text: self.text.clone(),
+            metadata: RecordMetadata {
+                source: self.document.clone(),
+                page: self.first_page,
+                page_label: self.first_page_label.clone(),
+                heading: (!self.headings.is_empty()).then(|| self.headings.join(" > ")),
+                lang,
+            },
+        }
+    }
+}
+
+/// A chunk in the shape that loaders for vector stores such as Qdrant and pgvector expect.
+#[derive(Serialize)]
+pub struct Record {
+    pub id: String,
+    pub text: String,
+    pub metadata: RecordMetadata,
+}
+
+#[derive(Serialize)]
+pub struct RecordMetadata {
+    pub source: String,
+    /// The page the chunk starts on.
+    pub page: u32,
+    /// The page number printed on that page, if it has one.
+    pub page_label: Option<String>,
+    /// The headings of the section the chunk starts in, outermost first and separated by `>`.
+    pub heading: Option<String>,
+    /// The ISO 639-3 code of the chunk's language, if it was built with language detection and there is enough text
+    /// to tell.
+    pub lang: Option<&'static str>,
+}
+
+/// Collects the paragraphs of every page, and writes them as chunks once the last page is done, either as a JSON array
+/// or as one vector store record per line.
+///
+/// The headings come from the outline, or from the headings found in the text if there isn't one.
+pub struct ChunkRenderer<W: Write> {
+    writer: W,
+    document: String,
+    outline: Vec<Bookmark>,
+    /// The most tokens in a chunk.
+    chunk_size: usize,
+    /// How many tokens from the end of a chunk to repeat at the start of the next one.
+    overlap: usize,
+    records: bool,
+    hyphenation: Hyphenation,
+    rows: Vec<TextChunk>,
+    paragraphs: Vec<(u32, String)>,
+    /// The page numbers printed on the pages, by the number of each page.
+    labels: HashMap<u32, String>,
+}
+
+impl<W: Write> ChunkRenderer<W> {
+    pub fn new(
+        writer: W,
+        document: String,
+        outline: Vec<Bookmark>,
+        chunk_size: usize,
+        overlap: usize,
+        records: bool,
+        hyphenation: Hyphenation,
+    ) -> Self {
+        Self {
+            writer,
+            document,
+            outline,
+            chunk_size,
+            // There has to be room in each chunk for something new.
+            overlap: overlap.min(chunk_size / 2),
+            records,
+            hyphenation,
+            rows: Vec::new(),
+            paragraphs: Vec::new(),
+            labels: HashMap::new(),
+        }
+    }
+
+    fn chunks(&mut self) -> Vec<Chunk> {
+        let headings = toc::table_of_contents(mem::take(&mut self.outline), &self.rows);
+        let mut headings = headings
+            .into_iter()
+            .filter(|heading| heading.page.is_some())
+            .peekable();
+        let mut open_headings: Vec<Bookmark> = Vec::new();
+
+        let mut chunks = Vec::new();
+        let mut chunk: Option<Chunk> = None;
+        let mut current_page = 0;
+        for (page, paragraph) in &self.paragraphs {
+            if *page != current_page {
+                current_page = *page;
+                let page_paragraphs: Vec<&str> = self
+                    .paragraphs
+                    .iter()
+                    .filter(|(other, _)| other == page)
+                    .map(|(_, paragraph)| paragraph.as_str())
+                    .collect();
+                // Headings from an outline may not match any text on their page, and they start at the top of it.
+                while let Some(heading) = headings.next_if(|heading| {
+                    heading.page < Some(*page)
+                        || heading.page == Some(*page)
+                            && !page_paragraphs
+                                .iter()
+                                .any(|text| is_title(text, &heading.title))
+                }) {
+                    open(&mut open_headings, heading);
+                }
+            }
+            while let Some(heading) = headings.next_if(|heading| {
+                heading.page == Some(*page) && is_title(paragraph, &heading.title)
+            }) {
+                open(&mut open_headings, heading);
+            }
+
+            let pieces = split_words(paragraph, self.chunk_size - self.overlap);
+            for (piece_index, piece) in pieces.into_iter().enumerate() {
+                let piece_tokens = estimate_tokens(&piece);
+                let is_full = |chunk: &mut Chunk| {
+                    estimate_tokens(&chunk.text) + piece_tokens > self.chunk_size
+                };
+                let (index, text, first_page) = match chunk.take_if(is_full) {
+                    Some(full) => {
+                        let overlap = tail(
+                            &full.text,
+                            self.overlap
+                                .min(self.chunk_size.saturating_sub(piece_tokens)),
+                        );
+                        let first_page = if overlap.is_empty() {
+                            *page
+                        } else {
+                            full.last_page
+                        };
+                        let index = full.index + 1;
+                        chunks.push(full);
+                        (index, overlap, first_page)
+                    }
+                    None => (0, String::new(), *page),
+                };
+                let chunk = chunk.get_or_insert_with(|| Chunk {
+                    document: self.document.clone(),
+                    index,
+                    first_page,
+                    last_page: *page,
+                    first_page_label: self.labels.get(&first_page).cloned(),
+                    last_page_label: None,
+                    headings: open_headings
+                        .iter()
+                        .map(|heading| heading.title.clone())
+                        .collect(),
+                    text,
+                });
+                // Only the first piece of a paragraph starts a new one, since the rest carry on from the piece before.
+                if piece_index > 0 {
+                    chunk.text.push(' ');
+                } else if !chunk.text.is_empty() {
+                    chunk.text.push_str("\n\n");
+                }
+                chunk.text.push_str(&piece);
+                chunk.last_page = *page;
+                chunk.last_page_label = self.labels.get(page).cloned();
+            }
+        }
+        chunks.extend(chunk);
+        chunks
+    }
+}
+
+impl<W: Write> OutputRenderer for ChunkRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.rows.extend(page.rows.iter().cloned());
+        if let Some(label) = &page.label {
+            self.labels.insert(page.number, label.clone());
+        }
+        for (index, paragraph) in speech::paragraphs(page, &self.hyphenation)
+            .into_iter()
+            .enumerate()
+        {
+            match self.paragraphs.last_mut() {
+                // A word hyphenated across the page break joins the first paragraph onto the one before.
+                Some((_, previous)) if index == 0 && previous.ends_with('-') => {
+                    self.hyphenation.join_line(previous, &paragraph)
+                }
+                _ => self.paragraphs.push((page.number, paragraph)),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let chunks = self.chunks();
+        if self.records {
+            for chunk in &chunks {
+                serde_json::to_writer(&mut self.writer, &chunk.to_record())?;
+                writeln!(self.writer)?;
+            }
+        } else {
+            serde_json::to_writer_pretty(&mut self.writer, &chunks)?;
+            writeln!(self.writer)?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Opens a section, closing any sections at its level or deeper which are open already.
+fn open(open_headings: &mut Vec<Bookmark>, heading: Bookmark) {
+    open_headings.retain(|open| open.level < heading.level);
+    open_headings.push(heading);
+}
+
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARACTERS_PER_TOKEN)
+}
+
+/// Whether a paragraph is the given heading, going by its words.
+fn is_title(paragraph: &str, title: &str) -> bool {
+    paragraph.split_whitespace().eq(title.split_whitespace())
+}
+
+/// Splits text into pieces of whole words which are each at most the given number of tokens.
+fn split_words(text: &str, max_tokens: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    for word in text.split_whitespace() {
+        if !piece.is_empty() && estimate_tokens(&piece) + estimate_tokens(word) + 1 > max_tokens {
+            pieces.push(mem::take(&mut piece));
+        }
+        if !piece.is_empty() {
+            piece.push(' ');
+        }
+        piece.push_str(word);
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// The whole words at the end of some text which make up at most the given number of tokens.
+fn tail(text: &str, max_tokens: usize) -> String {
+    let mut start = text.len();
+    for (index, _) in text.match_indices(char::is_whitespace).rev() {
+        if estimate_tokens(&text[index + 1..]) > max_tokens {
+            break;
+        }
+        start = index + 1;
+    }
+    text[start..].to_owned()
+}
@@ -0,0 +1,574 @@
+use std::{error::Error, num::NonZeroUsize, path::PathBuf, rc::Rc};
+
+use clap::{Args, Parser, Subcommand};
+
+use rust_pdf_test::{
+    content::PageBudget,
+    footnotes::FootnotePlacement,
+    hyphenation::Hyphenation,
+    load::LoadOptions,
+    mask::Mask,
+    output::{Format, PageSeparator},
+    page_range::PageSelection,
+    scripts::ScriptMarkup,
+    search::Context,
+    split::SplitBy,
+    text::{ExtractOptions, Order, ScriptOffset},
+};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Extracts text from PDF files",
+    subcommand_negates_reqs = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// The PDF file to extract the text of.
+    #[arg(required_unless_present = "daemon")]
+    pub file: Option<PathBuf>,
+    /// Writes the text to this file instead of to standard output.
+    #[arg(short, long, value_name = "FILE", conflicts_with = "split_by")]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub output: Option<PathBuf>,
+    /// Memory maps the input file instead of reading it into memory, which lowers peak memory use for large files.
+    #[arg(long, global = true)]
+    pub mmap: bool,
+    /// Opens files of many gigabytes in memory that depends on how much text they have rather than on their size, by
+    /// memory mapping them and discarding images, embedded files and metadata as they are loaded.
+    #[arg(long, global = true)]
+    pub large_file: bool,
+    /// How many parsed fonts to keep in memory at once.
+    #[arg(long, global = true, value_name = "FONTS", default_value = "64")]
+    pub font_cache_size: NonZeroUsize,
+    /// The pages to extract, such as `1-3,7,10-`.
+    #[arg(long, global = true, value_name = "PAGES")]
+    pub pages: Option<PageSelection>,
+    /// Skips any page with more than this many operators in its content.
+    #[arg(long, global = true, value_name = "COUNT", default_value_t = 5_000_000)]
+    pub max_operators: usize,
+    /// Skips any page whose content decompresses to more than this many bytes.
+    #[arg(long, global = true, value_name = "BYTES", default_value_t = 256 * 1024 * 1024)]
+    pub max_content_bytes: usize,
+    /// Roughly how much memory to use, such as `512M` or `2G`.
+    ///
+    /// Files too big to load are refused, pages which don't fit are skipped, and fonts are dropped from the cache to
+    /// make room for later pages.
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_size)]
+    pub max_memory: Option<usize>,
+    /// Whether to join the text into rows as it is laid out on the page, or keep every piece of text as it comes in
+    /// the page's content.
+    #[arg(long, global = true, value_enum, default_value_t = Order::Layout)]
+    pub order: Order,
+    /// Keeps the text in the order the page's content draws it, the same as `--order raw`.
+    #[arg(long, global = true, conflicts_with = "order")]
+    pub raw_order: bool,
+    /// How far, in thousandths of the font size, text has to be kerned apart for a space to go between its two
+    /// parts, for documents which leave out the spaces between words and move the words apart instead.
+    #[arg(
+        long,
+        global = true,
+        value_name = "THOUSANDTHS",
+        default_value_t = 200.0,
+        value_parser = parse_non_negative
+    )]
+    pub space_threshold: f32,
+    /// How far, in the units of the page, pieces of text can be above or below each other and still be joined into
+    /// the same line, for documents whose baselines aren't quite level.
+    #[arg(
+        long,
+        global = true,
+        value_name = "UNITS",
+        default_value_t = 1.0,
+        value_parser = parse_non_negative
+    )]
+    pub line_tolerance: f32,
+    /// Leaves text which is a little above or below the rest of its row where it is drawn, rather than guessing that
+    /// it is a superscript or subscript, for forms and other documents whose text is put wherever it fits. Text
+    /// raised or lowered with the PDF's own text rise is still marked up, since the document says what it is.
+    #[arg(long, global = true)]
+    pub no_scripts: bool,
+    /// How far text can be above or below the text before it on its row and still be a superscript or subscript:
+    /// `auto` for however far text most often moves up in the document, a distance in the units of the page such as
+    /// `3`, or a percentage of the size of the text before it such as `40%`.
+    #[arg(
+        long,
+        global = true,
+        value_name = "OFFSET",
+        default_value = "auto",
+        value_parser = parse_script_offset,
+        conflicts_with = "no_scripts"
+    )]
+    pub script_offset: ScriptOffset,
+    /// How far after the text before it a superscript or subscript can start, as a fraction of its own height, so
+    /// that text further along is taken to be in another column instead.
+    #[arg(
+        long,
+        global = true,
+        value_name = "FRACTION",
+        default_value_t = 1.0,
+        value_parser = parse_non_negative,
+        conflicts_with = "no_scripts"
+    )]
+    pub script_gap: f32,
+    /// How much of the width of a space has to be left between two pieces of text on the same line for a space to go
+    /// between them, for documents which draw each word on its own without the spaces.
+    #[arg(
+        long,
+        global = true,
+        value_name = "FRACTION",
+        default_value_t = 0.5,
+        value_parser = parse_non_negative
+    )]
+    pub word_gap: f32,
+    /// Leaves out text drawn invisibly, with text rendering mode 3, which the OCR layers of scanned pages and some
+    /// watermarking tools use.
+    #[arg(long, global = true, overrides_with = "include_invisible")]
+    pub exclude_invisible: bool,
+    /// Keeps invisible text, which is what happens unless `--exclude-invisible` is given.
+    #[arg(long, global = true, overrides_with = "exclude_invisible")]
+    pub include_invisible: bool,
+    /// Extracts only the text in this optional content group, or layer, such as `Annotations`. Can be given more than
+    /// once, to keep the text of several layers.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub layer: Vec<String>,
+    /// Leaves out the text in this layer, such as `Watermark`. Can be given more than once.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub exclude_layer: Vec<String>,
+    /// Keeps the text as it is drawn, rather than using the `ActualText` that tagged documents give for parts of it,
+    /// such as the whole of a hyphenated word.
+    #[arg(long, global = true)]
+    pub ignore_actual_text: bool,
+    /// Keeps the running headers, footers and page numbers that tagged documents mark as artifacts, which are left
+    /// out to give just the body text otherwise.
+    #[arg(long, global = true)]
+    pub keep_artifacts: bool,
+    /// A directory of Adobe's CMap resources, which the text of Chinese, Japanese and Korean fonts without a
+    /// `ToUnicode` map is looked up in.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub cmap_dir: Option<PathBuf>,
+    /// Keeps Arabic, Hebrew and other right to left text in the order it is drawn on the page, which is backwards,
+    /// instead of the order it is read in.
+    #[arg(long, global = true)]
+    pub visual_order: bool,
+    /// Puts the text of tagged documents in the order their structure tree gives, which is the order it is meant to
+    /// be read in even when columns, sidebars and captions are drawn in some other order. Untagged pages come out as
+    /// usual. Has no effect with `--order raw`.
+    #[arg(long, global = true)]
+    pub use_structure: bool,
+    /// Keeps the text extracted from each file in this directory, so that files which haven't changed since the last
+    /// run don't have to be extracted again.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+    /// Writes the text to one file per page or per bookmarked section instead of to standard output.
+    #[arg(long, value_enum, requires = "output_dir")]
+    pub split_by: Option<SplitBy>,
+    /// The directory to write split output, or the files of PAGE XML, to.
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+    /// How deeply nested a bookmark can be and still start a new section when splitting by bookmarks.
+    #[arg(long, value_name = "LEVEL", default_value_t = 1)]
+    pub split_depth: usize,
+    /// Writes the text in a format provided by this WebAssembly module instead of as plain text.
+    #[cfg(feature = "plugins")]
+    #[arg(long, value_name = "FILE", conflicts_with = "split_by")]
+    pub plugin: Option<PathBuf>,
+    /// Copies the text to the clipboard instead of writing it to standard output.
+    #[cfg(feature = "clipboard")]
+    #[arg(long, conflicts_with = "split_by")]
+    #[cfg_attr(feature = "plugins", arg(conflicts_with = "plugin"))]
+    pub clipboard: bool,
+    /// Writes the text as flowing paragraphs for a screen reader or text to speech, with hyphenated words joined and
+    /// markup left out.
+    #[arg(long, conflicts_with = "split_by")]
+    #[cfg_attr(feature = "plugins", arg(conflicts_with = "plugin"))]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub linearize_for_speech: bool,
+    /// Writes the values filled in on a form as a JSON object, keyed by the label each one is next to.
+    #[arg(long, conflicts_with_all = ["split_by", "linearize_for_speech"])]
+    #[cfg_attr(feature = "plugins", arg(conflicts_with = "plugin"))]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub key_values: bool,
+    /// Writes the text as plain text laid out the way it is on the page, with each piece of each line as far across as
+    /// it is drawn, so that the columns of tables, invoices and forms stay lined up.
+    #[arg(
+        long,
+        conflicts_with_all = ["split_by", "linearize_for_speech", "key_values", "format"]
+    )]
+    #[cfg_attr(feature = "plugins", arg(conflicts_with = "plugin"))]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub layout: bool,
+    /// Writes the text as paragraphs, with the rows of each one joined into a single line and a blank line after it,
+    /// instead of each row on its own line.
+    #[arg(
+        long,
+        conflicts_with_all = ["split_by", "linearize_for_speech", "key_values", "format", "layout"]
+    )]
+    #[cfg_attr(feature = "plugins", arg(conflicts_with = "plugin"))]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub paragraphs: bool,
+    /// Keeps the hyphens at the ends of lines when joining them into paragraphs, for `--paragraphs`,
+    /// `--linearize-for-speech` and chunks, instead of putting the words they break back together.
+    #[arg(long)]
+    pub keep_hyphens: bool,
+    /// A list of words, one to a line, which decides whether words hyphenated at the end of a line are put back
+    /// together, so that compounds such as "well-known" keep their hyphen.
+    #[arg(long, value_name = "FILE", conflicts_with = "keep_hyphens")]
+    pub hyphenation_dictionary: Option<PathBuf>,
+    /// Keeps the running headers and footers which repeat at the top and bottom of most pages, which are left out
+    /// otherwise. Leaving them out means holding back the text until the last page has been extracted.
+    #[arg(long)]
+    pub keep_headers: bool,
+    /// Keeps the page numbers printed at the top or bottom of pages in their text. Otherwise they are left out, and
+    /// the chunks, vector records and plugins get them as the labels of the pages instead.
+    #[arg(long)]
+    pub keep_page_numbers: bool,
+    /// Keeps watermarks, the text such as "DRAFT" which is drawn at an angle across most pages, which is left out
+    /// otherwise.
+    #[arg(long)]
+    pub keep_watermarks: bool,
+    /// Finds the footnotes at the bottom of each page, in smaller text than the rest and referred to by raised
+    /// numbers or symbols in it, and moves them to here, rather than leaving them where they are drawn.
+    #[arg(long, value_enum, value_name = "WHERE")]
+    pub footnotes: Option<FootnotePlacement>,
+    /// Writes each table found in the text as CSV to this file, one after another with a blank line between them, or
+    /// to a file of its own named after its page in this directory, if it is one.
+    #[arg(long, value_name = "PATH")]
+    pub tables_out: Option<PathBuf>,
+    /// The format to write the text in.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Format::Text,
+        conflicts_with_all = ["split_by", "linearize_for_speech", "key_values"]
+    )]
+    #[cfg_attr(feature = "plugins", arg(conflicts_with = "plugin"))]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub format: Format,
+    /// Separates the pages of plain text, so that where each one starts can be told, rather than running them on
+    /// from one to the next.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "SEPARATOR",
+        conflicts_with_all = ["split_by", "linearize_for_speech", "key_values", "layout", "paragraphs"]
+    )]
+    #[cfg_attr(feature = "plugins", arg(conflicts_with = "plugin"))]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub page_separator: Option<PageSeparator>,
+    /// How superscripts and subscripts are written in plain text. The other formats have markup of their own for
+    /// them.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MARKUP",
+        default_value_t = ScriptMarkup::Html
+    )]
+    pub script_markup: ScriptMarkup,
+    /// The most tokens in each chunk, when writing chunks or vector store records.
+    #[arg(long, value_name = "TOKENS", default_value = "1000")]
+    pub chunk_size: NonZeroUsize,
+    /// How many tokens from the end of each chunk to repeat at the start of the next, when writing chunks or vector
+    /// store records.
+    #[arg(long, value_name = "TOKENS", default_value_t = 100)]
+    pub overlap: usize,
+    /// Masks personal data in the text, replacing it with what kind of data it was.
+    #[arg(long, value_enum, value_name = "KIND")]
+    pub mask: Vec<Mask>,
+    /// Masks the text matching this regular expression as well, replacing it with `[REDACTED]`.
+    #[arg(long, value_name = "PATTERN")]
+    pub mask_pattern: Vec<String>,
+    /// Answers JSON-RPC requests on standard input instead, keeping documents open between them, for editors and other
+    /// front ends which ask for pages as they need them.
+    #[arg(long, conflicts_with = "file")]
+    pub daemon: bool,
+}
+
+impl Cli {
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            mmap: self.mmap,
+            large_file: self.large_file,
+            max_memory: self.max_memory,
+        }
+    }
+
+    pub fn extract_options(&self) -> ExtractOptions {
+        ExtractOptions {
+            font_cache_size: self.font_cache_size,
+            pages: self.pages.clone(),
+            budget: PageBudget {
+                max_operators: self.max_operators,
+                max_bytes: self.max_content_bytes,
+            },
+            max_memory: self.max_memory,
+            order: if self.raw_order {
+                Order::Raw
+            } else {
+                self.order
+            },
+            space_threshold: self.space_threshold,
+            line_tolerance: self.line_tolerance,
+            word_gap: self.word_gap,
+            keep_columns: self.layout,
+            detect_scripts: !self.no_scripts,
+            script_offset: self.script_offset,
+            script_gap: self.script_gap,
+            keep_chunks: matches!(
+                self.format,
+                Format::Json
+                    | Format::Jsonl
+                    | Format::Hocr
+                    | Format::Alto
+                    | Format::PageXml
+                    | Format::PositionedXhtml
+                    | Format::Csv
+            ),
+            mark_styles: matches!(self.format, Format::Html | Format::Markdown | Format::Tei),
+            mark_links: matches!(self.format, Format::Html | Format::Markdown | Format::Tei),
+            include_invisible: !self.exclude_invisible,
+            layers: self.layer.clone(),
+            excluded_layers: self.exclude_layer.clone(),
+            actual_text: !self.ignore_actual_text,
+            include_artifacts: self.keep_artifacts,
+            cmap_dir: self.cmap_dir.clone(),
+            visual_order: self.visual_order,
+            use_structure: self.use_structure,
+        }
+    }
+
+    pub fn hyphenation(&self) -> Result<Hyphenation, Box<dyn Error>> {
+        let dictionary = match &self.hyphenation_dictionary {
+            Some(path) => Some(Rc::new(Hyphenation::load_dictionary(path).map_err(
+                |error| format!("couldn't read the dictionary {}: {}", path.display(), error),
+            )?)),
+            None => None,
+        };
+        Ok(Hyphenation {
+            keep_hyphens: self.keep_hyphens,
+            dictionary,
+        })
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Finds a regular expression in the extracted text, reporting the page and position of each match.
+    ///
+    /// Matches may continue across line breaks within a page, and words hyphenated across them are matched whole.
+    Search(SearchArgs),
+    /// Lists the most frequent terms and pairs of terms in the document, and the pages they appear on.
+    Keywords {
+        /// The PDF file to examine.
+        file: PathBuf,
+        /// How many terms and bigrams to list.
+        #[arg(short = 'n', long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Lists the formulas of a tagged document with their MathML and replacement text, printed as JSON.
+    Formulas {
+        /// The PDF file to examine.
+        file: PathBuf,
+    },
+    /// Prints the table of contents as JSON, taken from the outline if there is one and otherwise from the headings
+    /// found in the text.
+    Toc {
+        /// The PDF file to examine.
+        file: PathBuf,
+    },
+    /// Scores how well the text of each page came out, printed as JSON, to pick out pages which need OCR or a closer
+    /// look.
+    Quality {
+        /// The PDF file to examine.
+        file: PathBuf,
+    },
+    /// Splits the reference section of a paper into its citations, printed as JSON.
+    References {
+        /// The PDF file to examine.
+        file: PathBuf,
+    },
+    /// Writes the text of documents produced for discovery with their Bates numbers taken out, along with CSV load
+    /// files of each document's Bates range and each page's Bates number.
+    Bates {
+        /// The directory to write the text and load files to.
+        #[arg(long, value_name = "DIR")]
+        output_dir: PathBuf,
+        /// The PDF files to process.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Detects the language of each page and of the whole document, printed as JSON.
+    #[cfg(feature = "language")]
+    Language {
+        /// The PDF file to examine.
+        file: PathBuf,
+    },
+    /// Lines up the paragraphs of a bilingual document with their translations, printed as JSON, for documents with
+    /// the two languages in columns side by side or on facing pages.
+    #[cfg(feature = "language")]
+    Parallel {
+        /// The PDF file to examine.
+        file: PathBuf,
+    },
+    /// Browses the extracted text a page at a time in the terminal, with search and the outline in a sidebar.
+    #[cfg(feature = "tui")]
+    View {
+        /// The PDF file to view.
+        file: PathBuf,
+    },
+    /// Runs a gRPC service which extracts the documents sent to it, streaming back one message per page.
+    #[cfg(feature = "grpc")]
+    Grpc {
+        /// The address to listen on.
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        listen: std::net::SocketAddr,
+        /// Rejects documents bigger than this, such as `64M`.
+        #[arg(long, value_name = "SIZE", default_value = "256M", value_parser = parse_size)]
+        max_document_size: usize,
+    },
+    /// Adds the text of PDF files to a full-text index, replacing what was there for any of them before.
+    #[cfg(feature = "index")]
+    Index {
+        /// The directory the index is kept in, which is created if it doesn't exist.
+        #[arg(long, value_name = "DIR")]
+        index_dir: PathBuf,
+        /// The PDF files to index.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Searches a full-text index made by the `index` command, printing the best matching rows.
+    #[cfg(feature = "index")]
+    Query {
+        /// The directory the index is kept in.
+        #[arg(long, value_name = "DIR")]
+        index_dir: PathBuf,
+        /// What to look for, such as `fox AND dog` or `"quick brown"`.
+        query: String,
+        /// How many rows to print.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// The regular expression to look for.
+    pub pattern: String,
+    /// The PDF file to search.
+    pub file: PathBuf,
+    /// Matches regardless of case.
+    #[arg(short, long)]
+    pub ignore_case: bool,
+    /// Treats the pattern as literal text rather than a regular expression.
+    #[arg(short = 'F', long)]
+    pub fixed_strings: bool,
+    /// Prints this many rows after each match.
+    #[arg(short = 'A', long, value_name = "ROWS")]
+    pub after_context: Option<usize>,
+    /// Prints this many rows before each match.
+    #[arg(short = 'B', long, value_name = "ROWS")]
+    pub before_context: Option<usize>,
+    /// Prints this many rows before and after each match.
+    #[arg(short = 'C', long, value_name = "ROWS")]
+    pub context: Option<usize>,
+}
+
+impl SearchArgs {
+    pub fn context(&self) -> Context {
+        let around = self.context.unwrap_or(0);
+        Context {
+            before: self.before_context.unwrap_or(around),
+            after: self.after_context.unwrap_or(around),
+        }
+    }
+}
+
+/// Parses a number of bytes, optionally followed by a `K`, `M` or `G` suffix.
+fn parse_size(size: &str) -> Result<usize, String> {
+    let size = size.trim();
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&size[..index], 1 << 10),
+        Some((index, 'm' | 'M')) => (&size[..index], 1 << 20),
+        Some((index, 'g' | 'G')) => (&size[..index], 1 << 30),
+        _ => (size, 1),
+    };
+    number
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("{:?} is not a size in bytes, such as 512M", size))
+}
+
+/// Parses a number which can't be negative, such as a distance or a fraction of one.
+fn parse_non_negative(number: &str) -> Result<f32, String> {
+    number
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|number| number.is_finite() && *number >= 0.0)
+        .ok_or_else(|| format!("{:?} is not a number of zero or more", number))
+}
+
+/// Parses how far superscripts and subscripts can be moved: `auto`, a distance, or a percentage of the size of the
+/// text.
+fn parse_script_offset(offset: &str) -> Result<ScriptOffset, String> {
+    let offset = offset.trim();
+    let error = || {
+        format!(
+            "{:?} is not auto, a distance such as 3 or a percentage such as 40%",
+            offset
+        )
+    };
+    if offset.eq_ignore_ascii_case("auto") {
+        return Ok(ScriptOffset::Auto);
+    }
+    let (number, percentage) = match offset.strip_suffix('%') {
+        Some(number) => (number.trim(), true),
+        None => (offset, false),
+    };
+    let number = parse_non_negative(number).map_err(|_| error())?;
+    Ok(if percentage {
+        ScriptOffset::FontSize(number / 100.0)
+    } else {
+        ScriptOffset::Fixed(number)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes_with_suffixes() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("4k"), Ok(4 << 10));
+        assert_eq!(parse_size("4K"), Ok(4 << 10));
+        assert_eq!(parse_size("512M"), Ok(512 << 20));
+        assert_eq!(parse_size(" 2 g "), Ok(2 << 30));
+        assert_eq!(parse_size("0M"), Ok(0));
+    }
+
+    #[test]
+    fn refuses_sizes_that_overflow() {
+        assert!(parse_size(&format!("{}K", usize::MAX)).is_err());
+        assert!(parse_size(&format!("{}G", usize::MAX >> 29)).is_err());
+        assert!(parse_size("18446744073709551616").is_err());
+        assert_eq!(
+            parse_size(&format!("{}G", usize::MAX >> 30)),
+            Ok(usize::MAX >> 30 << 30)
+        );
+    }
+
+    #[test]
+    fn refuses_invalid_sizes() {
+        for size in ["", "M", "-1M", "1.5G", "12T", "1 MB", "ten", "0x10"] {
+            assert!(parse_size(size).is_err(), "{:?} was accepted", size);
+        }
+        assert_eq!(
+            parse_size("12T").unwrap_err(),
+            r#""12T" is not a size in bytes, such as 512M"#
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Puts the extracted text on the system clipboard instead of writing it out.
+
+use std::io;
+
+use arboard::Clipboard;
+
+use crate::{
+    output::{OutputRenderer, PlainTextRenderer},
+    text::Page,
+};
+
+/// Collects the text as plain text, and copies all of it to the clipboard once the last page is done.
+pub struct ClipboardRenderer {
+    text: PlainTextRenderer<Vec<u8>>,
+}
+
+impl ClipboardRenderer {
+    pub fn new() -> Self {
+        Self {
+            text: PlainTextRenderer::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for ClipboardRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputRenderer for ClipboardRenderer {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.text.render_page(page)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let text =
+            String::from_utf8(std::mem::take(self.text.get_mut())).map_err(io::Error::other)?;
+        // On Linux the clipboard belongs to whichever program set it, so dropping it here hands the text over to the
+        // clipboard manager, if there is one, for it to outlive this process.
+        Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|error| {
+                io::Error::other(format!(
+                    "couldn't copy the text to the clipboard: {}",
+                    error
+                ))
+            })
+    }
+}
@@ -0,0 +1,224 @@
+//! The predefined CMaps of composite fonts, which Chinese, Japanese and Korean documents often use instead of
+//! embedding their own, and which say which CID each code is.
+//!
+//! The `Uni` CMaps, such as `UniJIS-UCS2-H`, have UTF-16 codes, so their text can be read straight from the codes.
+//! For the rest, and for `Identity-H`, the codes only say which CID to draw, and the text of each CID comes from the
+//! UCS2 CMap of the font's character collection, such as `Adobe-Japan1-UCS2`. These are far too big to build in, so
+//! they are loaded as they are needed from a copy of Adobe's CMap resources, in a directory given with `--cmap-dir`.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::font::{self, CMap, CodeRange, UnicodeTable};
+
+/// How deeply CMaps can be built on each other with `usecmap`, which in the predefined CMaps is never more than once.
+const MAX_CMAP_DEPTH: usize = 4;
+
+/// How many directories deep to look for CMap files, which covers both Adobe's layout of a directory per character
+/// collection with a `CMap` directory in each and a single flat directory.
+const MAX_DIRECTORY_DEPTH: usize = 3;
+
+/// Which CID each code of a composite font is.
+#[derive(Debug)]
+pub struct CidMap {
+    code_ranges: Vec<CodeRange>,
+    /// Runs of consecutive codes with consecutive CIDs, as `(first code, last code, first CID)`, sorted by code.
+    cids: Vec<(u32, u32, u32)>,
+    /// The map this one adds to, for codes it doesn't have.
+    parent: Option<Rc<CidMap>>,
+}
+
+impl CidMap {
+    /// The map of `Identity-H` and `Identity-V`, whose two byte codes are the CIDs.
+    pub fn identity() -> Self {
+        Self {
+            code_ranges: Vec::new(),
+            cids: vec![(0, 0xFFFF, 0)],
+            parent: None,
+        }
+    }
+
+    /// Builds the map from a parsed CMap, along with the map it adds to if it has one.
+    pub fn new(cmap: CMap, parent: Option<Rc<CidMap>>) -> Self {
+        let mut cids = cmap.cids;
+        cids.sort_by_key(|&(first_code, _, _)| first_code);
+        Self {
+            code_ranges: cmap.code_ranges,
+            cids,
+            parent,
+        }
+    }
+
+    /// The byte sequences which are codes, which come from the map this one adds to if it doesn't have any itself.
+    pub fn code_ranges(&self) -> &[CodeRange] {
+        match &self.parent {
+            Some(parent) if self.code_ranges.is_empty() => parent.code_ranges(),
+            _ => &self.code_ranges,
+        }
+    }
+
+    pub fn cid(&self, code: u32) -> Option<u32> {
+        let index = self
+            .cids
+            .partition_point(|&(first_code, _, _)| first_code <= code);
+        let own = index
+            .checked_sub(1)
+            .map(|index| self.cids[index])
+            .filter(|&(_, last_code, _)| code <= last_code)
+            .map(|(first_code, _, first_cid)| first_cid.saturating_add(code - first_code));
+        own.or_else(|| self.parent.as_ref()?.cid(code))
+    }
+
+    /// Roughly how many bytes the map takes up in memory, not counting the map it adds to.
+    pub fn approximate_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.code_ranges.capacity() * std::mem::size_of::<CodeRange>()
+            + self.cids.capacity() * std::mem::size_of::<(u32, u32, u32)>()
+    }
+}
+
+/// Whether the codes of a predefined CMap are UTF-16 text, as those of the `Uni` CMaps for UCS-2 and UTF-16 are.
+pub fn has_utf16_codes(name: &str) -> bool {
+    name.starts_with("Uni") && (name.contains("-UCS2-") || name.contains("-UTF16-"))
+}
+
+/// Loads predefined CMaps from a directory as they are asked for, keeping the ones it has loaded for other fonts.
+#[derive(Default)]
+pub struct CMapLibrary {
+    directory: Option<PathBuf>,
+    /// Where each file under the directory is, by name, once the directory has been looked through.
+    files: Option<HashMap<OsString, PathBuf>>,
+    cid_maps: HashMap<String, Option<Rc<CidMap>>>,
+    collections: HashMap<String, Option<Rc<UnicodeTable>>>,
+}
+
+impl CMapLibrary {
+    /// A library of the CMaps in a directory, or of none if there is no directory, in which case only the CMaps that
+    /// need no data work.
+    pub fn new(directory: Option<PathBuf>) -> Self {
+        Self {
+            directory,
+            ..Self::default()
+        }
+    }
+
+    /// The predefined CMap with this name, if it is `Identity-H` or `Identity-V` or can be found in the directory.
+    pub fn cid_map(&mut self, name: &str) -> Option<Rc<CidMap>> {
+        self.cid_map_at_depth(name, 0)
+    }
+
+    /// Builds the map of a CMap embedded in the document, whose `usecmap` can name a predefined one to add to.
+    pub fn embedded_cid_map(&mut self, cmap: CMap) -> Rc<CidMap> {
+        let parent = cmap
+            .parent
+            .as_deref()
+            .and_then(|parent| self.cid_map_at_depth(parent, 1));
+        Rc::new(CidMap::new(cmap, parent))
+    }
+
+    fn cid_map_at_depth(&mut self, name: &str, depth: usize) -> Option<Rc<CidMap>> {
+        if name == "Identity-H" || name == "Identity-V" {
+            return Some(Rc::new(CidMap::identity()));
+        }
+        if let Some(map) = self.cid_maps.get(name) {
+            return map.clone();
+        }
+        let map = self.load(name).and_then(|cmap| {
+            let parent = match &cmap.parent {
+                Some(parent) if depth < MAX_CMAP_DEPTH => {
+                    Some(self.cid_map_at_depth(parent, depth + 1)?)
+                }
+                Some(_) => return None,
+                None => None,
+            };
+            Some(Rc::new(CidMap::new(cmap, parent)))
+        });
+        self.cid_maps.insert(name.to_owned(), map.clone());
+        map
+    }
+
+    /// The text of each CID in a character collection, such as `Adobe-Japan1`, from its UCS2 CMap.
+    pub fn collection_text(&mut self, collection: &str) -> Option<Rc<UnicodeTable>> {
+        if let Some(table) = self.collections.get(collection) {
+            return table.clone();
+        }
+        let table = self
+            .load(&format!("{}-UCS2", collection))
+            .map(|cmap| Rc::new(UnicodeTable::new(&cmap.characters)));
+        self.collections
+            .insert(collection.to_owned(), table.clone());
+        table
+    }
+
+    /// Roughly how many bytes the loaded CMaps take up in memory.
+    pub fn approximate_size(&self) -> usize {
+        let cid_maps: usize = self
+            .cid_maps
+            .values()
+            .flatten()
+            .map(|map| map.approximate_size())
+            .sum();
+        let collections: usize = self
+            .collections
+            .values()
+            .flatten()
+            .map(|table| table.approximate_size())
+            .sum();
+        cid_maps + collections
+    }
+
+    /// Forgets the loaded CMaps, to make room in memory, though fonts which use them keep them until they are dropped
+    /// as well.
+    pub fn clear(&mut self) {
+        self.cid_maps.clear();
+        self.collections.clear();
+    }
+
+    fn load(&mut self, name: &str) -> Option<CMap> {
+        let directory = self.directory.as_ref()?;
+        let files = self.files.get_or_insert_with(|| {
+            let mut files = HashMap::new();
+            find_files(directory, MAX_DIRECTORY_DEPTH, &mut files);
+            files
+        });
+        let path = files.get(&OsString::from(name))?;
+        match fs::read(path) {
+            Ok(content) => Some(font::parse_cmap(&content)),
+            Err(error) => {
+                eprintln!(
+                    "warning: couldn't read the CMap {}: {}",
+                    path.display(),
+                    error
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Adds every file in a directory and the directories in it to `files`, by name, keeping the first of any with the
+/// same name.
+fn find_files(directory: &Path, depth: usize, files: &mut HashMap<OsString, PathBuf>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        eprintln!(
+            "warning: couldn't read the CMap directory {}",
+            directory.display()
+        );
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                find_files(&path, depth - 1, files);
+            }
+        } else {
+            files.entry(entry.file_name()).or_insert(path);
+        }
+    }
+}
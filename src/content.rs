@@ -0,0 +1,279 @@
+use std::{borrow::Cow, error::Error, fmt::Display};
+
+use lopdf::{Document, Object, ObjectId};
+
+use crate::filters::{self, DecodeError};
+
+/// How much work a single page may take before it is abandoned.
+///
+/// Content streams can be made to decompress into gigabytes or to contain endless operators, which would otherwise
+/// hang whatever is doing the extraction.
+#[derive(Clone, Copy, Debug)]
+pub struct PageBudget {
+    pub max_operators: usize,
+    pub max_bytes: usize,
+}
+
+#[derive(Debug)]
+pub enum BudgetExceeded {
+    Operators(usize),
+    Bytes(usize),
+}
+
+impl Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetExceeded::Operators(limit) => write!(f, "it has more than {} operators", limit),
+            BudgetExceeded::Bytes(limit) => {
+                write!(
+                    f,
+                    "its content is more than {} bytes once decompressed",
+                    limit
+                )
+            }
+        }
+    }
+}
+
+impl Error for BudgetExceeded {}
+
+/// Decompresses all of a page's content streams into one, giving up if they come to more than the budget allows.
+pub fn page_content(
+    document: &Document,
+    page_id: ObjectId,
+    budget: PageBudget,
+) -> Result<Vec<u8>, BudgetExceeded> {
+    let mut content = Vec::new();
+    for stream_id in document.get_page_contents(page_id) {
+        let Ok(stream) = document.get_object(stream_id).and_then(Object::as_stream) else {
+            continue;
+        };
+        let remaining = budget.max_bytes.saturating_sub(content.len());
+        let data = match filters::decode(stream, remaining) {
+            Ok(data) => data,
+            Err(DecodeError::TooBig(_)) => return Err(BudgetExceeded::Bytes(budget.max_bytes)),
+            Err(error) => {
+                eprintln!("warning: skipping a content stream because {}", error);
+                continue;
+            }
+        };
+        content.extend(data);
+        // Operators may not be split across streams, but the streams still need something between them.
+        content.push(b'\n');
+    }
+    Ok(content)
+}
+
+/// The most bytes after the `EI` at the end of an inline image which have to look like content for it to be taken as
+/// the end, rather than as part of the image's data.
+const INLINE_IMAGE_LOOKAHEAD: usize = 16;
+
+/// Takes the inline images out of content, replacing each `BI` ... `ID` ... `EI` block with a space.
+///
+/// The data of an inline image is raw bytes in the middle of the content, which can contain anything, including bytes
+/// that look like strings or operators, and the content parser loses track of the rest of the page when it tries to
+/// read them. The data ends at the `EI` after it, but the data can contain `EI` too, so the image's `L` gives its
+/// length when there is one, and otherwise the end is the first `EI` which is followed by something that looks like
+/// content rather than binary data.
+pub fn without_inline_images(content: &[u8]) -> Cow<'_, [u8]> {
+    if !content.windows(2).any(|window| window == b"BI") {
+        return Cow::Borrowed(content);
+    }
+    let mut result = Vec::with_capacity(content.len());
+    let mut copied = 0;
+    let mut position = 0;
+    while let Some((start, end)) = next_word(content, &mut position) {
+        if &content[start..end] != b"BI" {
+            continue;
+        }
+        // The image's dictionary runs up to `ID`, and is needed for the length of the data.
+        let mut length = None;
+        let mut previous_word: &[u8] = &[];
+        let data_start = loop {
+            let Some((word_start, word_end)) = next_word(content, &mut position) else {
+                break None;
+            };
+            let word = &content[word_start..word_end];
+            if word == b"ID" {
+                // A single whitespace byte separates `ID` from the data.
+                break Some(word_end + 1);
+            }
+            if previous_word == b"/L" || previous_word == b"/Length" {
+                length = std::str::from_utf8(word)
+                    .ok()
+                    .and_then(|length| length.parse::<usize>().ok());
+            }
+            previous_word = word;
+        };
+        result.extend_from_slice(&content[copied..start]);
+        result.push(b' ');
+        let image_end =
+            data_start.and_then(|data_start| inline_image_end(content, data_start, length));
+        match image_end {
+            Some(image_end) => {
+                copied = image_end;
+                position = image_end;
+            }
+            // An image which never ends takes up the rest of the content.
+            None => {
+                copied = content.len();
+                break;
+            }
+        }
+    }
+    result.extend_from_slice(&content[copied..]);
+    Cow::Owned(result)
+}
+
+/// Finds the end of the `EI` after an inline image's data.
+fn inline_image_end(content: &[u8], data_start: usize, length: Option<usize>) -> Option<usize> {
+    let ends_image = |position: usize| {
+        content.get(position..position + 2) == Some(b"EI")
+            && content
+                .get(position + 2)
+                .is_none_or(|&byte| is_whitespace(byte) || is_delimiter(byte))
+    };
+    if let Some(length) = length {
+        let mut position = data_start.checked_add(length)?;
+        while content
+            .get(position)
+            .is_some_and(|&byte| is_whitespace(byte))
+        {
+            position += 1;
+        }
+        if ends_image(position) {
+            return Some(position + 2);
+        }
+    }
+    (data_start..content.len()).find_map(|position| {
+        let after_whitespace = is_whitespace(content[position - 1]);
+        let looks_like_content = content[(position + 2).min(content.len())..]
+            .iter()
+            .take(INLINE_IMAGE_LOOKAHEAD)
+            .all(|&byte| byte.is_ascii_graphic() || is_whitespace(byte));
+        (after_whitespace && ends_image(position) && looks_like_content).then_some(position + 2)
+    })
+}
+
+/// Finds the next run of regular characters in content, skipping over strings and comments, as `(start, end)`.
+/// Names come with their slash.
+fn next_word(content: &[u8], position: &mut usize) -> Option<(usize, usize)> {
+    loop {
+        let byte = *content.get(*position)?;
+        match byte {
+            _ if is_whitespace(byte) => *position += 1,
+            b'%' => {
+                while content
+                    .get(*position)
+                    .is_some_and(|&byte| byte != b'\n' && byte != b'\r')
+                {
+                    *position += 1;
+                }
+            }
+            b'(' => {
+                // Balanced parentheses are allowed inside the string without escaping them.
+                let mut depth = 0;
+                while let Some(&byte) = content.get(*position) {
+                    *position += 1;
+                    match byte {
+                        b'\\' => *position += 1,
+                        b'(' => depth += 1,
+                        b')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            b'<' if content.get(*position + 1) != Some(&b'<') => {
+                while content.get(*position).is_some_and(|&byte| byte != b'>') {
+                    *position += 1;
+                }
+                *position += 1;
+            }
+            _ if is_delimiter(byte) && byte != b'/' => *position += 1,
+            _ => {
+                let start = *position;
+                *position += 1;
+                while content
+                    .get(*position)
+                    .is_some_and(|&byte| !is_whitespace(byte) && !is_delimiter(byte))
+                {
+                    *position += 1;
+                }
+                return Some((start, *position));
+            }
+        }
+    }
+}
+
+pub fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\0' | b'\t' | b'\n' | 0x0c | b'\r' | b' ')
+}
+
+pub fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(content: &[u8]) -> String {
+        String::from_utf8_lossy(&without_inline_images(content)).into_owned()
+    }
+
+    #[test]
+    fn leaves_content_without_images_as_it_is() {
+        let content = b"BT (text) Tj ET";
+        assert!(matches!(without_inline_images(content), Cow::Borrowed(_)));
+        // Strings which happen to have the operators in them aren't images.
+        assert_eq!(
+            strip(b"BT (BI and ID and EI) Tj ET"),
+            "BT (BI and ID and EI) Tj ET"
+        );
+    }
+
+    #[test]
+    fn takes_out_inline_images() {
+        let content = b"BT (a) Tj ET q BI /W 2 /H 1 /BPC 8 /CS /G ID \x01\x02 EI Q BT (b) Tj ET";
+        assert_eq!(strip(content), "BT (a) Tj ET q   Q BT (b) Tj ET");
+    }
+
+    #[test]
+    fn skips_ei_inside_image_data() {
+        // The first `EI` is followed by binary data, so it is part of the image.
+        let content = b"q BI /W 4 /H 1 /CS /G /BPC 8 ID \x00 EI\n\xff\x80\xfe\x01 EI Q (after) Tj";
+        assert_eq!(strip(content), "q   Q (after) Tj");
+    }
+
+    #[test]
+    fn uses_the_length_of_the_data() {
+        // The data has an `EI` in it which looks like the end, but the length says where the end really is.
+        let content = b"BI /W 8 /H 1 /BPC 8 /L 8 ID  EI BT x EI (after) Tj";
+        assert_eq!(strip(content), "  (after) Tj");
+        // A length which doesn't end at an `EI` is ignored.
+        let content = b"BI /W 8 /H 1 /BPC 8 /L 3 ID ab EI (after) Tj";
+        assert_eq!(strip(content), "  (after) Tj");
+    }
+
+    #[test]
+    fn takes_out_the_rest_of_an_image_without_ei() {
+        let content = b"BT (before) Tj ET BI /W 2 /H 2 ID \x00\x01\x02\x03 Q BT (lost) Tj ET";
+        assert_eq!(strip(content), "BT (before) Tj ET  ");
+        let content = b"BT (before) Tj ET BI /W 2 /H 2";
+        assert_eq!(strip(content), "BT (before) Tj ET  ");
+    }
+
+    #[test]
+    fn takes_out_every_image() {
+        let content = b"BI /W 1 ID x EI (a) Tj BI /W 1 ID y EI (b) Tj";
+        assert_eq!(strip(content), "  (a) Tj   (b) Tj");
+    }
+}
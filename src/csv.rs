@@ -0,0 +1,73 @@
+//! Writes the chunks of text as CSV, one chunk to a line, for looking through in a spreadsheet or loading into a data
+//! frame.
+//!
+//! The chunks are the pieces of text each row was joined from, in the order the rows are read in, with the page each
+//! is on, where it starts on the page and how big it is in the units of the page, from the page's bottom left corner,
+//! and the family and size of its font. The text of each chunk has no markup.
+
+use std::io::{self, Write};
+
+use crate::{
+    bates::csv_field,
+    output::OutputRenderer,
+    text::{self, Page, TextChunk},
+};
+
+const HEADER: &str = "page,x,y,width,height,font,size,text";
+
+pub struct CsvRenderer<W: Write> {
+    writer: W,
+    started: bool,
+}
+
+impl<W: Write> CsvRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            started: false,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            writeln!(self.writer, "{}", HEADER)?;
+            self.started = true;
+        }
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, chunk: &TextChunk) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{}",
+            chunk.page,
+            chunk.x,
+            chunk.y,
+            chunk.width,
+            chunk.height,
+            csv_field(chunk.font_family.as_deref().unwrap_or_default()),
+            chunk.font_size,
+            csv_field(&text::plain_text(&chunk.text))
+        )
+    }
+}
+
+impl<W: Write> OutputRenderer for CsvRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.start()?;
+        for row in &page.rows {
+            if row.chunks.is_empty() {
+                self.write_chunk(row)?;
+            }
+            for chunk in &row.chunks {
+                self.write_chunk(chunk)?;
+            }
+        }
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        self.writer.flush()
+    }
+}
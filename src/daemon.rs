@@ -0,0 +1,303 @@
+//! A long-running mode which answers JSON-RPC 2.0 requests on standard input, one per line, so that editors and other
+//! front ends can keep documents open and ask for pages as they need them.
+//!
+//! The methods are:
+//!
+//! - `open` with `{"path": ...}`, which loads a document and returns `{"document": id, "pages": count}`.
+//! - `extract-page` with `{"document": id, "page": number}`, which returns the page's rows, with where each one is,
+//!   how big it is and the font it is in, and how well its text came out.
+//! - `search` with `{"document": id, "pattern": ..., "ignore_case": bool, "fixed_strings": bool}`, where the last two
+//!   are optional, which returns `{"hits": [...]}` with the row each match starts in and where in the row it starts.
+//! - `close` with `{"document": id}`, which frees the document.
+//!
+//! Each page is only extracted once, however many times it is asked for, and fonts parsed for one page stay cached
+//! for the others.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use lopdf::{Document, ObjectId};
+use regex::RegexBuilder;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    load::{load_document, LoadOptions},
+    quality, search,
+    text::{self, ExtractOptions, Page, TextExtractor},
+    traversal,
+};
+
+// The standard JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// For requests which were understood, but couldn't be carried out, such as opening a file which doesn't exist.
+const REQUEST_FAILED: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for RpcError {
+    fn from(error: Box<dyn Error>) -> Self {
+        RpcError::new(REQUEST_FAILED, error)
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenParams {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    document: u64,
+    page: u32,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    document: u64,
+    pattern: String,
+    #[serde(default)]
+    ignore_case: bool,
+    #[serde(default)]
+    fixed_strings: bool,
+}
+
+#[derive(Deserialize)]
+struct CloseParams {
+    document: u64,
+}
+
+#[derive(Serialize)]
+struct SearchHit {
+    page: u32,
+    x: f32,
+    y: f32,
+    /// Where the match starts in the row, in characters.
+    column: usize,
+    /// The text of every row the match is in, joined by newlines.
+    text: String,
+}
+
+/// A document kept open between requests.
+struct OpenDocument {
+    extractor: TextExtractor<Document>,
+    page_ids: BTreeMap<u32, ObjectId>,
+    pages: BTreeMap<u32, Page>,
+}
+
+impl OpenDocument {
+    fn page(&mut self, number: u32) -> Result<&Page, RpcError> {
+        if !self.pages.contains_key(&number) {
+            let page_id = *self.page_ids.get(&number).ok_or_else(|| {
+                RpcError::new(
+                    INVALID_PARAMS,
+                    format!("the document has no page {}", number),
+                )
+            })?;
+            let page = self.extractor.extract_page(number, page_id)?;
+            self.pages.insert(number, page);
+        }
+        Ok(&self.pages[&number])
+    }
+}
+
+struct Daemon {
+    load_options: LoadOptions,
+    options: ExtractOptions,
+    documents: HashMap<u64, OpenDocument>,
+    next_id: u64,
+}
+
+impl Daemon {
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "open" => {
+                let params: OpenParams = parse_params(params)?;
+                let document = load_document(&params.path, &self.load_options)?;
+                let page_ids = traversal::pages(&document)
+                    .map_err(|error| RpcError::new(REQUEST_FAILED, error))?;
+                let id = self.next_id;
+                self.next_id += 1;
+                let result = json!({ "document": id, "pages": page_ids.len() });
+                self.documents.insert(
+                    id,
+                    OpenDocument {
+                        extractor: TextExtractor::new(document, &self.options),
+                        page_ids,
+                        pages: BTreeMap::new(),
+                    },
+                );
+                Ok(result)
+            }
+            "extract-page" => {
+                let params: PageParams = parse_params(params)?;
+                let page = self.document(params.document)?.page(params.page)?;
+                Ok(json!({
+                    "number": page.number,
+                    "rows": page.rows,
+                    "tables": page.tables,
+                    "quality": quality::assess(page),
+                }))
+            }
+            "search" => {
+                let params: SearchParams = parse_params(params)?;
+                let pattern = if params.fixed_strings {
+                    regex::escape(&params.pattern)
+                } else {
+                    params.pattern
+                };
+                let pattern = RegexBuilder::new(&pattern)
+                    .case_insensitive(params.ignore_case)
+                    .build()
+                    .map_err(|error| RpcError::new(INVALID_PARAMS, error))?;
+                let document = self.document(params.document)?;
+                let numbers: Vec<u32> = document.page_ids.keys().copied().collect();
+                let mut rows = Vec::new();
+                for number in numbers {
+                    rows.extend(document.page(number)?.rows.iter().cloned());
+                }
+                let hits: Vec<SearchHit> = search::search(&rows, &pattern)
+                    .into_iter()
+                    .map(|hit| {
+                        let row = &rows[hit.first_row];
+                        SearchHit {
+                            page: row.page,
+                            x: row.x,
+                            y: row.y,
+                            column: hit.column(&rows),
+                            text: rows[hit.first_row..=hit.last_row]
+                                .iter()
+                                .map(|row| text::plain_text(&row.text))
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                        }
+                    })
+                    .collect();
+                Ok(json!({ "hits": hits }))
+            }
+            "close" => {
+                let params: CloseParams = parse_params(params)?;
+                self.document(params.document)?;
+                self.documents.remove(&params.document);
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("there is no method called {:?}", method),
+            )),
+        }
+    }
+
+    fn document(&mut self, id: u64) -> Result<&mut OpenDocument, RpcError> {
+        self.documents.get_mut(&id).ok_or_else(|| {
+            RpcError::new(
+                INVALID_PARAMS,
+                format!("no document is open with id {}", id),
+            )
+        })
+    }
+
+    /// Handles a single request, returning the response, or `None` if it was a notification.
+    fn handle(&mut self, request: Value) -> Option<Value> {
+        let id = request.get("id").cloned();
+        let result = match (
+            request.get("jsonrpc").and_then(Value::as_str),
+            request.get("method").and_then(Value::as_str),
+        ) {
+            (Some("2.0"), Some(method)) => {
+                let params = request.get("params").cloned().unwrap_or(Value::Null);
+                // A document which breaks the extractor fails the request rather than the daemon, so that the other
+                // documents stay open.
+                text::catching_panics(|| self.call(method, params))
+                    .unwrap_or_else(|message| Err(RpcError::new(REQUEST_FAILED, message)))
+            }
+            _ => Err(RpcError::new(
+                INVALID_REQUEST,
+                "requests need a \"jsonrpc\" of \"2.0\" and a \"method\"",
+            )),
+        };
+        // Requests without an id are notifications, which never get a response, even if they fail.
+        let id = id?;
+        Some(response(id, result))
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|error| RpcError::new(INVALID_PARAMS, error))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    }
+}
+
+/// Answers requests from `input` until it ends, writing each response to `output` as a line of its own.
+pub fn run(
+    input: impl BufRead,
+    mut output: impl Write,
+    load_options: LoadOptions,
+    options: ExtractOptions,
+) -> io::Result<()> {
+    let mut daemon = Daemon {
+        load_options,
+        options,
+        documents: HashMap::new(),
+        next_id: 1,
+    };
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            // A batch of requests gets a batch of responses, leaving out the notifications.
+            Ok(Value::Array(requests)) if !requests.is_empty() => {
+                let responses: Vec<Value> = requests
+                    .into_iter()
+                    .filter_map(|request| daemon.handle(request))
+                    .collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(request @ Value::Object(_)) => daemon.handle(request),
+            Ok(_) => Some(response(
+                Value::Null,
+                Err(RpcError::new(INVALID_REQUEST, "requests must be objects")),
+            )),
+            Err(error) => Some(response(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, error)),
+            )),
+        };
+        if let Some(response) = response {
+            serde_json::to_writer(&mut output, &response)?;
+            writeln!(output)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
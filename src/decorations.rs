@@ -0,0 +1,72 @@
+//! Finds text which is underlined or struck through, from the lines drawn under it or through the middle of it.
+//!
+//! PDF has no underlined or struck through text of its own, so documents draw a thin line across the text instead,
+//! with the same rulings tables are drawn with. A line just below the baseline of some text underlines it, and a line
+//! about half way up its lowercase letters strikes it through. Lines which mostly run past the text they are under,
+//! such as the borders of tables and the rules under headings, don't count, and nor does text only just touched by
+//! the end of a line.
+
+use crate::{tables::Ruling, text::TextChunk, text_state};
+
+/// Underlines are at most this fraction of the height of the text below its baseline.
+const UNDERLINE_DEPTH: f32 = 0.3;
+
+/// Lines through text are between these fractions of the height of the text above its baseline.
+const STRIKE_BOTTOM: f32 = 0.15;
+const STRIKE_TOP: f32 = 0.5;
+
+/// Text has to cover at least this fraction of the length of a line for the line to be underlining or striking it
+/// through.
+const MIN_COVERAGE: f32 = 0.8;
+
+/// A line has to go at least this fraction of the way across a piece of text to mark it.
+const MIN_OVERLAP: f32 = 0.5;
+
+/// Marks up the chunks which lines underline with `<u>` and those they strike through with `<s>`.
+pub fn mark(chunks: &mut [TextChunk], rulings: &[Ruling]) {
+    // Whether each chunk is underlined and whether it is struck through.
+    let mut marks = vec![(false, false); chunks.len()];
+    for ruling in rulings.iter().filter(|ruling| ruling.horizontal) {
+        for strike in [false, true] {
+            let is_near = |chunk: &TextChunk| {
+                let offset = ruling.position - chunk.y;
+                if strike {
+                    offset >= chunk.height * STRIKE_BOTTOM && offset <= chunk.height * STRIKE_TOP
+                } else {
+                    offset <= 0.0 && offset >= -chunk.height * UNDERLINE_DEPTH
+                }
+            };
+            let overlaps: Vec<(usize, f32)> = chunks
+                .iter()
+                .enumerate()
+                .filter(|(_, chunk)| !chunk.vertical && chunk.angle.abs() <= 1.0 && is_near(chunk))
+                .map(|(index, chunk)| {
+                    let overlap = ruling.end.min(chunk.x + chunk.width) - ruling.start.max(chunk.x);
+                    (index, overlap)
+                })
+                .filter(|&(_, overlap)| overlap > 0.0)
+                .collect();
+            let covered: f32 = overlaps.iter().map(|&(_, overlap)| overlap).sum();
+            if covered < (ruling.end - ruling.start) * MIN_COVERAGE {
+                continue;
+            }
+            for (index, overlap) in overlaps {
+                if overlap >= chunks[index].width * MIN_OVERLAP {
+                    if strike {
+                        marks[index].1 = true;
+                    } else {
+                        marks[index].0 = true;
+                    }
+                }
+            }
+        }
+    }
+    for (chunk, (underlined, struck_through)) in chunks.iter_mut().zip(marks) {
+        if struck_through {
+            chunk.text = text_state::wrap(&chunk.text, "s");
+        }
+        if underlined {
+            chunk.text = text_state::wrap(&chunk.text, "u");
+        }
+    }
+}
@@ -0,0 +1,504 @@
+//! Decodes the filters that streams are compressed and encoded with, stopping as soon as the data comes to more than
+//! the most it may, so that a small stream which decompresses into gigabytes is given up on before it gets there.
+//!
+//! Content streams and the other streams text is read from are nearly always deflated, sometimes with a predictor or
+//! encoded as ASCII as well. The filters for images, such as `DCTDecode`, are left out, and so is encryption, which
+//! the document has already had taken off when it was loaded.
+
+use std::{error::Error, fmt::Display, io::Read};
+
+use flate2::read::ZlibDecoder;
+use lopdf::{filters::png, Dictionary, Object, Stream};
+use weezl::{decode::Decoder, BitOrder, LzwStatus};
+
+/// Why a stream couldn't be decoded.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// It comes to more than this many bytes.
+    TooBig(usize),
+    /// It uses a filter for something other than text, or its data doesn't decode with its filters.
+    Undecodable(String),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::TooBig(limit) => {
+                write!(f, "it is more than {} bytes once decompressed", limit)
+            }
+            DecodeError::Undecodable(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// How much output LZW is decoded into at a time.
+const LZW_BUFFER: usize = 64 * 1024;
+
+/// Decodes a stream with each of its filters in turn, none of which may make more than `max_bytes`.
+pub fn decode(stream: &Stream, max_bytes: usize) -> Result<Vec<u8>, DecodeError> {
+    let filters = match stream.dict.get(b"Filter") {
+        Err(_) => Vec::new(),
+        Ok(_) => stream
+            .filters()
+            .map_err(|_| DecodeError::Undecodable("its filters aren't names".to_owned()))?,
+    };
+    let mut data = stream.content.clone();
+    for (index, filter) in filters.iter().enumerate() {
+        let params = decode_params(stream, index);
+        data = match filter.as_str() {
+            "FlateDecode" | "Fl" => {
+                let data = inflate(&data, predicted_limit(params, max_bytes))
+                    .map_err(limited(max_bytes))?;
+                unpredict(data, params, max_bytes)?
+            }
+            "LZWDecode" | "LZW" => {
+                let early_change = params
+                    .and_then(|params| params.get(b"EarlyChange").ok())
+                    .and_then(|early_change| early_change.as_i64().ok())
+                    != Some(0);
+                let data = lzw(&data, early_change, predicted_limit(params, max_bytes))
+                    .map_err(limited(max_bytes))?;
+                unpredict(data, params, max_bytes)?
+            }
+            "ASCIIHexDecode" | "AHx" => ascii_hex(&data)?,
+            "ASCII85Decode" | "A85" => ascii_85(&data)?,
+            "RunLengthDecode" | "RL" => run_length(&data, max_bytes)?,
+            _ => {
+                return Err(DecodeError::Undecodable(format!(
+                    "its {} filter isn't one that text is compressed with",
+                    filter
+                )))
+            }
+        };
+    }
+    if data.len() > max_bytes {
+        return Err(DecodeError::TooBig(max_bytes));
+    }
+    Ok(data)
+}
+
+/// The parameters of the filter at `index`, from `DecodeParms`, which is an array of them when there are several
+/// filters.
+fn decode_params(stream: &Stream, index: usize) -> Option<&Dictionary> {
+    match stream.dict.get(b"DecodeParms").ok()? {
+        Object::Dictionary(params) if index == 0 => Some(params),
+        Object::Array(params) => params.get(index)?.as_dict().ok(),
+        _ => None,
+    }
+}
+
+/// Reports data which is too big before its predictor is undone as being more than `max_bytes`, rather than the
+/// higher limit it was decoded with.
+fn limited(max_bytes: usize) -> impl Fn(DecodeError) -> DecodeError {
+    move |error| match error {
+        DecodeError::TooBig(_) => DecodeError::TooBig(max_bytes),
+        error => error,
+    }
+}
+
+/// How the data of a predictor is laid out, as `(predictor, colours, bits per component, columns)`.
+fn predictor(params: Option<&Dictionary>) -> (i64, usize, usize, usize) {
+    let get = |key: &[u8], default: i64| {
+        params
+            .and_then(|params| params.get(key).ok())
+            .and_then(|value| value.as_i64().ok())
+            .unwrap_or(default)
+    };
+    let positive = |value: i64| usize::try_from(value).unwrap_or(0).max(1);
+    (
+        get(b"Predictor", 1),
+        positive(get(b"Colors", 1)),
+        positive(get(b"BitsPerComponent", 8)),
+        positive(get(b"Columns", 1)),
+    )
+}
+
+/// How many bytes a row of data takes up, without the byte PNG predictors start each row with, if it isn't absurd.
+fn row_length(colours: usize, bits: usize, columns: usize) -> Option<usize> {
+    Some(colours.checked_mul(bits)?.checked_mul(columns)?.div_ceil(8))
+}
+
+/// The most bytes the data before a predictor can have, which for PNG predictors is a byte more than the limit for
+/// each row.
+fn predicted_limit(params: Option<&Dictionary>, max_bytes: usize) -> usize {
+    match predictor(params) {
+        (10..=15, colours, bits, columns) => {
+            let row_length = row_length(colours, bits, columns).unwrap_or(usize::MAX);
+            max_bytes.saturating_add(max_bytes / row_length + 1)
+        }
+        _ => max_bytes,
+    }
+}
+
+/// Undoes the predictor that data was encoded with before it was compressed, if there is one.
+fn unpredict(
+    mut data: Vec<u8>,
+    params: Option<&Dictionary>,
+    max_bytes: usize,
+) -> Result<Vec<u8>, DecodeError> {
+    let (predictor, colours, bits, columns) = predictor(params);
+    let row_length = row_length(colours, bits, columns)
+        .filter(|&row_length| row_length <= data.len().max(1))
+        .ok_or_else(|| {
+            DecodeError::Undecodable("its predictor's rows are longer than its data".to_owned())
+        })?;
+    match predictor {
+        1 => {}
+        // Horizontal differencing, in which each byte is the difference from the byte of the pixel before.
+        2 if bits == 8 => {
+            for row in data.chunks_mut(row_length) {
+                for index in colours.min(row.len())..row.len() {
+                    row[index] = row[index].wrapping_add(row[index - colours]);
+                }
+            }
+        }
+        10..=15 => {
+            let bytes_per_pixel = (colours * bits).div_ceil(8);
+            data = png::decode_frame(&data, bytes_per_pixel, row_length / bytes_per_pixel)
+                .map_err(|error| {
+                    DecodeError::Undecodable(format!("its predicted data is corrupt: {}", error))
+                })?;
+        }
+        _ => {
+            return Err(DecodeError::Undecodable(format!(
+                "its predictor {} isn't one that text is compressed with",
+                predictor
+            )))
+        }
+    }
+    if data.len() > max_bytes {
+        return Err(DecodeError::TooBig(max_bytes));
+    }
+    Ok(data)
+}
+
+fn inflate(data: &[u8], max_bytes: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut inflated = Vec::new();
+    let result = ZlibDecoder::new(data)
+        .take(max_bytes as u64 + 1)
+        .read_to_end(&mut inflated);
+    if inflated.len() > max_bytes {
+        return Err(DecodeError::TooBig(max_bytes));
+    }
+    // Truncated streams are common, and whatever could be inflated is more useful than nothing.
+    match result {
+        Err(error) if inflated.is_empty() => Err(DecodeError::Undecodable(format!(
+            "its deflated data is corrupt: {}",
+            error
+        ))),
+        _ => Ok(inflated),
+    }
+}
+
+fn lzw(data: &[u8], early_change: bool, max_bytes: usize) -> Result<Vec<u8>, DecodeError> {
+    // Codes start 9 bits long, one more than the 8 bits of the bytes they stand for.
+    let mut decoder = if early_change {
+        Decoder::with_tiff_size_switch(BitOrder::Msb, 8)
+    } else {
+        Decoder::new(BitOrder::Msb, 8)
+    };
+    let mut decoded = Vec::new();
+    let mut buffer = vec![0; LZW_BUFFER];
+    let mut input = data;
+    loop {
+        let result = decoder.decode_bytes(input, &mut buffer);
+        input = &input[result.consumed_in..];
+        decoded.extend_from_slice(&buffer[..result.consumed_out]);
+        if decoded.len() > max_bytes {
+            return Err(DecodeError::TooBig(max_bytes));
+        }
+        match result.status {
+            Ok(LzwStatus::Ok) => {}
+            Ok(LzwStatus::Done | LzwStatus::NoProgress) => break,
+            // As with deflate, whatever was decoded before the data went wrong is kept.
+            Err(error) if decoded.is_empty() => {
+                return Err(DecodeError::Undecodable(format!(
+                    "its LZW data is corrupt: {}",
+                    error
+                )))
+            }
+            Err(_) => break,
+        }
+    }
+    Ok(decoded)
+}
+
+/// Decodes pairs of hexadecimal digits, up to the `>` they end with. The output is never longer than the input.
+fn ascii_hex(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut digits = Vec::with_capacity(data.len());
+    for &byte in data {
+        match byte {
+            b'>' => break,
+            _ if byte.is_ascii_whitespace() || byte == 0 => {}
+            _ => digits.push(char::from(byte).to_digit(16).ok_or_else(|| {
+                DecodeError::Undecodable(format!(
+                    "its hexadecimal data has {:?} in it",
+                    char::from(byte)
+                ))
+            })? as u8),
+        }
+    }
+    // A missing final digit is taken to be zero.
+    Ok(digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect())
+}
+
+/// Decodes groups of five base 85 digits into four bytes each, up to the `~>` they end with. The output is never
+/// longer than the input.
+fn ascii_85(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let data = data.strip_prefix(b"<~").unwrap_or(data);
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut group = Vec::with_capacity(5);
+    let invalid = |byte: u8| {
+        DecodeError::Undecodable(format!("its base 85 data has {:?} in it", char::from(byte)))
+    };
+    for &byte in data {
+        match byte {
+            b'~' => break,
+            _ if byte.is_ascii_whitespace() || byte == 0 => {}
+            // A whole group of zeros is written as `z`.
+            b'z' if group.is_empty() => decoded.extend([0; 4]),
+            b'!'..=b'u' => {
+                group.push(u32::from(byte - b'!'));
+                if group.len() == 5 {
+                    let value = group
+                        .iter()
+                        .try_fold(0u32, |value, &digit| {
+                            value.checked_mul(85)?.checked_add(digit)
+                        })
+                        .ok_or_else(|| invalid(byte))?;
+                    decoded.extend(value.to_be_bytes());
+                    group.clear();
+                }
+            }
+            _ => return Err(invalid(byte)),
+        }
+    }
+    // The last group can be short, in which case it is padded with the highest digit and as many bytes taken off.
+    if group.len() > 1 {
+        let length = group.len() - 1;
+        group.resize(5, 84);
+        let value = group
+            .iter()
+            .try_fold(0u32, |value, &digit| {
+                value.checked_mul(85)?.checked_add(digit)
+            })
+            .ok_or_else(|| invalid(b'u'))?;
+        decoded.extend(&value.to_be_bytes()[..length]);
+    }
+    Ok(decoded)
+}
+
+/// Decodes runs of bytes, each of which is either copied as it is or a single byte repeated.
+fn run_length(data: &[u8], max_bytes: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut decoded = Vec::new();
+    let mut position = 0;
+    while let Some(&length) = data.get(position) {
+        match length {
+            // The end of the data.
+            128 => break,
+            0..=127 => {
+                let run = data
+                    .get(position + 1..position + 2 + length as usize)
+                    .unwrap_or(&data[position + 1..]);
+                decoded.extend_from_slice(run);
+                position += 2 + length as usize;
+            }
+            _ => {
+                let Some(&byte) = data.get(position + 1) else {
+                    break;
+                };
+                decoded.extend(std::iter::repeat_n(byte, 257 - length as usize));
+                position += 2;
+            }
+        }
+        if decoded.len() > max_bytes {
+            return Err(DecodeError::TooBig(max_bytes));
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+    use lopdf::{dictionary, Object};
+
+    use super::*;
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn stream(filters: &[&str], params: Option<Object>, content: Vec<u8>) -> Stream {
+        let filters = filters
+            .iter()
+            .map(|&filter| Object::from(filter))
+            .collect::<Vec<_>>();
+        let mut dict = dictionary! { "Filter" => filters };
+        if let Some(params) = params {
+            dict.set("DecodeParms", params);
+        }
+        Stream::new(dict, content)
+    }
+
+    #[test]
+    fn reads_streams_without_filters_as_they_are() {
+        let stream = Stream::new(Dictionary::new(), b"BT ET".to_vec());
+        assert_eq!(decode(&stream, 100).unwrap(), b"BT ET");
+        assert!(matches!(decode(&stream, 4), Err(DecodeError::TooBig(4))));
+    }
+
+    #[test]
+    fn stops_inflating_at_the_limit() {
+        let stream = stream(&["FlateDecode"], None, deflate(&vec![b' '; 1 << 20]));
+        assert_eq!(decode(&stream, 1 << 20).unwrap().len(), 1 << 20);
+        assert!(matches!(
+            decode(&stream, 1000),
+            Err(DecodeError::TooBig(1000))
+        ));
+    }
+
+    #[test]
+    fn refuses_corrupt_deflate_rather_than_returning_it() {
+        let stream = stream(&["FlateDecode"], None, b"not deflated at all".to_vec());
+        assert!(matches!(
+            decode(&stream, 1000),
+            Err(DecodeError::Undecodable(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_what_truncated_deflate_gives() {
+        let mut data = deflate(b"BT (Hello) Tj ET");
+        data.truncate(data.len() - 4);
+        let stream = stream(&["FlateDecode"], None, data);
+        assert_eq!(decode(&stream, 1000).unwrap(), b"BT (Hello) Tj ET");
+    }
+
+    #[test]
+    fn undoes_png_predictors() {
+        // Two rows of three bytes, the first with no filter and the second each the byte above plus one.
+        let data = deflate(&[0, 1, 2, 3, 2, 1, 1, 1]);
+        let params = dictionary! { "Predictor" => 12, "Columns" => 3 };
+        let stream = stream(&["FlateDecode"], Some(params.into()), data);
+        assert_eq!(decode(&stream, 6).unwrap(), [1, 2, 3, 2, 3, 4]);
+        assert!(matches!(decode(&stream, 5), Err(DecodeError::TooBig(5))));
+    }
+
+    #[test]
+    fn stops_bombs_behind_predictors() {
+        let rows = [0u8; 101].repeat(100_000);
+        let params = dictionary! { "Predictor" => 12, "Columns" => 100 };
+        let stream = stream(&["FlateDecode"], Some(params.into()), deflate(&rows));
+        assert!(matches!(
+            decode(&stream, 10_000),
+            Err(DecodeError::TooBig(10_000))
+        ));
+    }
+
+    #[test]
+    fn undoes_tiff_predictors() {
+        let params = dictionary! { "Predictor" => 2, "Colors" => 1, "Columns" => 3 };
+        let stream = stream(
+            &["FlateDecode"],
+            Some(params.into()),
+            deflate(&[5, 1, 1, 7, 0, 2]),
+        );
+        assert_eq!(decode(&stream, 100).unwrap(), [5, 6, 7, 7, 7, 9]);
+    }
+
+    #[test]
+    fn stops_bombs_behind_ascii_encoding() {
+        let hex = deflate(&vec![0; 1 << 20])
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<String>()
+            + ">";
+        let stream = stream(&["ASCIIHexDecode", "FlateDecode"], None, hex.into_bytes());
+        assert!(matches!(
+            decode(&stream, 1000),
+            Err(DecodeError::TooBig(1000))
+        ));
+        assert_eq!(decode(&stream, 1 << 20).unwrap().len(), 1 << 20);
+    }
+
+    #[test]
+    fn reads_parameters_for_each_filter() {
+        let hex = deflate(&[0, 1, 2, 3, 2, 1, 1, 1])
+            .iter()
+            .map(|byte| format!("{:02x} ", byte))
+            .collect::<String>();
+        let params = vec![
+            Object::Null,
+            dictionary! { "Predictor" => 12, "Columns" => 3 }.into(),
+        ];
+        let stream = stream(&["AHx", "Fl"], Some(params.into()), hex.into_bytes());
+        assert_eq!(decode(&stream, 100).unwrap(), [1, 2, 3, 2, 3, 4]);
+    }
+
+    #[test]
+    fn decodes_ascii_hex() {
+        assert_eq!(ascii_hex(b"48 65\n6c6C 6f7>ignored").unwrap(), b"Hello\x70");
+        assert!(ascii_hex(b"4G>").is_err());
+    }
+
+    #[test]
+    fn decodes_ascii_85() {
+        assert_eq!(ascii_85(b"<~87cURD_*#QAU&;?~>").unwrap(), b"Hello, text!");
+        assert_eq!(ascii_85(b"z87cURDZ~>").unwrap(), b"\0\0\0\0Hello");
+        assert!(ascii_85(b"87c{URD~>").is_err());
+        assert!(ascii_85(b"uuuuu~>").is_err());
+    }
+
+    #[test]
+    fn decodes_run_length() {
+        assert_eq!(
+            run_length(b"\x02abc\xfdx\x80ignored", 100).unwrap(),
+            b"abcxxxx"
+        );
+        // A run of 128 copies of a byte, from only two bytes of data.
+        let bomb = b"\x81\0".repeat(1000);
+        assert!(matches!(
+            run_length(&bomb, 1000),
+            Err(DecodeError::TooBig(1000))
+        ));
+    }
+
+    #[test]
+    fn decodes_lzw() {
+        let text = b"BT (Hello Hello Hello Hello) Tj ET".repeat(100);
+        for early_change in [true, false] {
+            let mut encoder = if early_change {
+                weezl::encode::Encoder::with_tiff_size_switch(BitOrder::Msb, 8)
+            } else {
+                weezl::encode::Encoder::new(BitOrder::Msb, 8)
+            };
+            let data = encoder.encode(&text).unwrap();
+            let params = dictionary! { "EarlyChange" => i64::from(early_change) };
+            let stream = stream(&["LZWDecode"], Some(params.into()), data);
+            assert_eq!(decode(&stream, text.len()).unwrap(), text);
+            assert!(matches!(
+                decode(&stream, 100),
+                Err(DecodeError::TooBig(100))
+            ));
+        }
+    }
+
+    #[test]
+    fn refuses_filters_for_images() {
+        let stream = stream(&["DCTDecode"], None, vec![0xff, 0xd8]);
+        assert!(matches!(
+            decode(&stream, 1000),
+            Err(DecodeError::Undecodable(_))
+        ));
+    }
+}
@@ -0,0 +1,1332 @@
+use std::{
+    borrow::Cow, collections::BTreeMap, error::Error, num::NonZeroUsize, path::PathBuf, rc::Rc,
+};
+
+use lopdf::{content::Content, Dictionary, Document, Object, ObjectId, Stream};
+use lru::LruCache;
+
+use crate::{
+    cmap::{self, CMapLibrary, CidMap},
+    fontprog, glyph_names, standard_fonts, truetype,
+};
+
+#[derive(Debug)]
+pub struct Font {
+    /// The font's `BaseFont`, for telling the user which font something is about.
+    pub name: String,
+    encoding: Encoding,
+    /// The text of the codes a simple font's `Differences` gives other glyphs to than its base encoding does, or which
+    /// its embedded TrueType font program says are other characters.
+    differences: BTreeMap<u32, String>,
+    unicode_map: Option<UnicodeTable>,
+    /// The text of each CID of a composite font without a `ToUnicode` map, from its character collection, if the
+    /// collection's CMap could be found.
+    cid_text: Option<Rc<UnicodeTable>>,
+    /// Whether this is a `Type0` font, whose codes can be more than one byte long. The codes of simple fonts are
+    /// always single bytes, whatever their `ToUnicode` map says.
+    composite: bool,
+    /// The byte sequences which are codes, from the code space of the font's CMap or else of its `ToUnicode` map,
+    /// which say how many bytes each code in a string of a composite font takes up.
+    code_ranges: Vec<CodeRange>,
+    /// The widths of the glyphs, as runs of codes with the same width, `(first code, last code, width)`, sorted by
+    /// code. The codes of composite fonts are their CIDs.
+    widths: Vec<(u32, u32, f32)>,
+    /// The width of glyphs which aren't in `widths`.
+    default_width: f32,
+    /// How far down each glyph moves the text of a font whose CMap writes downwards, in thousandths of the font size,
+    /// from the `DW2` of its descendant font.
+    vertical_advance: f32,
+    /// How far above and below the baseline the glyphs go, in thousandths of the font size, from the `Ascent` and
+    /// `Descent` of its font descriptor.
+    ascent: f32,
+    descent: f32,
+    /// The family the font belongs to, such as `Helvetica` for `Helvetica-Bold`, from its font descriptor or else its
+    /// name, if it has one.
+    pub family: Option<String>,
+    /// Whether the font is bold and whether it is italic, from its font descriptor or else its name.
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// How far some text moves the next text along, before the text state's spacing and scaling are applied.
+pub struct Measurement {
+    /// The total width of the glyphs, in thousandths of the font size.
+    pub width: f32,
+    /// How many codes there are, which character spacing is added after each of.
+    pub codes: usize,
+    /// How many of the codes are the single byte code 32, which word spacing is added after as well.
+    pub spaces: usize,
+    /// How far down the glyphs move the text if the font writes downwards, in thousandths of the font size.
+    pub height: f32,
+}
+
+impl Font {
+    pub fn load(
+        document: &Document,
+        font_data: &Dictionary,
+        cmaps: &mut CMapLibrary,
+    ) -> Result<Self, Box<dyn Error>> {
+        let composite = is_composite(font_data);
+        let (unicode_map, mut code_ranges) = if let Ok(Object::Reference(unicode_map_id)) =
+            font_data.get(b"ToUnicode")
+        {
+            let unicode_map = parse_unicode_map(document.get_object(*unicode_map_id)?.as_stream()?);
+            (
+                Some(UnicodeTable::new(&unicode_map.characters)),
+                unicode_map.code_ranges,
+            )
+        } else {
+            (None, Vec::new())
+        };
+        if !composite {
+            code_ranges.clear();
+        }
+        let (widths, default_width) = load_widths(document, font_data);
+        let (ascent, descent) = load_extent(document, font_data);
+        let name = font_data
+            .get(b"BaseFont")
+            .and_then(Object::as_name)
+            .map_or_else(
+                |_| "an unnamed font".to_owned(),
+                |name| String::from_utf8_lossy(name).into_owned(),
+            );
+        let descriptor = font_descriptor(document, font_data);
+        let (bold, italic) = load_style(document, descriptor, &name);
+        let family = load_family(document, font_data, descriptor);
+        let (encoding, mut differences, cid_text) = if composite {
+            let (encoding, cid_text) = load_cmap(document, font_data, cmaps, unicode_map.is_none());
+            (encoding, BTreeMap::new(), cid_text)
+        } else {
+            let (encoding, differences) = load_encoding(document, font_data, &name);
+            (encoding, differences, None)
+        };
+        if let Encoding::CMap {
+            cids: Some(cids), ..
+        } = &encoding
+        {
+            if !cids.code_ranges().is_empty() {
+                code_ranges = cids.code_ranges().to_vec();
+            }
+        }
+        if !composite && unicode_map.is_none() {
+            for (code, character) in embedded_truetype_characters(document, font_data) {
+                differences
+                    .entry(code)
+                    .or_insert_with(|| character.to_string());
+            }
+        }
+        Ok(Font {
+            name,
+            encoding,
+            differences,
+            unicode_map,
+            cid_text,
+            composite,
+            code_ranges,
+            widths,
+            default_width,
+            vertical_advance: load_vertical_advance(document, font_data),
+            ascent,
+            descent,
+            family,
+            bold,
+            italic,
+        })
+    }
+
+    /// Whether the font's CMap writes its text in columns from top to bottom, as `Identity-V` does.
+    pub fn is_vertical(&self) -> bool {
+        matches!(self.encoding, Encoding::CMap { vertical: true, .. })
+    }
+
+    /// How wide a space is in the font, in thousandths of the font size, or how wide one usually is if the font
+    /// doesn't have one.
+    pub fn space_width(&self) -> f32 {
+        match self.measure(b" ").width {
+            width if width > 0.0 => width,
+            _ => SPACE_WIDTH,
+        }
+    }
+
+    /// How tall the glyphs are from the lowest descender to the highest ascender, in thousandths of the font size.
+    pub fn height(&self) -> f32 {
+        self.ascent - self.descent
+    }
+
+    /// Roughly how many bytes the font takes up in memory.
+    pub fn approximate_size(&self) -> usize {
+        let unicode_map_size = self
+            .unicode_map
+            .as_ref()
+            .map_or(0, UnicodeTable::approximate_size);
+        std::mem::size_of::<Self>()
+            + self.name.len()
+            + self.family.as_ref().map_or(0, String::len)
+            + match &self.encoding {
+                Encoding::Named(name) => name.len(),
+                // CMaps are shared between fonts, so the library counts them instead.
+                Encoding::BuiltIn(_) | Encoding::CMap { .. } => 0,
+            }
+            + self
+                .differences
+                .values()
+                .map(|text| std::mem::size_of::<(u32, String)>() + text.len())
+                .sum::<usize>()
+            + unicode_map_size
+            + self.widths.capacity() * std::mem::size_of::<(u32, u32, f32)>()
+            + self.code_ranges.capacity() * std::mem::size_of::<CodeRange>()
+    }
+
+    /// Splits a string into its codes.
+    fn codes<'a>(&'a self, mut text: &'a [u8]) -> impl Iterator<Item = (u32, usize)> + 'a {
+        std::iter::from_fn(move || {
+            let length = self.code_length(text);
+            if length == 0 || text.len() < length {
+                return None;
+            }
+            let (code, rest) = text.split_at(length);
+            text = rest;
+            Some((code_from_bytes(code), length))
+        })
+    }
+
+    /// How many bytes the code at the start of a string takes up.
+    fn code_length(&self, text: &[u8]) -> usize {
+        if !self.composite {
+            return 1;
+        }
+        // Composite fonts without a code space are nearly always `Identity-H`, with two bytes to a code.
+        if self.code_ranges.is_empty() {
+            return 2;
+        }
+        let matching = self
+            .code_ranges
+            .iter()
+            .filter(|range| range.contains(text))
+            .map(|range| range.length)
+            .min();
+        // Bytes which aren't in the code space are taken as a code of the shortest length there is, as the
+        // specification says.
+        matching.unwrap_or_else(|| {
+            self.code_ranges
+                .iter()
+                .map(|range| range.length)
+                .min()
+                .unwrap_or(1)
+        })
+    }
+
+    /// Measures some text shown in the font, splitting it into codes the same way as [`Font::decode`].
+    pub fn measure(&self, text: &[u8]) -> Measurement {
+        let mut measurement = Measurement {
+            width: 0.0,
+            codes: 0,
+            spaces: 0,
+            height: 0.0,
+        };
+        for (code, length) in self.codes(text) {
+            measurement.width += self.width(self.cid(code).unwrap_or(code));
+            measurement.codes += 1;
+            measurement.height += self.vertical_advance;
+            if length == 1 && code == 32 {
+                measurement.spaces += 1;
+            }
+        }
+        measurement
+    }
+
+    /// The CID of a code of a composite font, if its CMap could be found.
+    fn cid(&self, code: u32) -> Option<u32> {
+        match &self.encoding {
+            Encoding::CMap {
+                cids: Some(cids), ..
+            } => cids.cid(code),
+            _ => None,
+        }
+    }
+
+    fn width(&self, code: u32) -> f32 {
+        let index = self
+            .widths
+            .partition_point(|&(_, last_code, _)| last_code < code);
+        self.widths
+            .get(index)
+            .filter(|&&(first_code, _, _)| first_code <= code)
+            .map_or(self.default_width, |&(_, _, width)| width)
+    }
+
+    pub fn decode(&self, text: &[u8]) -> String {
+        match (
+            &self.unicode_map,
+            self.differences.is_empty(),
+            &self.encoding,
+        ) {
+            (None, true, Encoding::Named(encoding)) => {
+                return Document::decode_text(Some(encoding), text)
+            }
+            (
+                None,
+                _,
+                Encoding::CMap {
+                    utf16_codes: true, ..
+                },
+            ) => return decode_utf16(text),
+            _ => {}
+        }
+        let mut result = String::new();
+        for (code, _) in self.codes(text) {
+            match self
+                .unicode_map
+                .as_ref()
+                .and_then(|unicode_map| unicode_map.get(code))
+            {
+                Some(Mapping::Character(character)) => result.push(character),
+                Some(Mapping::Text(text)) => result.push_str(text),
+                // Simple fonts often only map the codes their encoding gets wrong, and leave the rest to it.
+                None if !self.composite => match (self.differences.get(&code), &self.encoding) {
+                    (Some(text), _) => result.push_str(text),
+                    (None, Encoding::Named(encoding)) => {
+                        result.push_str(&Document::decode_text(Some(encoding), &[code as u8]))
+                    }
+                    (None, Encoding::BuiltIn(table)) => {
+                        if let Ok(index) =
+                            table.binary_search_by_key(&(code as u8), |&(code, _)| code)
+                        {
+                            result.push(table[index].1);
+                        }
+                    }
+                    (None, Encoding::CMap { .. }) => {}
+                },
+                // Without a `ToUnicode` map, the text comes from the CIDs the codes are, if they can be worked out.
+                None if self.unicode_map.is_none() => {
+                    let cid_text = self
+                        .cid_text
+                        .as_ref()
+                        .and_then(|cid_text| cid_text.get(self.cid(code)?));
+                    match cid_text {
+                        Some(Mapping::Character(character)) => result.push(character),
+                        Some(Mapping::Text(text)) => result.push_str(text),
+                        None => result.push(char::REPLACEMENT_CHARACTER),
+                    }
+                }
+                // Codes which aren't in the map are usually Unicode already, in fonts made by simple generators.
+                None => result.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)),
+            }
+        }
+        result
+    }
+}
+
+/// A range of codes from a CMap's code space, such as `<8140> <9FFC>`, in which each byte of a code has to be between
+/// the bytes at the same place in the two ends.
+#[derive(Clone, Debug)]
+pub struct CodeRange {
+    low: [u8; 4],
+    high: [u8; 4],
+    /// How many bytes the codes take up, from 1 to 4.
+    length: usize,
+}
+
+impl CodeRange {
+    /// Whether a string starts with a code in the range.
+    fn contains(&self, text: &[u8]) -> bool {
+        text.len() >= self.length
+            && (0..self.length)
+                .all(|index| (self.low[index]..=self.high[index]).contains(&text[index]))
+    }
+}
+
+/// Turns the bytes of a code into a number, the first byte being the most significant.
+fn code_from_bytes(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |code, &byte| code << 8 | u32::from(byte))
+}
+
+/// The bit of a font descriptor's `Flags` which says that the font has glyphs outside of the standard Latin set, and
+/// so doesn't use the standard encodings.
+const SYMBOLIC_FLAG: i64 = 1 << 2;
+
+/// The bits of a font descriptor's `Flags` which say that the font is italic, and that its glyphs are drawn bold
+/// even at small sizes, which only bold fonts set.
+const ITALIC_FLAG: i64 = 1 << 6;
+const FORCE_BOLD_FLAG: i64 = 1 << 18;
+
+/// Fonts whose descriptor gives them at least this weight are bold, 400 being normal.
+const BOLD_WEIGHT: f32 = 600.0;
+
+/// Most glyphs in the standard fonts, which needn't say how wide their glyphs are, are about this wide.
+const STANDARD_FONT_WIDTH: f32 = 500.0;
+
+/// Composite fonts which don't say how wide their glyphs are default to this, which is square.
+const COMPOSITE_FONT_WIDTH: f32 = 1000.0;
+
+/// Spaces in most fonts are about this wide.
+const SPACE_WIDTH: f32 = 250.0;
+
+/// Fonts without a font descriptor to say how tall they are, such as the standard ones, go about this far above the
+/// baseline and this far below it.
+const ASCENT: f32 = 750.0;
+const DESCENT: f32 = -250.0;
+
+/// Vertical glyphs which don't say how far they move the text move it down by this, the height of a square glyph.
+const VERTICAL_ADVANCE: f32 = 1000.0;
+
+fn is_composite(font_data: &Dictionary) -> bool {
+    matches!(
+        font_data.get(b"Subtype").and_then(Object::as_name),
+        Ok(b"Type0")
+    )
+}
+
+/// Whether this is a `Type3` font, whose glyphs are drawn by content streams of its own rather than by a font program.
+fn is_type3(font_data: &Dictionary) -> bool {
+    matches!(
+        font_data.get(b"Subtype").and_then(Object::as_name),
+        Ok(b"Type3")
+    )
+}
+
+/// How many thousandths of the font size each unit of a `Type3` font's glyph space is, from the first number of its
+/// `FontMatrix`, which is what its widths are measured in. Other fonts always measure their widths in thousandths.
+fn glyph_scale(document: &Document, font_data: &Dictionary) -> f32 {
+    if !is_type3(font_data) {
+        return 1.0;
+    }
+    get(document, font_data, b"FontMatrix")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|matrix| matrix.first()?.as_float().ok())
+        .map_or(1.0, |scale| scale * 1000.0)
+}
+
+/// How the single byte codes of a simple font are turned into text, when its `ToUnicode` map doesn't say.
+#[derive(Debug)]
+enum Encoding {
+    /// One of the encodings named in the specification, which lopdf decodes.
+    Named(String),
+    /// The font's own encoding, from the table of a standard font that has one, sorted by code.
+    BuiltIn(&'static [(u8, char)]),
+    /// The CMap of a composite font, which says which CID each code is, if it could be found.
+    CMap {
+        cids: Option<Rc<CidMap>>,
+        /// Whether the codes are UTF-16 text, as they are in the `Uni` CMaps.
+        utf16_codes: bool,
+        /// Whether the CMap's writing mode is vertical, as in the `-V` CMaps.
+        vertical: bool,
+    },
+}
+
+/// The encoding of a simple font with no usable encoding of its own, which is the built-in one of the standard fonts
+/// that have one and otherwise the standard encoding.
+fn default_encoding(name: &str) -> Encoding {
+    standard_fonts::built_in_encoding(name).map_or_else(
+        || Encoding::Named("StandardEncoding".to_owned()),
+        Encoding::BuiltIn,
+    )
+}
+
+/// Turns an encoding's name into an [`Encoding`], if it is one of the ones in the specification.
+fn named_encoding(name: &[u8]) -> Option<Encoding> {
+    match name {
+        b"StandardEncoding" | b"MacRomanEncoding" | b"MacExpertEncoding" | b"WinAnsiEncoding" => {
+            Some(Encoding::Named(String::from_utf8_lossy(name).into_owned()))
+        }
+        _ => None,
+    }
+}
+
+/// Reads which encoding a simple font uses, along with the text of the glyphs its `Differences` puts in place of the
+/// encoding's, if the encoding is a dictionary. Glyphs with names that don't say what text they are are left to the
+/// base encoding.
+fn load_encoding(
+    document: &Document,
+    font_data: &Dictionary,
+    name: &str,
+) -> (Encoding, BTreeMap<u32, String>) {
+    let mut differences = BTreeMap::new();
+    let encoding = match get(document, font_data, b"Encoding") {
+        Ok(Object::Name(encoding)) => {
+            let encoding = named_encoding(encoding)
+                .unwrap_or_else(|| built_in_encoding(document, font_data, name, &mut differences));
+            return (encoding, differences);
+        }
+        Ok(Object::Dictionary(encoding)) => encoding,
+        _ => {
+            let encoding = built_in_encoding(document, font_data, name, &mut differences);
+            return (encoding, differences);
+        }
+    };
+    // The glyphs of Type 3 fonts are often named only by a number, which is usually their character.
+    let type3 = is_type3(font_data);
+    // The array is made up of codes, each followed by the names of the glyphs for it and the codes after it.
+    let mut code = 0;
+    for entry in get(document, encoding, b"Differences")
+        .and_then(Object::as_array)
+        .into_iter()
+        .flatten()
+    {
+        match entry {
+            Object::Integer(first_code) => code = *first_code as u32,
+            Object::Name(name) => {
+                let name = String::from_utf8_lossy(name);
+                let text = glyph_names::glyph_text(&name).or_else(|| {
+                    type3
+                        .then(|| glyph_names::numbered_glyph_text(&name))
+                        .flatten()
+                });
+                if let Some(text) = text {
+                    differences.insert(code, text);
+                }
+                code += 1;
+            }
+            _ => {}
+        }
+    }
+    let base_encoding = get(document, encoding, b"BaseEncoding")
+        .and_then(Object::as_name)
+        .ok()
+        .and_then(named_encoding)
+        .unwrap_or_else(|| built_in_encoding(document, font_data, name, &mut differences));
+    (base_encoding, differences)
+}
+
+/// The encoding of a simple font with no usable encoding in the document, which is the one built into its embedded
+/// font program if it has one. The codes the program gives glyphs to are added to `differences`, unless they are
+/// already there, and the rest go through the font's default encoding.
+fn built_in_encoding(
+    document: &Document,
+    font_data: &Dictionary,
+    name: &str,
+    differences: &mut BTreeMap<u32, String>,
+) -> Encoding {
+    for (code, text) in embedded_program_encoding(document, font_data) {
+        differences.entry(code).or_insert(text);
+    }
+    default_encoding(name)
+}
+
+/// The characters of the codes of a symbolic simple font that has no `ToUnicode` map, from the `cmap` table of its
+/// embedded TrueType font program if it has one. The codes of other fonts go through their encoding, which is left to
+/// say what they are.
+fn embedded_truetype_characters(
+    document: &Document,
+    font_data: &Dictionary,
+) -> BTreeMap<u32, char> {
+    let Ok(descriptor) = get(document, font_data, b"FontDescriptor").and_then(Object::as_dict)
+    else {
+        return BTreeMap::new();
+    };
+    let flags = get(document, descriptor, b"Flags")
+        .and_then(Object::as_i64)
+        .unwrap_or(0);
+    let program = get(document, descriptor, b"FontFile2").and_then(Object::as_stream);
+    let (true, Ok(program)) = (flags & SYMBOLIC_FLAG != 0, program) else {
+        return BTreeMap::new();
+    };
+    truetype::unicode_by_code(&stream_content(program))
+}
+
+/// The text of the codes which the built-in encoding of a simple font's embedded Type 1 or CFF font program gives
+/// glyphs to.
+fn embedded_program_encoding(document: &Document, font_data: &Dictionary) -> BTreeMap<u32, String> {
+    let Ok(descriptor) = get(document, font_data, b"FontDescriptor").and_then(Object::as_dict)
+    else {
+        return BTreeMap::new();
+    };
+    let program = |key| {
+        get(document, descriptor, key)
+            .and_then(Object::as_stream)
+            .ok()
+    };
+    if let Some(program) = program(b"FontFile") {
+        return fontprog::type1_encoding(&stream_content(program));
+    }
+    match program(b"FontFile3") {
+        Some(program)
+            if matches!(
+                program.dict.get(b"Subtype").and_then(Object::as_name),
+                Ok(b"Type1C")
+            ) =>
+        {
+            fontprog::cff_encoding(&stream_content(program))
+        }
+        _ => BTreeMap::new(),
+    }
+}
+
+/// The content of a stream, which has to be inflated here if it is compressed, since the document isn't decompressed
+/// upfront.
+fn stream_content(stream: &Stream) -> Cow<'_, [u8]> {
+    match stream.decompressed_content() {
+        Ok(content) => Cow::Owned(content),
+        Err(_) => Cow::Borrowed(&stream.content),
+    }
+}
+
+/// Reads which CMap a composite font uses, and, if the font has no `ToUnicode` map and the codes of the CMap aren't
+/// text already, the text of the CIDs in its character collection.
+fn load_cmap(
+    document: &Document,
+    font_data: &Dictionary,
+    cmaps: &mut CMapLibrary,
+    needs_text: bool,
+) -> (Encoding, Option<Rc<UnicodeTable>>) {
+    let (cids, utf16_codes, vertical) = match get(document, font_data, b"Encoding") {
+        Ok(Object::Name(name)) => {
+            let name = String::from_utf8_lossy(name);
+            (
+                cmaps.cid_map(&name),
+                cmap::has_utf16_codes(&name),
+                name.ends_with("-V"),
+            )
+        }
+        Ok(Object::Stream(stream)) => {
+            let mut cmap = parse_cmap(&stream_content(stream));
+            if cmap.parent.is_none() {
+                cmap.parent = stream
+                    .dict
+                    .get(b"UseCMap")
+                    .and_then(Object::as_name)
+                    .ok()
+                    .map(|name| String::from_utf8_lossy(name).into_owned());
+            }
+            let vertical = cmap.vertical
+                || matches!(stream.dict.get(b"WMode").and_then(Object::as_i64), Ok(1));
+            (Some(cmaps.embedded_cid_map(cmap)), false, vertical)
+        }
+        _ => (None, false, false),
+    };
+    let cid_text = if needs_text && !utf16_codes {
+        character_collection(document, font_data)
+            .and_then(|collection| cmaps.collection_text(&collection))
+    } else {
+        None
+    };
+    (
+        Encoding::CMap {
+            cids,
+            utf16_codes,
+            vertical,
+        },
+        cid_text,
+    )
+}
+
+/// The character collection of a composite font's CIDs, such as `Adobe-Japan1`, unless they are only the font's own.
+fn character_collection(document: &Document, font_data: &Dictionary) -> Option<String> {
+    let system_info = get(
+        document,
+        descendant_font(document, font_data)?,
+        b"CIDSystemInfo",
+    )
+    .and_then(Object::as_dict)
+    .ok()?;
+    let registry = get(document, system_info, b"Registry")
+        .and_then(Object::as_str)
+        .ok()?;
+    let ordering = get(document, system_info, b"Ordering")
+        .and_then(Object::as_str)
+        .ok()?;
+    if ordering == b"Identity" {
+        return None;
+    }
+    Some(format!(
+        "{}-{}",
+        String::from_utf8_lossy(registry),
+        String::from_utf8_lossy(ordering)
+    ))
+}
+
+/// The CIDFont a composite font draws its glyphs from.
+fn descendant_font<'a>(
+    document: &'a Document,
+    font_data: &'a Dictionary,
+) -> Option<&'a Dictionary> {
+    get(document, font_data, b"DescendantFonts")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|fonts| fonts.first())
+        .and_then(|font| document.dereference(font).ok())
+        .and_then(|(_, font)| font.as_dict().ok())
+}
+
+/// Reads how far down each glyph of a composite font moves vertical text, which is the second number of `DW2` in its
+/// descendant font. The specification's default is used for the rest, and for simple fonts, which can't be vertical.
+fn load_vertical_advance(document: &Document, font_data: &Dictionary) -> f32 {
+    descendant_font(document, font_data)
+        .and_then(|descendant| {
+            get(document, descendant, b"DW2")
+                .and_then(Object::as_array)
+                .ok()
+        })
+        .and_then(|metrics| metrics.get(1)?.as_float().ok())
+        .map_or(VERTICAL_ADVANCE, |advance| -advance)
+}
+
+/// Reads how far above and below the baseline a font's glyphs go, from the font descriptor of a simple font or of the
+/// descendant font of a composite one. Descriptors which leave them out, or give nonsense, get the usual extent.
+fn load_extent(document: &Document, font_data: &Dictionary) -> (f32, f32) {
+    let descriptor = if is_composite(font_data) {
+        descendant_font(document, font_data)
+    } else {
+        Some(font_data)
+    }
+    .and_then(|font| get(document, font, b"FontDescriptor").ok())
+    .and_then(|descriptor| descriptor.as_dict().ok());
+    let metric = |key| {
+        descriptor
+            .and_then(|descriptor| get(document, descriptor, key).ok())
+            .and_then(|metric| metric.as_float().ok())
+    };
+    let scale = glyph_scale(document, font_data);
+    match (metric(b"Ascent"), metric(b"Descent")) {
+        (Some(ascent), Some(descent)) if ascent > descent => (ascent * scale, descent * scale),
+        _ => (ASCENT, DESCENT),
+    }
+}
+
+/// The font descriptor of a font, which for composite fonts is in their descendant font.
+fn font_descriptor<'a>(
+    document: &'a Document,
+    font_data: &'a Dictionary,
+) -> Option<&'a Dictionary> {
+    if is_composite(font_data) {
+        descendant_font(document, font_data)
+    } else {
+        Some(font_data)
+    }
+    .and_then(|font| get(document, font, b"FontDescriptor").ok())
+    .and_then(|descriptor| descriptor.as_dict().ok())
+}
+
+/// The family a font belongs to. The font descriptor can give it as `FontFamily`, but few do, so otherwise it is the
+/// name of the font without the tag of a subset or the style after a hyphen or comma, as in `Arial,Bold`.
+fn load_family(
+    document: &Document,
+    font_data: &Dictionary,
+    descriptor: Option<&Dictionary>,
+) -> Option<String> {
+    if let Some(family) = descriptor
+        .and_then(|descriptor| get(document, descriptor, b"FontFamily").ok())
+        .and_then(|family| family.as_str().ok())
+        .filter(|family| !family.is_empty())
+    {
+        return Some(String::from_utf8_lossy(family).into_owned());
+    }
+    let name = font_data.get(b"BaseFont").and_then(Object::as_name).ok()?;
+    let name = String::from_utf8_lossy(name);
+    let name = name.split_once('+').map_or(&*name, |(_, name)| name);
+    let family = name.split(['-', ',']).next().unwrap_or(name);
+    (!family.is_empty()).then(|| family.to_owned())
+}
+
+/// Whether a font is bold and whether it is italic. The font descriptor says so with its flags, weight and italic
+/// angle, but many fonts leave them out, so the name of the font counts too, as in `Helvetica-BoldOblique`.
+fn load_style(document: &Document, descriptor: Option<&Dictionary>, name: &str) -> (bool, bool) {
+    let number = |key| {
+        descriptor
+            .and_then(|descriptor| get(document, descriptor, key).ok())
+            .and_then(|number| number.as_float().ok())
+    };
+    let flags = number(b"Flags").map_or(0, |flags| flags as i64);
+    // Subsets of fonts have a tag such as `ABCDEF+` in front of their name.
+    let name = name
+        .split_once('+')
+        .map_or(name, |(_, name)| name)
+        .to_ascii_lowercase();
+    let bold = flags & FORCE_BOLD_FLAG != 0
+        || number(b"FontWeight").is_some_and(|weight| weight >= BOLD_WEIGHT)
+        || ["bold", "black", "heavy", "demi"]
+            .iter()
+            .any(|word| name.contains(word));
+    let italic = flags & ITALIC_FLAG != 0
+        || number(b"ItalicAngle").is_some_and(|angle| angle != 0.0)
+        || ["italic", "oblique"].iter().any(|word| name.contains(word));
+    (bold, italic)
+}
+
+/// Reads the widths of a font's glyphs, from `Widths` for simple fonts and from `W` in the descendant font for
+/// composite ones, along with the width of any glyph they leave out.
+fn load_widths(document: &Document, font_data: &Dictionary) -> (Vec<(u32, u32, f32)>, f32) {
+    let mut widths: Vec<(u32, u32, f32)> = Vec::new();
+    let mut push = |code: u32, last_code: u32, width: f32| match widths.last_mut() {
+        Some((_, previous_last_code, previous_width))
+            if *previous_last_code + 1 == code && *previous_width == width =>
+        {
+            *previous_last_code = last_code;
+        }
+        _ => widths.push((code, last_code, width)),
+    };
+
+    if is_composite(font_data) {
+        let Some(descendant) = descendant_font(document, font_data) else {
+            return (widths, COMPOSITE_FONT_WIDTH);
+        };
+        let default_width = get(document, descendant, b"DW")
+            .and_then(Object::as_float)
+            .unwrap_or(COMPOSITE_FONT_WIDTH);
+        let entries = get(document, descendant, b"W").and_then(Object::as_array);
+        // Entries are either a code followed by an array of widths for it and the codes after it, or a range of codes
+        // followed by the one width they all have.
+        let mut entries = entries.into_iter().flatten();
+        while let Some(Ok(first)) = entries.next().map(Object::as_i64) {
+            match entries.next() {
+                Some(Object::Array(run)) => {
+                    for (offset, width) in run.iter().enumerate() {
+                        let code = first as u32 + offset as u32;
+                        push(code, code, width.as_float().unwrap_or(default_width));
+                    }
+                }
+                Some(last) => {
+                    let (Ok(last), Some(Ok(width))) =
+                        (last.as_i64(), entries.next().map(Object::as_float))
+                    else {
+                        break;
+                    };
+                    push(first as u32, last as u32, width);
+                }
+                None => break,
+            }
+        }
+        return (widths, default_width);
+    }
+
+    let Ok(font_widths) = get(document, font_data, b"Widths").and_then(Object::as_array) else {
+        return (widths, STANDARD_FONT_WIDTH);
+    };
+    let first_code = get(document, font_data, b"FirstChar")
+        .and_then(Object::as_i64)
+        .unwrap_or(0) as u32;
+    let scale = glyph_scale(document, font_data);
+    for (offset, width) in font_widths.iter().enumerate() {
+        let code = first_code + offset as u32;
+        let width = document
+            .dereference(width)
+            .and_then(|(_, width)| width.as_float())
+            .unwrap_or(0.0);
+        push(code, code, width * scale);
+    }
+    let missing_width = get(document, font_data, b"FontDescriptor")
+        .and_then(Object::as_dict)
+        .and_then(|descriptor| get(document, descriptor, b"MissingWidth"))
+        .and_then(Object::as_float)
+        .unwrap_or(0.0);
+    (widths, missing_width * scale)
+}
+
+/// Looks up an entry of a dictionary, following it if it is a reference.
+fn get<'a>(
+    document: &'a Document,
+    dictionary: &'a Dictionary,
+    key: &[u8],
+) -> lopdf::Result<&'a Object> {
+    dictionary
+        .get(key)
+        .and_then(|object| document.dereference(object))
+        .map(|(_, object)| object)
+}
+
+/// Fonts with less text than this on a page aren't checked for wrong decoding, since there is too little to go on.
+const MIN_CHECKED_CHARACTERS: usize = 20;
+
+/// Fonts with at least this share of their characters looking wrongly decoded are reported.
+const MAX_GARBLED_SHARE: f32 = 0.2;
+
+/// Runs of at least this many accented Latin-1 letters in a row are assumed to be text in another encoding read as
+/// Latin-1, since real words mix accented letters with plain ones.
+const MIN_LATIN1_RUN: usize = 4;
+
+/// Counts the characters on a page which look like they were decoded wrongly, for each font, so that fonts which come
+/// out as garbage can be named in a warning rather than passing silently.
+///
+/// The symptoms are control characters, which is what unmapped glyph codes usually become when they are passed through
+/// as they are, replacement characters, UTF-8 read as Latin-1, and long runs of accented Latin-1 letters.
+#[derive(Default)]
+pub struct DecodingCheck {
+    /// The number of characters and the number of those that look wrong, for each font.
+    fonts: BTreeMap<String, (usize, usize)>,
+}
+
+impl DecodingCheck {
+    pub fn check(&mut self, font: &Font, text: &str) {
+        let characters: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut garbled = 0;
+        let mut latin1_run = 0;
+        for (index, &c) in characters.iter().enumerate() {
+            if c.is_control() || c == char::REPLACEMENT_CHARACTER {
+                garbled += 1;
+            }
+            let next = characters.get(index + 1).copied().unwrap_or_default();
+            if matches!(c, 'Ã' | 'Â') && matches!(next, '\u{80}'..='\u{BF}') {
+                garbled += 2;
+            }
+            if matches!(c, '\u{C0}'..='\u{FF}') {
+                latin1_run += 1;
+            } else {
+                latin1_run = 0;
+            }
+            if latin1_run == MIN_LATIN1_RUN {
+                garbled += MIN_LATIN1_RUN;
+            } else if latin1_run > MIN_LATIN1_RUN {
+                garbled += 1;
+            }
+        }
+        let counts = self.fonts.entry(font.name.clone()).or_default();
+        counts.0 += characters.len();
+        counts.1 += garbled;
+    }
+
+    /// The names of the fonts whose text looks wrongly decoded.
+    pub fn garbled_fonts(&self) -> impl Iterator<Item = &str> {
+        self.fonts
+            .iter()
+            .filter(|(_, &(characters, garbled))| {
+                characters >= MIN_CHECKED_CHARACTERS
+                    && garbled as f32 >= characters as f32 * MAX_GARBLED_SHARE
+            })
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Maps whose codes are all below this are indexed directly, which costs at most 16 KiB each.
+const MAX_DIRECT_CODES: u32 = 4096;
+
+/// Marks the codes in a directly indexed table which have nothing mapped to them.
+const UNMAPPED: u32 = u32::MAX;
+
+/// Entries in a table above this stand for the text at that index in [`UnicodeTable::texts`], rather than for a
+/// single character.
+const FIRST_TEXT: u32 = char::MAX as u32 + 1;
+
+/// What a code stands for.
+pub enum Mapping<'a> {
+    Character(char),
+    /// More than one character, such as the letters of a ligature.
+    Text(&'a str),
+}
+
+/// A `ToUnicode` map compiled into a form that is quick to look codes up in, since it is consulted for every
+/// character.
+///
+/// Codes for a single character, which are nearly all of them, are stored as that character, and the rest as the
+/// index of their text in a separate list plus [`FIRST_TEXT`].
+#[derive(Debug)]
+pub struct UnicodeTable {
+    lookup: Lookup,
+    texts: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Lookup {
+    /// The entry for each code, indexed by the code itself.
+    Direct(Vec<u32>),
+    /// Runs of consecutive codes mapped to consecutive entries, as `(first code, last code, first entry)`, sorted by
+    /// code.
+    Ranges(Vec<(u32, u32, u32)>),
+}
+
+impl UnicodeTable {
+    pub fn new(unicode_map: &BTreeMap<u32, String>) -> Self {
+        let mut texts = Vec::new();
+        let entries = unicode_map.iter().map(|(&code, text)| {
+            let mut characters = text.chars();
+            let entry = match (characters.next(), characters.next()) {
+                (Some(character), None) => character as u32,
+                _ => {
+                    texts.push(text.clone());
+                    FIRST_TEXT + texts.len() as u32 - 1
+                }
+            };
+            (code, entry)
+        });
+        let lookup = match unicode_map.last_key_value() {
+            Some((&last_code, _)) if last_code < MAX_DIRECT_CODES => {
+                let mut table = vec![UNMAPPED; last_code as usize + 1];
+                for (code, entry) in entries {
+                    table[code as usize] = entry;
+                }
+                Lookup::Direct(table)
+            }
+            _ => {
+                let mut ranges: Vec<(u32, u32, u32)> = Vec::new();
+                for (code, entry) in entries {
+                    match ranges.last_mut() {
+                        Some((first_code, last_code, first_entry))
+                            if code == *last_code + 1
+                                && entry == *first_entry + (code - *first_code) =>
+                        {
+                            *last_code = code;
+                        }
+                        _ => ranges.push((code, code, entry)),
+                    }
+                }
+                Lookup::Ranges(ranges)
+            }
+        };
+        Self { lookup, texts }
+    }
+
+    pub fn get(&self, code: u32) -> Option<Mapping<'_>> {
+        let entry = match &self.lookup {
+            Lookup::Direct(table) => table
+                .get(code as usize)
+                .copied()
+                .filter(|&entry| entry != UNMAPPED),
+            Lookup::Ranges(ranges) => {
+                let index = ranges.partition_point(|&(_, last_code, _)| last_code < code);
+                ranges
+                    .get(index)
+                    .filter(|&&(first_code, _, _)| first_code <= code)
+                    .map(|&(first_code, _, first_entry)| first_entry + (code - first_code))
+            }
+        }?;
+        match char::from_u32(entry) {
+            Some(character) => Some(Mapping::Character(character)),
+            None => entry
+                .checked_sub(FIRST_TEXT)
+                .and_then(|index| self.texts.get(index as usize))
+                .map(|text| Mapping::Text(text)),
+        }
+    }
+
+    /// Roughly how many bytes the table takes up in memory.
+    pub fn approximate_size(&self) -> usize {
+        let lookup_size = match &self.lookup {
+            Lookup::Direct(table) => table.capacity() * std::mem::size_of::<u32>(),
+            Lookup::Ranges(ranges) => ranges.capacity() * std::mem::size_of::<(u32, u32, u32)>(),
+        };
+        lookup_size
+            + self
+                .texts
+                .iter()
+                .map(|text| std::mem::size_of::<String>() + text.capacity())
+                .sum::<usize>()
+    }
+}
+
+/// Fonts are only parsed the first time a page uses them, and only the most recently used ones are kept, since
+/// documents with thousands of subset fonts would otherwise pay for all of them upfront and keep them in memory.
+pub struct FontCache {
+    fonts: LruCache<ObjectId, Rc<Font>>,
+    /// The total approximate size of the cached fonts.
+    size: usize,
+    cmaps: CMapLibrary,
+}
+
+impl FontCache {
+    /// A cache of up to `capacity` fonts, which loads the predefined CMaps composite fonts use from `cmap_directory`.
+    pub fn new(capacity: NonZeroUsize, cmap_directory: Option<PathBuf>) -> Self {
+        Self {
+            fonts: LruCache::new(capacity),
+            size: 0,
+            cmaps: CMapLibrary::new(cmap_directory),
+        }
+    }
+
+    /// Roughly how many bytes the cached fonts and the CMaps they use take up in memory.
+    pub fn approximate_size(&self) -> usize {
+        self.size + self.cmaps.approximate_size()
+    }
+
+    /// Forgets every cached font, to make room in memory.
+    pub fn clear(&mut self) {
+        self.fonts.clear();
+        self.size = 0;
+        self.cmaps.clear();
+    }
+
+    /// Gets the font for an entry in a page's font resources, which is usually a reference to the font dictionary.
+    pub fn get(&mut self, document: &Document, font: &Object) -> Result<Rc<Font>, Box<dyn Error>> {
+        let id = match font {
+            Object::Reference(id) => *id,
+            // Fonts defined inline can't be shared between pages, so there is no point caching them.
+            font => {
+                return Ok(Rc::new(Font::load(
+                    document,
+                    font.as_dict()?,
+                    &mut self.cmaps,
+                )?))
+            }
+        };
+        if let Some(font) = self.fonts.get(&id) {
+            return Ok(font.clone());
+        }
+        let font = Rc::new(Font::load(
+            document,
+            document.get_dictionary(id)?,
+            &mut self.cmaps,
+        )?);
+        self.size += font.approximate_size();
+        if let Some((_, evicted)) = self.fonts.push(id, font.clone()) {
+            self.size -= evicted.approximate_size();
+        }
+        Ok(font)
+    }
+}
+
+/// The most codes a single `bfrange` can map.
+const MAX_RANGE_CODES: u32 = 0xFFFF;
+
+fn utf16_units(bytes: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+}
+
+/// Decodes the big-endian UTF-16 that `ToUnicode` maps give text in, where characters outside the Basic Multilingual
+/// Plane take up a pair of surrogates.
+fn decode_utf16(bytes: &[u8]) -> String {
+    char::decode_utf16(utf16_units(bytes))
+        .map(|character| character.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// What a CMap says about a font's codes, which for a `ToUnicode` map is the text of each of them, and for the CMap
+/// of a composite font is which CID each of them is.
+pub struct CMap {
+    pub code_ranges: Vec<CodeRange>,
+    /// The text each code stands for, which is usually one character but can be more, as for ligatures.
+    pub characters: BTreeMap<u32, String>,
+    /// Runs of consecutive codes with consecutive CIDs, as `(first code, last code, first CID)`.
+    pub cids: Vec<(u32, u32, u32)>,
+    /// The name of the CMap this one adds to with `usecmap`, for codes it doesn't say anything about itself.
+    pub parent: Option<String>,
+    /// Whether the CMap sets its `WMode` to 1, for text written from top to bottom.
+    pub vertical: bool,
+}
+
+pub fn parse_unicode_map(unicode_map: &Stream) -> CMap {
+    parse_cmap(&stream_content(unicode_map))
+}
+
+/// Takes the comments out of a CMap, which the predefined ones start with a lot of and which the content parser
+/// doesn't understand.
+fn without_comments(content: &[u8]) -> Cow<'_, [u8]> {
+    if !content.contains(&b'%') {
+        return Cow::Borrowed(content);
+    }
+    let mut result = Vec::with_capacity(content.len());
+    let mut string_depth: usize = 0;
+    let mut in_comment = false;
+    for &byte in content {
+        match byte {
+            b'\r' | b'\n' => in_comment = false,
+            _ if in_comment => continue,
+            b'%' if string_depth == 0 => {
+                in_comment = true;
+                continue;
+            }
+            b'(' => string_depth += 1,
+            b')' => string_depth = string_depth.saturating_sub(1),
+            _ => {}
+        }
+        result.push(byte);
+    }
+    Cow::Owned(result)
+}
+
+/// Parses a CMap, skipping over anything that doesn't make sense rather than failing, since a map that is only partly
+/// right still decodes most of the text.
+pub fn parse_cmap(content: &[u8]) -> CMap {
+    let mut result = CMap {
+        code_ranges: Vec::new(),
+        characters: BTreeMap::new(),
+        cids: Vec::new(),
+        parent: None,
+        vertical: false,
+    };
+    let Ok(operations) = Content::decode(&without_comments(content)) else {
+        return result;
+    };
+    for operation in operations.operations {
+        match operation.operator.as_str() {
+            "endcodespacerange" => {
+                for ends in operation.operands.chunks_exact(2) {
+                    let (Ok(low), Ok(high)) = (ends[0].as_str(), ends[1].as_str()) else {
+                        continue;
+                    };
+                    if low.len() != high.len() || !(1..=4).contains(&low.len()) {
+                        continue;
+                    }
+                    let mut range = CodeRange {
+                        low: [0; 4],
+                        high: [0; 4],
+                        length: low.len(),
+                    };
+                    range.low[..low.len()].copy_from_slice(low);
+                    range.high[..high.len()].copy_from_slice(high);
+                    result.code_ranges.push(range);
+                }
+            }
+            "endbfchar" => {
+                for operands in operation.operands.chunks_exact(2) {
+                    let (Ok(code), Ok(text)) = (operands[0].as_str(), operands[1].as_str()) else {
+                        continue;
+                    };
+                    result
+                        .characters
+                        .insert(code_from_bytes(code), decode_utf16(text));
+                }
+            }
+            "endbfrange" => {
+                for operands in operation.operands.chunks_exact(3) {
+                    let (Ok(first), Ok(last)) = (operands[0].as_str(), operands[1].as_str()) else {
+                        continue;
+                    };
+                    let first = code_from_bytes(first);
+                    // Ranges are cut short rather than filling memory with billions of codes.
+                    let last = code_from_bytes(last).min(first.saturating_add(MAX_RANGE_CODES));
+                    match &operands[2] {
+                        // Each code in the range has its own text in the array.
+                        Object::Array(texts) => {
+                            for (code, text) in (first..=last).zip(texts) {
+                                if let Ok(text) = text.as_str() {
+                                    result.characters.insert(code, decode_utf16(text));
+                                }
+                            }
+                        }
+                        // The codes map to consecutive text, counting up from this in the last UTF-16 code unit.
+                        text => {
+                            let Ok(text) = text.as_str() else {
+                                continue;
+                            };
+                            let mut units: Vec<u16> = utf16_units(text).collect();
+                            let Some(&last_unit) = units.last() else {
+                                continue;
+                            };
+                            for (offset, code) in (first..=last).enumerate() {
+                                *units.last_mut().unwrap() = last_unit.wrapping_add(offset as u16);
+                                result
+                                    .characters
+                                    .insert(code, String::from_utf16_lossy(&units));
+                            }
+                        }
+                    }
+                }
+            }
+            "endcidchar" => {
+                for operands in operation.operands.chunks_exact(2) {
+                    if let (Ok(code), Ok(cid)) = (operands[0].as_str(), operands[1].as_i64()) {
+                        let code = code_from_bytes(code);
+                        result.cids.push((code, code, cid as u32));
+                    }
+                }
+            }
+            "endcidrange" => {
+                for operands in operation.operands.chunks_exact(3) {
+                    if let (Ok(first), Ok(last), Ok(cid)) = (
+                        operands[0].as_str(),
+                        operands[1].as_str(),
+                        operands[2].as_i64(),
+                    ) {
+                        result.cids.push((
+                            code_from_bytes(first),
+                            code_from_bytes(last),
+                            cid as u32,
+                        ));
+                    }
+                }
+            }
+            "def" => {
+                if let [Object::Name(key), Object::Integer(mode)] = operation.operands.as_slice() {
+                    if key == b"WMode" {
+                        result.vertical = *mode == 1;
+                    }
+                }
+            }
+            "usecmap" => {
+                if let Some(Ok(name)) = operation.operands.last().map(Object::as_name) {
+                    result.parent = Some(String::from_utf8_lossy(name).into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::dictionary;
+
+    use super::*;
+
+    /// Loads a font with a `ToUnicode` map, of the subtype given, which is composite if it is `Type0`.
+    fn font_with_unicode_map(subtype: &str, unicode_map: &str) -> Font {
+        let mut document = Document::with_version("1.7");
+        let unicode_map =
+            document.add_object(Stream::new(dictionary! {}, unicode_map.as_bytes().to_vec()));
+        let font_data = dictionary! {
+            "Type" => "Font",
+            "Subtype" => subtype,
+            "BaseFont" => "Test",
+            "Encoding" => "Identity-H",
+            "ToUnicode" => unicode_map,
+        };
+        Font::load(&document, &font_data, &mut CMapLibrary::new(None)).unwrap()
+    }
+
+    #[test]
+    fn splits_codes_by_code_space() {
+        let font = font_with_unicode_map(
+            "Type0",
+            "2 begincodespacerange <00> <80> <8140> <9FFC> endcodespacerange \
+             3 beginbfchar <41> <0041> <8140> <3000> <42> <0042> endbfchar",
+        );
+        assert_eq!(font.decode(b"A\x81\x40B"), "A\u{3000}B");
+        assert_eq!(font.measure(b"A\x81\x40B").codes, 3);
+    }
+
+    #[test]
+    fn takes_codes_outside_code_space_at_shortest_length() {
+        let font = font_with_unicode_map(
+            "Type0",
+            "2 begincodespacerange <00> <80> <8140> <9FFC> endcodespacerange \
+             1 beginbfchar <41> <0041> endbfchar",
+        );
+        assert_eq!(font.measure(b"\xFF\x81\x40A").codes, 3);
+    }
+
+    #[test]
+    fn keeps_single_byte_codes_of_simple_fonts() {
+        let font = font_with_unicode_map(
+            "TrueType",
+            "1 begincodespacerange <0000> <FFFF> endcodespacerange \
+             2 beginbfchar <41> <0058> <42> <0059> endbfchar",
+        );
+        assert_eq!(font.decode(b"AB"), "XY");
+    }
+
+    #[test]
+    fn maps_codes_to_several_characters() {
+        let cmap = parse_cmap(b"2 beginbfchar <01> <00660069> <02> <D83DDE00> endbfchar");
+        assert_eq!(cmap.characters[&1], "fi");
+        assert_eq!(cmap.characters[&2], "\u{1F600}");
+    }
+
+    #[test]
+    fn counts_bfrange_up_from_last_code_unit() {
+        let cmap = parse_cmap(b"1 beginbfrange <0010> <0012> <D83DDE00> endbfrange");
+        let characters: Vec<&str> = cmap.characters.values().map(String::as_str).collect();
+        assert_eq!(characters, ["\u{1F600}", "\u{1F601}", "\u{1F602}"]);
+    }
+
+    #[test]
+    fn gives_each_code_of_bfrange_array_its_own_text() {
+        let cmap = parse_cmap(b"1 beginbfrange <20> <22> [<0041> <00660066> <0043>] endbfrange");
+        assert_eq!(cmap.characters[&0x21], "ff");
+        assert_eq!(cmap.characters.len(), 3);
+    }
+
+    #[test]
+    fn skips_malformed_entries() {
+        let cmap = parse_cmap(
+            b"% comment (not a string\n\
+              2 beginbfchar <01> 5 <02> <0042> endbfchar \
+              1 beginbfrange <03> <04> /Name endbfrange",
+        );
+        assert_eq!(cmap.characters.len(), 1);
+        assert_eq!(cmap.characters[&2], "B");
+    }
+}
@@ -0,0 +1,803 @@
+//! Reads the built-in encodings of embedded Type 1 and CFF font programs, which say which glyph each code of fonts
+//! without an encoding of their own draws.
+//!
+//! The glyphs are named, and the names are turned into text with the Adobe Glyph List, the same way as the names in
+//! an encoding's `Differences`.
+
+use std::collections::BTreeMap;
+
+use crate::{glyph_names, truetype::read_u16};
+
+/// The text of the codes a Type 1 font program's `/Encoding` gives glyphs to, which is nothing if the font uses the
+/// standard encoding.
+///
+/// The encoding is in the clear text part of the program, before `eexec`, as lines of the form `dup 65 /A put`.
+pub fn type1_encoding(program: &[u8]) -> BTreeMap<u32, String> {
+    let mut result = BTreeMap::new();
+    let clear_text = match find(program, b"eexec") {
+        Some(end) => &program[..end],
+        None => program,
+    };
+    let Some(start) = find(clear_text, b"/Encoding") else {
+        return result;
+    };
+    let mut tokens = clear_text[start + b"/Encoding".len()..]
+        .split(|byte| byte.is_ascii_whitespace())
+        .filter(|token| !token.is_empty());
+    let mut previous: [&[u8]; 3] = [b"", b"", b""];
+    for token in tokens.by_ref() {
+        match token {
+            b"StandardEncoding" => return result,
+            b"def" | b"readonly" => break,
+            b"put" => {
+                if let [b"dup", code, name] = previous {
+                    let code = std::str::from_utf8(code)
+                        .ok()
+                        .and_then(|code| code.parse().ok());
+                    let name = name.strip_prefix(b"/").map(String::from_utf8_lossy);
+                    if let (Some(code), Some(text)) =
+                        (code, name.and_then(|name| glyph_names::glyph_text(&name)))
+                    {
+                        result.insert(code, text);
+                    }
+                }
+            }
+            _ => {}
+        }
+        previous = [previous[1], previous[2], token];
+    }
+    result
+}
+
+/// The text of the codes a CFF font program's encoding gives glyphs to, which is nothing if the font uses the
+/// standard encoding or is a CID font.
+pub fn cff_encoding(program: &[u8]) -> BTreeMap<u32, String> {
+    cff_glyph_names(program)
+        .into_iter()
+        .filter_map(|(code, name)| Some((code, glyph_names::glyph_text(&name)?)))
+        .collect()
+}
+
+fn cff_glyph_names(program: &[u8]) -> BTreeMap<u32, String> {
+    let mut result = BTreeMap::new();
+    let Some(&header_size) = program.get(2) else {
+        return result;
+    };
+    // The names of the fonts come first, and then their top DICTs and the strings they all share.
+    let Some((_, top_dicts)) = read_index(program, header_size.into()) else {
+        return result;
+    };
+    let Some((top_dict, strings)) = read_index(program, top_dicts) else {
+        return result;
+    };
+    let Some((strings, _)) = read_index(program, strings) else {
+        return result;
+    };
+    let Some(top_dict) = top_dict.first() else {
+        return result;
+    };
+    let top_dict = read_dict(top_dict);
+    // CID fonts are only ever used by composite fonts, whose codes aren't single bytes.
+    if top_dict.contains_key(&ROS_OPERATOR) {
+        return result;
+    }
+    let offset = |operator| {
+        top_dict
+            .get(&operator)
+            .and_then(|operands| operands.last())
+            .and_then(|&offset| usize::try_from(offset).ok())
+    };
+    let (Some(char_strings), Some(encoding)) =
+        (offset(CHAR_STRINGS_OPERATOR), offset(ENCODING_OPERATOR))
+    else {
+        return result;
+    };
+    // Offsets 0 and 1 are the predefined standard and expert encodings.
+    if encoding <= 1 {
+        return result;
+    }
+    let Some((char_strings, _)) = read_index(program, char_strings) else {
+        return result;
+    };
+    let charset = read_charset(
+        program,
+        offset(CHARSET_OPERATOR).unwrap_or(0),
+        char_strings.len(),
+    );
+    let name = |string_id: u16| -> Option<String> {
+        let string_id = string_id as usize;
+        match STANDARD_STRINGS.get(string_id) {
+            Some(name) => Some((*name).to_owned()),
+            None => strings
+                .get(string_id - STANDARD_STRINGS.len())
+                .map(|name| String::from_utf8_lossy(name).into_owned()),
+        }
+    };
+
+    let Some(&format) = program.get(encoding) else {
+        return result;
+    };
+    // Glyph 0 is always .notdef, so the codes are given for the glyphs from 1 onwards.
+    let mut glyph = 1;
+    let mut add = |code: u8, glyph: usize| {
+        if let Some(name) = charset.get(glyph).and_then(|&string_id| name(string_id)) {
+            result.insert(code.into(), name);
+        }
+    };
+    let supplements = match format & 0x7F {
+        0 => {
+            let count = program.get(encoding + 1).copied().unwrap_or(0) as usize;
+            for &code in program
+                .get(encoding + 2..encoding + 2 + count)
+                .unwrap_or_default()
+            {
+                add(code, glyph);
+                glyph += 1;
+            }
+            encoding + 2 + count
+        }
+        1 => {
+            let count = program.get(encoding + 1).copied().unwrap_or(0) as usize;
+            for range in program
+                .get(encoding + 2..encoding + 2 + count * 2)
+                .unwrap_or_default()
+                .chunks_exact(2)
+            {
+                for code in range[0]..=range[0].saturating_add(range[1]) {
+                    add(code, glyph);
+                    glyph += 1;
+                }
+            }
+            encoding + 2 + count * 2
+        }
+        _ => return result,
+    };
+    // The high bit of the format says that some codes are given to glyphs by their names as well.
+    if format & 0x80 != 0 {
+        let count = program.get(supplements).copied().unwrap_or(0) as usize;
+        for supplement in 0..count {
+            let record = supplements + 1 + supplement * 3;
+            let (Some(&code), Some(string_id)) =
+                (program.get(record), read_u16(program, record + 1))
+            else {
+                break;
+            };
+            if let Some(name) = name(string_id) {
+                result.insert(code.into(), name);
+            }
+        }
+    }
+    result
+}
+
+const CHARSET_OPERATOR: u16 = 15;
+const ENCODING_OPERATOR: u16 = 16;
+const CHAR_STRINGS_OPERATOR: u16 = 17;
+/// The operator which says which character collection a CID font is for, which two byte operators are counted
+/// from 1200 for.
+const ROS_OPERATOR: u16 = 1230;
+
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len())
+        .position(|window| window == pattern)
+}
+
+/// Reads an INDEX, a list of pieces of data, returning them along with where the data after the INDEX starts.
+fn read_index(program: &[u8], start: usize) -> Option<(Vec<&[u8]>, usize)> {
+    let count = read_u16(program, start)? as usize;
+    if count == 0 {
+        return Some((Vec::new(), start + 2));
+    }
+    let offset_size = *program.get(start + 2)? as usize;
+    if !(1..=4).contains(&offset_size) {
+        return None;
+    }
+    let offsets = start + 3;
+    let offset = |index: usize| -> Option<usize> {
+        let bytes =
+            program.get(offsets + index * offset_size..offsets + (index + 1) * offset_size)?;
+        Some(
+            bytes
+                .iter()
+                .fold(0, |offset, &byte| offset << 8 | byte as usize),
+        )
+    };
+    // The offsets count from 1, at the byte before the data.
+    let data = offsets + (count + 1) * offset_size - 1;
+    let mut items = Vec::with_capacity(count.min(program.len()));
+    for index in 0..count {
+        let (start, end) = (offset(index)?, offset(index + 1)?);
+        items.push(program.get(data + start..data + end)?);
+    }
+    Some((items, data + offset(count)?))
+}
+
+/// Reads a DICT into its operators and their operands, leaving out real numbers, which none of the operators used
+/// here take.
+fn read_dict(dict: &[u8]) -> BTreeMap<u16, Vec<i32>> {
+    let mut result = BTreeMap::new();
+    let mut operands = Vec::new();
+    let mut position = 0;
+    while let Some(&byte) = dict.get(position) {
+        let byte = i32::from(byte);
+        let next = |offset: usize| {
+            dict.get(position + offset)
+                .map_or(0, |&byte| i32::from(byte))
+        };
+        position += match byte {
+            0..=11 | 13..=21 => {
+                result.insert(byte as u16, std::mem::take(&mut operands));
+                1
+            }
+            12 => {
+                result.insert(1200 + next(1) as u16, std::mem::take(&mut operands));
+                2
+            }
+            28 => {
+                operands.push(i32::from(next(1) as i8) << 8 | next(2));
+                3
+            }
+            29 => {
+                operands.push(next(1) << 24 | next(2) << 16 | next(3) << 8 | next(4));
+                5
+            }
+            // Real numbers are nibbles of digits, ending with a nibble of 0xF.
+            30 => {
+                let length = dict[position + 1..]
+                    .iter()
+                    .position(|&byte| byte & 0x0F == 0x0F || byte >> 4 == 0x0F)
+                    .map_or(dict.len(), |end| end + 2);
+                operands.push(0);
+                length
+            }
+            32..=246 => {
+                operands.push(byte - 139);
+                1
+            }
+            247..=250 => {
+                operands.push((byte - 247) * 256 + next(1) + 108);
+                2
+            }
+            251..=254 => {
+                operands.push(-(byte - 251) * 256 - next(1) - 108);
+                2
+            }
+            _ => 1,
+        };
+    }
+    result
+}
+
+/// Reads the string ids of the names of a font's glyphs, in order of glyph.
+fn read_charset(program: &[u8], charset: usize, glyph_count: usize) -> Vec<u16> {
+    // Offset 0 is the predefined ISOAdobe charset, in which every glyph's string id is its number. The expert ones
+    // at 1 and 2 are left out, since fonts using them are very rare.
+    match charset {
+        0 => return (0..glyph_count.min(229) as u16).collect(),
+        1 | 2 => return Vec::new(),
+        _ => {}
+    }
+    let mut string_ids = vec![0];
+    let format = program.get(charset).copied();
+    let mut position = charset + 1;
+    while string_ids.len() < glyph_count {
+        match format {
+            Some(0) => match read_u16(program, position) {
+                Some(string_id) => string_ids.push(string_id),
+                None => break,
+            },
+            // Ranges of consecutive string ids, with one or two bytes for how many more there are after the first.
+            Some(1) | Some(2) => {
+                let first = read_u16(program, position);
+                let left = if format == Some(1) {
+                    program.get(position + 2).map(|&left| left.into())
+                } else {
+                    read_u16(program, position + 2)
+                };
+                let (Some(first), Some(left)) = (first, left) else {
+                    break;
+                };
+                string_ids.extend(
+                    (first..=first.saturating_add(left)).take(glyph_count - string_ids.len()),
+                );
+                position += if format == Some(1) { 3 } else { 4 };
+                continue;
+            }
+            _ => break,
+        }
+        position += 2;
+    }
+    string_ids
+}
+
+/// The names of the glyphs with string ids below 391, which CFF fonts share rather than including them.
+static STANDARD_STRINGS: [&str; 391] = [
+    ".notdef",
+    "space",
+    "exclam",
+    "quotedbl",
+    "numbersign",
+    "dollar",
+    "percent",
+    "ampersand",
+    "quoteright",
+    "parenleft",
+    "parenright",
+    "asterisk",
+    "plus",
+    "comma",
+    "hyphen",
+    "period",
+    "slash",
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "colon",
+    "semicolon",
+    "less",
+    "equal",
+    "greater",
+    "question",
+    "at",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "bracketleft",
+    "backslash",
+    "bracketright",
+    "asciicircum",
+    "underscore",
+    "quoteleft",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "braceleft",
+    "bar",
+    "braceright",
+    "asciitilde",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "fraction",
+    "yen",
+    "florin",
+    "section",
+    "currency",
+    "quotesingle",
+    "quotedblleft",
+    "guillemotleft",
+    "guilsinglleft",
+    "guilsinglright",
+    "fi",
+    "fl",
+    "endash",
+    "dagger",
+    "daggerdbl",
+    "periodcentered",
+    "paragraph",
+    "bullet",
+    "quotesinglbase",
+    "quotedblbase",
+    "quotedblright",
+    "guillemotright",
+    "ellipsis",
+    "perthousand",
+    "questiondown",
+    "grave",
+    "acute",
+    "circumflex",
+    "tilde",
+    "macron",
+    "breve",
+    "dotaccent",
+    "dieresis",
+    "ring",
+    "cedilla",
+    "hungarumlaut",
+    "ogonek",
+    "caron",
+    "emdash",
+    "AE",
+    "ordfeminine",
+    "Lslash",
+    "Oslash",
+    "OE",
+    "ordmasculine",
+    "ae",
+    "dotlessi",
+    "lslash",
+    "oslash",
+    "oe",
+    "germandbls",
+    "onesuperior",
+    "logicalnot",
+    "mu",
+    "trademark",
+    "Eth",
+    "onehalf",
+    "plusminus",
+    "Thorn",
+    "onequarter",
+    "divide",
+    "brokenbar",
+    "degree",
+    "thorn",
+    "threequarters",
+    "twosuperior",
+    "registered",
+    "minus",
+    "eth",
+    "multiply",
+    "threesuperior",
+    "copyright",
+    "Aacute",
+    "Acircumflex",
+    "Adieresis",
+    "Agrave",
+    "Aring",
+    "Atilde",
+    "Ccedilla",
+    "Eacute",
+    "Ecircumflex",
+    "Edieresis",
+    "Egrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Igrave",
+    "Ntilde",
+    "Oacute",
+    "Ocircumflex",
+    "Odieresis",
+    "Ograve",
+    "Otilde",
+    "Scaron",
+    "Uacute",
+    "Ucircumflex",
+    "Udieresis",
+    "Ugrave",
+    "Yacute",
+    "Ydieresis",
+    "Zcaron",
+    "aacute",
+    "acircumflex",
+    "adieresis",
+    "agrave",
+    "aring",
+    "atilde",
+    "ccedilla",
+    "eacute",
+    "ecircumflex",
+    "edieresis",
+    "egrave",
+    "iacute",
+    "icircumflex",
+    "idieresis",
+    "igrave",
+    "ntilde",
+    "oacute",
+    "ocircumflex",
+    "odieresis",
+    "ograve",
+    "otilde",
+    "scaron",
+    "uacute",
+    "ucircumflex",
+    "udieresis",
+    "ugrave",
+    "yacute",
+    "ydieresis",
+    "zcaron",
+    "exclamsmall",
+    "Hungarumlautsmall",
+    "dollaroldstyle",
+    "dollarsuperior",
+    "ampersandsmall",
+    "Acutesmall",
+    "parenleftsuperior",
+    "parenrightsuperior",
+    "twodotenleader",
+    "onedotenleader",
+    "zerooldstyle",
+    "oneoldstyle",
+    "twooldstyle",
+    "threeoldstyle",
+    "fouroldstyle",
+    "fiveoldstyle",
+    "sixoldstyle",
+    "sevenoldstyle",
+    "eightoldstyle",
+    "nineoldstyle",
+    "commasuperior",
+    "threequartersemdash",
+    "periodsuperior",
+    "questionsmall",
+    "asuperior",
+    "bsuperior",
+    "centsuperior",
+    "dsuperior",
+    "esuperior",
+    "isuperior",
+    "lsuperior",
+    "msuperior",
+    "nsuperior",
+    "osuperior",
+    "rsuperior",
+    "ssuperior",
+    "tsuperior",
+    "ff",
+    "ffi",
+    "ffl",
+    "parenleftinferior",
+    "parenrightinferior",
+    "Circumflexsmall",
+    "hyphensuperior",
+    "Gravesmall",
+    "Asmall",
+    "Bsmall",
+    "Csmall",
+    "Dsmall",
+    "Esmall",
+    "Fsmall",
+    "Gsmall",
+    "Hsmall",
+    "Ismall",
+    "Jsmall",
+    "Ksmall",
+    "Lsmall",
+    "Msmall",
+    "Nsmall",
+    "Osmall",
+    "Psmall",
+    "Qsmall",
+    "Rsmall",
+    "Ssmall",
+    "Tsmall",
+    "Usmall",
+    "Vsmall",
+    "Wsmall",
+    "Xsmall",
+    "Ysmall",
+    "Zsmall",
+    "colonmonetary",
+    "onefitted",
+    "rupiah",
+    "Tildesmall",
+    "exclamdownsmall",
+    "centoldstyle",
+    "Lslashsmall",
+    "Scaronsmall",
+    "Zcaronsmall",
+    "Dieresissmall",
+    "Brevesmall",
+    "Caronsmall",
+    "Dotaccentsmall",
+    "Macronsmall",
+    "figuredash",
+    "hypheninferior",
+    "Ogoneksmall",
+    "Ringsmall",
+    "Cedillasmall",
+    "questiondownsmall",
+    "oneeighth",
+    "threeeighths",
+    "fiveeighths",
+    "seveneighths",
+    "onethird",
+    "twothirds",
+    "zerosuperior",
+    "foursuperior",
+    "fivesuperior",
+    "sixsuperior",
+    "sevensuperior",
+    "eightsuperior",
+    "ninesuperior",
+    "zeroinferior",
+    "oneinferior",
+    "twoinferior",
+    "threeinferior",
+    "fourinferior",
+    "fiveinferior",
+    "sixinferior",
+    "seveninferior",
+    "eightinferior",
+    "nineinferior",
+    "centinferior",
+    "dollarinferior",
+    "periodinferior",
+    "commainferior",
+    "Agravesmall",
+    "Aacutesmall",
+    "Acircumflexsmall",
+    "Atildesmall",
+    "Adieresissmall",
+    "Aringsmall",
+    "AEsmall",
+    "Ccedillasmall",
+    "Egravesmall",
+    "Eacutesmall",
+    "Ecircumflexsmall",
+    "Edieresissmall",
+    "Igravesmall",
+    "Iacutesmall",
+    "Icircumflexsmall",
+    "Idieresissmall",
+    "Ethsmall",
+    "Ntildesmall",
+    "Ogravesmall",
+    "Oacutesmall",
+    "Ocircumflexsmall",
+    "Otildesmall",
+    "Odieresissmall",
+    "OEsmall",
+    "Oslashsmall",
+    "Ugravesmall",
+    "Uacutesmall",
+    "Ucircumflexsmall",
+    "Udieresissmall",
+    "Yacutesmall",
+    "Thornsmall",
+    "Ydieresissmall",
+    "001.000",
+    "001.001",
+    "001.002",
+    "001.003",
+    "Black",
+    "Bold",
+    "Book",
+    "Light",
+    "Medium",
+    "Regular",
+    "Roman",
+    "Semibold",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An INDEX of the items, with one byte offsets.
+    fn index(items: &[&[u8]]) -> Vec<u8> {
+        let mut index = (items.len() as u16).to_be_bytes().to_vec();
+        index.push(1);
+        let mut offset = 1;
+        index.push(offset);
+        for item in items {
+            offset += item.len() as u8;
+            index.push(offset);
+        }
+        index.extend(items.concat());
+        index
+    }
+
+    /// A CFF font program with glyphs named `A` and `uni20AC`, given codes 65 and 128 by its encoding, with any of
+    /// its top DICT's offsets replaced by those in `offsets`, as `(operator, offset)`.
+    fn cff_program(offsets: &[(u8, i32)]) -> Vec<u8> {
+        // Each offset is 5 bytes and its operator 1.
+        let top_dict_length = 3 * 6;
+        let start = 4 + index(&[b"F"]).len() + index(&[&vec![0; top_dict_length]]).len();
+        let strings = index(&[b"uni20AC"]);
+        let char_strings = start + strings.len();
+        let charset = char_strings + index(&[&[14][..]; 3]).len();
+        let encoding = charset + 5;
+        let mut top_dict = Vec::with_capacity(top_dict_length);
+        for (operator, offset) in [
+            (CHAR_STRINGS_OPERATOR as u8, char_strings),
+            (CHARSET_OPERATOR as u8, charset),
+            (ENCODING_OPERATOR as u8, encoding),
+        ] {
+            let offset = offsets
+                .iter()
+                .find(|&&(replaced, _)| replaced == operator)
+                .map_or(offset as i32, |&(_, offset)| offset);
+            top_dict.push(29);
+            top_dict.extend(offset.to_be_bytes());
+            top_dict.push(operator);
+        }
+        let mut program = vec![1, 0, 4, 1];
+        program.extend(index(&[b"F"]));
+        program.extend(index(&[&top_dict]));
+        program.extend(strings);
+        program.extend(index(&[&[14][..]; 3]));
+        // Glyph 1 is the standard string `A` and glyph 2 the first of the font's own strings.
+        program.extend([0, 0, 34, 0x01, 0x87]);
+        program.extend([0, 2, 65, 128]);
+        program
+    }
+
+    #[test]
+    fn reads_names_of_encoded_glyphs() {
+        assert_eq!(
+            cff_encoding(&cff_program(&[])),
+            BTreeMap::from([(65, "A".to_owned()), (128, "\u{20AC}".to_owned())])
+        );
+    }
+
+    #[test]
+    fn reads_no_more_than_is_there_from_truncated_programs() {
+        let program = cff_program(&[]);
+        let whole = cff_glyph_names(&program);
+        for length in 0..program.len() {
+            let truncated = cff_glyph_names(&program[..length]);
+            assert!(truncated
+                .iter()
+                .all(|(code, name)| whole.get(code) == Some(name)));
+        }
+        // An encoding which is cut short gives no codes at all, rather than codes to the wrong glyphs.
+        assert!(cff_glyph_names(&program[..program.len() - 1]).is_empty());
+    }
+
+    #[test]
+    fn reads_nothing_from_offsets_out_of_program() {
+        for offsets in [
+            [(CHAR_STRINGS_OPERATOR as u8, -1)],
+            [(CHARSET_OPERATOR as u8, i32::MAX)],
+            [(ENCODING_OPERATOR as u8, -2)],
+        ] {
+            let names = cff_glyph_names(&cff_program(&offsets));
+            assert!(names.is_empty(), "{offsets:?} gave {names:?}");
+        }
+    }
+
+    #[test]
+    fn reads_type1_encoding_before_eexec() {
+        let program = b"/Encoding 256 array\ndup 65 /B put\ndup 66 /notaglyph put\nreadonly def\neexec dup 67 /C put";
+        assert_eq!(
+            type1_encoding(program),
+            BTreeMap::from([(65, "B".to_owned())])
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Finds the footnotes at the bottom of each page and moves them to where they are wanted.
+//!
+//! The footnotes of a page are the rows at the bottom of it in smaller text than most of the page, from the first of
+//! them which starts with a number or symbol such as "*" or "†". Each note starts at a row with one of these markers
+//! and goes on over the rows after it. Small text at the bottom of a page is just as often a caption or the small print
+//! of a form, so the notes are only taken to be footnotes if the text above them refers to at least one of them, with
+//! its marker raised as a superscript.
+
+use std::io;
+
+use clap::ValueEnum;
+
+use crate::{
+    hyphenation::Hyphenation,
+    output::OutputRenderer,
+    text::{self, Page, TextChunk},
+};
+
+/// Footnotes are in text no taller than this fraction of the height of most of the rows of their page.
+const SMALL_TEXT: f32 = 0.9;
+
+/// The symbols which footnotes are marked with instead of numbers, which can be repeated, as in "**".
+const SYMBOLS: [char; 5] = ['*', '†', '‡', '§', '¶'];
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FootnotePlacement {
+    /// Puts each footnote in square brackets straight after the marker which refers to it.
+    Inline,
+    /// Puts the footnotes of each page after the rest of it, each on a row of its own.
+    PageEnd,
+    /// Puts every footnote after the end of the last page, each on a row of its own.
+    DocumentEnd,
+}
+
+/// A footnote, with the marker it is referred to by.
+pub struct Footnote {
+    pub marker: String,
+    pub text: String,
+}
+
+impl Footnote {
+    /// The footnote as a row of its own, in the place of the rows it was on.
+    fn row(&self, first_row: &TextChunk) -> TextChunk {
+        TextChunk {
+            text: format!("{} {}", self.marker, self.text),
+            ..first_row.clone()
+        }
+    }
+}
+
+/// Moves the footnotes of each page before passing them on to another renderer.
+pub struct FootnoteMover {
+    inner: Box<dyn OutputRenderer>,
+    placement: FootnotePlacement,
+    /// The page before the current one, held back until it is known not to be the last when the footnotes go at the
+    /// end of the document.
+    held_page: Option<Page>,
+    /// The rows of the footnotes which go at the end of the document so far.
+    notes: Vec<TextChunk>,
+}
+
+impl FootnoteMover {
+    pub fn new(inner: Box<dyn OutputRenderer>, placement: FootnotePlacement) -> Self {
+        Self {
+            inner,
+            placement,
+            held_page: None,
+            notes: Vec::new(),
+        }
+    }
+}
+
+impl OutputRenderer for FootnoteMover {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let mut page = Page {
+            number: page.number,
+            label: page.label.clone(),
+            rows: page.rows.clone(),
+            tables: page.tables.clone(),
+            bounds: page.bounds,
+        };
+        let footnotes = take_footnotes(&mut page);
+        match self.placement {
+            FootnotePlacement::Inline => {
+                for row in &mut page.rows {
+                    for (footnote, _) in &footnotes {
+                        let reference = format!("<sup>{}</sup>", footnote.marker);
+                        row.text = row
+                            .text
+                            .replace(&reference, &format!("{} [{}]", reference, footnote.text));
+                    }
+                }
+            }
+            FootnotePlacement::PageEnd => page.rows.extend(
+                footnotes
+                    .iter()
+                    .map(|(footnote, first_row)| footnote.row(first_row)),
+            ),
+            FootnotePlacement::DocumentEnd => {
+                self.notes.extend(
+                    footnotes
+                        .iter()
+                        .map(|(footnote, first_row)| footnote.row(first_row)),
+                );
+                if let Some(held_page) = self.held_page.replace(page) {
+                    self.inner.render_page(&held_page)?;
+                }
+                return Ok(());
+            }
+        }
+        self.inner.render_page(&page)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(mut page) = self.held_page.take() {
+            page.rows.append(&mut self.notes);
+            self.inner.render_page(&page)?;
+        }
+        self.inner.finish()
+    }
+}
+
+/// Takes the footnotes out of the rows of a page, returning each one with the first of the rows it was on.
+pub fn take_footnotes(page: &mut Page) -> Vec<(Footnote, TextChunk)> {
+    let mut heights: Vec<f32> = page.rows.iter().map(|row| row.height).collect();
+    heights.sort_by(f32::total_cmp);
+    let Some(&usual_height) = heights.get(heights.len() / 2) else {
+        return Vec::new();
+    };
+    // The rows at the bottom of the page in small text, from the first one which starts a note.
+    let small_rows = page
+        .rows
+        .iter()
+        .rev()
+        .take_while(|row| !row.vertical && row.height <= usual_height * SMALL_TEXT)
+        .count();
+    let Some(start) = (page.rows.len() - small_rows..page.rows.len())
+        .find(|&index| marker(&page.rows[index].text).is_some())
+    else {
+        return Vec::new();
+    };
+
+    let hyphenation = Hyphenation::default();
+    let mut footnotes: Vec<(Footnote, TextChunk)> = Vec::new();
+    for row in &page.rows[start..] {
+        match marker(&row.text) {
+            Some((marker, text)) => footnotes.push((Footnote { marker, text }, row.clone())),
+            None => {
+                if let Some((footnote, _)) = footnotes.last_mut() {
+                    hyphenation.join_line(&mut footnote.text, &text::plain_text(&row.text));
+                }
+            }
+        }
+    }
+    let is_referred_to = page.rows[..start].iter().any(|row| {
+        footnotes.iter().any(|(footnote, _)| {
+            row.text
+                .contains(&format!("<sup>{}</sup>", footnote.marker))
+        })
+    });
+    if !is_referred_to {
+        return Vec::new();
+    }
+    page.rows.truncate(start);
+    footnotes
+}
+
+/// Splits a row which starts a footnote into its marker and the text after it. The marker can be raised or not, and
+/// followed by a full stop or bracket.
+fn marker(text: &str) -> Option<(String, String)> {
+    let text = text::plain_text(text);
+    let text = text.trim_start();
+    let marker_length = if text.starts_with(|character: char| character.is_ascii_digit()) {
+        let length = text
+            .find(|character: char| !character.is_ascii_digit())
+            .unwrap_or(text.len());
+        // Longer numbers are more likely years or amounts than footnotes.
+        if length > 3 {
+            return None;
+        }
+        length
+    } else {
+        text.find(|character: char| !SYMBOLS.contains(&character))
+            .unwrap_or(text.len())
+    };
+    if marker_length == 0 {
+        return None;
+    }
+    let (marker, rest) = text.split_at(marker_length);
+    let rest = rest.strip_prefix(['.', ')']).unwrap_or(rest).trim_start();
+    // The text of a note starts with a word, rather than more of a number such as "3.5".
+    if !rest.starts_with(|character: char| character.is_alphabetic()) {
+        return None;
+    }
+    Some((marker.to_owned(), rest.to_owned()))
+}
@@ -0,0 +1,87 @@
+//! Finds the formulas of tagged documents, along with the MathML or text that stands in for each of them, so that
+//! equations aren't lost in extraction the way the pieces of text that draw them often are.
+
+use lopdf::{Dictionary, Document, Object};
+use serde::Serialize;
+
+use crate::{
+    filters,
+    outline::decode_text_string,
+    structure::{self, StructElement},
+    traversal::TraversalError,
+};
+
+/// A `Formula` element of the structure tree.
+#[derive(Serialize)]
+pub struct Formula {
+    pub page: Option<u32>,
+    /// The MathML associated with the formula, if the document has any.
+    pub mathml: Option<String>,
+    /// The text the formula stands for, given as its replacement or alternate description.
+    pub text: Option<String>,
+}
+
+/// Finds every formula in the structure tree, in the order of the tree.
+///
+/// MathML files bigger than `max_bytes` once decompressed are left out with a warning.
+pub fn find_formulas(
+    document: &Document,
+    max_bytes: usize,
+) -> Result<Vec<Formula>, TraversalError> {
+    Ok(structure::elements(document)?
+        .iter()
+        .filter(|element| element.role == b"Formula")
+        .map(|element| Formula {
+            page: element.page,
+            mathml: mathml(document, element, max_bytes),
+            text: [b"ActualText".as_slice(), b"Alt"]
+                .iter()
+                .find_map(|key| element.dictionary.get(key).and_then(Object::as_str).ok())
+                .map(decode_text_string),
+        })
+        .collect())
+}
+
+/// Looks through the files associated with an element for one which is MathML.
+pub fn mathml(document: &Document, element: &StructElement, max_bytes: usize) -> Option<String> {
+    let files = match element.dictionary.get(b"AF").ok()? {
+        Object::Array(files) => files.iter().collect(),
+        file => vec![file],
+    };
+    for file in files {
+        let Some(file) = dictionary(document, file) else {
+            continue;
+        };
+        let Some(stream) = file
+            .get(b"EF")
+            .ok()
+            .and_then(|embedded| dictionary(document, embedded))
+            .and_then(|embedded| embedded.get(b"UF").or_else(|_| embedded.get(b"F")).ok())
+            .and_then(|stream| document.dereference(stream).ok())
+            .and_then(|(_, stream)| stream.as_stream().ok())
+        else {
+            continue;
+        };
+        let is_mathml = stream
+            .dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .is_ok_and(|subtype| {
+                // Names can't contain a slash as it is, so it is normally written escaped as `#2F`.
+                let subtype = String::from_utf8_lossy(subtype).to_lowercase();
+                subtype == "application/mathml+xml" || subtype == "application#2fmathml+xml"
+            });
+        if !is_mathml {
+            continue;
+        }
+        match filters::decode(stream, max_bytes) {
+            Ok(data) => return Some(String::from_utf8_lossy(&data).into_owned()),
+            Err(error) => eprintln!("warning: skipping a formula's MathML because {}", error),
+        }
+    }
+    None
+}
+
+fn dictionary<'a>(document: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    document.dereference(object).ok()?.1.as_dict().ok()
+}
@@ -0,0 +1,111 @@
+//! A gRPC service for extracting text, defined in `proto/extractor.proto`, for pipelines which send lots of documents
+//! through and care about the overhead of each one.
+
+use std::{error::Error, net::SocketAddr, str::FromStr};
+
+use lopdf::Document;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::{
+    page_range::PageSelection,
+    text::{self, ExtractOptions, TextExtractor},
+};
+
+pub mod proto {
+    tonic::include_proto!("pdf_text_extractor");
+}
+
+use proto::{
+    extractor_server::{Extractor, ExtractorServer},
+    ExtractRequest, PageText, Row,
+};
+
+/// How many extracted pages can be waiting to be sent before extraction pauses for the client to catch up.
+const PAGE_BUFFER: usize = 16;
+
+struct ExtractorService {
+    options: ExtractOptions,
+}
+
+#[tonic::async_trait]
+impl Extractor for ExtractorService {
+    type ExtractPagesStream = ReceiverStream<Result<PageText, Status>>;
+
+    async fn extract_pages(
+        &self,
+        request: Request<ExtractRequest>,
+    ) -> Result<Response<Self::ExtractPagesStream>, Status> {
+        let request = request.into_inner();
+        let mut options = self.options.clone();
+        if !request.pages.is_empty() {
+            let pages = PageSelection::from_str(&request.pages)
+                .map_err(|error| Status::invalid_argument(error.to_string()))?;
+            options.pages = Some(pages);
+        }
+        let (sender, receiver) = mpsc::channel(PAGE_BUFFER);
+        // Extraction is synchronous and keeps its fonts in a cache that can't move between threads, so each request
+        // gets a thread of its own.
+        tokio::task::spawn_blocking(move || {
+            // A panic would otherwise end the stream as if every page had been sent.
+            let result =
+                text::catching_panics(|| extract_pages(&request.document, &options, &sender))
+                    .unwrap_or_else(|message| Err(Status::internal(message)));
+            if let Err(status) = result {
+                // If the client has gone away there is nobody to tell.
+                let _ = sender.blocking_send(Err(status));
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+fn extract_pages(
+    document: &[u8],
+    options: &ExtractOptions,
+    sender: &mpsc::Sender<Result<PageText, Status>>,
+) -> Result<(), Status> {
+    let document = Document::load_mem(document).map_err(|error| {
+        Status::invalid_argument(format!("couldn't load the document: {}", error))
+    })?;
+    let mut extractor = TextExtractor::new(&document, options);
+    let pages = extractor
+        .pages()
+        .map_err(|error| Status::invalid_argument(error.to_string()))?;
+    for page in pages {
+        let page = page.map_err(|error| Status::internal(error.to_string()))?;
+        let message = PageText {
+            number: page.number,
+            rows: page
+                .rows
+                .into_iter()
+                .map(|row| Row {
+                    text: row.text,
+                    x: row.x,
+                    y: row.y,
+                })
+                .collect(),
+        };
+        if sender.blocking_send(Ok(message)).is_err() {
+            // The client has stopped listening, so there is no point extracting the rest.
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Serves requests on the address until the process is stopped.
+///
+/// Documents bigger than `max_document_size` are rejected before they are read in full.
+pub fn serve(
+    address: SocketAddr,
+    options: ExtractOptions,
+    max_document_size: usize,
+) -> Result<(), Box<dyn Error>> {
+    let service = ExtractorServer::new(ExtractorService { options })
+        .max_decoding_message_size(max_document_size);
+    tokio::runtime::Runtime::new()?
+        .block_on(Server::builder().add_service(service).serve(address))?;
+    Ok(())
+}
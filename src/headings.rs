@@ -0,0 +1,161 @@
+//! Finds the headings in the text of untagged documents by the size of their text, for the formats which mark them up.
+//!
+//! Most of a document's text is in the same size, which is the size of its body text. A heading is a short row in
+//! text noticeably bigger than that, on its own or with one more row in the same size if it wraps, rather than in a
+//! block of big text such as a title page or a pull quote. The sizes headings are found in are sorted from the
+//! biggest down, and the biggest three are the first three levels of heading, with any smaller ones at the third
+//! level too.
+//!
+//! The size of the text of a row is measured by its height, which is the height of its font at the size it is drawn
+//! in, so the same size of text in fonts with much taller or shorter ascenders and descenders can count as different
+//! sizes.
+
+use std::{collections::BTreeMap, io};
+
+use crate::{
+    output::OutputRenderer,
+    text::{self, Page, TextChunk},
+};
+
+/// Headings are in text at least this many times bigger than the body text.
+const MIN_HEADING_SIZE: f32 = 1.15;
+
+/// Sizes this close to each other, as a fraction of the bigger one, are the same size.
+const SIZE_TOLERANCE: f32 = 0.05;
+
+/// Headings are never longer than this many characters.
+pub const MAX_HEADING_LENGTH: usize = 80;
+
+/// A heading wraps onto at most this many rows.
+const MAX_HEADING_ROWS: usize = 2;
+
+/// How many levels of heading there are.
+const LEVELS: u8 = 3;
+
+/// Marks the headings on the pages before passing them on to another renderer.
+pub struct HeadingFinder {
+    inner: Box<dyn OutputRenderer>,
+    pages: Vec<Page>,
+}
+
+impl HeadingFinder {
+    pub fn new(inner: Box<dyn OutputRenderer>) -> Self {
+        Self {
+            inner,
+            pages: Vec::new(),
+        }
+    }
+}
+
+impl OutputRenderer for HeadingFinder {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.pages.push(Page {
+            number: page.number,
+            label: page.label.clone(),
+            rows: page.rows.clone(),
+            tables: page.tables.clone(),
+            bounds: page.bounds,
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        mark_headings(&mut self.pages);
+        for page in &self.pages {
+            self.inner.render_page(page)?;
+        }
+        self.inner.finish()
+    }
+}
+
+/// Sets the heading level of every row of the pages which is a heading.
+pub fn mark_headings(pages: &mut [Page]) {
+    mark_page_rows(pages.iter_mut().map(|page| &mut page.rows[..]).collect());
+}
+
+/// Sets the heading level of every row which is a heading, for rows from all of the pages of a document one after
+/// the other.
+pub fn mark_heading_rows(rows: &mut [TextChunk]) {
+    mark_page_rows(rows.chunk_by_mut(|a, b| a.page == b.page).collect());
+}
+
+fn mark_page_rows(mut pages: Vec<&mut [TextChunk]>) {
+    // The body text is whatever size the most characters are in.
+    let mut characters: BTreeMap<i64, usize> = BTreeMap::new();
+    for row in pages.iter().flat_map(|rows| rows.iter()) {
+        *characters.entry(size_key(row.height)).or_default() += row.text.chars().count();
+    }
+    let Some(body_size) = characters
+        .into_iter()
+        .max_by_key(|&(_, count)| count)
+        .map(|(key, _)| key as f32 / 10.0)
+    else {
+        return;
+    };
+
+    // The rows which are headings, by page and row, with the size of their text.
+    let mut headings = Vec::new();
+    for (page_index, rows) in pages.iter().enumerate() {
+        let mut start = 0;
+        while start < rows.len() {
+            // The rows after this one in the same size, which a heading can wrap onto.
+            let end = start
+                + rows[start..]
+                    .iter()
+                    .take_while(|row| is_same_size(row.height, rows[start].height))
+                    .count();
+            let is_heading = end - start <= MAX_HEADING_ROWS
+                && rows[start].height >= body_size * MIN_HEADING_SIZE
+                && rows[start..end].iter().all(could_be_heading);
+            if is_heading {
+                headings.extend((start..end).map(|row| (page_index, row, rows[start].height)));
+            }
+            start = end;
+        }
+    }
+
+    // Each size headings are in is its own level, from the biggest down.
+    let mut sizes: Vec<f32> = headings.iter().map(|&(_, _, size)| size).collect();
+    sizes.sort_by(|a, b| b.total_cmp(a));
+    sizes.dedup_by(|smaller, bigger| is_same_size(*smaller, *bigger));
+    for (page_index, row, size) in headings {
+        let level = sizes
+            .iter()
+            .position(|&heading_size| is_same_size(size, heading_size))
+            .map_or(LEVELS, |index| (index as u8 + 1).min(LEVELS));
+        pages[page_index][row].heading = Some(level);
+    }
+}
+
+/// The text of a heading without the bold markup around all of it, since headings are bold anyway.
+pub fn heading_text(text: &str) -> &str {
+    match text
+        .strip_prefix("<strong>")
+        .and_then(|text| text.strip_suffix("</strong>"))
+    {
+        Some(inner) if !inner.contains("</strong>") => inner,
+        _ => text,
+    }
+}
+
+/// Whether a row is short enough, and has the letters, to be a heading.
+fn could_be_heading(row: &TextChunk) -> bool {
+    let text = text::plain_text(&row.text);
+    let text = text.trim();
+    !row.vertical
+        && text.chars().count() <= MAX_HEADING_LENGTH
+        && text
+            .chars()
+            .filter(|character| character.is_alphabetic())
+            .count()
+            >= 2
+}
+
+fn is_same_size(a: f32, b: f32) -> bool {
+    (a - b).abs() <= a.max(b) * SIZE_TOLERANCE
+}
+
+/// Rounds a size to a tenth of a unit, so that sizes which differ only by rounding are counted together.
+fn size_key(size: f32) -> i64 {
+    (size * 10.0).round() as i64
+}
@@ -0,0 +1,135 @@
+//! Writes the text as hOCR, the HTML which OCR engines write what they recognise in, so that the text of PDFs can go
+//! through the same tools as the text of scans.
+//!
+//! Each page is an `ocr_page`, with the blocks of text on it, found as they are for JSON but with each table as a
+//! block of its own, as `ocr_carea`s, each with one `ocr_par` in it. The rows of a block are its `ocr_line`s, and the words
+//! of each row, found by `words`, are its `ocrx_word`s. Every element has the box around it, in points from the top
+//! left corner of the part of the page that is shown, which is an image of 72 dots per inch.
+
+use std::io::{self, Write};
+
+use crate::{html, json, output::OutputRenderer, text::Page, words};
+
+const HEADER: &str = concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" ",
+    "\"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n",
+    "<html xmlns=\"http://www.w3.org/1999/xhtml\">\n",
+    "<head>\n",
+    "<title></title>\n",
+    "<meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\"/>\n",
+    "<meta name=\"ocr-system\" content=\"rust-pdf-test ",
+    env!("CARGO_PKG_VERSION"),
+    "\"/>\n",
+    "<meta name=\"ocr-capabilities\" content=\"ocr_page ocr_carea ocr_par ocr_line ocrx_word\"/>\n",
+    "</head>\n",
+    "<body>\n",
+);
+
+const FOOTER: &str = "</body>\n</html>\n";
+
+pub struct HocrRenderer<W: Write> {
+    writer: W,
+    word_gap: f32,
+    started: bool,
+}
+
+impl<W: Write> HocrRenderer<W> {
+    pub fn new(writer: W, word_gap: f32) -> Self {
+        Self {
+            writer,
+            word_gap,
+            started: false,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.writer.write_all(HEADER.as_bytes())?;
+            self.started = true;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputRenderer for HocrRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.start()?;
+        let [left, bottom, right, top] = page.bounds;
+        // Boxes are given by their left, top, right and bottom edges, going down from the top of the page.
+        let bbox = |x: f32, y: f32, width: f32, height: f32| {
+            format!(
+                "bbox {} {} {} {}",
+                (x - left).round() as i64,
+                (top - y - height).round() as i64,
+                (x + width - left).round() as i64,
+                (top - y).round() as i64
+            )
+        };
+        writeln!(
+            self.writer,
+            "<div class=\"ocr_page\" id=\"page_{}\" title=\"{}; ppageno {}; scan_res 72 72\">",
+            page.number,
+            bbox(left, bottom, right - left, top - bottom),
+            page.number - 1
+        )?;
+        let blocks = json::blocks_with_tables(page);
+        let (mut line_count, mut word_count) = (0, 0);
+        for (index, block) in blocks.iter().enumerate() {
+            let lines = words::lines(block, self.word_gap);
+            if lines.is_empty() {
+                continue;
+            }
+            let block_box = bbox(block.x, block.y, block.width, block.height);
+            writeln!(
+                self.writer,
+                "<div class=\"ocr_carea\" id=\"block_{}_{}\" title=\"{}\">",
+                page.number,
+                index + 1,
+                block_box
+            )?;
+            writeln!(
+                self.writer,
+                "<p class=\"ocr_par\" id=\"par_{}_{}\" title=\"{}\">",
+                page.number,
+                index + 1,
+                block_box
+            )?;
+            for (line, words) in lines {
+                line_count += 1;
+                // The boxes of rows go up from their baselines, so the baseline is the bottom of the box.
+                write!(
+                    self.writer,
+                    "<span class=\"ocr_line\" id=\"line_{}_{}\" title=\"{}; baseline 0 0\">",
+                    page.number,
+                    line_count,
+                    bbox(line.x, line.y, line.width, line.height)
+                )?;
+                for (index, word) in words.iter().enumerate() {
+                    word_count += 1;
+                    if index > 0 {
+                        write!(self.writer, " ")?;
+                    }
+                    write!(
+                        self.writer,
+                        "<span class=\"ocrx_word\" id=\"word_{}_{}\" title=\"{}\">{}</span>",
+                        page.number,
+                        word_count,
+                        bbox(word.x, word.y, word.width, word.height),
+                        html::escape_plain(&word.text)
+                    )?;
+                }
+                writeln!(self.writer, "</span>")?;
+            }
+            writeln!(self.writer, "</p>\n</div>")?;
+        }
+        writeln!(self.writer, "</div>")?;
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        self.writer.write_all(FOOTER.as_bytes())?;
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,404 @@
+//! Writes the text as an HTML document.
+//!
+//! Tagged documents say what each part of their content is in their structure tree, and each element of it becomes
+//! the HTML element for the same thing: headings, paragraphs, lists, tables and figures, with formulas as their
+//! MathML where the document has it. The text inside each element is the text of the marked content it contains, in
+//! the order of the tree, so text outside of the structure, such as artifacts, is left out. Elements with no text on
+//! the pages being extracted are left out as well.
+//!
+//! Other documents have no structure to go by, so each page becomes a `div` of its own, and the rows of its text are
+//! put back together into the paragraphs they were wrapped from. The rows of the lists found in the text become their
+//! items instead, the headings found by the size of their text become headings of the same level, with the rows of a
+//! heading which wraps joined into one, and the tables found in the text become tables.
+
+use std::{
+    borrow::Borrow,
+    collections::BTreeMap,
+    error::Error,
+    io::{self, Write},
+    mem,
+};
+
+use lopdf::Document;
+
+use crate::{
+    formula,
+    hyphenation::Hyphenation,
+    lists::{self, Block, ListItem},
+    outline::decode_text_string,
+    output::{self, OutputRenderer, PagePart},
+    paragraphs,
+    structure::{self, Node, StructElement},
+    text::{self, Page, TextChunk, TextExtractor},
+};
+
+const HEADER: &str = "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n<body>\n";
+
+const FOOTER: &str = "</body>\n</html>\n";
+
+/// Writes each page of an untagged document as a `div` of the paragraphs, headings, lists and tables on it.
+pub struct HtmlRenderer<W: Write> {
+    writer: W,
+    hyphenation: Hyphenation,
+    started: bool,
+    /// The lists which are open, outermost first, each with an item open in it.
+    open_lists: Vec<&'static str>,
+}
+
+impl<W: Write> HtmlRenderer<W> {
+    pub fn new(writer: W, hyphenation: Hyphenation) -> Self {
+        Self {
+            writer,
+            hyphenation,
+            started: false,
+            open_lists: Vec::new(),
+        }
+    }
+
+    /// Writes some rows of a page which aren't in a table or a heading, as the list items and paragraphs in them.
+    fn write_blocks(&mut self, page: &Page, rows: &[TextChunk]) -> io::Result<()> {
+        // The rows of the paragraphs before the next list item.
+        let mut paragraph_rows = Vec::new();
+        for block in lists::find(rows) {
+            match block {
+                Block::Row(row) => paragraph_rows.push(row.clone()),
+                Block::Item(item) => {
+                    self.write_paragraphs(page, mem::take(&mut paragraph_rows))?;
+                    self.write_item(&item)?;
+                }
+            }
+        }
+        self.write_paragraphs(page, paragraph_rows)
+    }
+
+    fn write_paragraphs(&mut self, page: &Page, rows: Vec<TextChunk>) -> io::Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.close_lists()?;
+        let rows = Page {
+            number: page.number,
+            label: None,
+            rows,
+            tables: Vec::new(),
+            bounds: page.bounds,
+        };
+        for paragraph in paragraphs::split(&rows, escape, &self.hyphenation) {
+            writeln!(self.writer, "<p>{}</p>", paragraph)?;
+        }
+        Ok(())
+    }
+
+    /// Starts an item, closing the items and lists before it that it isn't nested in and opening the list it is in if
+    /// it is the first item of it.
+    fn write_item(&mut self, item: &ListItem) -> io::Result<()> {
+        let tag = if item.ordered { "ol" } else { "ul" };
+        while self.open_lists.len() > item.level + 1
+            || self.open_lists.len() == item.level + 1 && self.open_lists.last() != Some(&tag)
+        {
+            self.close_list()?;
+        }
+        if self.open_lists.len() == item.level + 1 {
+            writeln!(self.writer, "</li>")?;
+        } else {
+            // Items are never more than one level deeper than the item before them.
+            writeln!(self.writer, "<{}>", tag)?;
+            self.open_lists.push(tag);
+        }
+        write!(self.writer, "<li>{}", escape(&item.text))
+    }
+
+    fn close_list(&mut self) -> io::Result<()> {
+        if let Some(tag) = self.open_lists.pop() {
+            writeln!(self.writer, "</li>\n</{}>", tag)?;
+        }
+        Ok(())
+    }
+
+    fn close_lists(&mut self) -> io::Result<()> {
+        while !self.open_lists.is_empty() {
+            self.close_list()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputRenderer for HtmlRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        if !self.started {
+            self.writer.write_all(HEADER.as_bytes())?;
+            self.started = true;
+        }
+        writeln!(
+            self.writer,
+            "<div class=\"page\" id=\"page-{}\">",
+            page.number
+        )?;
+        for part in output::page_parts(page) {
+            match part {
+                PagePart::Rows(rows) => self.write_blocks(page, rows)?,
+                PagePart::Heading(level, text) => {
+                    self.close_lists()?;
+                    writeln!(self.writer, "<h{}>{}</h{}>", level, escape(&text), level)?;
+                }
+                PagePart::Table(table) => {
+                    self.close_lists()?;
+                    write!(self.writer, "{}", table.to_html(escape))?;
+                }
+            }
+        }
+        // Lists don't carry on over page breaks.
+        self.close_lists()?;
+        writeln!(self.writer, "</div>")?;
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.writer.write_all(HEADER.as_bytes())?;
+        }
+        self.writer.write_all(FOOTER.as_bytes())?;
+        self.writer.flush()
+    }
+}
+
+/// An element of the structure tree which is still having its content added to.
+struct OpenElement {
+    depth: usize,
+    /// The HTML element it becomes, if any, since some structure types only group their content.
+    tag: Option<&'static str>,
+    attributes: String,
+    /// What stands in for the content, such as the MathML of a formula.
+    replacement: Option<String>,
+    /// Whether to keep the element even if it has no text, as a figure with a description has to be.
+    keep: bool,
+    content: String,
+    /// Whether any of the content is block elements, in which case the end tag goes on a line of its own.
+    has_blocks: bool,
+}
+
+impl OpenElement {
+    fn new(depth: usize, tag: Option<&'static str>) -> Self {
+        Self {
+            depth,
+            tag,
+            attributes: String::new(),
+            replacement: None,
+            keep: false,
+            content: String::new(),
+            has_blocks: false,
+        }
+    }
+
+    /// Adds some text, or an inline element, to the end of the content.
+    fn push_inline(&mut self, text: &str) {
+        if self.content.ends_with('\n') {
+            self.content.push_str(text);
+        } else {
+            text::join_line(&mut self.content, text);
+        }
+    }
+
+    /// Adds a block element to the content, on a line of its own.
+    fn push_block(&mut self, html: &str) {
+        if !self.content.is_empty() && !self.content.ends_with('\n') {
+            self.content.push('\n');
+        }
+        self.content.push_str(html);
+        self.content.push('\n');
+        self.has_blocks = true;
+    }
+}
+
+/// Writes a tagged document as HTML, following its structure tree. The MathML of formulas is left out if it is over
+/// `max_bytes` once decompressed.
+pub fn write_tagged<D: Borrow<Document>>(
+    extractor: &mut TextExtractor<D>,
+    max_bytes: usize,
+    mut writer: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut texts = BTreeMap::new();
+    for (number, page_id) in extractor.selected_pages()? {
+        for (identifier, text) in extractor.extract_marked_content(number, page_id)? {
+            texts.insert((number, identifier), text);
+        }
+    }
+    let document = extractor.document();
+
+    let mut open = vec![OpenElement::new(0, None)];
+    structure::walk(document, |node| match node {
+        Node::Element(element) => {
+            close(&mut open, element.depth);
+            let parent = open.last().and_then(|parent| parent.tag);
+            open.push(open_element(document, &element, parent, max_bytes));
+        }
+        Node::MarkedContent {
+            page,
+            identifier,
+            depth,
+        } => {
+            close(&mut open, depth);
+            if let Some(text) = page.and_then(|page| texts.get(&(page, identifier))) {
+                if let Some(parent) = open.last_mut() {
+                    parent.push_inline(&escape(text));
+                }
+            }
+        }
+    })?;
+    close(&mut open, 1);
+
+    writer.write_all(HEADER.as_bytes())?;
+    if let Some(root) = open.pop() {
+        let content = root.content.trim();
+        if !content.is_empty() {
+            writeln!(writer, "{}", content)?;
+        }
+    }
+    writer.write_all(FOOTER.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Starts the HTML element for an element of the structure tree.
+fn open_element(
+    document: &Document,
+    element: &StructElement,
+    parent: Option<&str>,
+    max_bytes: usize,
+) -> OpenElement {
+    let tag = match element.role.as_slice() {
+        b"Part" | b"Div" => Some("div"),
+        b"Art" => Some("article"),
+        b"Sect" => Some("section"),
+        b"BlockQuote" => Some("blockquote"),
+        b"P" => Some("p"),
+        b"H" | b"H1" => Some("h1"),
+        b"H2" => Some("h2"),
+        b"H3" => Some("h3"),
+        b"H4" => Some("h4"),
+        b"H5" => Some("h5"),
+        b"H6" => Some("h6"),
+        b"L" | b"TOC" => Some("ul"),
+        b"LI" | b"TOCI" => Some("li"),
+        b"Table" => Some("table"),
+        b"THead" => Some("thead"),
+        b"TBody" => Some("tbody"),
+        b"TFoot" => Some("tfoot"),
+        b"TR" => Some("tr"),
+        b"TH" => Some("th"),
+        b"TD" => Some("td"),
+        b"Figure" => Some("figure"),
+        b"Caption" => Some(match parent {
+            Some("table") => "caption",
+            Some("figure") => "figcaption",
+            _ => "p",
+        }),
+        b"Note" => Some("aside"),
+        b"Code" => Some("code"),
+        b"Quote" => Some("q"),
+        // Everything else, such as `Document`, `Span` and the labels and bodies of list items, only groups its
+        // content. So do links, whose text is already marked up as a link to the address of their annotation.
+        _ => None,
+    };
+    let mut open = OpenElement::new(element.depth, tag);
+    let text_entry = |key: &[u8]| {
+        let (_, text) = document
+            .dereference(element.dictionary.get(key).ok()?)
+            .ok()?;
+        Some(decode_text_string(text.as_str().ok()?))
+    };
+    if element.role == b"Formula" {
+        open.replacement = formula::mathml(document, element, max_bytes);
+    }
+    if open.replacement.is_none() {
+        open.replacement = text_entry(b"ActualText").map(|text| escape(&text));
+    }
+    if element.role == b"Figure" {
+        // Figures are mostly pictures, with no text but their description.
+        if let Some(description) = text_entry(b"Alt") {
+            open.attributes = format!(" role=\"img\" aria-label=\"{}\"", escape(&description));
+            open.keep = true;
+        }
+    }
+    open
+}
+
+/// Finishes every open element nested at least as deeply as `depth`, adding each to the element around it.
+fn close(open: &mut Vec<OpenElement>, depth: usize) {
+    while open.len() > 1 && open.last().is_some_and(|element| element.depth >= depth) {
+        let element = open.pop().unwrap();
+        let parent = open.last_mut().unwrap();
+        let content = element.replacement.unwrap_or(element.content);
+        let content = content.trim();
+        if content.is_empty() && !element.keep {
+            continue;
+        }
+        match element.tag {
+            Some(tag) if is_block(tag) => {
+                let content = if element.has_blocks {
+                    format!("\n{}\n", content)
+                } else {
+                    content.to_owned()
+                };
+                parent.push_block(&format!(
+                    "<{}{}>{}</{}>",
+                    tag, element.attributes, content, tag
+                ));
+            }
+            Some(tag) => parent.push_inline(&format!(
+                "<{}{}>{}</{}>",
+                tag, element.attributes, content, tag
+            )),
+            None if element.has_blocks => parent.push_block(content),
+            None => parent.push_inline(content),
+        }
+    }
+}
+
+fn is_block(tag: &str) -> bool {
+    !matches!(tag, "code" | "q" | "a")
+}
+
+/// Escapes the characters which mean something in HTML, keeping the markup of extracted text, which is already HTML,
+/// as it is.
+pub fn escape(text: &str) -> String {
+    let mut escaped = escape_plain(text);
+    for tag in text::MARKUP {
+        escaped = escaped.replace(&tag.replace('<', "&lt;").replace('>', "&gt;"), tag);
+    }
+    // The addresses of links have no quotes or angle brackets in them, so the first `">` after the start of a link is
+    // the end of its tag.
+    let link_start = "&lt;a href=&quot;";
+    let mut rest = escaped.as_str();
+    let mut result = String::with_capacity(escaped.len());
+    while let Some(start) = rest.find(link_start) {
+        let Some(end) = rest[start..].find("&quot;&gt;").map(|end| start + end) else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        result.push_str(text::LINK_START);
+        result.push_str(&rest[start + link_start.len()..end]);
+        result.push_str("\">");
+        rest = &rest[end + "&quot;&gt;".len()..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Escapes every character which means something in HTML and XML, for text which has no markup. Control characters,
+/// which neither can have in them even escaped, become the replacement character.
+pub fn escape_plain(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(character),
+            character if character.is_control() => escaped.push(char::REPLACEMENT_CHARACTER),
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,151 @@
+//! Puts words hyphenated across line breaks back together, when lines are joined into paragraphs.
+//!
+//! A line which ends in a hyphen after a letter, followed by one which starts in lower case, is taken to be a word
+//! that was broken across the two. A hyphen anywhere else, as in "1990-" before "1995" or a dash ending a line before
+//! a new sentence, is left as it is, with a space after it as at any other line break.
+//! That gets genuine compounds which happen to be broken at their hyphen wrong, so a dictionary can be given as well,
+//! and then the two halves are only joined if the whole word is in it or the halves aren't both words of their own,
+//! so that "well-" followed by "known" stays "well-known".
+
+use std::{collections::HashSet, fs, io, path::Path, rc::Rc};
+
+/// How the lines of a paragraph are joined.
+#[derive(Clone, Default)]
+pub struct Hyphenation {
+    /// Whether to keep the hyphens at the ends of lines, rather than joining the words they break.
+    pub keep_hyphens: bool,
+    /// The words of a dictionary, in lower case.
+    pub dictionary: Option<Rc<HashSet<String>>>,
+}
+
+impl Hyphenation {
+    /// Loads a dictionary with one word on each line.
+    pub fn load_dictionary(path: &Path) -> io::Result<HashSet<String>> {
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect())
+    }
+
+    /// Adds some text to the end of a paragraph, putting back together any word hyphenated across the break between
+    /// them unless hyphens are kept.
+    pub fn join_line(&self, paragraph: &mut String, text: &str) {
+        if paragraph.is_empty() {
+            paragraph.push_str(text);
+        } else if is_hyphenated(paragraph, text) {
+            if !self.keep_hyphens && self.is_broken_word(paragraph, text) {
+                paragraph.pop();
+            }
+            paragraph.push_str(text);
+        } else {
+            paragraph.push(' ');
+            paragraph.push_str(text);
+        }
+    }
+
+    /// Whether the hyphen at the end of a paragraph, which [`is_hyphenated`] says is in the middle of a word, breaks
+    /// a word rather than joining the two parts of a compound.
+    fn is_broken_word(&self, paragraph: &str, text: &str) -> bool {
+        let Some(dictionary) = &self.dictionary else {
+            return true;
+        };
+        let before = paragraph[..paragraph.len() - 1]
+            .rsplit(|character: char| !character.is_alphabetic())
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let after = text
+            .split(|character: char| !character.is_alphabetic())
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        dictionary.contains(&format!("{}{}", before, after))
+            || !(dictionary.contains(&before) && dictionary.contains(&after))
+    }
+}
+
+/// Whether a paragraph ends in a hyphen after a letter and the text carries on in lower case, so that the hyphen is in
+/// the middle of a word, whether of one broken across the lines or of a compound.
+fn is_hyphenated(paragraph: &str, text: &str) -> bool {
+    let mut end = paragraph.chars().rev();
+    end.next() == Some('-')
+        && end.next().is_some_and(char::is_alphabetic)
+        && text.starts_with(char::is_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn join(hyphenation: &Hyphenation, lines: &[&str]) -> String {
+        let mut paragraph = String::new();
+        for line in lines {
+            hyphenation.join_line(&mut paragraph, line);
+        }
+        paragraph
+    }
+
+    #[test]
+    fn joins_words_broken_across_lines() {
+        let hyphenation = Hyphenation::default();
+        assert_eq!(
+            join(&hyphenation, &["an extrac-", "tion tool"]),
+            "an extraction tool"
+        );
+        assert_eq!(
+            join(&hyphenation, &["a line", "and another"]),
+            "a line and another"
+        );
+    }
+
+    #[test]
+    fn keeps_space_after_hyphens_not_in_a_word() {
+        let hyphenation = Hyphenation::default();
+        assert_eq!(
+            join(&hyphenation, &["from 1990-", "1995"]),
+            "from 1990- 1995"
+        );
+        assert_eq!(
+            join(&hyphenation, &["the cost -", "and the time"]),
+            "the cost - and the time"
+        );
+        assert_eq!(
+            join(&hyphenation, &["in pre-", "Roman times"]),
+            "in pre- Roman times"
+        );
+    }
+
+    #[test]
+    fn keeps_hyphens_when_asked() {
+        let hyphenation = Hyphenation {
+            keep_hyphens: true,
+            ..Hyphenation::default()
+        };
+        assert_eq!(
+            join(&hyphenation, &["an extrac-", "tion tool"]),
+            "an extrac-tion tool"
+        );
+        assert_eq!(
+            join(&hyphenation, &["from 1990-", "1995"]),
+            "from 1990- 1995"
+        );
+    }
+
+    #[test]
+    fn keeps_hyphens_of_compounds_in_dictionary() {
+        let dictionary = ["well", "known", "extraction"].map(str::to_owned);
+        let hyphenation = Hyphenation {
+            dictionary: Some(Rc::new(HashSet::from(dictionary))),
+            ..Hyphenation::default()
+        };
+        assert_eq!(
+            join(&hyphenation, &["a well-", "known tool"]),
+            "a well-known tool"
+        );
+        assert_eq!(
+            join(&hyphenation, &["an extrac-", "tion tool"]),
+            "an extraction tool"
+        );
+    }
+}
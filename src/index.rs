@@ -0,0 +1,157 @@
+//! A full-text index of the rows extracted from any number of files, kept on disk so that it can be searched without
+//! extracting anything again.
+//!
+//! Each row is a document of its own in the index, stored along with the file, page and position it came from, so that
+//! every hit says exactly where the text is. The superscript and subscript markup is left out, so that it doesn't get
+//! in the way of matching words. Indexing a file again replaces whatever was indexed for it before.
+
+use std::{error::Error, fs, io, path::Path};
+
+use tantivy::{
+    collector::TopDocs,
+    directory::MmapDirectory,
+    doc,
+    query::QueryParser,
+    schema::{Field, Schema, Value, INDEXED, STORED, STRING, TEXT},
+    Index, IndexWriter, TantivyDocument, Term,
+};
+
+use crate::{
+    output::OutputRenderer,
+    text::{self, Page},
+};
+
+/// How much memory the index writer can buffer documents in before it has to write them out.
+const WRITER_MEMORY: usize = 50_000_000;
+
+#[derive(Clone, Copy)]
+struct Fields {
+    path: Field,
+    page: Field,
+    x: Field,
+    y: Field,
+    text: Field,
+}
+
+impl Fields {
+    fn schema() -> (Schema, Fields) {
+        let mut builder = Schema::builder();
+        let fields = Fields {
+            // Paths aren't split into words, so that a file's rows can be found again to replace them.
+            path: builder.add_text_field("path", STRING | STORED),
+            page: builder.add_u64_field("page", INDEXED | STORED),
+            x: builder.add_i64_field("x", STORED),
+            y: builder.add_i64_field("y", STORED),
+            text: builder.add_text_field("text", TEXT | STORED),
+        };
+        (builder.build(), fields)
+    }
+}
+
+pub struct TextIndex {
+    index: Index,
+    fields: Fields,
+}
+
+/// A row which matched a query.
+pub struct IndexHit {
+    pub path: String,
+    pub page: u32,
+    pub x: i32,
+    pub y: i32,
+    pub text: String,
+}
+
+impl TextIndex {
+    /// Opens the index in a directory, creating it if it isn't there yet.
+    pub fn open(directory: &Path) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(directory)?;
+        let (schema, fields) = Fields::schema();
+        let index = Index::open_or_create(MmapDirectory::open(directory)?, schema)?;
+        Ok(Self { index, fields })
+    }
+
+    /// Starts adding files to the index, which only one writer can do at a time.
+    pub fn writer(&self) -> Result<IndexRenderer, Box<dyn Error>> {
+        Ok(IndexRenderer {
+            writer: self.index.writer(WRITER_MEMORY)?,
+            fields: self.fields,
+            path: String::new(),
+        })
+    }
+
+    /// Finds the rows which best match a query in tantivy's query syntax, best first.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<IndexHit>, Box<dyn Error>> {
+        let searcher = self.index.reader()?.searcher();
+        let query =
+            QueryParser::for_index(&self.index, vec![self.fields.text]).parse_query(query)?;
+        let mut hits = Vec::new();
+        for (_, address) in searcher.search(&query, &TopDocs::with_limit(limit).order_by_score())? {
+            let document: TantivyDocument = searcher.doc(address)?;
+            let text = |field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_owned()
+            };
+            let number = |field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_i64().or(value.as_u64().map(|value| value as i64)))
+                    .unwrap_or_default()
+            };
+            hits.push(IndexHit {
+                path: text(self.fields.path),
+                page: number(self.fields.page) as u32,
+                x: number(self.fields.x) as i32,
+                y: number(self.fields.y) as i32,
+                text: text(self.fields.text),
+            });
+        }
+        Ok(hits)
+    }
+}
+
+/// Adds the rows of each page it is given to the index, committing them once the file is finished.
+pub struct IndexRenderer {
+    writer: IndexWriter,
+    fields: Fields,
+    /// The file the pages being rendered come from.
+    path: String,
+}
+
+impl IndexRenderer {
+    /// Starts on the pages of another file, replacing whatever was indexed for it before.
+    pub fn start_file(&mut self, path: &Path) -> io::Result<()> {
+        // Files are identified by their full path, so that indexing them again from elsewhere still replaces them.
+        self.path = fs::canonicalize(path)?.display().to_string();
+        self.writer
+            .delete_term(Term::from_field_text(self.fields.path, &self.path));
+        Ok(())
+    }
+}
+
+impl OutputRenderer for IndexRenderer {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let fields = self.fields;
+        for row in &page.rows {
+            self.writer
+                .add_document(doc!(
+                    fields.path => self.path.as_str(),
+                    fields.page => u64::from(page.number),
+                    // Positions are indexed to the nearest whole unit.
+                    fields.x => row.x.round() as i64,
+                    fields.y => row.y.round() as i64,
+                    fields.text => text::plain_text(&row.text),
+                ))
+                .map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.commit().map_err(io::Error::other)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+//! Writes the text as JSON, with where everything is on the page and the fonts it is in.
+//!
+//! The output is an array of the pages, each with the blocks of text on it and the tables found in its text. A block
+//! is a paragraph, found as the paragraphs of plain text are, or a heading, and is made of lines, which are the rows
+//! of the page. Each line has the chunks it was joined from, which are the pieces of text drawn in one font at one
+//! place. Everything has where it is and how big it is in the units of the page, from its bottom left corner, and
+//! lines and chunks have the family, size and style of their font. The rows of tables are left out of the blocks,
+//! since the cells of the tables have their text.
+//!
+//! Each page also has the score of how well its text came out, so that pages which need OCR can be picked out, and,
+//! with the `language` feature, the language it is in.
+//!
+//! Each page is written on a line of its own as soon as it is extracted, so that the output can be read page by page
+//! without waiting for the rest. The pages can be written as JSON Lines instead, without the array around them, so
+//! that every line is a whole JSON object.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+#[cfg(feature = "language")]
+use crate::language;
+use crate::{
+    output::OutputRenderer,
+    paragraphs, quality,
+    tables::Table,
+    text::{Page, TextChunk},
+};
+
+/// A page, as it is written as JSON.
+#[derive(Serialize)]
+pub struct JsonPage {
+    pub number: u32,
+    /// The page number printed on the page, if it has been found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub blocks: Vec<TextBlock>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<Table>,
+    /// How well the text came out, from 0 to 1, as scored by the `quality` command.
+    pub quality: f64,
+    /// Whether the page should be checked by hand or run through OCR, going by its quality.
+    pub needs_review: bool,
+    /// The language the page is in, if it has enough text to tell.
+    #[cfg(feature = "language")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<language::Detection>,
+}
+
+/// A paragraph or heading, made of one or more lines.
+#[derive(Serialize)]
+pub struct TextBlock {
+    /// The smallest rectangle around the lines, with each line taken to go from its baseline up by its height.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// The lines of the block from the first to the last, each with every chunk it was joined from.
+    pub lines: Vec<TextChunk>,
+}
+
+impl JsonPage {
+    pub fn new(page: &Page) -> Self {
+        let rows: Vec<TextChunk> = page
+            .rows
+            .iter()
+            .filter(|row| !page.tables.iter().any(|table| table.contains(row)))
+            .cloned()
+            .collect();
+        let mut blocks = Vec::new();
+        // Headings are blocks of their own, apart from the paragraphs around them.
+        for rows in rows.chunk_by(|a, b| a.heading == b.heading) {
+            let rows = Page {
+                number: page.number,
+                label: None,
+                rows: rows.to_vec(),
+                tables: Vec::new(),
+                bounds: page.bounds,
+            };
+            if rows.rows[0].heading.is_some() {
+                blocks.push(TextBlock::new(rows.rows.iter().collect()));
+                continue;
+            }
+            for paragraph in paragraphs::group(&rows, str::to_owned) {
+                blocks.push(TextBlock::new(
+                    paragraph.into_iter().map(|(row, _)| row).collect(),
+                ));
+            }
+        }
+        let quality = quality::assess(page);
+        Self {
+            number: page.number,
+            label: page.label.clone(),
+            blocks,
+            tables: page.tables.clone(),
+            quality: quality.score,
+            needs_review: quality.needs_review,
+            #[cfg(feature = "language")]
+            language: language::detect_rows(&page.rows),
+        }
+    }
+}
+
+/// The blocks of text on a page, with each table as a block of its own, made of its rows, rather than left out.
+pub fn blocks_with_tables(page: &Page) -> Vec<TextBlock> {
+    let table_of = |row: &TextChunk| page.tables.iter().position(|table| table.contains(row));
+    let mut blocks = Vec::new();
+    for rows in page.rows.chunk_by(|a, b| table_of(a) == table_of(b)) {
+        if table_of(&rows[0]).is_some() {
+            blocks.push(TextBlock::new(rows.iter().collect()));
+        } else {
+            let rows = Page {
+                number: page.number,
+                label: None,
+                rows: rows.to_vec(),
+                tables: Vec::new(),
+                bounds: page.bounds,
+            };
+            blocks.extend(JsonPage::new(&rows).blocks);
+        }
+    }
+    blocks
+}
+
+impl TextBlock {
+    /// Puts some lines together into a block, in the order they are given in.
+    pub fn new(lines: Vec<&TextChunk>) -> Self {
+        let left = lines
+            .iter()
+            .map(|line| line.x)
+            .fold(f32::INFINITY, f32::min);
+        let right = lines
+            .iter()
+            .map(|line| line.x + line.width)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let bottom = lines
+            .iter()
+            .map(|line| line.y)
+            .fold(f32::INFINITY, f32::min);
+        let top = lines
+            .iter()
+            .map(|line| line.y + line.height)
+            .fold(f32::NEG_INFINITY, f32::max);
+        Self {
+            x: left,
+            y: bottom,
+            width: right - left,
+            height: top - bottom,
+            lines: lines
+                .into_iter()
+                .map(|line| {
+                    let mut line = line.clone();
+                    // A line which wasn't joined from anything is its own only chunk.
+                    if line.chunks.is_empty() {
+                        line.chunks.push(line.clone());
+                    }
+                    line
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes each page on a line of its own, in a JSON array or as JSON Lines.
+pub struct JsonRenderer<W: Write> {
+    writer: W,
+    /// Whether to write JSON Lines rather than an array.
+    lines: bool,
+    started: bool,
+}
+
+impl<W: Write> JsonRenderer<W> {
+    /// Writes the pages as a JSON array.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            lines: false,
+            started: false,
+        }
+    }
+
+    /// Writes the pages as JSON Lines.
+    pub fn lines(writer: W) -> Self {
+        Self {
+            writer,
+            lines: true,
+            started: false,
+        }
+    }
+}
+
+impl<W: Write> OutputRenderer for JsonRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        if !self.lines {
+            self.writer
+                .write_all(if self.started { b",\n" } else { b"[\n" })?;
+        }
+        self.started = true;
+        serde_json::to_writer(&mut self.writer, &JsonPage::new(page))?;
+        if self.lines {
+            writeln!(self.writer)?;
+        }
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.lines {
+            if !self.started {
+                self.writer.write_all(b"[")?;
+            }
+            self.writer.write_all(b"\n]\n")?;
+        }
+        self.writer.flush()
+    }
+}
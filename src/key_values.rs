@@ -0,0 +1,131 @@
+//! Pairs up the labels on forms with their values, going by where they are on the page.
+//!
+//! A label is a few words ending in a colon, like `Invoice Number:`. Its value is whatever follows it on the same row,
+//! up to the next label, or if nothing does, the nearest row below it which starts close to where the label does.
+//! A colon after something other than a capitalised word, as in `10:30`, belongs to the value rather than starting a
+//! new label.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    output::OutputRenderer,
+    text::{self, Page},
+};
+
+/// Labels are never longer than this many words.
+const MAX_LABEL_WORDS: usize = 5;
+
+/// How far to either side of a label the row below can start and still be its value.
+const MAX_VALUE_OFFSET: f32 = 100.0;
+
+/// Collects the key-value pairs of every page, and writes them as a JSON object once the last page is done.
+///
+/// When the same label turns up more than once, the first value is kept.
+pub struct KeyValueRenderer<W: Write> {
+    writer: W,
+    pairs: BTreeMap<String, String>,
+}
+
+impl<W: Write> KeyValueRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            pairs: BTreeMap::new(),
+        }
+    }
+}
+
+impl<W: Write> OutputRenderer for KeyValueRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let texts: Vec<String> = page
+            .rows
+            .iter()
+            .map(|row| text::plain_text(&row.text))
+            .collect();
+        for (index, text) in texts.iter().enumerate() {
+            let mut pairs = split_pairs(text);
+            let Some((_, last_value)) = pairs.last_mut() else {
+                continue;
+            };
+            if last_value.is_empty() {
+                let row = &page.rows[index];
+                // The value is below the label, which means further down the page and so lower down in PDF space.
+                let below = page
+                    .rows
+                    .iter()
+                    .zip(&texts)
+                    .filter(|(other, text)| {
+                        other.y < row.y
+                            && (other.x - row.x).abs() <= MAX_VALUE_OFFSET
+                            && split_pairs(text).is_empty()
+                    })
+                    .max_by(|(a, _), (b, _)| a.y.total_cmp(&b.y));
+                if let Some((_, value)) = below {
+                    *last_value = value.trim().to_owned();
+                }
+            }
+            for (label, value) in pairs {
+                if !value.is_empty() {
+                    self.pairs.entry(label).or_insert(value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut self.writer, &self.pairs)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
+
+/// Splits a row into the labels on it and the text after each of them, or nothing if it doesn't start with a label.
+fn split_pairs(text: &str) -> Vec<(String, String)> {
+    let mut segments = text.split(':');
+    let Some(first_label) = segments
+        .next()
+        .map(str::trim)
+        .filter(|label| is_label(label))
+    else {
+        return Vec::new();
+    };
+    let mut segments = segments.peekable();
+    if segments.peek().is_none() {
+        return Vec::new();
+    }
+    let mut pairs = vec![(first_label.to_owned(), String::new())];
+    while let Some(segment) = segments.next() {
+        let value = &mut pairs.last_mut().unwrap().1;
+        if segments.peek().is_none() {
+            value.push_str(segment);
+            break;
+        }
+        // Anything between two colons is the value of one label, followed by the next label, which is taken to be
+        // the last word since there is no telling where a label of several words would start.
+        match segment.trim_end().rsplit_once(' ') {
+            Some((rest, label)) if is_label(label) => {
+                value.push_str(rest);
+                *value = value.trim().to_owned();
+                pairs.push((label.to_owned(), String::new()));
+            }
+            _ if segment.trim().is_empty() => {}
+            _ => {
+                value.push_str(segment);
+                value.push(':');
+            }
+        }
+    }
+    for (_, value) in &mut pairs {
+        *value = value.trim().to_owned();
+    }
+    pairs
+}
+
+fn is_label(text: &str) -> bool {
+    let words = text.split_whitespace().count();
+    (1..=MAX_LABEL_WORDS).contains(&words) && text.starts_with(|first: char| first.is_uppercase())
+}
@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::text::{self, TextChunk};
+
+/// Common English words which say nothing about what a document is about.
+#[rustfmt::skip]
+const STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "also", "am", "an", "and", "any",
+    "are", "as", "at", "be", "because", "been", "before", "being", "below", "between", "both",
+    "but", "by", "can", "could", "did", "do", "does", "doing", "down", "during", "each", "few",
+    "for", "from", "further", "had", "has", "have", "having", "he", "her", "here", "hers",
+    "herself", "him", "himself", "his", "how", "i", "if", "in", "into", "is", "it", "its",
+    "itself", "just", "may", "me", "might", "more", "most", "must", "my", "myself", "no", "nor",
+    "not", "now", "of", "off", "on", "once", "only", "or", "other", "our", "ours", "ourselves",
+    "out", "over", "own", "same", "shall", "she", "should", "so", "some", "such", "than", "that",
+    "the", "their", "theirs", "them", "themselves", "then", "there", "these", "they", "this",
+    "those", "through", "to", "too", "under", "until", "up", "upon", "very", "was", "we", "were",
+    "what", "when", "where", "which", "while", "who", "whom", "why", "will", "with", "would",
+    "you", "your", "yours", "yourself", "yourselves",
+];
+
+#[derive(Default)]
+pub struct Keyword {
+    pub count: usize,
+    /// How many times the keyword appears on each page.
+    pub pages: BTreeMap<u32, usize>,
+}
+
+pub struct KeywordReport {
+    pub terms: Vec<(String, Keyword)>,
+    pub bigrams: Vec<(String, Keyword)>,
+}
+
+/// Counts the terms and pairs of adjacent terms in the rows, keeping the `top` most frequent of each.
+pub fn find_keywords(rows: &[TextChunk], top: usize) -> KeywordReport {
+    let mut terms: HashMap<String, Keyword> = HashMap::new();
+    let mut bigrams: HashMap<String, Keyword> = HashMap::new();
+    for page_rows in rows.chunk_by(|a, b| a.page == b.page) {
+        let page = page_rows[0].page;
+        // Stopwords are kept as `None` so that bigrams never span them.
+        let words = page_rows
+            .iter()
+            .flat_map(|row| tokenize(&text::plain_text(&row.text)))
+            .map(|word| (!is_stopword(&word)).then_some(word))
+            .collect::<Vec<_>>();
+        for word in words.iter().flatten() {
+            count(&mut terms, word.clone(), page);
+        }
+        for pair in words.windows(2) {
+            if let [Some(first), Some(second)] = pair {
+                count(&mut bigrams, format!("{} {}", first, second), page);
+            }
+        }
+    }
+    KeywordReport {
+        terms: most_frequent(terms, top),
+        bigrams: most_frequent(bigrams, top),
+    }
+}
+
+pub fn print_keywords(report: &KeywordReport) {
+    println!("Terms:");
+    print_keyword_list(&report.terms);
+    println!();
+    println!("Bigrams:");
+    print_keyword_list(&report.bigrams);
+}
+
+fn print_keyword_list(keywords: &[(String, Keyword)]) {
+    let width = keywords
+        .iter()
+        .map(|(word, _)| word.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (word, keyword) in keywords {
+        let pages = keyword
+            .pages
+            .iter()
+            .map(|(page, count)| format!("{} ({})", page, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("  {:width$}  {:>5}  pages {}", word, keyword.count, pages);
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        // Single letters and bare numbers are almost never useful keywords.
+        .filter(|word| word.chars().count() > 1 && !word.chars().all(|c| c.is_numeric()))
+        .collect()
+}
+
+/// Whether a lowercase word is one of the common English words that make up much of any English text.
+pub fn is_stopword(word: &str) -> bool {
+    STOPWORDS.binary_search(&word).is_ok()
+}
+
+fn count(keywords: &mut HashMap<String, Keyword>, word: String, page: u32) {
+    let keyword = keywords.entry(word).or_default();
+    keyword.count += 1;
+    *keyword.pages.entry(page).or_insert(0) += 1;
+}
+
+fn most_frequent(keywords: HashMap<String, Keyword>, top: usize) -> Vec<(String, Keyword)> {
+    let mut keywords = keywords.into_iter().collect::<Vec<_>>();
+    // Ties are broken alphabetically so that the output is stable.
+    keywords
+        .sort_by(|(a_word, a), (b_word, b)| b.count.cmp(&a.count).then_with(|| a_word.cmp(b_word)));
+    keywords.truncate(top);
+    keywords
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+use crate::text::TextChunk;
+
+#[derive(Serialize)]
+pub struct Detection {
+    /// The ISO 639-3 code of the language.
+    pub language: &'static str,
+    pub name: &'static str,
+    pub confidence: f64,
+    /// Whether there was enough text for the detection to be trusted.
+    pub reliable: bool,
+}
+
+#[derive(Serialize)]
+pub struct PageLanguage {
+    pub page: u32,
+    /// This is `None` if the page has too little text to tell.
+    pub detection: Option<Detection>,
+}
+
+#[derive(Serialize)]
+pub struct LanguageReport {
+    pub document: Option<Detection>,
+    pub pages: Vec<PageLanguage>,
+}
+
+pub fn detect(text: &str) -> Option<Detection> {
+    whatlang::detect(text).map(|info| Detection {
+        language: info.lang().code(),
+        name: info.lang().eng_name(),
+        confidence: info.confidence(),
+        reliable: info.is_reliable(),
+    })
+}
+
+/// Detects the language of some rows, taken together.
+pub fn detect_rows(rows: &[TextChunk]) -> Option<Detection> {
+    detect(&join_rows(rows))
+}
+
+pub fn detect_languages(rows: &[TextChunk]) -> LanguageReport {
+    let pages = rows
+        .chunk_by(|a, b| a.page == b.page)
+        .map(|page_rows| PageLanguage {
+            page: page_rows[0].page,
+            detection: detect_rows(page_rows),
+        })
+        .collect();
+    LanguageReport {
+        document: detect_rows(rows),
+        pages,
+    }
+}
+
+fn join_rows(rows: &[TextChunk]) -> String {
+    rows.iter()
+        .map(|row| row.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,131 @@
+//! Plain text laid out the way it is on the page, in a grid of characters, as `pdftotext -layout` does.
+//!
+//! Each line of the page becomes a line of text, and each piece of the line starts as many characters across as it
+//! is drawn across the page, counting in the usual width of a character on that page, so that the columns of
+//! tables, invoices and forms stay lined up. Lines further apart than usual have blank lines between them, as
+//! paragraphs and sections do on the page.
+//!
+//! The pieces of a line are the parts of it with a wide gap between them, which extraction keeps apart when columns
+//! are kept. Superscript and subscript markup is left out, since it would push the rest of the line out of place, and
+//! columns of vertical text, which can't go in a grid of lines, come after the rest of the page, one to a line.
+
+use std::io::{self, Write};
+
+use crate::{
+    output::OutputRenderer,
+    text::{self, Page, TextChunk},
+};
+
+/// Pages without any text to measure are laid out with characters this wide, which is about how wide the characters
+/// of 12 point text are.
+const CHARACTER_WIDTH: f32 = 6.0;
+
+/// The most blank lines that go between two lines, however far apart they are on the page.
+const MAX_BLANK_LINES: usize = 2;
+
+pub struct LayoutRenderer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LayoutRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write> OutputRenderer for LayoutRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        for line in lay_out(page) {
+            writeln!(self.writer, "{}", line)?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Lays the rows of a page out in a grid of characters, returning the lines of the grid.
+pub fn lay_out(page: &Page) -> Vec<String> {
+    let (mut horizontal, vertical): (Vec<_>, Vec<_>) = page
+        .rows
+        .iter()
+        .map(|row| (row, text::plain_text(&row.text)))
+        .filter(|(_, text)| !text.trim().is_empty())
+        .partition(|(row, _)| !row.vertical);
+
+    let character_width = median(
+        horizontal
+            .iter()
+            .filter(|(row, _)| row.width > 0.0)
+            .map(|(row, text)| row.width / text.chars().count() as f32)
+            .collect(),
+    )
+    .unwrap_or(CHARACTER_WIDTH);
+    let left = horizontal
+        .iter()
+        .map(|(row, _)| row.x)
+        .min_by(f32::total_cmp)
+        .unwrap_or(0.0);
+
+    // Rows whose baselines are less than half their height apart are on the same line, which keeps superscripts and
+    // subscripts on the line they belong to.
+    horizontal.sort_by(|(a, _), (b, _)| b.y.total_cmp(&a.y));
+    let mut lines: Vec<Vec<(&TextChunk, String)>> = Vec::new();
+    for (row, text) in horizontal {
+        match lines.last_mut() {
+            Some(line) if line[0].0.y - row.y < row.height.min(line[0].0.height) / 2.0 => {
+                line.push((row, text))
+            }
+            _ => lines.push(vec![(row, text)]),
+        }
+    }
+    let usual_gap = lower_median(
+        lines
+            .windows(2)
+            .map(|pair| pair[0][0].0.y - pair[1][0].0.y)
+            .filter(|&gap| gap > 0.0)
+            .collect(),
+    );
+
+    let mut result = Vec::new();
+    let mut previous_y: Option<f32> = None;
+    for mut line in lines {
+        let y = line[0].0.y;
+        if let (Some(previous_y), Some(usual_gap)) = (previous_y, usual_gap) {
+            let blank_lines = ((previous_y - y) / usual_gap).round() as usize;
+            for _ in 1..blank_lines.min(MAX_BLANK_LINES + 1) {
+                result.push(String::new());
+            }
+        }
+        previous_y = Some(y);
+        line.sort_by(|(a, _), (b, _)| a.x.total_cmp(&b.x));
+        let mut text = String::new();
+        let mut length = 0;
+        for (row, piece) in line {
+            let column = ((row.x - left) / character_width).round().max(0.0) as usize;
+            // Pieces which would overlap the one before them start a space after it instead.
+            let column = if length == 0 {
+                column
+            } else {
+                column.max(length + 1)
+            };
+            text.extend(std::iter::repeat_n(' ', column - length));
+            text.push_str(&piece);
+            length = column + piece.chars().count();
+        }
+        result.push(text.trim_end().to_owned());
+    }
+    result.extend(vertical.into_iter().map(|(_, text)| text));
+    result
+}
+
+/// The middle of some numbers, or `None` if there aren't any.
+fn median(mut numbers: Vec<f32>) -> Option<f32> {
+    numbers.sort_by(f32::total_cmp);
+    numbers.get(numbers.len() / 2).copied()
+}
+
+/// The middle of some numbers, or the smaller of the two in the middle if there is an even number of them, so that
+/// the gaps between the lines of a page with only a few lines are measured against the narrower ones.
+fn lower_median(mut numbers: Vec<f32>) -> Option<f32> {
+    numbers.sort_by(f32::total_cmp);
+    numbers.get(numbers.len().checked_sub(1)? / 2).copied()
+}
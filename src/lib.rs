@@ -0,0 +1,70 @@
+pub mod alto;
+pub mod bates;
+pub mod bidi;
+pub mod cache;
+pub mod chunks;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod cmap;
+pub mod content;
+pub mod csv;
+pub mod daemon;
+pub mod decorations;
+pub mod filters;
+pub mod font;
+pub mod fontprog;
+pub mod footnotes;
+pub mod formula;
+pub mod glyph_names;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod headings;
+pub mod hocr;
+pub mod html;
+pub mod hyphenation;
+#[cfg(feature = "index")]
+pub mod index;
+pub mod json;
+pub mod key_values;
+pub mod keywords;
+#[cfg(feature = "language")]
+pub mod language;
+pub mod layout;
+pub mod links;
+pub mod lists;
+pub mod load;
+pub mod markdown;
+pub mod mask;
+pub mod outline;
+pub mod output;
+pub mod page_numbers;
+pub mod page_range;
+pub mod page_xml;
+pub mod paragraphs;
+#[cfg(feature = "language")]
+pub mod parallel;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod positioned;
+pub mod quality;
+pub mod references;
+pub mod resources;
+pub mod running_headers;
+pub mod scripts;
+pub mod search;
+pub mod simple_page;
+pub mod speech;
+pub mod split;
+pub mod standard_fonts;
+pub mod structure;
+pub mod tables;
+pub mod tei;
+pub mod text;
+pub mod text_state;
+pub mod toc;
+pub mod traversal;
+pub mod truetype;
+#[cfg(feature = "tui")]
+pub mod viewer;
+pub mod watermarks;
+pub mod words;
@@ -0,0 +1,105 @@
+//! Finds the links on a page and marks up the text they are on.
+//!
+//! A link is an annotation covering a rectangle of the page, which does something when it is clicked, and the text
+//! drawn inside the rectangle is the text of the link. Only links which open an address outside of the document, with
+//! a `URI` action, are marked up, since links to other places in the document lead nowhere once its text is out of it.
+//! Text is in a link if the middle of it is, since the rectangles of links are often drawn a little smaller or bigger
+//! than their text.
+
+use lopdf::{Document, Object, ObjectId};
+
+use crate::text::TextChunk;
+
+/// The characters percent-encoded in the addresses of links, which would otherwise end the markup they are in.
+const ENCODED_CHARACTERS: [char; 4] = ['"', '<', '>', ' '];
+
+/// A link to an address outside of the document.
+pub struct Link {
+    /// The corners of the rectangle it covers, left, bottom, right and top, in the default coordinates of the page.
+    pub rectangle: [f32; 4],
+    pub uri: String,
+}
+
+/// Finds the links to addresses on a page, from its annotations. Annotations which can't be read are left out.
+pub fn page_links(document: &Document, page_id: ObjectId) -> Vec<Link> {
+    let Some(annotations) = document
+        .get_dictionary(page_id)
+        .ok()
+        .and_then(|page| page.get(b"Annots").ok())
+        .and_then(|annotations| document.dereference(annotations).ok())
+        .and_then(|(_, annotations)| annotations.as_array().ok())
+    else {
+        return Vec::new();
+    };
+    annotations
+        .iter()
+        .filter_map(|annotation| {
+            let (_, annotation) = document.dereference(annotation).ok()?;
+            let annotation = annotation.as_dict().ok()?;
+            if annotation.get(b"Subtype").and_then(Object::as_name).ok()? != b"Link" {
+                return None;
+            }
+            let (_, action) = document.dereference(annotation.get(b"A").ok()?).ok()?;
+            let action = action.as_dict().ok()?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"URI" {
+                return None;
+            }
+            let (_, uri) = document.dereference(action.get(b"URI").ok()?).ok()?;
+            let uri = String::from_utf8_lossy(uri.as_str().ok()?)
+                .trim()
+                .to_owned();
+            let (_, rectangle) = document.dereference(annotation.get(b"Rect").ok()?).ok()?;
+            let numbers = rectangle
+                .as_array()
+                .ok()?
+                .iter()
+                .map(Object::as_float)
+                .collect::<Result<Vec<_>, _>>()
+                .ok()?;
+            let [x0, y0, x1, y1] = <[f32; 4]>::try_from(numbers).ok()?;
+            (!uri.is_empty()).then(|| Link {
+                rectangle: [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)],
+                uri,
+            })
+        })
+        .collect()
+}
+
+/// Marks up the chunks inside links with `<a href="...">`, outside of any other markup they have.
+pub fn mark(chunks: &mut [TextChunk], links: &[Link]) {
+    for chunk in chunks {
+        // The middle of the text is a quarter of its height above the baseline, since that is about half way up its
+        // lowercase letters.
+        let x = chunk.x + chunk.width / 2.0;
+        let y = chunk.y + chunk.height / 4.0;
+        let Some(link) = links.iter().find(|link| {
+            let [left, bottom, right, top] = link.rectangle;
+            (left..=right).contains(&x) && (bottom..=top).contains(&y)
+        }) else {
+            continue;
+        };
+        let text = &chunk.text;
+        let start = text.len() - text.trim_start().len();
+        let end = text.trim_end().len();
+        if start >= end {
+            continue;
+        }
+        let uri: String = link
+            .uri
+            .chars()
+            .map(|character| match character {
+                character if ENCODED_CHARACTERS.contains(&character) => {
+                    format!("%{:02X}", character as u32)
+                }
+                character => character.to_string(),
+            })
+            .collect();
+        chunk.text = format!(
+            "{}<a href=\"{}\">{}</a>{}",
+            &text[..start],
+            uri,
+            &text[start..end],
+            &text[end..]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Finds the bulleted and numbered lists in the rows of a page.
+//!
+//! A list item starts with a row which begins with a bullet, such as "•" or "-", or with a number, letter or Roman
+//! numeral followed by a full stop or bracket, such as "3.", "b)" or "(iv)". It goes on over the rows after it which
+//! are indented further than its marker, as the lines of an item are when they wrap, and items whose markers are
+//! indented further than the one before them are nested inside it. The items of a list are all bulleted or all
+//! numbered, apart from the lists nested in them, so a numbered item straight after a bulleted list starts another.
+//! Numbers and letters also start headings and the odd sentence, so a numbered item on its own isn't taken to be a
+//! list, while a bullet always starts one.
+
+use crate::{hyphenation::Hyphenation, text::TextChunk};
+
+/// The bullets list items can start with.
+const BULLETS: [&str; 16] = [
+    "•", "◦", "▪", "▫", "‣", "⁃", "●", "○", "■", "□", "►", "➢", "–", "—", "-", "*",
+];
+
+/// Markers this close to each other across the page, in the units of the page, are at the same level.
+const MARGIN_TOLERANCE: f32 = 1.0;
+
+/// The rows of an item are indented at most this many times the height of their text past its marker.
+const MAX_INDENT: f32 = 4.0;
+
+/// The rows of an item are at most this many times the height of their text below the row before them.
+const LINE_SPACING: f32 = 1.5;
+
+/// Items are at most this many times the height of their text below the item before them, however the items of the
+/// list are spaced out.
+const ITEM_SPACING: f32 = 3.0;
+
+pub struct ListItem {
+    /// How deeply the item is nested in other lists, from 0 for an item of the outermost one.
+    pub level: usize,
+    /// Whether the item is numbered or lettered, rather than bulleted.
+    pub ordered: bool,
+    /// The bullet or number the item starts with, as printed.
+    pub marker: String,
+    /// The text of the item after its marker, with its rows joined into one line.
+    pub text: String,
+}
+
+/// A row of a page, or the item of a list that some of its rows make up.
+pub enum Block<'a> {
+    Row(&'a TextChunk),
+    Item(ListItem),
+}
+
+/// Splits the start of a row into its list marker, whether the marker is a number rather than a bullet, and the text
+/// after it, if the row starts a list item.
+pub fn marker(text: &str) -> Option<(&str, bool, &str)> {
+    let (marker, rest) = text.trim_start().split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    if rest.is_empty() {
+        return None;
+    }
+    if BULLETS.contains(&marker) {
+        return Some((marker, false, rest));
+    }
+    let number = marker
+        .strip_prefix('(')
+        .and_then(|marker| marker.strip_suffix(')'))
+        .or_else(|| marker.strip_suffix(['.', ')']))?;
+    let is_number = (1..=3).contains(&number.len())
+        && number.chars().all(|character| character.is_ascii_digit());
+    let is_letter = number.len() == 1
+        && number
+            .chars()
+            .all(|character| character.is_ascii_alphabetic());
+    let is_roman = (1..=6).contains(&number.len())
+        && number
+            .chars()
+            .all(|character| "ivxlcdm".contains(character));
+    (is_number || is_letter || is_roman).then_some((marker, true, rest))
+}
+
+/// An item being put together, with the rows it is made of so far.
+struct OpenItem<'a> {
+    item: ListItem,
+    rows: Vec<&'a TextChunk>,
+}
+
+/// Puts the rows of a page which are in lists together into their items, leaving the other rows as they are.
+pub fn find(rows: &[TextChunk]) -> Vec<Block<'_>> {
+    let hyphenation = Hyphenation::default();
+    let mut blocks = Vec::new();
+    // The items of the list being put together, and where the markers of the lists it is nested in are.
+    let mut items: Vec<OpenItem> = Vec::new();
+    let mut levels: Vec<f32> = Vec::new();
+    for row in rows {
+        if let Some((marker, ordered, rest)) = marker(&row.text).filter(|_| !row.vertical) {
+            if items
+                .last()
+                .is_some_and(|open| !is_next_item(&open.rows, row))
+            {
+                end_list(&mut blocks, &mut items);
+                levels.clear();
+            }
+            while levels.last().is_some_and(|&x| row.x < x - MARGIN_TOLERANCE) {
+                levels.pop();
+            }
+            if levels.last().is_none_or(|&x| row.x > x + MARGIN_TOLERANCE) {
+                levels.push(row.x);
+            }
+            let level = levels.len() - 1;
+            let sibling = items
+                .iter()
+                .rev()
+                .map(|open| &open.item)
+                .find(|item| item.level <= level);
+            if sibling.is_some_and(|item| item.level == level && item.ordered != ordered) {
+                end_list(&mut blocks, &mut items);
+                levels.clear();
+                levels.push(row.x);
+            }
+            items.push(OpenItem {
+                item: ListItem {
+                    level: levels.len() - 1,
+                    ordered,
+                    marker: marker.to_owned(),
+                    text: rest.to_owned(),
+                },
+                rows: vec![row],
+            });
+            continue;
+        }
+        if let Some(open) = items.last_mut().filter(|open| continues(open, row)) {
+            hyphenation.join_line(&mut open.item.text, &row.text);
+            open.rows.push(row);
+            continue;
+        }
+        end_list(&mut blocks, &mut items);
+        levels.clear();
+        blocks.push(Block::Row(row));
+    }
+    end_list(&mut blocks, &mut items);
+    blocks
+}
+
+/// Whether a row starting an item is close enough below the last row of the item before to be in the same list.
+fn is_next_item(rows: &[&TextChunk], row: &TextChunk) -> bool {
+    let last = rows[rows.len() - 1];
+    let drop = last.y - row.y;
+    drop > 0.0 && drop <= last.height * ITEM_SPACING
+}
+
+/// Whether a row carries on the text of an item, by being on the next line and indented past its marker.
+fn continues(open: &OpenItem, row: &TextChunk) -> bool {
+    let first = open.rows[0];
+    let last = open.rows[open.rows.len() - 1];
+    let indent = row.x - first.x;
+    let drop = last.y - row.y;
+    !row.vertical
+        && indent > MARGIN_TOLERANCE
+        && indent <= row.height * MAX_INDENT
+        && drop > 0.0
+        && drop <= last.height * LINE_SPACING
+}
+
+/// Adds the items of a list to the blocks, or their rows if they are a numbered item on its own.
+fn end_list<'a>(blocks: &mut Vec<Block<'a>>, items: &mut Vec<OpenItem<'a>>) {
+    if items.len() == 1 && items[0].item.ordered {
+        blocks.extend(items.remove(0).rows.into_iter().map(Block::Row));
+    } else {
+        blocks.extend(items.drain(..).map(|open| Block::Item(open.item)));
+    }
+}
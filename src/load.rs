@@ -0,0 +1,93 @@
+//! Loads documents, including ones far bigger than the memory available.
+//!
+//! Normally the whole file is read into memory and then parsed, which leaves two copies of nearly everything in it
+//! around while it loads. In large file mode the file is memory mapped instead, and the data of any stream that can't
+//! hold text (images, embedded files and metadata) is thrown away as soon as each object has been parsed.
+//! Those streams are what make files big, so what remains is roughly the size of the text content and fonts alone.
+//!
+//! Past that point, memory use is bounded by the way extraction already works: pages are extracted and output one at
+//! a time, content streams are only inflated while their page is being processed and never beyond the page budget,
+//! and parsed fonts live in a cache of fixed size. So the memory needed for a multi-gigabyte file mostly depends on
+//! how much text it has, not on how big it is. The one exception is the commands which look at the whole document at
+//! once, such as searches, which keep all of its text in memory.
+//!
+//! The mapping itself doesn't count against memory in the same way, since the operating system can drop its pages
+//! whenever it likes. Files can't be bigger than 4 GiB though, since the parser keeps the positions of objects as 32
+//! bit numbers.
+
+use std::{error::Error, fs, fs::File, path::Path};
+
+use lopdf::{Document, Object, ObjectId, Reader};
+use memmap2::Mmap;
+
+pub struct LoadOptions {
+    /// Memory maps the file instead of reading it into memory.
+    pub mmap: bool,
+    /// Memory maps the file and drops the data of streams which can't hold text, as described in the module docs.
+    pub large_file: bool,
+    /// Files bigger than this are refused, unless in large file mode.
+    pub max_memory: Option<usize>,
+}
+
+/// Loads the document without decompressing it, since streams are inflated on demand as pages are processed.
+///
+/// Files bigger than the memory limit are refused up front, since the parsed document holds a copy of nearly all of
+/// the file. Large file mode doesn't, so it leaves the limit to be enforced page by page instead.
+pub fn load_document(path: &Path, options: &LoadOptions) -> Result<Document, Box<dyn Error>> {
+    let file_size = fs::metadata(path)?.len();
+    if file_size > u64::from(u32::MAX) {
+        return Err(format!(
+            "{} is {} bytes, and files over 4 GiB aren't supported yet",
+            path.display(),
+            file_size
+        )
+        .into());
+    }
+    if let (Some(max_memory), false) = (options.max_memory, options.large_file) {
+        if file_size > max_memory as u64 {
+            return Err(format!(
+                "{} is {} bytes, which doesn't fit in the memory limit of {} bytes",
+                path.display(),
+                file_size,
+                max_memory
+            )
+            .into());
+        }
+    }
+    if options.mmap || options.large_file {
+        let file = File::open(path)?;
+        // SAFETY: If another process changes the file while it is mapped, parsing may see inconsistent data, but the
+        // mapping is only read while the document is parsed (the parsed objects own copies of their data).
+        let map = unsafe { Mmap::map(&file)? };
+        let filter: fn(_, &mut _) -> _ = drop_textless_streams;
+        let reader = Reader {
+            buffer: &map,
+            document: Document::new(),
+        };
+        return Ok(reader.read(options.large_file.then_some(filter))?);
+    }
+    Ok(Document::load(path)?)
+}
+
+/// Replaces streams which can't hold text with just their dictionary, as each object is parsed.
+fn drop_textless_streams(id: ObjectId, object: &mut Object) -> Option<(ObjectId, Object)> {
+    if let Object::Stream(stream) = object {
+        let is_textless = matches!(
+            stream.dict.get(b"Subtype").and_then(Object::as_name),
+            Ok(b"Image")
+        ) || matches!(
+            stream.dict.get(b"Type").and_then(Object::as_name),
+            Ok(b"EmbeddedFile" | b"Metadata")
+        );
+        if is_textless {
+            // Leaving the stream empty instead would make the loader go back and read its data from the file again.
+            *object = Object::Dictionary(std::mem::take(&mut stream.dict));
+        } else {
+            // Streams are never inside object streams, so the loader keeps the object as changed in place and has no
+            // use for what is returned, which would otherwise be a copy of all of the stream's data.
+            return Some((id, Object::Null));
+        }
+    }
+    // Objects are kept as changed in place, except inside object streams, where what is returned is kept instead.
+    Some((id, object.clone()))
+}
@@ -1,270 +1,455 @@
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    borrow::Cow,
     error::Error,
-    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
 };
 
-use lopdf::{Document, Object, Stream};
-
-#[derive(Debug)]
-struct Font {
-    encoding: String,
-    unicode_map: Option<BTreeMap<u32, u32>>,
-}
-
-impl Font {
-    fn decode(&self, text: &[u8]) -> String {
-        if let Some(unicode_map) = &self.unicode_map {
-            // The unicode map uses 16-byte integers, so we have to convert the text to u16.
-            let mut result = String::new();
-            for byte_pairs in text.chunks_exact(2) {
-                let code = u16::from_be_bytes(byte_pairs.try_into().unwrap()) as u32;
-                let code = unicode_map.get(&code).unwrap_or(&code);
-                result.push(std::char::from_u32(*code).unwrap());
-            }
-            return result;
-        }
-        Document::decode_text(Some(self.encoding.as_str()), text)
-    }
-}
-
-#[derive(PartialEq, Eq, Clone)]
-struct TextChunk {
-    text: String,
-    x: i32,
-    y: i32,
-}
-
-impl PartialOrd for TextChunk {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for TextChunk {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.y.cmp(&other.y).then_with(|| self.x.cmp(&other.x))
-    }
-}
+use clap::Parser;
+use cli::{Cli, Command};
+use lopdf::Document;
+use regex::{Regex, RegexBuilder};
+#[cfg(feature = "clipboard")]
+use rust_pdf_test::clipboard::ClipboardRenderer;
+#[cfg(feature = "index")]
+use rust_pdf_test::index::TextIndex;
+#[cfg(feature = "plugins")]
+use rust_pdf_test::plugin::PluginRenderer;
+#[cfg(feature = "tui")]
+use rust_pdf_test::viewer::Viewer;
+use rust_pdf_test::{
+    alto::AltoRenderer,
+    bates::{BatesRenderer, LoadFile},
+    cache::{CachedResult, ResultCache},
+    chunks::ChunkRenderer,
+    csv::CsvRenderer,
+    daemon,
+    footnotes::FootnoteMover,
+    formula,
+    headings::HeadingFinder,
+    hocr::HocrRenderer,
+    html,
+    html::HtmlRenderer,
+    json::JsonRenderer,
+    key_values::KeyValueRenderer,
+    keywords,
+    layout::LayoutRenderer,
+    load::{load_document, LoadOptions},
+    markdown::MarkdownRenderer,
+    mask::MaskingRenderer,
+    outline::{self, Bookmark},
+    output::{Format, OutputRenderer, PlainTextRenderer, WINDOW_PAGES},
+    page_numbers::PageNumberRemover,
+    page_xml::PageXmlRenderer,
+    paragraphs::ParagraphRenderer,
+    positioned::PositionedRenderer,
+    quality::QualityRenderer,
+    references,
+    running_headers::RunningHeaderRemover,
+    scripts::{ScriptMarkup, ScriptRewriter},
+    search,
+    speech::SpeechRenderer,
+    split::{BookmarkSplitter, PageSplitter, SplitBy},
+    structure,
+    tables::TableExporter,
+    tei::TeiRenderer,
+    text::{self, ExtractOptions, RowCollector, TextChunk, TextExtractor},
+    toc,
+    traversal::{self, TraversalError},
+    watermarks::WatermarkRemover,
+};
+#[cfg(feature = "language")]
+use rust_pdf_test::{language, parallel};
 
-impl Display for TextChunk {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.text.fmt(f)
-    }
-}
+mod cli;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut document = lopdf::Document::load("test.pdf")?;
-    document.decompress();
+    let cli = Cli::parse();
+    let options = cli.extract_options();
+    let load_options = cli.load_options();
+    let hyphenation = cli.hyphenation()?;
+    let cache = cli.cache_dir.map(ResultCache::new).transpose()?;
+    let cache = cache.as_ref();
 
-    let mut fonts = BTreeMap::new();
-    // We have to find the fonts for each page, since there is no API to get all of the fonts.
-    for page_id in document.get_pages().values() {
-        for (font_id, font_data) in document.get_page_fonts(*page_id) {
-            if !fonts.contains_key(font_id.as_slice()) {
-                let unicode_map =
-                    if let Ok(Object::Reference(unicode_map_id)) = font_data.get(b"ToUnicode") {
-                        let unicode_map = document
-                            .objects
-                            .get(unicode_map_id)
-                            .expect("Unicode map id invalid");
-                        Some(parse_unicode_map(unicode_map.as_stream()?))
-                    } else {
-                        None
-                    };
-                let font = Font {
-                    encoding: font_data.get_font_encoding().to_owned(),
-                    unicode_map,
-                };
-                fonts.insert(font_id, font);
-            }
+    if cli.daemon {
+        if cli.command.is_some() {
+            return Err("--daemon can't be used with a subcommand".into());
         }
+        daemon::run(
+            io::stdin().lock(),
+            io::stdout().lock(),
+            load_options,
+            options,
+        )?;
+        return Ok(());
     }
 
-    let mut text_chunks = Vec::new();
-
-    for (page_number, page_id) in document.get_pages() {
-        let mut current_font_id = None;
-
-        let mut in_text = false;
-        let mut current_text = String::new();
-        let mut x = 0;
-        let mut y = 0;
-
-        for operation in document
-            .get_and_decode_page_content(page_id)
-            .unwrap()
-            .operations
-        {
-            match operation.operator.as_str() {
-                "BT" => in_text = true,
-                "ET" => {
-                    in_text = false;
-                    text_chunks.push(TextChunk {
-                        text: current_text,
-                        x,
-                        y,
-                    });
-                    current_text = String::new();
+    match cli.command {
+        Some(Command::Search(args)) => {
+            let pattern = if args.fixed_strings {
+                regex::escape(&args.pattern)
+            } else {
+                args.pattern.clone()
+            };
+            let pattern = RegexBuilder::new(&pattern)
+                .case_insensitive(args.ignore_case)
+                .build()?;
+            let source = Source::open(&args.file, &load_options, &options, cache)?;
+            let rows = source.rows(&options, cache)?;
+            let hits = search::search(&rows, &pattern);
+            search::print_hits(&rows, &hits, args.context());
+        }
+        Some(Command::Keywords { file, top }) => {
+            let source = Source::open(&file, &load_options, &options, cache)?;
+            let rows = source.rows(&options, cache)?;
+            keywords::print_keywords(&keywords::find_keywords(&rows, top));
+        }
+        Some(Command::Formulas { file }) => {
+            let document = load_document(&file, &load_options)?;
+            let formulas = formula::find_formulas(&document, options.budget.max_bytes)?;
+            println!("{}", serde_json::to_string_pretty(&formulas)?);
+        }
+        Some(Command::Toc { file }) => {
+            let source = Source::open(&file, &load_options, &options, cache)?;
+            let outline = source.outline(&file, &load_options)?;
+            let contents = toc::table_of_contents(outline, &source.rows(&options, cache)?);
+            println!("{}", serde_json::to_string_pretty(&contents)?);
+        }
+        Some(Command::Quality { file }) => {
+            let source = Source::open(&file, &load_options, &options, cache)?;
+            source.render(
+                &options,
+                cache,
+                &mut QualityRenderer::new(io::stdout().lock()),
+            )?;
+        }
+        Some(Command::References { file }) => {
+            let source = Source::open(&file, &load_options, &options, cache)?;
+            let references = references::find_references(&source.rows(&options, cache)?);
+            println!("{}", serde_json::to_string_pretty(&references)?);
+        }
+        Some(Command::Bates { output_dir, files }) => {
+            let mut load_file = LoadFile::new(output_dir)?;
+            for file in files {
+                let text_path = load_file.text_path(&file);
+                let mut renderer = BatesRenderer::new(&text_path)?;
+                let source = Source::open(&file, &load_options, &options, cache)?;
+                source.render(&options, cache, &mut renderer)?;
+                load_file.add(&file, &text_path, &renderer)?;
+            }
+            load_file.finish()?;
+        }
+        #[cfg(feature = "language")]
+        Some(Command::Language { file }) => {
+            let source = Source::open(&file, &load_options, &options, cache)?;
+            let rows = source.rows(&options, cache)?;
+            let report = language::detect_languages(&rows);
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        #[cfg(feature = "language")]
+        Some(Command::Parallel { file }) => {
+            let source = Source::open(&file, &load_options, &options, cache)?;
+            let rows = source.rows(&options, cache)?;
+            println!("{}", serde_json::to_string_pretty(&parallel::align(&rows))?);
+        }
+        #[cfg(feature = "tui")]
+        Some(Command::View { file }) => {
+            let source = Source::open(&file, &load_options, &options, cache)?;
+            let mut viewer = Viewer::new(source.outline(&file, &load_options)?);
+            source.render(&options, cache, &mut viewer)?;
+            viewer.run()?;
+        }
+        #[cfg(feature = "grpc")]
+        Some(Command::Grpc {
+            listen,
+            max_document_size,
+        }) => {
+            rust_pdf_test::grpc::serve(listen, options, max_document_size)?;
+        }
+        #[cfg(feature = "index")]
+        Some(Command::Index { index_dir, files }) => {
+            let mut writer = TextIndex::open(&index_dir)?.writer()?;
+            for file in files {
+                let source = Source::open(&file, &load_options, &options, cache)?;
+                writer.start_file(&file)?;
+                source.render(&options, cache, &mut writer)?;
+            }
+        }
+        #[cfg(feature = "index")]
+        Some(Command::Query {
+            index_dir,
+            query,
+            limit,
+        }) => {
+            for hit in TextIndex::open(&index_dir)?.search(&query, limit)? {
+                println!(
+                    "{}: page {} ({}, {}): {}",
+                    hit.path, hit.page, hit.x, hit.y, hit.text
+                );
+            }
+        }
+        None => {
+            let path = cli
+                .file
+                .as_deref()
+                .ok_or("no file to extract the text of")?;
+            let source = Source::open(path, &load_options, &options, cache)?;
+            let writer: Box<dyn Write> = match &cli.output {
+                Some(output) => Box::new(BufWriter::new(File::create(output)?)),
+                None => Box::new(io::stdout().lock()),
+            };
+            let masked = !cli.mask.is_empty() || !cli.mask_pattern.is_empty();
+            if cli.format == Format::Html {
+                let document = source.document(path, &load_options)?;
+                // Masking works on rows, which tagged documents don't go through, so they are written as rows too.
+                if structure::is_tagged(&document) && !masked {
+                    let mut extractor = TextExtractor::new(document.as_ref(), &options);
+                    html::write_tagged(&mut extractor, options.budget.max_bytes, writer)?;
+                    return Ok(());
                 }
-                "Tf" => {
-                    let font_id = operation.operands[0].as_name().unwrap();
-                    current_font_id = Some(font_id.to_owned());
+            }
+            if cli.script_markup != ScriptMarkup::Html && cli.format != Format::Text {
+                return Err("--script-markup only applies to --format text".into());
+            }
+            if cli.format == Format::PageXml && cli.output_dir.is_none() {
+                return Err("PAGE XML has one page in each file, so it needs --output-dir".into());
+            }
+            let mut renderer: Box<dyn OutputRenderer> = match (cli.split_by, cli.output_dir) {
+                (None, Some(directory)) if cli.format == Format::PageXml => {
+                    let image = path.display().to_string();
+                    let word_gap = cli.word_gap;
+                    Box::new(PageSplitter::with_renderer(
+                        directory,
+                        source.page_count()?,
+                        "xml",
+                        Box::new(move |file| {
+                            Box::new(PageXmlRenderer::new(file, image.clone(), word_gap))
+                        }),
+                    )?)
+                }
+                (Some(SplitBy::Pages), Some(directory)) => {
+                    Box::new(PageSplitter::new(directory, source.page_count()?)?)
+                }
+                (Some(SplitBy::Bookmarks), Some(directory)) => Box::new(BookmarkSplitter::new(
+                    directory,
+                    &source.outline(path, &load_options)?,
+                    cli.split_depth,
+                )?),
+                #[cfg(feature = "plugins")]
+                _ if cli.plugin.is_some() => Box::new(PluginRenderer::load(
+                    cli.plugin.as_deref().unwrap(),
+                    writer,
+                )?),
+                #[cfg(feature = "clipboard")]
+                _ if cli.clipboard => Box::new(ClipboardRenderer::new()),
+                _ if matches!(cli.format, Format::Chunks | Format::VectorRecords) => {
+                    Box::new(ChunkRenderer::new(
+                        writer,
+                        path.display().to_string(),
+                        source.outline(path, &load_options)?,
+                        cli.chunk_size.get(),
+                        cli.overlap,
+                        cli.format == Format::VectorRecords,
+                        hyphenation,
+                    ))
                 }
-                "Tj" if in_text => {
-                    let text = operation.operands[0].as_str().unwrap();
-                    let font = fonts.get(current_font_id.as_ref().unwrap()).unwrap();
-                    current_text.push_str(&font.decode(text));
+                _ if cli.format == Format::Html => Box::new(HtmlRenderer::new(writer, hyphenation)),
+                _ if cli.format == Format::Json => Box::new(JsonRenderer::new(writer)),
+                _ if cli.format == Format::Jsonl => Box::new(JsonRenderer::lines(writer)),
+                _ if cli.format == Format::Hocr => {
+                    Box::new(HocrRenderer::new(writer, cli.word_gap))
                 }
-                "Tm" => {
-                    // The matrix is 3x2, where the first two rows give us scaling and stuff, and the third one gives us the position.
-                    let new_x = match operation.operands[4] {
-                        Object::Integer(x) => x as i32,
-                        Object::Real(x) => x as i32,
-                        _ => panic!(
-                            "Expected integer or real, found {:?}",
-                            operation.operands[4]
-                        ),
-                    };
-                    let new_y = match operation.operands[5] {
-                        Object::Integer(y) => y as i32,
-                        Object::Real(y) => y as i32,
-                        _ => panic!(
-                            "Expected integer or real, found {:?}",
-                            operation.operands[5]
-                        ),
-                    };
-                    x = new_x;
-                    y = new_y;
+                _ if cli.format == Format::Alto => {
+                    Box::new(AltoRenderer::new(writer, cli.word_gap))
                 }
-                _ => {}
+                _ if cli.format == Format::Csv => Box::new(CsvRenderer::new(writer)),
+                _ if cli.format == Format::PositionedXhtml => {
+                    Box::new(PositionedRenderer::new(writer))
+                }
+                _ if cli.format == Format::Tei => Box::new(TeiRenderer::new(
+                    writer,
+                    path.file_stem()
+                        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+                    hyphenation,
+                )),
+                _ if cli.format == Format::Markdown => {
+                    Box::new(MarkdownRenderer::new(writer, hyphenation))
+                }
+                _ if cli.key_values => Box::new(KeyValueRenderer::new(writer)),
+                _ if cli.layout => Box::new(LayoutRenderer::new(writer)),
+                _ if cli.paragraphs => Box::new(ParagraphRenderer::new(writer, hyphenation)),
+                _ if cli.linearize_for_speech => Box::new(SpeechRenderer::new(writer, hyphenation)),
+                _ => match cli.page_separator {
+                    Some(separator) => {
+                        Box::new(PlainTextRenderer::with_page_separator(writer, separator))
+                    }
+                    None => Box::new(PlainTextRenderer::new(writer)),
+                },
+            };
+            // Superscripts and subscripts are only rewritten once nothing else needs their markup.
+            if cli.script_markup != ScriptMarkup::Html {
+                renderer = Box::new(ScriptRewriter::new(renderer, cli.script_markup));
+            }
+            if matches!(
+                cli.format,
+                Format::Html | Format::Markdown | Format::Json | Format::PageXml | Format::Tei
+            ) {
+                renderer = Box::new(HeadingFinder::new(renderer));
+            }
+            // Tables are written out after masking, so that what is masked in the text is masked in them too.
+            if let Some(path) = cli.tables_out {
+                renderer = Box::new(TableExporter::new(renderer, path)?);
+            }
+            if masked {
+                let patterns = cli
+                    .mask_pattern
+                    .iter()
+                    .map(|pattern| Regex::new(pattern))
+                    .collect::<Result<_, _>>()?;
+                renderer = Box::new(MaskingRenderer::new(renderer, &cli.mask, patterns));
             }
+            if let Some(placement) = cli.footnotes {
+                renderer = Box::new(FootnoteMover::new(renderer, placement));
+            }
+            // JSON Lines pages go out as soon as they are extracted, so they are only compared with the pages before.
+            let lookahead = if cli.format == Format::Jsonl {
+                0
+            } else {
+                WINDOW_PAGES
+            };
+            if !cli.keep_headers {
+                renderer = Box::new(RunningHeaderRemover::new(renderer, lookahead));
+            }
+            if !cli.keep_page_numbers {
+                renderer = Box::new(PageNumberRemover::new(renderer, lookahead));
+            }
+            if !cli.keep_watermarks {
+                renderer = Box::new(WatermarkRemover::new(renderer, lookahead));
+            }
+            source.render(&options, cache, renderer.as_mut())?;
         }
     }
 
-    let text_chunks = merge_text_rows(&text_chunks);
-
-    // When doing superscripts, the general pattern is that the y position moves upwards rather than downwards.
-    // We manipulate this to try to find the superscript offset, which we assume is the most common of these.
-    let mut upward_offsets = BTreeMap::new();
-    let mut previous_y = 0;
-    for text_chunk in text_chunks.iter().skip(1) {
-        let offset = text_chunk.y - previous_y;
-        // We are only interested in negative offsets, which mean that it moved upwards.
-        if offset < 0 {
-            *upward_offsets.entry(-offset).or_insert(0) += 1;
-        }
-        previous_y = text_chunk.y;
-    }
-    let (&superscript_offset, _) = upward_offsets
-        .iter()
-        .max_by_key(|(_, &count)| count)
-        .expect("No superscript offset");
-    println!("Superscript offset: {}", superscript_offset);
-    // We assume that if the difference between consecutive chunks is less than or equal to the superscript offset, it is probably a superscript or subscript.
-    let mut new_text_chunks = Vec::new();
-    let mut last_y = 0;
-    let mut last_x = 0;
-    for text_chunk in text_chunks {
-        if text_chunk.x < last_x {
-            // If the x position is less than the last x position, we assume it is a new line.
-            last_x = text_chunk.x;
-            last_y = text_chunk.y;
-            new_text_chunks.push(text_chunk);
-            continue;
-        }
-        let offset = text_chunk.y - last_y;
-        if offset.abs() <= superscript_offset && offset != 0 {
-            // If the difference is negative, it is a superscript.
-            let html_tag_name = if offset > 0 { "sub" } else { "sup" };
-            last_x = text_chunk.x;
-            new_text_chunks.push(TextChunk {
-                text: format!("<{}>{}</{}>", html_tag_name, text_chunk.text, html_tag_name),
-                x: text_chunk.x,
-                y: last_y,
-            });
-        } else {
-            last_x = text_chunk.x;
-            last_y = text_chunk.y;
-            new_text_chunks.push(text_chunk);
-        }
-    }
+    Ok(())
+}
 
-    let text_chunks = merge_text_rows(&new_text_chunks);
+/// Where the text of a file comes from: the result cache if it is there, or otherwise the loaded document.
+enum Source {
+    Cached(CachedResult),
+    Document {
+        document: Box<Document>,
+        /// The cache entry to write the extracted text to, if there is a cache.
+        cache_key: Option<String>,
+    },
+}
 
-    for text_chunk in text_chunks {
-        println!("{}", text_chunk);
+impl Source {
+    /// Looks for a file in the cache, and loads it only if it isn't there.
+    fn open(
+        path: &Path,
+        load_options: &LoadOptions,
+        options: &ExtractOptions,
+        cache: Option<&ResultCache>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let cache_key = match cache {
+            Some(cache) => {
+                let key = cache.key(path, options)?;
+                if let Some(result) = cache.load(&key) {
+                    return Ok(Source::Cached(result));
+                }
+                Some(key)
+            }
+            None => None,
+        };
+        Ok(Source::Document {
+            document: Box::new(load_document(path, load_options)?),
+            cache_key,
+        })
     }
 
-    Ok(())
-}
+    fn outline(
+        &self,
+        path: &Path,
+        load_options: &LoadOptions,
+    ) -> Result<Vec<Bookmark>, Box<dyn Error>> {
+        // The outline isn't kept in the cache, so the document has to be loaded for it anyway.
+        Ok(outline::read_outline(&*self.document(path, load_options)?)?)
+    }
 
-fn merge_text_rows(text_chunks: &[TextChunk]) -> Vec<TextChunk> {
-    let mut merged_text_chunks = Vec::new();
-    let mut last_text_chunk: Option<TextChunk> = None;
-    for text_chunk in text_chunks {
-        if let Some(last_text_chunk) = last_text_chunk.as_mut() {
-            if last_text_chunk.y == text_chunk.y {
-                last_text_chunk.text.push_str(&text_chunk.text);
-                continue;
-            }
-            merged_text_chunks.push(last_text_chunk.clone());
+    /// The document itself, which has to be loaded after all if its text came from the cache.
+    fn document(
+        &self,
+        path: &Path,
+        load_options: &LoadOptions,
+    ) -> Result<Cow<'_, Document>, Box<dyn Error>> {
+        match self {
+            Source::Document { document, .. } => Ok(Cow::Borrowed(document)),
+            Source::Cached(_) => Ok(Cow::Owned(load_document(path, load_options)?)),
         }
-        last_text_chunk = Some(text_chunk.clone());
     }
-    if let Some(last_text_chunk) = last_text_chunk {
-        merged_text_chunks.push(last_text_chunk);
+
+    fn page_count(&self) -> Result<usize, TraversalError> {
+        match self {
+            Source::Cached(result) => Ok(result.page_count),
+            Source::Document { document, .. } => Ok(traversal::pages(document)?.len()),
+        }
     }
-    merged_text_chunks
-}
 
-fn parse_unicode_map(unicode_map: &Stream) -> BTreeMap<u32, u32> {
-    let operations = unicode_map
-        .decode_content()
-        .expect("failed to decode unicode map");
-    let mut result = BTreeMap::new();
-    // The important thing to find is the endbfchar instruction, which has the actual mappings.
-    for operation in operations.operations {
-        match operation.operator.as_str() {
-            "endbfchar" => {
-                assert!(
-                    operation.operands.len() % 2 == 0,
-                    "Expected even number of operands, found {}",
-                    operation.operands.len()
-                );
-                for operands in operation.operands.chunks_exact(2).map(|operands| {
-                    operands
-                        .into_iter()
-                        .map(|operand| {
-                            u16::from_be_bytes(
-                                operand
-                                    .as_str()
-                                    .expect(
-                                        format!(
-                                            "Expected a hexadecimal integer, found {:?}",
-                                            operand
-                                        )
-                                        .as_str(),
-                                    )
-                                    .try_into()
-                                    .expect(
-                                        format!(
-                                            "Expected a hexadecimal integer, found {:?}",
-                                            operand
-                                        )
-                                        .as_str(),
-                                    ),
-                            )
-                        })
-                        .collect::<Vec<_>>()
-                }) {
-                    let key = operands[0] as u32;
-                    let value = operands[1] as u32;
-                    result.insert(key, value);
+    /// Hands each selected page to the renderer in order, adding them to the cache if they had to be extracted.
+    fn render(
+        &self,
+        options: &ExtractOptions,
+        cache: Option<&ResultCache>,
+        renderer: &mut dyn OutputRenderer,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            Source::Cached(result) => {
+                for page in &result.pages {
+                    renderer.render_page(page)?;
+                }
+            }
+            Source::Document {
+                document,
+                cache_key,
+            } => {
+                let mut cache_writer = match (cache, cache_key) {
+                    (Some(cache), Some(key)) => Some(cache.writer(key, self.page_count()?)?),
+                    _ => None,
+                };
+                // Each page is output as soon as it has been extracted, rather than after the whole document.
+                for page in TextExtractor::new(document.as_ref(), options).pages()? {
+                    let page = page?;
+                    renderer.render_page(&page)?;
+                    if let Some(cache_writer) = &mut cache_writer {
+                        cache_writer.render_page(&page)?;
+                    }
+                }
+                if let Some(cache_writer) = &mut cache_writer {
+                    cache_writer.finish()?;
                 }
             }
-            _ => {}
         }
+        renderer.finish()?;
+        Ok(())
+    }
+
+    /// Collects the text of every selected page, for commands which need all of it at once.
+    fn rows(
+        &self,
+        options: &ExtractOptions,
+        cache: Option<&ResultCache>,
+    ) -> Result<Vec<TextChunk>, Box<dyn Error>> {
+        let document_size = match self {
+            Source::Cached(_) => 0,
+            Source::Document { document, .. } => text::document_size(document),
+        };
+        let mut collector = RowCollector::new(
+            options
+                .max_memory
+                .map(|max_memory| max_memory.saturating_sub(document_size)),
+        );
+        self.render(options, cache, &mut collector)?;
+        Ok(collector.rows)
     }
-    result
 }
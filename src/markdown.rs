@@ -0,0 +1,227 @@
+//! Writes the text as Markdown.
+//!
+//! The rows of each page are put back together into paragraphs, with a blank line between each one and the next as
+//! Markdown separates them, the headings found by the size of their text become headings of the same level, and the
+//! tables found on the page become Markdown tables, with their first row as the header. The lists found in the text
+//! become Markdown lists, nested as they are on the page, and links become Markdown links to the same addresses.
+//! Bold, italic and struck through text is marked up with `**`, `*` and `~~`, and superscripts, subscripts and
+//! underlining are kept as the HTML they are marked up with, which Markdown allows. Characters which would otherwise
+//! be read as Markdown are escaped.
+
+use std::{
+    io::{self, Write},
+    mem,
+};
+
+use crate::{
+    hyphenation::Hyphenation,
+    lists::{self, Block},
+    output::{self, OutputRenderer, PagePart},
+    paragraphs,
+    text::{self, Page, TextChunk},
+};
+
+/// The Markdown for each tag of the markup of extracted text, which for superscripts, subscripts and underlining is the
+/// tag itself, since Markdown has nothing of its own for them.
+const MARKUP: [(&str, &str); 12] = [
+    ("<sup>", "<sup>"),
+    ("</sup>", "</sup>"),
+    ("<sub>", "<sub>"),
+    ("</sub>", "</sub>"),
+    ("<u>", "<u>"),
+    ("</u>", "</u>"),
+    ("<s>", "~~"),
+    ("</s>", "~~"),
+    ("<strong>", "**"),
+    ("</strong>", "**"),
+    ("<em>", "*"),
+    ("</em>", "*"),
+];
+
+/// Characters which are escaped wherever they are, since they start emphasis, strikethrough, code, links or HTML.
+const SPECIAL_CHARACTERS: [char; 8] = ['\\', '`', '*', '_', '~', '[', ']', '<'];
+
+pub struct MarkdownRenderer<W: Write> {
+    writer: W,
+    hyphenation: Hyphenation,
+    /// The paragraph being put together, which can continue onto the next page if it ends in a hyphenated word.
+    paragraph: String,
+}
+
+impl<W: Write> MarkdownRenderer<W> {
+    pub fn new(writer: W, hyphenation: Hyphenation) -> Self {
+        Self {
+            writer,
+            hyphenation,
+            paragraph: String::new(),
+        }
+    }
+
+    fn end_paragraph(&mut self) -> io::Result<()> {
+        if !self.paragraph.is_empty() {
+            writeln!(self.writer, "{}\n", self.paragraph)?;
+            self.paragraph.clear();
+        }
+        Ok(())
+    }
+
+    /// Writes some rows of a page which aren't in a table or a heading as the lists and paragraphs in them, keeping
+    /// back the last paragraph as `write_paragraphs` does.
+    fn write_blocks(&mut self, page: &Page, rows: Vec<TextChunk>, last: bool) -> io::Result<()> {
+        let mut paragraph_rows = Vec::new();
+        // Whether each list which is open is numbered, and how many items it has had so far, outermost first.
+        let mut lists: Vec<(bool, usize)> = Vec::new();
+        for block in lists::find(&rows) {
+            match block {
+                Block::Row(row) => {
+                    if !lists.is_empty() {
+                        lists.clear();
+                        writeln!(self.writer)?;
+                    }
+                    paragraph_rows.push(row.clone());
+                }
+                Block::Item(item) => {
+                    if lists.is_empty() {
+                        self.write_paragraphs(page, mem::take(&mut paragraph_rows), false)?;
+                        self.end_paragraph()?;
+                    }
+                    lists.truncate(item.level + 1);
+                    if lists.len() <= item.level || lists[item.level].0 != item.ordered {
+                        lists.resize(item.level, (false, 0));
+                        lists.push((item.ordered, 0));
+                    }
+                    lists[item.level].1 += 1;
+                    let marker = if item.ordered {
+                        format!("{}.", lists[item.level].1)
+                    } else {
+                        "-".to_owned()
+                    };
+                    // Indenting each level by four spaces nests it inside the item before it, however long that item's
+                    // marker is.
+                    writeln!(
+                        self.writer,
+                        "{}{} {}",
+                        "    ".repeat(item.level),
+                        marker,
+                        escape(&item.text)
+                    )?;
+                }
+            }
+        }
+        if !lists.is_empty() {
+            writeln!(self.writer)?;
+        }
+        self.write_paragraphs(page, paragraph_rows, last)
+    }
+
+    /// Writes some rows of a page which aren't in a table as paragraphs. The last paragraph is kept back if it ends in
+    /// a hyphen and nothing comes after the rows on the page, in case it carries on over the page break.
+    fn write_paragraphs(
+        &mut self,
+        page: &Page,
+        rows: Vec<TextChunk>,
+        last: bool,
+    ) -> io::Result<()> {
+        let rows = Page {
+            number: page.number,
+            label: None,
+            rows,
+            tables: Vec::new(),
+            bounds: page.bounds,
+        };
+        let paragraphs = paragraphs::split(&rows, escape, &self.hyphenation);
+        let count = paragraphs.len();
+        for (index, paragraph) in paragraphs.into_iter().enumerate() {
+            self.hyphenation.join_line(&mut self.paragraph, &paragraph);
+            if index + 1 < count || !last || !self.paragraph.ends_with('-') {
+                self.end_paragraph()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputRenderer for MarkdownRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let parts = output::page_parts(page);
+        let count = parts.len();
+        for (index, part) in parts.into_iter().enumerate() {
+            match part {
+                PagePart::Rows(rows) => {
+                    self.write_blocks(page, rows.to_vec(), index + 1 == count)?
+                }
+                PagePart::Heading(level, text) => {
+                    self.end_paragraph()?;
+                    writeln!(
+                        self.writer,
+                        "{} {}\n",
+                        "#".repeat(usize::from(level)),
+                        escape(&text)
+                    )?;
+                }
+                PagePart::Table(table) => {
+                    self.end_paragraph()?;
+                    writeln!(self.writer, "{}", table.to_markdown(escape))?;
+                }
+            }
+        }
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.end_paragraph()?;
+        self.writer.flush()
+    }
+}
+
+/// Escapes the characters which mean something in Markdown, turning the markup of extracted text into Markdown.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    // The addresses of the links which are open, innermost last.
+    let mut links = Vec::new();
+    let mut rest = text;
+    while let Some(character) = rest.chars().next() {
+        if let Some(address) = rest.strip_prefix(text::LINK_START) {
+            if let Some((address, after)) = address.split_once("\">") {
+                escaped.push('[');
+                links.push(address);
+                rest = after;
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix("</a>") {
+            if let Some(address) = links.pop() {
+                let address = address.replace('(', "%28").replace(')', "%29");
+                escaped.push_str(&format!("]({})", address));
+            }
+            rest = after;
+            continue;
+        }
+        if let Some((tag, markdown)) = MARKUP.iter().find(|(tag, _)| rest.starts_with(tag)) {
+            escaped.push_str(markdown);
+            rest = &rest[tag.len()..];
+            continue;
+        }
+        if SPECIAL_CHARACTERS.contains(&character) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+        rest = &rest[character.len_utf8()..];
+    }
+    // Some characters only mean something at the start of a line, where they start quotes, and headings and lists
+    // when a space comes after them.
+    let ends_marker = |rest: &str| rest.is_empty() || rest.starts_with(char::is_whitespace);
+    if escaped.starts_with('>')
+        || escaped.starts_with(['#', '+', '-']) && ends_marker(&escaped[1..])
+    {
+        escaped.insert(0, '\\');
+    } else {
+        let digits = escaped.find(|character: char| !character.is_ascii_digit());
+        if let Some(end) = digits.filter(|&end| end > 0) {
+            if escaped[end..].starts_with(['.', ')']) && ends_marker(&escaped[end + 1..]) {
+                escaped.insert(end, '\\');
+            }
+        }
+    }
+    escaped
+}
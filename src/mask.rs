@@ -0,0 +1,251 @@
+//! Masks personal data in the extracted text, so that it can be handed on to someone who shouldn't see it.
+//!
+//! Each match is replaced with the kind of thing it was, like `[EMAIL]`, so that the text still reads sensibly.
+
+use std::io;
+
+use clap::ValueEnum;
+use regex::{Captures, Regex};
+
+use crate::{output::OutputRenderer, text::Page};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Mask {
+    /// Masks email addresses, phone numbers, US social security numbers and IBANs.
+    Pii,
+}
+
+/// A pattern to mask, along with what to replace it with.
+struct MaskPattern {
+    pattern: Regex,
+    replacement: &'static str,
+    /// Checks a match further, for patterns which would otherwise catch too much.
+    is_valid: fn(&str) -> bool,
+}
+
+/// Masks the text of each page before passing it on to another renderer.
+pub struct MaskingRenderer {
+    inner: Box<dyn OutputRenderer>,
+    patterns: Vec<MaskPattern>,
+}
+
+impl MaskingRenderer {
+    /// Masks the matches of the given patterns, as well as the built in ones for each kind of mask.
+    pub fn new(inner: Box<dyn OutputRenderer>, masks: &[Mask], patterns: Vec<Regex>) -> Self {
+        let mut all_patterns = Vec::new();
+        if masks.contains(&Mask::Pii) {
+            // Emails and IBANs go first, since they can have runs of digits in them which look like phone numbers.
+            // Phone numbers need something between their groups of digits, so that other long numbers are left alone,
+            // and a shape of their own, so that ranges of years and pages aren't taken for them.
+            all_patterns.extend([
+                MaskPattern {
+                    pattern: Regex::new(
+                        r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+                    )
+                    .unwrap(),
+                    replacement: "[EMAIL]",
+                    is_valid: |_| true,
+                },
+                MaskPattern {
+                    pattern: Regex::new(
+                        r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b",
+                    )
+                    .unwrap(),
+                    replacement: "[IBAN]",
+                    is_valid: is_iban,
+                },
+                MaskPattern {
+                    pattern: Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap(),
+                    replacement: "[SSN]",
+                    is_valid: |_| true,
+                },
+                MaskPattern {
+                    pattern: Regex::new(
+                        r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?|\b\d{1,4}[ .-]|\b)\d{3,4}[ .-]\d{4}\b",
+                    )
+                    .unwrap(),
+                    replacement: "[PHONE]",
+                    is_valid: is_phone_number,
+                },
+            ]);
+        }
+        all_patterns.extend(patterns.into_iter().map(|pattern| MaskPattern {
+            pattern,
+            replacement: "[REDACTED]",
+            is_valid: |_| true,
+        }));
+        Self {
+            inner,
+            patterns: all_patterns,
+        }
+    }
+
+    fn mask(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+        for pattern in &self.patterns {
+            text = pattern
+                .pattern
+                .replace_all(&text, |captures: &Captures| {
+                    if (pattern.is_valid)(&captures[0]) {
+                        pattern.replacement.to_owned()
+                    } else {
+                        captures[0].to_owned()
+                    }
+                })
+                .into_owned();
+        }
+        text
+    }
+}
+
+impl OutputRenderer for MaskingRenderer {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let mut rows = page.rows.clone();
+        for row in &mut rows {
+            row.text = self.mask(&row.text);
+            // What is masked can be split across the chunks of a row, where masking each chunk on its own would miss
+            // it, so they are left out.
+            row.chunks.clear();
+        }
+        let mut tables = page.tables.clone();
+        for cell in tables
+            .iter_mut()
+            .flat_map(|table| &mut table.cells)
+            .flatten()
+        {
+            *cell = self.mask(cell);
+        }
+        self.inner.render_page(&Page {
+            number: page.number,
+            label: page.label.clone(),
+            rows,
+            tables,
+            bounds: page.bounds,
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.finish()
+    }
+}
+
+/// Checks that a match is shaped like a phone number rather than a pair of numbers, like `2019-2023` or `1234-5678`,
+/// by it having a country code, an area code in brackets, or at least ten digits with the same thing between each
+/// group of them.
+fn is_phone_number(candidate: &str) -> bool {
+    if candidate.starts_with('+') || candidate.contains('(') {
+        return true;
+    }
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    let mut separators = candidate.chars().filter(|c| !c.is_ascii_digit());
+    let first = separators.next();
+    digits >= 10 && separators.all(|separator| Some(separator) == first)
+}
+
+/// Checks an IBAN's check digits, so that other codes of capitals and numbers aren't masked.
+fn is_iban(candidate: &str) -> bool {
+    let candidate: String = candidate.chars().filter(|c| *c != ' ').collect();
+    if !(15..=34).contains(&candidate.len()) {
+        return false;
+    }
+    // The country code and check digits are moved to the end, and every letter becomes a two digit number.
+    let (start, rest) = candidate.split_at(4);
+    let remainder = rest
+        .chars()
+        .chain(start.chars())
+        .try_fold(0, |remainder, c| {
+            let value = c.to_digit(36)?;
+            Some(if value < 10 {
+                (remainder * 10 + value) % 97
+            } else {
+                (remainder * 100 + value) % 97
+            })
+        });
+    remainder == Some(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::PlainTextRenderer;
+
+    fn mask(text: &str) -> String {
+        let renderer = MaskingRenderer::new(
+            Box::new(PlainTextRenderer::new(io::sink())),
+            &[Mask::Pii],
+            vec![Regex::new(r"case \d+").unwrap()],
+        );
+        renderer.mask(text)
+    }
+
+    #[test]
+    fn masks_emails() {
+        assert_eq!(
+            mask("Write to jo.smith+pdf@mail.example.co.uk."),
+            "Write to [EMAIL]."
+        );
+        assert_eq!(
+            mask("at @home or user@localhost"),
+            "at @home or user@localhost"
+        );
+    }
+
+    #[test]
+    fn masks_ibans_with_valid_check_digits() {
+        assert_eq!(
+            mask("Pay GB82 WEST 1234 5698 7654 32 now"),
+            "Pay [IBAN] now"
+        );
+        assert_eq!(mask("DE89370400440532013000"), "[IBAN]");
+        assert_eq!(
+            mask("Pay GB83WEST12345698765432 now"),
+            "Pay GB83WEST12345698765432 now"
+        );
+    }
+
+    #[test]
+    fn masks_social_security_numbers() {
+        assert_eq!(mask("SSN: 078-05-1120."), "SSN: [SSN].");
+        assert_eq!(mask("ISBN 978-05-11203"), "ISBN 978-05-11203");
+    }
+
+    #[test]
+    fn masks_phone_numbers() {
+        assert_eq!(mask("Call (555) 123-4567 today"), "Call [PHONE] today");
+        assert_eq!(mask("Call +44 20 7946 0958"), "Call [PHONE]");
+        assert_eq!(
+            mask("Call 555-123-4567 or 555.123.4567"),
+            "Call [PHONE] or [PHONE]"
+        );
+        assert_eq!(mask("Call 0161 496 0000"), "Call [PHONE]");
+    }
+
+    #[test]
+    fn leaves_ranges_of_numbers_alone() {
+        for text in [
+            "from 2019-2023",
+            "in 1999 2024",
+            "pages 1234-5678",
+            "between 1998-2003 2005",
+            "the 2020 census counted 1234",
+        ] {
+            assert_eq!(mask(text), text);
+        }
+    }
+
+    #[test]
+    fn masks_extra_patterns() {
+        assert_eq!(mask("See case 42."), "See [REDACTED].");
+    }
+
+    #[test]
+    fn checks_iban_check_digits() {
+        assert!(is_iban("GB82 WEST 1234 5698 7654 32"));
+        assert!(is_iban("NL91ABNA0417164300"));
+        assert!(!is_iban("NL92ABNA0417164300"));
+        // Too short, too long and not alphanumeric.
+        assert!(!is_iban("GB82 WEST 12"));
+        assert!(!is_iban(&format!("GB82{}", "1".repeat(31))));
+        assert!(!is_iban("GB82-WEST-1234-5698-7654-32"));
+    }
+}
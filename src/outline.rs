@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+
+use crate::traversal::{self, TraversalError, MAX_DEPTH};
+
+/// An entry in the document's outline (its bookmarks).
+#[derive(Serialize)]
+pub struct Bookmark {
+    pub title: String,
+    /// How deeply the bookmark is nested, where top-level bookmarks have level 1.
+    pub level: usize,
+    /// The page the bookmark points to, if it points into this document at all.
+    pub page: Option<u32>,
+}
+
+/// Reads every bookmark in the outline tree, in the order they appear in the outline.
+pub fn read_outline(document: &Document) -> Result<Vec<Bookmark>, TraversalError> {
+    let mut bookmarks = Vec::new();
+    let Some(outlines) = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| resolve(document, catalog.get(b"Outlines").ok()))
+        .and_then(|outlines| outlines.as_dict().ok())
+    else {
+        return Ok(bookmarks);
+    };
+    let page_numbers = traversal::pages(document)?
+        .into_iter()
+        .map(|(number, id)| (id, number))
+        .collect::<BTreeMap<_, _>>();
+
+    // Each entry is the first child of an outline item, along with the level of that child.
+    let mut pending = vec![(outlines.get(b"First").ok(), 1)];
+    let mut visited = BTreeSet::new();
+    // The outline is walked depth first so that the bookmarks come out in reading order.
+    while let Some((item, level)) = pending.pop() {
+        let Some(&Object::Reference(id)) = item else {
+            continue;
+        };
+        if level > MAX_DEPTH {
+            return Err(TraversalError::TooDeep);
+        }
+        // Malformed files sometimes link items into a loop, which would otherwise never end.
+        if !visited.insert(id) {
+            return Err(TraversalError::Revisited(id));
+        }
+        let Ok(item) = document.get_dictionary(id) else {
+            continue;
+        };
+        let title = item
+            .get(b"Title")
+            .ok()
+            .and_then(|title| resolve(document, Some(title)))
+            .and_then(|title| title.as_str().ok())
+            .map(decode_text_string)
+            .unwrap_or_default();
+        bookmarks.push(Bookmark {
+            title,
+            level,
+            page: destination_page(document, item, &page_numbers),
+        });
+        pending.push((item.get(b"Next").ok(), level));
+        pending.push((item.get(b"First").ok(), level + 1));
+    }
+    Ok(bookmarks)
+}
+
+/// Decodes a PDF text string, which is either UTF-16 with a byte order mark or PDFDocEncoding.
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        return String::from_utf16_lossy(&units);
+    }
+    // PDFDocEncoding matches Latin-1 for everything that shows up in practice.
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+fn resolve<'a>(document: &'a Document, object: Option<&'a Object>) -> Option<&'a Object> {
+    document.dereference(object?).ok().map(|(_, object)| object)
+}
+
+fn destination_page(
+    document: &Document,
+    item: &Dictionary,
+    page_numbers: &BTreeMap<ObjectId, u32>,
+) -> Option<u32> {
+    let destination = match item.get(b"Dest") {
+        Ok(destination) => destination,
+        Err(_) => {
+            // Items can also point somewhere using a GoTo action.
+            let action = resolve(document, item.get(b"A").ok())?.as_dict().ok()?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+                return None;
+            }
+            action.get(b"D").ok()?
+        }
+    };
+    let destination = match resolve(document, Some(destination))? {
+        Object::Name(name) => named_destination(document, name)?,
+        Object::String(name, _) => named_destination(document, name)?,
+        destination => destination,
+    };
+    let destination = match destination {
+        // Named destinations may be wrapped in a dictionary.
+        Object::Dictionary(dictionary) => resolve(document, dictionary.get(b"D").ok())?,
+        destination => destination,
+    };
+    let page_id = destination.as_array().ok()?.first()?.as_reference().ok()?;
+    page_numbers.get(&page_id).copied()
+}
+
+fn named_destination<'a>(document: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = document.catalog().ok()?;
+    // Older files keep a plain dictionary of destinations in the catalog, and newer ones use a name tree.
+    if let Some(destinations) = resolve(document, catalog.get(b"Dests").ok()) {
+        if let Ok(destination) = destinations.as_dict().and_then(|dests| dests.get(name)) {
+            return resolve(document, Some(destination));
+        }
+    }
+    let names = resolve(document, catalog.get(b"Names").ok())?
+        .as_dict()
+        .ok()?;
+    let tree = resolve(document, names.get(b"Dests").ok())?
+        .as_dict()
+        .ok()?;
+    let mut pending = vec![tree];
+    let mut visited = BTreeSet::new();
+    while let Some(node) = pending.pop() {
+        if let Ok(entries) = node.get(b"Names").and_then(Object::as_array) {
+            for entry in entries.chunks_exact(2) {
+                if entry[0].as_str().is_ok_and(|key| key == name) {
+                    return resolve(document, Some(&entry[1]));
+                }
+            }
+        }
+        if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+            for kid in kids {
+                if let Ok(id) = kid.as_reference() {
+                    if visited.insert(id) {
+                        pending.extend(document.get_dictionary(id).ok());
+                    }
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    headings,
+    tables::Table,
+    text::{self, Page, TextChunk},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Writes each row of text on its own line.
+    Text,
+    /// Writes a JSON array of chunks of about the same size for retrieval-augmented generation, each with the pages
+    /// it comes from and the headings of the section it is in.
+    Chunks,
+    /// Writes the same chunks as records for loading into a vector store, one JSON object per line, each with an ID
+    /// which stays the same when the document is extracted again.
+    VectorRecords,
+    /// Writes an HTML document, with the headings, paragraphs, lists, tables and figures of tagged documents as the
+    /// same elements in HTML, and each page of untagged documents as a `div` of the paragraphs, headings, lists and
+    /// tables found in its text.
+    Html,
+    /// Writes Markdown, with the rows put back together into paragraphs, and the headings, lists, tables and links
+    /// found in the text as their Markdown.
+    Markdown,
+    /// Writes a JSON array of the pages, with the blocks, lines and chunks of text on each one and where they are, and
+    /// the fonts they are in.
+    Json,
+    /// Writes the same pages as JSON Lines, one object per line, each as soon as its page is extracted. Headings
+    /// aren't found, since that needs every page, and running headers, page numbers and watermarks are found by
+    /// comparing each page only with the pages before it, so the first few pages keep theirs.
+    Jsonl,
+    /// Writes hOCR, the HTML OCR engines write, with the blocks, lines and words on each page and the box around each
+    /// one.
+    Hocr,
+    /// Writes ALTO 4 XML, with the blocks, lines and words on each page and where each one is.
+    Alto,
+    /// Writes a PAGE XML file of each page to `--output-dir`, with the regions, lines and words on it and their
+    /// outlines.
+    PageXml,
+    /// Writes a TEI P5 document, with the headings, paragraphs, tables and footnotes found in the text, and a page
+    /// break at the start of each page.
+    Tei,
+    /// Writes XHTML with each piece of text placed where it is on its page, in the font it is in, as pdftohtml does.
+    PositionedXhtml,
+    /// Writes a CSV line for each chunk of text, with its page, where it is, its font and size, and its text.
+    Csv,
+}
+
+/// Turns extracted pages into output, one page at a time as they are extracted.
+pub trait OutputRenderer {
+    fn render_page(&mut self, page: &Page) -> io::Result<()>;
+
+    /// Called once after the last page, for formats which need to close anything off.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How many pages before and after a page are compared with it to find what repeats from page to page, such as
+/// running headers, which are all the pages of most documents and keeps the pages of longer ones streaming.
+pub const WINDOW_PAGES: usize = 8;
+
+/// The pages around the one being passed on, for renderers which leave out what repeats from page to page, so that
+/// only a few pages are held back rather than the whole document.
+pub struct PageWindow {
+    /// The pages as they were extracted, the first `passed_on` of which have been passed on already and are only kept
+    /// to compare the later ones with.
+    pages: VecDeque<Page>,
+    passed_on: usize,
+    /// How many pages after a page have to have been extracted before it is passed on.
+    lookahead: usize,
+}
+
+impl PageWindow {
+    pub fn new(lookahead: usize) -> Self {
+        Self {
+            pages: VecDeque::new(),
+            passed_on: 0,
+            lookahead,
+        }
+    }
+
+    /// Adds a page, calling `pass_on` with the pages in the window and the index of each page among them which now
+    /// has enough pages after it.
+    pub fn push(
+        &mut self,
+        page: &Page,
+        mut pass_on: impl FnMut(&[Page], usize) -> io::Result<()>,
+    ) -> io::Result<()> {
+        self.pages.push_back(page.clone());
+        while self.pages.len() - self.passed_on > self.lookahead {
+            self.pass_on_next(&mut pass_on)?;
+        }
+        Ok(())
+    }
+
+    /// Calls `pass_on` for each of the pages left, after the last page has been added.
+    pub fn finish(
+        &mut self,
+        mut pass_on: impl FnMut(&[Page], usize) -> io::Result<()>,
+    ) -> io::Result<()> {
+        while self.passed_on < self.pages.len() {
+            self.pass_on_next(&mut pass_on)?;
+        }
+        Ok(())
+    }
+
+    fn pass_on_next(
+        &mut self,
+        pass_on: &mut impl FnMut(&[Page], usize) -> io::Result<()>,
+    ) -> io::Result<()> {
+        pass_on(self.pages.make_contiguous(), self.passed_on)?;
+        self.passed_on += 1;
+        if self.passed_on > WINDOW_PAGES {
+            self.pages.pop_front();
+            self.passed_on -= 1;
+        }
+        Ok(())
+    }
+}
+
+/// A part of a page, for the formats which write the headings and tables found in the text as well as its
+/// paragraphs.
+pub enum PagePart<'a> {
+    /// Rows one after the other which are in neither a heading nor a table.
+    Rows(&'a [TextChunk]),
+    /// A heading at its level, with the rows of a heading which wraps joined into one, and without the bold markup
+    /// around all of it.
+    Heading(u8, String),
+    /// A table, which goes where its first row is, since the rest of its rows are already in it.
+    Table(&'a Table),
+}
+
+/// Splits a page into its headings, its tables and the rows between them, in the order they are on the page.
+pub fn page_parts(page: &Page) -> Vec<PagePart<'_>> {
+    let table_of = |row: &TextChunk| page.tables.iter().position(|table| table.contains(row));
+    // Rows in a table are part of it whether they are headings or not.
+    let part_of = |row: &TextChunk| match table_of(row) {
+        Some(index) => (Some(index), None),
+        None => (None, row.heading),
+    };
+    let mut written_tables = vec![false; page.tables.len()];
+    let mut parts = Vec::new();
+    for rows in page.rows.chunk_by(|a, b| part_of(a) == part_of(b)) {
+        match part_of(&rows[0]) {
+            (Some(index), _) => {
+                if !written_tables[index] {
+                    parts.push(PagePart::Table(&page.tables[index]));
+                    written_tables[index] = true;
+                }
+            }
+            (None, Some(level)) => {
+                let mut text = headings::heading_text(&rows[0].text).to_owned();
+                for row in &rows[1..] {
+                    text::join_line(&mut text, headings::heading_text(&row.text));
+                }
+                parts.push(PagePart::Heading(level, text));
+            }
+            (None, None) => parts.push(PagePart::Rows(rows)),
+        }
+    }
+    parts
+}
+
+/// How the pages of plain text are told apart, rather than running on from one to the next.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PageSeparator {
+    /// Ends each page with a form feed, as pdftotext does.
+    FormFeed,
+    /// Starts each page with a line such as `--- Page 3 ---`.
+    Banner,
+}
+
+/// Writes each row of text on its own line.
+pub struct PlainTextRenderer<W: Write> {
+    writer: W,
+    separator: Option<PageSeparator>,
+}
+
+impl<W: Write> PlainTextRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            separator: None,
+        }
+    }
+
+    /// Writes the rows with the pages separated.
+    pub fn with_page_separator(writer: W, separator: PageSeparator) -> Self {
+        Self {
+            writer,
+            separator: Some(separator),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> OutputRenderer for PlainTextRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        if self.separator == Some(PageSeparator::Banner) {
+            writeln!(self.writer, "--- Page {} ---", page.number)?;
+        }
+        for row in &page.rows {
+            writeln!(self.writer, "{}", row)?;
+        }
+        if self.separator == Some(PageSeparator::FormFeed) {
+            write!(self.writer, "\x0C")?;
+        }
+        // Flushing after every page means that whatever is reading the output sees each page as soon as it is done.
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(number: u32) -> Page {
+        Page {
+            number,
+            label: None,
+            rows: Vec::new(),
+            tables: Vec::new(),
+            bounds: [0.0, 0.0, 612.0, 792.0],
+        }
+    }
+
+    /// Pushes the pages through a window, giving which page each one passed on is and how many pages were around it.
+    fn pass_through(page_count: u32, lookahead: usize) -> Vec<(u32, usize, usize)> {
+        let mut window = PageWindow::new(lookahead);
+        let mut passed_on = Vec::new();
+        let mut record = |pages: &[Page], index: usize| {
+            passed_on.push((pages[index].number, index, pages.len() - index - 1));
+            Ok(())
+        };
+        for number in 1..=page_count {
+            window.push(&page(number), &mut record).unwrap();
+        }
+        window.finish(&mut record).unwrap();
+        passed_on
+    }
+
+    #[test]
+    fn passes_pages_on_in_order_with_pages_either_side() {
+        let passed_on = pass_through(30, 3);
+        let numbers: Vec<u32> = passed_on.iter().map(|&(number, _, _)| number).collect();
+        assert_eq!(numbers, (1..=30).collect::<Vec<_>>());
+        assert!(passed_on
+            .iter()
+            .all(|&(_, before, after)| before <= WINDOW_PAGES && after <= 3));
+        assert_eq!(passed_on[20], (21, WINDOW_PAGES, 3));
+    }
+
+    #[test]
+    fn holds_back_nothing_without_lookahead() {
+        let mut window = PageWindow::new(0);
+        let mut passed_on = Vec::new();
+        window
+            .push(&page(1), |pages, index| {
+                passed_on.push(pages[index].number);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(passed_on, [1]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Takes the page numbers printed at the top or bottom of pages out of their text, and keeps them as the page's label
+//! instead, which the structured outputs include with the page.
+//!
+//! A page number is a row near the top or bottom of the page with nothing but a number on it, in digits or Roman
+//! numerals, perhaps between dashes as in "- 3 -". A lone number could just as well be the last line of a table, so
+//! it is only taken to be the page number if other pages have numbers in the same style that count up with the
+//! pages from it, which finds front matter numbered in Roman numerals and the rest in digits as separate sequences,
+//! and leaves out pages that aren't numbered at all.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+use crate::{
+    output::{OutputRenderer, PageWindow},
+    text::{self, Page},
+};
+
+/// How many rows at the top and at the bottom of each page can be its page number.
+const EDGE_ROWS: usize = 2;
+
+/// Page numbers are never longer than this many characters, which is enough for numbers such as "xxviii".
+const MAX_LENGTH: usize = 8;
+
+/// A sequence of page numbers has to be on at least this many pages, or every page of shorter documents.
+const MIN_PAGES: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Style {
+    Digits,
+    LowerRoman,
+    UpperRoman,
+}
+
+/// Takes the page numbers out of the pages before passing them on to another renderer.
+pub struct PageNumberRemover {
+    inner: Box<dyn OutputRenderer>,
+    window: PageWindow,
+}
+
+impl PageNumberRemover {
+    /// Passes each page on once `lookahead` pages after it have been extracted to compare it with.
+    pub fn new(inner: Box<dyn OutputRenderer>, lookahead: usize) -> Self {
+        Self {
+            inner,
+            window: PageWindow::new(lookahead),
+        }
+    }
+}
+
+impl OutputRenderer for PageNumberRemover {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.window.push(page, |pages, index| {
+            inner.render_page(&without_page_number(pages, index))
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.window
+            .finish(|pages, index| inner.render_page(&without_page_number(pages, index)))?;
+        self.inner.finish()
+    }
+}
+
+/// The page at `index` with its page number taken out of its rows and made its label.
+pub fn without_page_number(pages: &[Page], index: usize) -> Page {
+    // Each row which could be a page number: which page and row it is, the number as printed, and its value.
+    let mut candidates = Vec::new();
+    for (page_index, page) in pages.iter().enumerate() {
+        let mut rows: Vec<usize> = (0..page.rows.len())
+            .filter(|&row_index| !page.rows[row_index].vertical)
+            .collect();
+        rows.sort_by(|&a, &b| page.rows[b].y.total_cmp(&page.rows[a].y));
+        let top = rows.iter().take(EDGE_ROWS);
+        let bottom = rows.iter().rev().take(EDGE_ROWS);
+        let mut edges: Vec<usize> = top.chain(bottom).copied().collect();
+        edges.sort();
+        edges.dedup();
+        for row_index in edges {
+            if let Some((label, style, value)) = page_number(&page.rows[row_index].text) {
+                candidates.push((page_index, row_index, label, style, value));
+            }
+        }
+    }
+    // Numbers in the same sequence are the same distance from the numbers of the pages they are on.
+    let offset =
+        |page_index: usize, value: u32| i64::from(value) - i64::from(pages[page_index].number);
+    let mut sequences: HashMap<(Style, i64), HashSet<usize>> = HashMap::new();
+    for &(page_index, _, _, style, value) in &candidates {
+        sequences
+            .entry((style, offset(page_index, value)))
+            .or_default()
+            .insert(page_index);
+    }
+    let needed = MIN_PAGES.min(pages.len()).max(2);
+    let mut page = pages[index].clone();
+    let number = candidates
+        .into_iter()
+        .filter(|&(page_index, ..)| page_index == index)
+        .find(|&(page_index, _, _, style, value)| {
+            sequences[&(style, offset(page_index, value))].len() >= needed
+        });
+    if let Some((_, row_index, label, _, _)) = number {
+        page.rows.remove(row_index);
+        page.label = Some(label);
+    }
+    page
+}
+
+/// The number on a row which has nothing else on it, as printed, with its style and value.
+fn page_number(text: &str) -> Option<(String, Style, u32)> {
+    let text = text::plain_text(text);
+    let number = text
+        .trim()
+        .trim_matches(|character: char| character == '-' || character == '\u{2013}')
+        .trim();
+    if number.is_empty() || number.chars().count() > MAX_LENGTH {
+        return None;
+    }
+    let (style, value) = if number.chars().all(|character| character.is_ascii_digit()) {
+        (Style::Digits, number.parse().ok()?)
+    } else if number
+        .chars()
+        .all(|character| "ivxlcdm".contains(character))
+    {
+        (Style::LowerRoman, roman_value(number)?)
+    } else if number
+        .chars()
+        .all(|character| "IVXLCDM".contains(character))
+    {
+        (Style::UpperRoman, roman_value(number)?)
+    } else {
+        return None;
+    };
+    Some((number.to_owned(), style, value))
+}
+
+/// The value of a number in Roman numerals of either case, or `None` if it isn't written the way Roman numerals are.
+fn roman_value(numerals: &str) -> Option<u32> {
+    let digit = |character: char| match character.to_ascii_lowercase() {
+        'i' => 1,
+        'v' => 5,
+        'x' => 10,
+        'l' => 50,
+        'c' => 100,
+        'd' => 500,
+        _ => 1000,
+    };
+    let digits: Vec<u32> = numerals.chars().map(digit).collect();
+    let mut value = 0;
+    for (index, &digit) in digits.iter().enumerate() {
+        match digits.get(index + 1) {
+            Some(&next) if next > digit => value -= digit as i64,
+            _ => value += digit as i64,
+        }
+    }
+    let value = u32::try_from(value).ok().filter(|&value| value > 0)?;
+    // Words such as "mix" and "dim" are made of the same letters, so only the way the value is normally written counts.
+    (to_roman(value) == numerals.to_ascii_lowercase()).then_some(value)
+}
+
+/// Writes a number in lower case Roman numerals.
+fn to_roman(mut value: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut numerals = String::new();
+    for (numeral_value, numeral) in NUMERALS {
+        while value >= numeral_value {
+            numerals.push_str(numeral);
+            value -= numeral_value;
+        }
+    }
+    numerals
+}
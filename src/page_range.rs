@@ -0,0 +1,127 @@
+use std::{fmt::Display, str::FromStr};
+
+/// A set of pages given as a comma separated list of page numbers and ranges, such as `1-3,7,10-`.
+#[derive(Clone, Debug)]
+pub struct PageSelection {
+    /// Inclusive ranges of page numbers, where an open-ended range goes up to `u32::MAX`.
+    ranges: Vec<(u32, u32)>,
+}
+
+impl PageSelection {
+    pub fn contains(&self, page: u32) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(first, last)| first <= page && page <= last)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsePageSelectionError(String);
+
+impl Display for ParsePageSelectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid page selection: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePageSelectionError {}
+
+impl FromStr for PageSelection {
+    type Err = ParsePageSelectionError;
+
+    fn from_str(selection: &str) -> Result<Self, Self::Err> {
+        let parse_page = |page: &str| {
+            page.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&page| page > 0)
+                .ok_or_else(|| ParsePageSelectionError(format!("{:?} is not a page number", page)))
+        };
+        let mut ranges = Vec::new();
+        for part in selection.split(',') {
+            let range = match part.split_once('-') {
+                Some((first, last)) => {
+                    let first = if first.trim().is_empty() {
+                        1
+                    } else {
+                        parse_page(first)?
+                    };
+                    let last = if last.trim().is_empty() {
+                        u32::MAX
+                    } else {
+                        parse_page(last)?
+                    };
+                    if first > last {
+                        return Err(ParsePageSelectionError(format!(
+                            "{:?} ends before it starts",
+                            part
+                        )));
+                    }
+                    (first, last)
+                }
+                None => {
+                    let page = parse_page(part)?;
+                    (page, page)
+                }
+            };
+            ranges.push(range);
+        }
+        Ok(Self { ranges })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(selection: &str) -> Vec<u32> {
+        let selection: PageSelection = selection.parse().unwrap();
+        (1..=12).filter(|&page| selection.contains(page)).collect()
+    }
+
+    #[test]
+    fn parses_pages_and_ranges() {
+        assert_eq!(pages("3"), [3]);
+        assert_eq!(pages("1-3,7,10-11"), [1, 2, 3, 7, 10, 11]);
+        assert_eq!(pages(" 2 - 4 , 6 "), [2, 3, 4, 6]);
+        assert_eq!(pages("5-5"), [5]);
+        // Overlapping ranges are fine.
+        assert_eq!(pages("1-4,3-5"), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn parses_open_ranges() {
+        assert_eq!(pages("10-"), [10, 11, 12]);
+        assert_eq!(pages("-2"), [1, 2]);
+        assert_eq!(pages("-"), (1..=12).collect::<Vec<_>>());
+        let selection: PageSelection = "2-".parse().unwrap();
+        assert!(selection.contains(u32::MAX));
+    }
+
+    #[test]
+    fn refuses_reversed_ranges() {
+        let error = "5-3".parse::<PageSelection>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"invalid page selection: "5-3" ends before it starts"#
+        );
+    }
+
+    #[test]
+    fn refuses_page_zero() {
+        assert!("0".parse::<PageSelection>().is_err());
+        assert!("0-3".parse::<PageSelection>().is_err());
+        assert!("2-0".parse::<PageSelection>().is_err());
+    }
+
+    #[test]
+    fn refuses_junk() {
+        for selection in ["", "a", "1,,2", "1-2-3", "1.5", "-1-", "4294967296", "1;2"] {
+            assert!(
+                selection.parse::<PageSelection>().is_err(),
+                "{:?} was accepted",
+                selection
+            );
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Writes the text as PAGE XML, the format of the PRImA research group which document analysis tools read and write
+//! the layout of pages in.
+//!
+//! A PAGE XML file is of a single page, so each page is written to a file of its own. The blocks of text on the page,
+//! found as they are for hOCR, are its `TextRegion`s, as paragraphs or headings, and each table is a `TableRegion`
+//! with a region of its rows inside it. The rows of a block are its `TextLine`s, each with its baseline, and the
+//! words of each row, found by `words`, are its `Word`s, each with its text. The regions are in reading order, which
+//! the page's `ReadingOrder` says as well.
+//!
+//! PAGE gives the outline of a region as a polygon, and regions are made of rows of different lengths, so the
+//! outline of a region goes around the box of each of its rows, with the boxes stretched to meet half way between one
+//! row and the next. Everything is placed in points from the top left corner of the part of the page that is shown, as
+//! the pixels of an image of 72 dots per inch, and the image is given as the PDF itself, since there is no other.
+
+use std::{
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    html,
+    json::{self, TextBlock},
+    output::OutputRenderer,
+    text::{self, Page},
+    words,
+};
+
+pub struct PageXmlRenderer<W: Write> {
+    writer: W,
+    /// The path of the PDF, which stands in for the image of the page.
+    image: String,
+    word_gap: f32,
+}
+
+impl<W: Write> PageXmlRenderer<W> {
+    pub fn new(writer: W, image: String, word_gap: f32) -> Self {
+        Self {
+            writer,
+            image,
+            word_gap,
+        }
+    }
+
+    /// Writes a `TextRegion` of a block, with the lines and words in it.
+    fn write_region(
+        &mut self,
+        id: &str,
+        kind: Option<&str>,
+        block: &TextBlock,
+        page: &Page,
+    ) -> io::Result<()> {
+        let page_box = |x: f32, y: f32, width: f32, height: f32| {
+            let [left, _, _, top] = page.bounds;
+            [x - left, top - y - height, x + width - left, top - y]
+        };
+        write!(self.writer, "<TextRegion id=\"{}\"", id)?;
+        if let Some(kind) = kind {
+            write!(self.writer, " type=\"{}\"", kind)?;
+        }
+        let boxes = block
+            .lines
+            .iter()
+            .map(|line| page_box(line.x, line.y, line.width, line.height))
+            .collect();
+        writeln!(self.writer, ">\n<Coords points=\"{}\"/>", points(boxes))?;
+        let mut region_text = Vec::new();
+        for (index, (line, words)) in words::lines(block, self.word_gap).into_iter().enumerate() {
+            let line_box = page_box(line.x, line.y, line.width, line.height);
+            writeln!(
+                self.writer,
+                "<TextLine id=\"{}_line_{}\">\n<Coords points=\"{}\"/>",
+                id,
+                index + 1,
+                points(vec![line_box])
+            )?;
+            // The boxes of rows go up from their baselines, so the baseline is along the bottom of the box.
+            writeln!(
+                self.writer,
+                "<Baseline points=\"{},{} {},{}\"/>",
+                line_box[0].round() as i64,
+                line_box[3].round() as i64,
+                line_box[2].round() as i64,
+                line_box[3].round() as i64
+            )?;
+            for (word_index, word) in words.iter().enumerate() {
+                writeln!(
+                    self.writer,
+                    "<Word id=\"{}_line_{}_word_{}\">\n<Coords points=\"{}\"/>\n{}\n</Word>",
+                    id,
+                    index + 1,
+                    word_index + 1,
+                    points(vec![page_box(word.x, word.y, word.width, word.height)]),
+                    text_equiv(&word.text)
+                )?;
+            }
+            let line_text = text::plain_text(&line.text).trim().to_owned();
+            writeln!(self.writer, "{}\n</TextLine>", text_equiv(&line_text))?;
+            region_text.push(line_text);
+        }
+        writeln!(
+            self.writer,
+            "{}\n</TextRegion>",
+            text_equiv(&region_text.join("\n"))
+        )
+    }
+}
+
+impl<W: Write> OutputRenderer for PageXmlRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let [left, bottom, right, top] = page.bounds;
+        let now = now();
+        writeln!(self.writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            self.writer,
+            concat!(
+                "<PcGts xmlns=\"http://schema.primaresearch.org/PAGE/gts/pagecontent/2019-07-15\" ",
+                "xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" ",
+                "xsi:schemaLocation=\"http://schema.primaresearch.org/PAGE/gts/pagecontent/2019-07-15 ",
+                "http://schema.primaresearch.org/PAGE/gts/pagecontent/2019-07-15/pagecontent.xsd\">"
+            )
+        )?;
+        writeln!(
+            self.writer,
+            "<Metadata>\n<Creator>rust-pdf-test {}</Creator>\n<Created>{}</Created>\n<LastChange>{}</LastChange>\n</Metadata>",
+            env!("CARGO_PKG_VERSION"),
+            now,
+            now
+        )?;
+        writeln!(
+            self.writer,
+            concat!(
+                "<Page imageFilename=\"{}\" imageWidth=\"{}\" imageHeight=\"{}\" ",
+                "imageXResolution=\"72\" imageYResolution=\"72\" imageResolutionUnit=\"PPI\">"
+            ),
+            html::escape_plain(&self.image),
+            (right - left).round() as i64,
+            (top - bottom).round() as i64
+        )?;
+        let blocks: Vec<TextBlock> = json::blocks_with_tables(page)
+            .into_iter()
+            .filter(|block| {
+                block
+                    .lines
+                    .iter()
+                    .any(|line| !text::plain_text(&line.text).trim().is_empty())
+            })
+            .collect();
+        if !blocks.is_empty() {
+            writeln!(
+                self.writer,
+                "<ReadingOrder>\n<OrderedGroup id=\"reading_order\">"
+            )?;
+            for index in 0..blocks.len() {
+                writeln!(
+                    self.writer,
+                    "<RegionRefIndexed index=\"{}\" regionRef=\"region_{}\"/>",
+                    index,
+                    index + 1
+                )?;
+            }
+            writeln!(self.writer, "</OrderedGroup>\n</ReadingOrder>")?;
+        }
+        for (index, block) in blocks.iter().enumerate() {
+            let id = format!("region_{}", index + 1);
+            let is_table = page
+                .tables
+                .iter()
+                .any(|table| block.lines.iter().all(|line| table.contains(line)));
+            if is_table {
+                let [x, y] = [block.x - left, top - block.y - block.height];
+                let table_box = [x, y, x + block.width, y + block.height];
+                writeln!(
+                    self.writer,
+                    "<TableRegion id=\"{}\">\n<Coords points=\"{}\"/>",
+                    id,
+                    points(vec![table_box])
+                )?;
+                self.write_region(&format!("{}_rows", id), None, block, page)?;
+                writeln!(self.writer, "</TableRegion>")?;
+            } else {
+                let kind = if block.lines[0].heading.is_some() {
+                    "heading"
+                } else {
+                    "paragraph"
+                };
+                self.write_region(&id, Some(kind), block, page)?;
+            }
+        }
+        writeln!(self.writer, "</Page>\n</PcGts>")?;
+        self.writer.flush()
+    }
+}
+
+/// The `TextEquiv` of some text, which is how PAGE gives the text of a region, line or word.
+fn text_equiv(text: &str) -> String {
+    format!(
+        "<TextEquiv>\n<Unicode>{}</Unicode>\n</TextEquiv>",
+        html::escape_plain(text)
+    )
+}
+
+/// The points of the outline around some boxes stacked one above another, each given by its left, top, right and
+/// bottom edges going down the page, clockwise from the top right corner of the top box.
+fn points(mut boxes: Vec<[f32; 4]>) -> String {
+    boxes.sort_by(|a, b| a[1].total_cmp(&b[1]));
+    // Each box is stretched to meet the next half way, so that the outline has no gaps in it.
+    for index in 1..boxes.len() {
+        let middle = (boxes[index - 1][3] + boxes[index][1]) / 2.0;
+        boxes[index - 1][3] = middle;
+        boxes[index][1] = middle;
+    }
+    let mut points: Vec<(i64, i64)> = Vec::new();
+    let right_side = boxes
+        .iter()
+        .flat_map(|&[_, top, right, bottom]| [(right, top), (right, bottom)]);
+    let left_side = boxes
+        .iter()
+        .rev()
+        .flat_map(|&[left, top, _, bottom]| [(left, bottom), (left, top)]);
+    for (x, y) in right_side.chain(left_side) {
+        let point = (x.round() as i64, y.round() as i64);
+        // Points in a straight line with the two before them are left out, since the outline goes straight through.
+        if let [.., before, last] = points[..] {
+            if (before.0 == last.0 && last.0 == point.0)
+                || (before.1 == last.1 && last.1 == point.1)
+            {
+                points.pop();
+            }
+        }
+        if points.last() != Some(&point) {
+            points.push(point);
+        }
+    }
+    points
+        .iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The time now, in UTC, as an XML date and time.
+fn now() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let (days, time) = ((seconds / 86400) as i64, seconds % 86400);
+    // Howard Hinnant's `civil_from_days`, which counts in eras of 400 years starting on the 1st of March, so that leap
+    // days come at the end of each year.
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
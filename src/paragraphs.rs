@@ -0,0 +1,194 @@
+//! Puts the rows of each page back together into the paragraphs they were wrapped from.
+//!
+//! The rows of a paragraph are joined with spaces, and words hyphenated across a line break are put back together.
+//! A paragraph ends wherever there is more space than usual before the next row, after a row that stops well short
+//! of the others, which is how the last lines of paragraphs and headings look, or before a row indented from the
+//! left edge the page's rows start at, which is how the first lines of many paragraphs look. Each item of a list is a
+//! paragraph of its own, and the rows it wraps onto, indented past its bullet or number, stay part of it.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    mem,
+};
+
+use crate::{
+    hyphenation::Hyphenation,
+    lists,
+    output::OutputRenderer,
+    text::{Page, TextChunk},
+};
+
+/// Rows which stop short of this fraction of the way from the left edge to where the longest rows end finish their
+/// paragraph.
+const SHORT_ROW: f32 = 0.8;
+
+/// Gaps this many times bigger than the usual distance between rows end a paragraph.
+const PARAGRAPH_GAP: f32 = 1.5;
+
+/// Rows which start at least this many times the height of their text to the right of the left edge are indented,
+/// and rows which start more than `MAX_INDENT` times it to the right of it are centred or in another column instead.
+const MIN_INDENT: f32 = 0.8;
+const MAX_INDENT: f32 = 4.0;
+
+/// Rows which start this close to the left edge, in the units of the page, start at it.
+const MARGIN_TOLERANCE: f32 = 1.0;
+
+/// Splits the rows of a page into paragraphs, with the text of each row as `text` gives it, which can tidy it up or
+/// leave parts of it out. Rows with no text left are left out, and the rest are joined as `hyphenation` says.
+///
+/// The last paragraph ends in a hyphen if its last word carries on over the page break.
+pub fn split(page: &Page, text: impl Fn(&str) -> String, hyphenation: &Hyphenation) -> Vec<String> {
+    group(page, text)
+        .into_iter()
+        .map(|rows| {
+            let mut paragraph = String::new();
+            for (_, text) in rows {
+                hyphenation.join_line(&mut paragraph, &text);
+            }
+            paragraph
+        })
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect()
+}
+
+/// Groups the rows of a page into the paragraphs they make up, each row with its text as `text` gives it. Rows with no
+/// text left are left out.
+pub fn group(page: &Page, text: impl Fn(&str) -> String) -> Vec<Vec<(&TextChunk, String)>> {
+    let rows: Vec<_> = page
+        .rows
+        .iter()
+        .map(|row| (row, text(&row.text)))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+    let usual_gap = rows
+        .windows(2)
+        .map(|pair| (pair[1].0.y - pair[0].0.y).abs())
+        .filter(|&gap| gap > 0.0)
+        .min_by(f32::total_cmp);
+    // The left edge is wherever the most rows start, which the indented first lines of paragraphs are few enough
+    // not to move.
+    let mut starts = BTreeMap::new();
+    for (row, _) in &rows {
+        *starts.entry(row.x.round() as i32).or_insert(0) += 1;
+    }
+    let left_edge = starts
+        .into_iter()
+        .rev()
+        .max_by_key(|&(_, count)| count)
+        .map_or(0.0, |(x, _)| x as f32);
+    let right_edge = rows
+        .iter()
+        .map(|(row, _)| row.x + row.width)
+        .max_by(f32::total_cmp)
+        .unwrap_or(0.0);
+
+    let is_short =
+        |row: &TextChunk| row.x + row.width - left_edge < (right_edge - left_edge) * SHORT_ROW;
+    let is_indented = |row: &TextChunk| {
+        let indent = row.x - left_edge;
+        indent >= row.height * MIN_INDENT && indent <= row.height * MAX_INDENT
+    };
+    // Whether a paragraph starts at each row going only by the gaps between rows and where rows stop short.
+    let starts: Vec<bool> = (0..rows.len())
+        .map(|index| {
+            let Some((previous, previous_text)) = index.checked_sub(1).map(|index| &rows[index])
+            else {
+                return true;
+            };
+            let is_gap = usual_gap.is_some_and(|usual_gap| {
+                (rows[index].0.y - previous.y).abs() > usual_gap * PARAGRAPH_GAP
+            });
+            is_gap
+                || (is_short(previous) && !previous_text.ends_with('-'))
+                || lists::marker(&rows[index].1).is_some()
+        })
+        .collect();
+    // Indented rows only start paragraphs on pages where most paragraphs start with one, since the references and
+    // lists of other pages indent every row of an entry but the first instead.
+    let indented_starts = (0..rows.len())
+        .filter(|&index| starts[index] && is_indented(rows[index].0))
+        .count();
+    let uses_indents =
+        indented_starts > 0 && indented_starts * 2 > starts.iter().filter(|&&start| start).count();
+
+    let mut paragraphs = Vec::new();
+    let mut paragraph = Vec::new();
+    // Where the list item the paragraph is, if it is one, starts.
+    let mut item_x = None;
+    for (index, (row, text)) in rows.iter().enumerate() {
+        // Only the first row of an indented block is the start of a paragraph, not every row of a block quote, and the
+        // rows of a list item are indented past its marker without starting anything.
+        let starts_indented = uses_indents
+            && is_indented(row)
+            && item_x.is_none_or(|x| row.x <= x + MARGIN_TOLERANCE)
+            && index
+                .checked_sub(1)
+                .is_some_and(|index| (rows[index].0.x - left_edge).abs() <= MARGIN_TOLERANCE);
+        if starts[index] || starts_indented {
+            if !paragraph.is_empty() {
+                paragraphs.push(mem::take(&mut paragraph));
+            }
+            item_x = lists::marker(text).map(|_| row.x);
+        }
+        paragraph.push((*row, text.clone()));
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    paragraphs
+}
+
+/// Writes each paragraph on a line of its own, with a blank line after it.
+pub struct ParagraphRenderer<W: Write> {
+    writer: W,
+    text: fn(&str) -> String,
+    hyphenation: Hyphenation,
+    /// The paragraph being put together, which can continue onto the next page if it ends in a hyphenated word.
+    paragraph: String,
+}
+
+impl<W: Write> ParagraphRenderer<W> {
+    /// Writes the paragraphs with the text of their rows as it is, markup and all.
+    pub fn new(writer: W, hyphenation: Hyphenation) -> Self {
+        Self::with_text(writer, str::to_owned, hyphenation)
+    }
+
+    /// Writes the paragraphs with the text of each row as `text` gives it.
+    pub fn with_text(writer: W, text: fn(&str) -> String, hyphenation: Hyphenation) -> Self {
+        Self {
+            writer,
+            text,
+            hyphenation,
+            paragraph: String::new(),
+        }
+    }
+
+    fn end_paragraph(&mut self) -> io::Result<()> {
+        if !self.paragraph.is_empty() {
+            writeln!(self.writer, "{}\n", self.paragraph)?;
+            self.paragraph.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputRenderer for ParagraphRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let paragraphs = split(page, self.text, &self.hyphenation);
+        let count = paragraphs.len();
+        for (index, paragraph) in paragraphs.into_iter().enumerate() {
+            self.hyphenation.join_line(&mut self.paragraph, &paragraph);
+            // Hyphenated words may carry on over the page break, but otherwise pages don't share paragraphs.
+            if index + 1 < count || !self.paragraph.ends_with('-') {
+                self.end_paragraph()?;
+            }
+        }
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.end_paragraph()?;
+        self.writer.flush()
+    }
+}
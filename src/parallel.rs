@@ -0,0 +1,126 @@
+//! Separates the two languages of a bilingual document and lines up each paragraph with its translation, for
+//! building translation memories.
+//!
+//! The languages are either side by side, in two columns on each page, or on facing pages, where a page in one
+//! language is followed by its translation. Columns are found from the widest gap between where rows start, and a page
+//! only counts as having two if there are a fair number of rows on each side of the gap, so that centred headings and
+//! indented lines don't look like a second column. Paragraphs are paired up in order, since a translation keeps to
+//! the paragraphs of the original even when it takes more or fewer lines.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    hyphenation::Hyphenation,
+    language, speech,
+    text::{self, Page, TextChunk},
+};
+
+/// Each column has to have at least this fraction of the page's rows.
+const MIN_COLUMN_SHARE: f32 = 0.25;
+
+/// Columns are at least this far apart, in PDF units.
+const MIN_COLUMN_GAP: f32 = 100.0;
+
+/// A paragraph along with its translation, keyed by the ISO 639-3 code of each one's language.
+///
+/// Paragraphs left over when one side has more of them than the other come out on their own.
+#[derive(Serialize)]
+pub struct SegmentPair {
+    /// The page the paragraph in the first language is on.
+    pub page: u32,
+    #[serde(flatten)]
+    pub texts: BTreeMap<&'static str, String>,
+}
+
+/// The text of one language on a page.
+struct Stream {
+    page: u32,
+    /// Whether the page has two columns, rather than being all in one language.
+    is_column: bool,
+    language: Option<&'static str>,
+    paragraphs: Vec<String>,
+}
+
+/// Pairs up the paragraphs of the document with their translations, in document order.
+pub fn align(rows: &[TextChunk]) -> Vec<SegmentPair> {
+    let mut streams = Vec::new();
+    for page_rows in rows.chunk_by(|a, b| a.page == b.page) {
+        let columns = split_columns(page_rows);
+        let is_column = columns.len() == 2;
+        for column in columns {
+            let page = Page {
+                number: page_rows[0].page,
+                label: None,
+                rows: column,
+                tables: Vec::new(),
+                bounds: text::DEFAULT_PAGE_BOX,
+            };
+            let paragraphs = speech::paragraphs(&page, &Hyphenation::default());
+            streams.push(Stream {
+                page: page.number,
+                is_column,
+                language: language::detect(&paragraphs.join("\n"))
+                    .map(|detection| detection.language),
+                paragraphs,
+            });
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let mut index = 0;
+    while index < streams.len() {
+        let first = &streams[index];
+        let second = streams.get(index + 1).filter(|second| {
+            let is_side_by_side = first.is_column && second.is_column && second.page == first.page;
+            let is_facing = !first.is_column && !second.is_column && second.page == first.page + 1;
+            is_side_by_side || is_facing
+        });
+        let languages = second.and_then(|second| match (first.language, second.language) {
+            (Some(first), Some(second)) if first != second => Some((first, second)),
+            _ => None,
+        });
+        // Pages with only one language, and columns that are in the same language, have nothing to line up.
+        let (Some(second), Some((first_language, second_language))) = (second, languages) else {
+            index += 1;
+            continue;
+        };
+        for position in 0..first.paragraphs.len().max(second.paragraphs.len()) {
+            let mut texts = BTreeMap::new();
+            if let Some(paragraph) = first.paragraphs.get(position) {
+                texts.insert(first_language, paragraph.clone());
+            }
+            if let Some(paragraph) = second.paragraphs.get(position) {
+                texts.insert(second_language, paragraph.clone());
+            }
+            pairs.push(SegmentPair {
+                page: first.page,
+                texts,
+            });
+        }
+        index += 2;
+    }
+    pairs
+}
+
+/// Splits the rows of a page into a left and a right column, or returns them as they are if there is only one.
+fn split_columns(rows: &[TextChunk]) -> Vec<Vec<TextChunk>> {
+    let mut starts: Vec<f32> = rows.iter().map(|row| row.x).collect();
+    starts.sort_unstable_by(f32::total_cmp);
+    starts.dedup();
+    let boundary = starts
+        .windows(2)
+        .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+        .filter(|pair| pair[1] - pair[0] >= MIN_COLUMN_GAP)
+        .map(|pair| pair[1]);
+    if let Some(boundary) = boundary {
+        let (left, right): (Vec<TextChunk>, Vec<TextChunk>) =
+            rows.iter().cloned().partition(|row| row.x < boundary);
+        let min_rows = rows.len() as f32 * MIN_COLUMN_SHARE;
+        if left.len() as f32 >= min_rows && right.len() as f32 >= min_rows {
+            return vec![left, right];
+        }
+    }
+    vec![rows.to_vec()]
+}
@@ -0,0 +1,110 @@
+//! Output formats provided by WebAssembly modules loaded at runtime, so that formats of their own can be added without
+//! changing this crate.
+//!
+//! A plugin is a module, either binary or in the text format, which works like this:
+//!
+//! - It exports its `memory`, and an `alloc` function which takes a size in bytes and returns where in memory it has
+//!   made room for that many.
+//! - It exports a `render_page` function, which is called with the position and length of each page in memory, as
+//!   JSON like `{"number": 1, "rows": [{"text": "...", "page": 1, "x": 72, "y": 720}]}`. The page is only there for
+//!   the duration of the call.
+//! - It may export a `finish` function, which is called once after the last page.
+//! - It can import `write` from the `env` module, which takes the position and length of some bytes in its memory and
+//!   adds them to the output.
+//!
+//! Plugins can't do anything else outside of themselves, and a plugin which traps stops the extraction with an error.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use wasmi::{Caller, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::{output::OutputRenderer, text::Page};
+
+/// Renders pages by passing them to a plugin, which writes the output.
+pub struct PluginRenderer<W: Write + 'static> {
+    store: Store<W>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    render_page: TypedFunc<(i32, i32), ()>,
+    finish: Option<TypedFunc<(), ()>>,
+}
+
+impl<W: Write + 'static> PluginRenderer<W> {
+    pub fn load(path: &Path, writer: W) -> Result<Self, Box<dyn Error>> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, fs::read(path)?)
+            .map_err(|error| format!("{} isn't a valid plugin: {}", path.display(), error))?;
+        let mut store = Store::new(&engine, writer);
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap("env", "write", write)?;
+        let instance = linker.instantiate_and_start(&mut store, &module)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| format!("{} doesn't export its memory", path.display()))?;
+        Ok(Self {
+            memory,
+            alloc: instance.get_typed_func(&store, "alloc")?,
+            render_page: instance.get_typed_func(&store, "render_page")?,
+            // The plugin might not need to do anything at the end, so this one is optional.
+            finish: instance.get_typed_func(&store, "finish").ok(),
+            store,
+        })
+    }
+}
+
+/// Copies bytes from the plugin's memory to the output.
+fn write<W: Write>(
+    mut caller: Caller<'_, W>,
+    position: i32,
+    length: i32,
+) -> Result<(), wasmi::Error> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| wasmi::Error::new("the plugin doesn't export its memory"))?;
+    let (memory, writer) = memory.data_and_store_mut(&mut caller);
+    let bytes = usize::try_from(position)
+        .ok()
+        .zip(usize::try_from(length).ok())
+        .and_then(|(position, length)| memory.get(position..position.checked_add(length)?))
+        .ok_or_else(|| wasmi::Error::new("the plugin wrote bytes from outside its memory"))?;
+    writer
+        .write_all(bytes)
+        .map_err(|error| wasmi::Error::new(error.to_string()))
+}
+
+fn plugin_error(error: impl Display) -> io::Error {
+    io::Error::other(format!("the plugin failed: {}", error))
+}
+
+impl<W: Write + 'static> OutputRenderer for PluginRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let json = serde_json::to_vec(page)?;
+        let length = i32::try_from(json.len()).map_err(plugin_error)?;
+        let position = self
+            .alloc
+            .call(&mut self.store, length)
+            .map_err(plugin_error)?;
+        self.memory
+            .write(&mut self.store, position as u32 as usize, &json)
+            .map_err(plugin_error)?;
+        self.render_page
+            .call(&mut self.store, (position, length))
+            .map_err(plugin_error)?;
+        // Flushing after every page means that whatever is reading the output sees each page as soon as it is done.
+        self.store.data_mut().flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(finish) = &self.finish {
+            finish.call(&mut self.store, ()).map_err(plugin_error)?;
+        }
+        self.store.data_mut().flush()
+    }
+}
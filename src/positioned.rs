@@ -0,0 +1,134 @@
+//! Writes the text as XHTML with every piece of it where it is on the page, as pdftohtml does, for previewing the
+//! text as it was laid out and comparing it against the page.
+//!
+//! Each page is a `div` as big as the part of the page that is shown, and each of the chunks its rows were joined
+//! from is a `span` in it, placed by the top left corner of its text and drawn in its font's family, size and style.
+//! Sizes and places are in points, which are the units of the page. Text which runs at an angle is turned to run the
+//! same way, and vertical text runs down the page.
+
+use std::io::{self, Write};
+
+use crate::{
+    html,
+    output::OutputRenderer,
+    text::{self, Page, TextChunk},
+};
+
+const HEADER: &str = concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Strict//EN\" ",
+    "\"http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd\">\n",
+    "<html xmlns=\"http://www.w3.org/1999/xhtml\">\n",
+    "<head>\n",
+    "<meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\"/>\n",
+    "<title></title>\n",
+    "<style type=\"text/css\">\n",
+    ".page { position: relative; overflow: hidden; margin: 1em auto; border: 1px solid #ccc; }\n",
+    ".page span { position: absolute; white-space: pre; line-height: 1; }\n",
+    "</style>\n",
+    "</head>\n",
+    "<body>\n",
+);
+
+const FOOTER: &str = "</body>\n</html>\n";
+
+pub struct PositionedRenderer<W: Write> {
+    writer: W,
+    started: bool,
+}
+
+impl<W: Write> PositionedRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            started: false,
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.writer.write_all(HEADER.as_bytes())?;
+            self.started = true;
+        }
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, chunk: &TextChunk, page: &Page) -> io::Result<()> {
+        let text = text::plain_text(&chunk.text);
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+        let [left, _, _, top] = page.bounds;
+        let mut style = if chunk.vertical {
+            // Columns go down the page from where they start.
+            format!(
+                "left: {:.2}pt; top: {:.2}pt; writing-mode: vertical-rl;",
+                chunk.x - left,
+                top - chunk.y
+            )
+        } else {
+            format!(
+                "left: {:.2}pt; top: {:.2}pt;",
+                chunk.x - left,
+                top - chunk.y - chunk.height
+            )
+        };
+        style.push_str(&format!(" font-size: {:.2}pt;", chunk.font_size));
+        if let Some(family) = &chunk.font_family {
+            // Quotes would end the family's name early.
+            style.push_str(&format!(
+                " font-family: '{}';",
+                family.replace(['\'', '"'], "")
+            ));
+        }
+        if chunk.bold {
+            style.push_str(" font-weight: bold;");
+        }
+        if chunk.italic {
+            style.push_str(" font-style: italic;");
+        }
+        if chunk.angle != 0.0 {
+            // Angles go anticlockwise, and CSS turns things clockwise, about the baseline at the start of the text.
+            style.push_str(&format!(
+                " transform: rotate({:.2}deg); transform-origin: left bottom;",
+                -chunk.angle
+            ));
+        }
+        writeln!(
+            self.writer,
+            "<span style=\"{}\">{}</span>",
+            style,
+            html::escape_plain(&text)
+        )
+    }
+}
+
+impl<W: Write> OutputRenderer for PositionedRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.start()?;
+        let [left, bottom, right, top] = page.bounds;
+        writeln!(
+            self.writer,
+            "<div class=\"page\" id=\"page-{}\" style=\"width: {:.2}pt; height: {:.2}pt;\">",
+            page.number,
+            right - left,
+            top - bottom
+        )?;
+        for row in &page.rows {
+            if row.chunks.is_empty() {
+                self.write_chunk(row, page)?;
+            }
+            for chunk in &row.chunks {
+                self.write_chunk(chunk, page)?;
+            }
+        }
+        writeln!(self.writer, "</div>")?;
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        self.writer.write_all(FOOTER.as_bytes())?;
+        self.writer.flush()
+    }
+}
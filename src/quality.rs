@@ -0,0 +1,183 @@
+//! Scores how well the text of each page came out, so that pages which need OCR or a closer look can be picked out
+//! without reading them.
+//!
+//! Badly extracted text shows itself in a few ways: replacement characters and private use codepoints where glyphs
+//! couldn't be mapped to Unicode, words that don't look like words, and pairs of characters which hardly ever turn up
+//! next to each other in real text, such as control characters, capitals in the middle of lowercase words, and the
+//! `Ã©` left behind when UTF-8 is read as Latin-1. Words are judged by their shape: letters only, not too long, and
+//! with a vowel if they are more than a few plain Latin letters.
+//!
+//! Words are also looked up in a dictionary of the most common English words, which make up around a third to a half
+//! of English prose and hardly any of garbled text. That dictionary only knows English, so the share of words found
+//! in it is reported but left out of the score, which would otherwise flag every page in another language.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::{
+    keywords,
+    output::OutputRenderer,
+    text::{self, Page},
+};
+
+/// Pages scoring less than this are flagged for review.
+const REVIEW_THRESHOLD: f64 = 0.6;
+
+/// Words longer than this many characters are taken to be runs of glyphs rather than real words.
+const MAX_WORD_LENGTH: usize = 20;
+
+#[derive(Serialize)]
+pub struct PageQuality {
+    pub page: u32,
+    /// From 0 for text that is all garbage to 1 for text that looks perfectly fine, and 0 for pages without any text.
+    pub score: f64,
+    /// How many characters of text there are, not counting whitespace.
+    pub characters: usize,
+    /// The share of characters which are the replacement character, `U+FFFD`.
+    pub replacement_ratio: f64,
+    /// The share of characters which are private use codepoints, which no font decoding should leave behind.
+    pub private_use_ratio: f64,
+    /// The share of words which look like words.
+    pub word_ratio: f64,
+    /// The share of words which are among the most common words of English, which is low for English text that has
+    /// come out badly, but also for text in other languages.
+    pub dictionary_ratio: f64,
+    /// The share of pairs of neighbouring characters which would be strange to see in real text.
+    pub suspicious_bigram_ratio: f64,
+    /// Whether the page should be checked by hand or run through OCR.
+    pub needs_review: bool,
+}
+
+/// Scores the text of a page.
+pub fn assess(page: &Page) -> PageQuality {
+    let text: String = page
+        .rows
+        .iter()
+        .map(|row| text::plain_text(&row.text))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let characters = text.chars().filter(|c| !c.is_whitespace()).count();
+    let ratio = |count: usize, total: usize| {
+        if total == 0 {
+            0.0
+        } else {
+            count as f64 / total as f64
+        }
+    };
+
+    let replacements = text
+        .chars()
+        .filter(|&c| c == char::REPLACEMENT_CHARACTER)
+        .count();
+    let private_use = text.chars().filter(|&c| is_private_use(c)).count();
+
+    let mut words = 0;
+    let mut real_words = 0;
+    let mut dictionary_words = 0;
+    for word in text.split_whitespace() {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        // Numbers and punctuation aren't words, but they aren't a sign of anything wrong either.
+        if !word.chars().any(char::is_alphabetic) {
+            continue;
+        }
+        words += 1;
+        if looks_like_word(word) {
+            real_words += 1;
+        }
+        if keywords::is_stopword(&word.to_lowercase()) {
+            dictionary_words += 1;
+        }
+    }
+
+    let mut bigrams = 0;
+    let mut suspicious_bigrams = 0;
+    for word in text.split_whitespace() {
+        let characters: Vec<char> = word.chars().collect();
+        for pair in characters.windows(2) {
+            bigrams += 1;
+            if is_suspicious(pair[0], pair[1]) {
+                suspicious_bigrams += 1;
+            }
+        }
+    }
+
+    let replacement_ratio = ratio(replacements, characters);
+    let private_use_ratio = ratio(private_use, characters);
+    let word_ratio = ratio(real_words, words);
+    let dictionary_ratio = ratio(dictionary_words, words);
+    let suspicious_bigram_ratio = ratio(suspicious_bigrams, bigrams);
+    let score = if characters == 0 {
+        0.0
+    } else {
+        (word_ratio
+            * (1.0 - replacement_ratio - private_use_ratio)
+            * (1.0 - suspicious_bigram_ratio))
+            .clamp(0.0, 1.0)
+    };
+    PageQuality {
+        page: page.number,
+        score,
+        characters,
+        replacement_ratio,
+        private_use_ratio,
+        word_ratio,
+        dictionary_ratio,
+        suspicious_bigram_ratio,
+        needs_review: score < REVIEW_THRESHOLD,
+    }
+}
+
+fn is_private_use(c: char) -> bool {
+    matches!(c, '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{FFFFD}' | '\u{100000}'..='\u{10FFFD}')
+}
+
+fn looks_like_word(word: &str) -> bool {
+    let length = word.chars().count();
+    // Apostrophes, hyphens and full stops can come inside words, as in "don't", "well-known" and "e.g".
+    let is_letters = word
+        .chars()
+        .all(|c| c.is_alphabetic() || matches!(c, '\'' | '’' | '-' | '.'));
+    // Short words like "Mr" and initials can do without a vowel.
+    let has_vowel =
+        length <= 3 || !word.is_ascii() || word.chars().any(|c| "aeiouyAEIOUY".contains(c));
+    is_letters && length <= MAX_WORD_LENGTH && has_vowel
+}
+
+fn is_suspicious(first: char, second: char) -> bool {
+    // UTF-8 read as Latin-1 turns every accented letter into one of these followed by a character from the upper half
+    // of Latin-1.
+    let is_misread_utf8 = matches!(first, 'Ã' | 'Â' | 'â') && matches!(second, '\u{80}'..='\u{BF}');
+    first.is_control()
+        || second.is_control()
+        || (first.is_lowercase() && second.is_uppercase())
+        || is_misread_utf8
+}
+
+/// Scores every page, and writes the scores as a JSON array once the last page is done.
+pub struct QualityRenderer<W: Write> {
+    writer: W,
+    pages: Vec<PageQuality>,
+}
+
+impl<W: Write> QualityRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            pages: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> OutputRenderer for QualityRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.pages.push(assess(page));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut self.writer, &self.pages)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,96 @@
+//! Finds the reference section of a paper and splits it into its citations.
+//!
+//! The section starts at a row which is just a heading like "References" or "Bibliography", and runs until an
+//! appendix or acknowledgements heading, or the end of the document. Numbered entries, like `[3]` or `3.`, start at
+//! each number. Otherwise entries are taken to be in author-year style, where each one starts with a surname and a
+//! comma at the left margin of the section, and any lines indented further continue the entry before.
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::text::{self, TextChunk};
+
+/// How far to the right of the margin an entry can start, since where lines start is rarely exactly level.
+const MARGIN_TOLERANCE: f32 = 1.0;
+
+/// A single entry of the reference section.
+#[derive(Serialize)]
+pub struct Reference {
+    /// The entry's number, for numbered references.
+    pub number: Option<String>,
+    pub text: String,
+    /// The page the entry starts on.
+    pub page: u32,
+}
+
+/// Finds the entries of the document's reference section, in order, or nothing if there isn't one.
+pub fn find_references(rows: &[TextChunk]) -> Vec<Reference> {
+    let section_heading = Regex::new(
+        r"(?i)^(?:[\dIVX]+\.?\s+)?(?:references|bibliography|works cited|literature cited|reference list)$",
+    )
+    .unwrap();
+    let section_end =
+        Regex::new(r"(?i)^(?:[\dA-Z]+\.?\s+)?(?:appendix|appendices|acknowledge?ments?)\b")
+            .unwrap();
+    let number = Regex::new(r"^(?:\[(\d+)\]|(\d+)\.)\s*").unwrap();
+    let author = Regex::new(r"^\p{Lu}[\p{L}'’\-]+(?: [\p{Lu}][\p{L}'’\-]+)*,").unwrap();
+
+    // The last matching heading is used, since a paper may mention its references in its contents.
+    let Some(start) = rows
+        .iter()
+        .rposition(|row| section_heading.is_match(text::plain_text(&row.text).trim()))
+    else {
+        return Vec::new();
+    };
+    let section: Vec<(&TextChunk, String)> = rows[start + 1..]
+        .iter()
+        .map(|row| (row, text::plain_text(&row.text).trim().to_owned()))
+        .take_while(|(_, text)| !section_end.is_match(text))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+
+    let numbered_rows = section
+        .iter()
+        .filter(|(_, text)| number.is_match(text))
+        .count();
+    // Entries rarely run to more than a few lines, so a section that is numbered has numbers on a good share of rows.
+    let is_numbered = numbered_rows > 0 && numbered_rows * 4 >= section.len();
+    let margin = section
+        .iter()
+        .map(|(row, _)| row.x)
+        .min_by(f32::total_cmp)
+        .unwrap_or(0.0);
+
+    let mut references: Vec<Reference> = Vec::new();
+    for (row, text) in &section {
+        let starts_entry = if is_numbered {
+            number.is_match(text)
+        } else {
+            row.x - margin < MARGIN_TOLERANCE && author.is_match(text)
+        };
+        match references.last_mut() {
+            Some(reference) if !starts_entry => text::join_line(&mut reference.text, text),
+            // Rows before the first entry, such as a note about the citation style, aren't citations.
+            None if !starts_entry => {}
+            _ => {
+                let captures = is_numbered.then(|| number.captures(text)).flatten();
+                let (entry_number, entry_text) = match captures {
+                    Some(captures) => (
+                        captures
+                            .get(1)
+                            .or(captures.get(2))
+                            .map(|number| number.as_str().to_owned()),
+                        text[captures[0].len()..].to_owned(),
+                    ),
+                    None => (None, text.clone()),
+                };
+                references.push(Reference {
+                    number: entry_number,
+                    text: entry_text,
+                    page: row.page,
+                });
+            }
+        }
+    }
+    references
+}
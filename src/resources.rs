@@ -0,0 +1,100 @@
+//! The resources that content refers to by name, such as the fonts it shows text in and the XObjects it draws.
+//!
+//! Resources are inherited through the page tree, and many documents put them on a `Pages` node once rather than on
+//! every page. A page's resources are gathered from the page and each of its ancestors in turn, with the page's own
+//! taking priority over those it inherits. Form XObjects can have resources of their own instead.
+//!
+//! The properties among them include the optional content groups, or layers, that marked content belongs to.
+
+use std::collections::BTreeMap;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use crate::{
+    outline,
+    traversal::{self, TraversalError},
+};
+
+/// The resources of a page or form, each keyed by the name the content uses for it.
+#[derive(Default)]
+pub struct Resources<'a> {
+    pub fonts: BTreeMap<Vec<u8>, &'a Object>,
+    pub xobjects: BTreeMap<Vec<u8>, &'a Object>,
+    /// The property lists marked content refers to, such as the optional content groups it belongs to.
+    pub properties: BTreeMap<Vec<u8>, &'a Object>,
+}
+
+impl<'a> Resources<'a> {
+    /// The resources in a single resource dictionary, such as a form's.
+    pub fn new(document: &'a Document, resources: &'a Dictionary) -> Self {
+        let mut result = Self::default();
+        result.add(document, resources);
+        result
+    }
+
+    /// The effective resources of a page, including everything it inherits from the page tree.
+    pub fn of_page(document: &'a Document, page_id: ObjectId) -> Result<Self, TraversalError> {
+        let mut result = Self::default();
+        for node in traversal::page_ancestors(document, page_id)? {
+            if let Ok((_, Object::Dictionary(resources))) = node
+                .get(b"Resources")
+                .and_then(|resources| document.dereference(resources))
+            {
+                result.add(document, resources);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Adds the resources in a resource dictionary which aren't already there.
+    fn add(&mut self, document: &'a Document, resources: &'a Dictionary) {
+        add_entries(&mut self.fonts, document, resources, b"Font");
+        add_entries(&mut self.xobjects, document, resources, b"XObject");
+        add_entries(&mut self.properties, document, resources, b"Properties");
+    }
+}
+
+fn add_entries<'a>(
+    entries: &mut BTreeMap<Vec<u8>, &'a Object>,
+    document: &'a Document,
+    resources: &'a Dictionary,
+    kind: &[u8],
+) {
+    let Ok((_, Object::Dictionary(dictionary))) = resources
+        .get(kind)
+        .and_then(|dictionary| document.dereference(dictionary))
+    else {
+        return;
+    };
+    for (name, entry) in dictionary.iter() {
+        entries.entry(name.clone()).or_insert(entry);
+    }
+}
+
+/// The names of the layers something belongs to, from its optional content group, or from each of the groups of an
+/// optional content membership dictionary.
+pub fn layer_names(document: &Document, optional_content: &Object) -> Vec<String> {
+    let group_name = |group: &Dictionary| {
+        let (_, name) = document.dereference(group.get(b"Name").ok()?).ok()?;
+        Some(outline::decode_text_string(name.as_str().ok()?))
+    };
+    let Ok((_, Object::Dictionary(optional_content))) = document.dereference(optional_content)
+    else {
+        return Vec::new();
+    };
+    let Ok(groups) = optional_content.get(b"OCGs") else {
+        return group_name(optional_content).into_iter().collect();
+    };
+    let groups = match document.dereference(groups) {
+        Ok((_, Object::Array(groups))) => groups.as_slice(),
+        Ok((_, group)) => std::slice::from_ref(group),
+        Err(_) => &[],
+    };
+    groups
+        .iter()
+        .filter_map(|group| match document.dereference(group) {
+            Ok((_, Object::Dictionary(group))) => group_name(group),
+            _ => None,
+        })
+        .collect()
+}
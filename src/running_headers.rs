@@ -0,0 +1,241 @@
+//! Leaves out the running headers and footers which repeat at the top and bottom of most pages, such as the title of
+//! the document or chapter and "Page 3 of 10".
+//!
+//! Only the first and last few rows of each page can be headers or footers. One of them is if the same text, with the
+//! numbers in it counting as the same whatever they are, is at nearly the same height on at least half of the pages,
+//! so that the different headers of left and right hand pages are found as well. The headers at the top of a page
+//! are then the rows from the top down to the last of them which is set apart from the body by a wider gap than the
+//! rows of the page usually have, and likewise for the footers at the bottom, which keeps the first lines of pages
+//! whose bodies happen to start the same way. Each page is compared with the pages either side of it rather than with
+//! the whole document, so that only those few pages are held back.
+//!
+//! Tagged documents usually mark their headers and footers as artifacts, which are left out before this.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+use crate::{
+    output::{OutputRenderer, PageWindow},
+    text::{self, Page},
+};
+
+/// How many rows at the top and at the bottom of each page can be headers or footers.
+const EDGE_ROWS: usize = 3;
+
+/// How far apart, in the units of the page, the same header can be from one page to the next.
+const HEIGHT_TOLERANCE: f32 = 2.0;
+
+/// Documents with fewer pages than this have too few to tell what repeats on them.
+const MIN_PAGES: usize = 3;
+
+/// Headers and footers are set apart from the body by gaps at least this many times the usual gap between rows.
+const SEPARATION: f32 = 1.5;
+
+/// Removes the running headers and footers from the pages before passing them on to another renderer.
+pub struct RunningHeaderRemover {
+    inner: Box<dyn OutputRenderer>,
+    window: PageWindow,
+}
+
+impl RunningHeaderRemover {
+    /// Passes each page on once `lookahead` pages after it have been extracted to compare it with.
+    pub fn new(inner: Box<dyn OutputRenderer>, lookahead: usize) -> Self {
+        Self {
+            inner,
+            window: PageWindow::new(lookahead),
+        }
+    }
+}
+
+impl OutputRenderer for RunningHeaderRemover {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.window.push(page, |pages, index| {
+            inner.render_page(&without_running_headers(pages, index))
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.window
+            .finish(|pages, index| inner.render_page(&without_running_headers(pages, index)))?;
+        self.inner.finish()
+    }
+}
+
+/// The page at `index` without the rows at its top and bottom which repeat at about the same height on at least half
+/// of the pages.
+pub fn without_running_headers(pages: &[Page], index: usize) -> Page {
+    let mut page = pages[index].clone();
+    if pages.len() < MIN_PAGES {
+        return page;
+    }
+    // Where each row at the edge of a page is, by its text: the page it is on and how high up.
+    let mut places: HashMap<String, Vec<(usize, f32)>> = HashMap::new();
+    for (page_index, other_page) in pages.iter().enumerate() {
+        let rows = rows_down_the_page(other_page);
+        let top = rows.iter().take(EDGE_ROWS);
+        let bottom = rows.iter().rev().take(EDGE_ROWS);
+        for &row_index in top.chain(bottom) {
+            let row = &other_page.rows[row_index];
+            let key = header_key(&row.text);
+            if !key.is_empty() {
+                places.entry(key).or_default().push((page_index, row.y));
+            }
+        }
+    }
+    let needed = pages.len().div_ceil(2);
+    let is_repeated = |&row_index: &usize| {
+        let row = &page.rows[row_index];
+        places.get(&header_key(&row.text)).is_some_and(|places| {
+            let pages_with_it: HashSet<usize> = places
+                .iter()
+                .filter(|&&(_, other_y)| (other_y - row.y).abs() <= HEIGHT_TOLERANCE)
+                .map(|&(other_page, _)| other_page)
+                .collect();
+            pages_with_it.len() >= needed
+        })
+    };
+    let rows = rows_down_the_page(&page);
+    // The gap after each row, down to the next one.
+    let gaps: Vec<f32> = rows
+        .windows(2)
+        .map(|pair| page.rows[pair[0]].y - page.rows[pair[1]].y)
+        .collect();
+    // Rows on the same line, such as the columns of a table, say nothing about how far apart lines are.
+    let mut sorted_gaps: Vec<f32> = gaps.iter().copied().filter(|&gap| gap > 0.0).collect();
+    sorted_gaps.sort_by(f32::total_cmp);
+    let Some(&usual_gap) = sorted_gaps.get(sorted_gaps.len() / 2) else {
+        return page;
+    };
+    let is_set_apart = |gap: f32| gap > usual_gap * SEPARATION;
+    // How many rows from the top, and from the bottom, are headers and footers.
+    let headers = (0..EDGE_ROWS.min(gaps.len()))
+        .take_while(|&position| is_repeated(&rows[position]))
+        .filter(|&position| is_set_apart(gaps[position]))
+        .last()
+        .map_or(0, |position| position + 1);
+    let footers = (0..EDGE_ROWS.min(gaps.len()))
+        .take_while(|&position| is_repeated(&rows[rows.len() - 1 - position]))
+        .filter(|&position| is_set_apart(gaps[gaps.len() - 1 - position]))
+        .last()
+        .map_or(0, |position| position + 1);
+    if headers + footers >= rows.len() {
+        return page;
+    }
+    let headers: HashSet<usize> = rows[..headers]
+        .iter()
+        .chain(&rows[rows.len() - footers..])
+        .copied()
+        .collect();
+    let mut row_index = 0;
+    page.rows.retain(|_| {
+        row_index += 1;
+        !headers.contains(&(row_index - 1))
+    });
+    page
+}
+
+/// Which of the rows of a page are where, from the top of the page down, leaving out columns of vertical text.
+fn rows_down_the_page(page: &Page) -> Vec<usize> {
+    let mut rows: Vec<usize> = (0..page.rows.len())
+        .filter(|&index| !page.rows[index].vertical)
+        .collect();
+    rows.sort_by(|&a, &b| page.rows[b].y.total_cmp(&page.rows[a].y));
+    rows
+}
+
+/// The text that has to be the same for rows to be the same header, in which every run of digits is the same, so
+/// that page numbers and dates don't keep the headers of different pages apart.
+fn header_key(text: &str) -> String {
+    let mut key = String::new();
+    for character in text::plain_text(text).trim().chars() {
+        if character.is_ascii_digit() {
+            if !key.ends_with('#') {
+                key.push('#');
+            }
+        } else {
+            key.push(character);
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{output::WINDOW_PAGES, text::TextChunk};
+
+    /// Collects the text of the pages it is given.
+    struct Collector(std::rc::Rc<std::cell::RefCell<Vec<Vec<String>>>>);
+
+    impl OutputRenderer for Collector {
+        fn render_page(&mut self, page: &Page) -> io::Result<()> {
+            let rows = page.rows.iter().map(|row| row.text.clone()).collect();
+            self.0.borrow_mut().push(rows);
+            Ok(())
+        }
+    }
+
+    fn row(text: &str, y: f32) -> TextChunk {
+        TextChunk {
+            text: text.to_owned(),
+            x: 72.0,
+            y,
+            width: 200.0,
+            height: 12.0,
+            font_size: 12.0,
+            ..TextChunk::default()
+        }
+    }
+
+    /// A page with a header and a footer, and a few rows of body in between.
+    fn page(number: u32) -> Page {
+        let mut rows = vec![row("Annual Report", 760.0)];
+        rows.extend(
+            (0..5).map(|line| row(&format!("Body {number}.{line}"), 700.0 - 14.0 * line as f32)),
+        );
+        rows.push(row(&format!("Page {number}"), 40.0));
+        Page {
+            number,
+            label: None,
+            rows,
+            tables: Vec::new(),
+            bounds: [0.0, 0.0, 612.0, 792.0],
+        }
+    }
+
+    #[test]
+    fn removes_headers_from_every_page_of_long_documents() {
+        let pages = std::rc::Rc::default();
+        let mut remover = RunningHeaderRemover::new(
+            Box::new(Collector(std::rc::Rc::clone(&pages))),
+            WINDOW_PAGES,
+        );
+        for number in 1..=40 {
+            remover.render_page(&page(number)).unwrap();
+            // Pages go out while the document is still being extracted, with only the window held back.
+            assert_eq!(
+                pages.borrow().len(),
+                (number as usize).saturating_sub(WINDOW_PAGES)
+            );
+        }
+        remover.finish().unwrap();
+        let pages = pages.borrow();
+        assert_eq!(pages.len(), 40);
+        assert!(pages
+            .iter()
+            .all(|rows| rows.len() == 5 && rows[0].starts_with("Body")));
+    }
+
+    #[test]
+    fn keeps_rows_which_only_start_some_pages() {
+        let mut pages: Vec<Page> = (1..=6).map(page).collect();
+        pages[0].rows[1].text = "Annual Report".to_owned();
+        let page = without_running_headers(&pages, 0);
+        assert_eq!(page.rows[0].text, "Annual Report");
+        assert_eq!(page.rows.len(), 5);
+    }
+}
@@ -0,0 +1,193 @@
+//! Writes the superscripts and subscripts of plain text some other way than as the HTML tags they are marked up with.
+//!
+//! Superscripts and subscripts are found while the rows are put together and marked up with `<sup>` and `<sub>`, which
+//! the formats with markup of their own turn into theirs, and which footnotes are found by. Plain text has no markup
+//! of its own, so they can be written as the carets and tildes of Pandoc's Markdown, as the Unicode characters for
+//! raised and lowered letters and numbers, or as ordinary text.
+
+use std::io;
+
+use clap::ValueEnum;
+
+use crate::{output::OutputRenderer, text::Page};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ScriptMarkup {
+    /// Marks them up with `<sup>` and `<sub>`.
+    #[default]
+    Html,
+    /// Marks them up as `^2^` and `~2~`.
+    Markdown,
+    /// Writes them with the superscript and subscript characters of Unicode, such as "²" and "₂", or as Markdown if
+    /// some of their characters have none.
+    Unicode,
+    /// Writes them as ordinary text.
+    None,
+}
+
+/// The superscript form of each character which has one.
+const SUPERSCRIPTS: [(char, char); 39] = [
+    ('0', '⁰'),
+    ('1', '¹'),
+    ('2', '²'),
+    ('3', '³'),
+    ('4', '⁴'),
+    ('5', '⁵'),
+    ('6', '⁶'),
+    ('7', '⁷'),
+    ('8', '⁸'),
+    ('9', '⁹'),
+    ('+', '⁺'),
+    ('-', '⁻'),
+    ('=', '⁼'),
+    ('(', '⁽'),
+    (')', '⁾'),
+    ('a', 'ᵃ'),
+    ('b', 'ᵇ'),
+    ('c', 'ᶜ'),
+    ('d', 'ᵈ'),
+    ('e', 'ᵉ'),
+    ('f', 'ᶠ'),
+    ('g', 'ᵍ'),
+    ('h', 'ʰ'),
+    ('i', 'ⁱ'),
+    ('j', 'ʲ'),
+    ('k', 'ᵏ'),
+    ('l', 'ˡ'),
+    ('m', 'ᵐ'),
+    ('n', 'ⁿ'),
+    ('o', 'ᵒ'),
+    ('p', 'ᵖ'),
+    ('r', 'ʳ'),
+    ('s', 'ˢ'),
+    ('t', 'ᵗ'),
+    ('u', 'ᵘ'),
+    ('v', 'ᵛ'),
+    ('w', 'ʷ'),
+    ('x', 'ˣ'),
+    ('y', 'ʸ'),
+];
+
+/// The subscript form of each character which has one.
+const SUBSCRIPTS: [(char, char); 32] = [
+    ('0', '₀'),
+    ('1', '₁'),
+    ('2', '₂'),
+    ('3', '₃'),
+    ('4', '₄'),
+    ('5', '₅'),
+    ('6', '₆'),
+    ('7', '₇'),
+    ('8', '₈'),
+    ('9', '₉'),
+    ('+', '₊'),
+    ('-', '₋'),
+    ('=', '₌'),
+    ('(', '₍'),
+    (')', '₎'),
+    ('a', 'ₐ'),
+    ('e', 'ₑ'),
+    ('h', 'ₕ'),
+    ('i', 'ᵢ'),
+    ('j', 'ⱼ'),
+    ('k', 'ₖ'),
+    ('l', 'ₗ'),
+    ('m', 'ₘ'),
+    ('n', 'ₙ'),
+    ('o', 'ₒ'),
+    ('p', 'ₚ'),
+    ('r', 'ᵣ'),
+    ('s', 'ₛ'),
+    ('t', 'ₜ'),
+    ('u', 'ᵤ'),
+    ('v', 'ᵥ'),
+    ('x', 'ₓ'),
+];
+
+/// Rewrites the superscripts and subscripts of the pages before passing them on to another renderer.
+pub struct ScriptRewriter {
+    inner: Box<dyn OutputRenderer>,
+    markup: ScriptMarkup,
+}
+
+impl ScriptRewriter {
+    pub fn new(inner: Box<dyn OutputRenderer>, markup: ScriptMarkup) -> Self {
+        Self { inner, markup }
+    }
+}
+
+impl OutputRenderer for ScriptRewriter {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let mut rows = page.rows.clone();
+        for row in &mut rows {
+            row.text = rewrite(&row.text, self.markup);
+        }
+        self.inner.render_page(&Page {
+            number: page.number,
+            label: page.label.clone(),
+            rows,
+            tables: page.tables.clone(),
+            bounds: page.bounds,
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.finish()
+    }
+}
+
+/// Rewrites the superscripts and subscripts of some text with the given markup.
+pub fn rewrite(text: &str, markup: ScriptMarkup) -> String {
+    if markup == ScriptMarkup::Html {
+        return text.to_owned();
+    }
+    let mut rewritten = String::with_capacity(text.len());
+    let mut rest = text;
+    loop {
+        let next = [("<sup>", "</sup>", true), ("<sub>", "</sub>", false)]
+            .into_iter()
+            .filter_map(|(start, end, raised)| Some((rest.find(start)?, start, end, raised)))
+            .min_by_key(|&(position, ..)| position);
+        let Some((position, start, end, raised)) = next else {
+            break;
+        };
+        let inner_start = position + start.len();
+        let Some(length) = rest[inner_start..].find(end) else {
+            break;
+        };
+        rewritten.push_str(&rest[..position]);
+        rewritten.push_str(&script(
+            &rest[inner_start..inner_start + length],
+            raised,
+            markup,
+        ));
+        rest = &rest[inner_start + length + end.len()..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+/// The text of a superscript or subscript, marked up as it is to be written.
+fn script(text: &str, raised: bool, markup: ScriptMarkup) -> String {
+    let marker = if raised { '^' } else { '~' };
+    match markup {
+        ScriptMarkup::Html => {
+            let tag = if raised { "sup" } else { "sub" };
+            format!("<{}>{}</{}>", tag, text, tag)
+        }
+        ScriptMarkup::Markdown => format!("{}{}{}", marker, text, marker),
+        ScriptMarkup::Unicode => {
+            let forms: &[(char, char)] = if raised { &SUPERSCRIPTS } else { &SUBSCRIPTS };
+            text.chars()
+                .map(|character| {
+                    forms
+                        .iter()
+                        .find(|&&(plain, _)| plain == character)
+                        .map(|&(_, form)| form)
+                })
+                .collect::<Option<String>>()
+                .unwrap_or_else(|| script(text, raised, ScriptMarkup::Markdown))
+        }
+        ScriptMarkup::None => text.to_owned(),
+    }
+}
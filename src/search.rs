@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use regex::Regex;
+
+use crate::text::{self, TextChunk};
+
+/// A single occurrence of the search pattern, which may span several rows of the same page.
+pub struct SearchHit {
+    /// The index of the row the match starts in.
+    pub first_row: usize,
+    /// The index of the row the match ends in, which is later than `first_row` if the match crosses a line break.
+    pub last_row: usize,
+    /// The byte offset of the match within the plain text of the first row, without its markup.
+    pub offset: usize,
+}
+
+impl SearchHit {
+    /// How many characters of the first row's plain text come before the match.
+    pub fn column(&self, rows: &[TextChunk]) -> usize {
+        text::plain_text(&rows[self.first_row].text)[..self.offset]
+            .chars()
+            .count()
+    }
+}
+
+/// How many rows of context to print around each hit.
+#[derive(Clone, Copy, Default)]
+pub struct Context {
+    pub before: usize,
+    pub after: usize,
+}
+
+/// Finds every match of the pattern in the rows, in document order.
+pub fn search(rows: &[TextChunk], pattern: &Regex) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    let mut page_start = 0;
+    while page_start < rows.len() {
+        let page = rows[page_start].page;
+        let page_end = rows[page_start..]
+            .iter()
+            .position(|row| row.page != page)
+            .map_or(rows.len(), |length| page_start + length);
+        // The plain text of the rows of a page is joined as it is into paragraphs, with spaces and with words
+        // hyphenated across lines put back together, so that phrases and words which wrap onto the next line still
+        // match.
+        let mut text = String::new();
+        let mut row_starts = Vec::new();
+        for row in &rows[page_start..page_end] {
+            let plain = text::plain_text(&row.text);
+            text::join_line(&mut text, &plain);
+            row_starts.push(text.len() - plain.len());
+        }
+        let row_containing =
+            |offset: usize| row_starts.partition_point(|&start| start <= offset) - 1;
+        for found in pattern.find_iter(&text) {
+            let first_row = row_containing(found.start());
+            let last_row = row_containing(found.end().saturating_sub(1).max(found.start()));
+            hits.push(SearchHit {
+                first_row: page_start + first_row,
+                last_row: page_start + last_row,
+                offset: found.start() - row_starts[first_row],
+            });
+        }
+        page_start = page_end;
+    }
+    hits
+}
+
+/// Prints the hits like grep does: matching rows are separated from their position by `:`, context rows by `-`, and
+/// non-adjacent groups of rows by `--`.
+pub fn print_hits(rows: &[TextChunk], hits: &[SearchHit], context: Context) {
+    let mut columns: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut matched_rows = BTreeSet::new();
+    let mut shown_rows = BTreeSet::new();
+    for hit in hits {
+        columns
+            .entry(hit.first_row)
+            .or_default()
+            .push(hit.column(rows) + 1);
+        matched_rows.extend(hit.first_row..=hit.last_row);
+        let first_shown = hit.first_row.saturating_sub(context.before);
+        let last_shown = (hit.last_row + context.after).min(rows.len() - 1);
+        shown_rows.extend(first_shown..=last_shown);
+    }
+
+    let has_context = context.before > 0 || context.after > 0;
+    let mut previous_row = None;
+    for index in shown_rows {
+        if has_context && previous_row.is_some_and(|previous| previous + 1 != index) {
+            println!("--");
+        }
+        previous_row = Some(index);
+        let row = &rows[index];
+        let position = format!("page {} ({}, {})", row.page, row.x, row.y);
+        let text = text::plain_text(&row.text);
+        if let Some(columns) = columns.get(&index) {
+            let columns = columns
+                .iter()
+                .map(|column| column.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!("{} column {}: {}", position, columns, text);
+        } else if matched_rows.contains(&index) {
+            println!("{}: {}", position, text);
+        } else {
+            println!("{}- {}", position, text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(pages: &[&[&str]]) -> Vec<TextChunk> {
+        let mut rows = Vec::new();
+        for (number, page) in pages.iter().enumerate() {
+            for (line, text) in page.iter().enumerate() {
+                rows.push(TextChunk {
+                    text: text.to_string(),
+                    page: number as u32 + 1,
+                    y: 700.0 - 14.0 * line as f32,
+                    ..TextChunk::default()
+                });
+            }
+        }
+        rows
+    }
+
+    fn find(rows: &[TextChunk], pattern: &str) -> Vec<(usize, usize, usize)> {
+        search(rows, &Regex::new(pattern).unwrap())
+            .into_iter()
+            .map(|hit| (hit.first_row, hit.last_row, hit.column(rows)))
+            .collect()
+    }
+
+    #[test]
+    fn finds_matches_within_rows() {
+        let rows = rows(&[&["one two", "two three"], &["two"]]);
+        assert_eq!(find(&rows, "two"), [(0, 0, 4), (1, 1, 0), (2, 2, 0)]);
+    }
+
+    #[test]
+    fn finds_phrases_across_line_breaks_but_not_pages() {
+        let rows = rows(&[&["the quick", "brown fox"], &["jumps"]]);
+        assert_eq!(find(&rows, "quick brown"), [(0, 1, 4)]);
+        assert!(find(&rows, "fox jumps").is_empty());
+    }
+
+    #[test]
+    fn finds_words_hyphenated_across_line_breaks() {
+        let rows = rows(&[&["an extra-", "ordinary result", "from 1990-", "1995"]]);
+        assert_eq!(find(&rows, "extraordinary"), [(0, 1, 3)]);
+        // Hyphens which don't break a word are kept, with a space after them.
+        assert_eq!(find(&rows, "1990- 1995"), [(2, 3, 5)]);
+    }
+
+    #[test]
+    fn ignores_markup() {
+        let rows = rows(&[&["E = mc<sup>2</sup> and H<sub>2</sub>O"]]);
+        assert_eq!(find(&rows, "mc2"), [(0, 0, 4)]);
+        assert_eq!(find(&rows, "H2O"), [(0, 0, 12)]);
+        assert!(find(&rows, "sup").is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+//! A faster way through pages which use a single simple font and nothing but plain text operators.
+//!
+//! Plain reports and the text layers of OCRed scans are nearly all like this. For these pages the content is scanned
+//! directly for the few operators that matter, rather than being parsed into a list of operations first. As soon as
+//! the scan comes across anything it doesn't understand it gives up, and the page goes through the normal path
+//! instead, so the output is the same either way.
+
+use lopdf::{Document, Object};
+
+use crate::{
+    content::{is_delimiter, is_whitespace, BudgetExceeded, PageBudget},
+    font::Font,
+    resources::Resources,
+    text_state::{PageText, TextOptions, TextState},
+};
+
+/// Operators which only draw graphics, so can be skipped over without affecting the text.
+const GRAPHICS_OPERATORS: [&[u8]; 21] = [
+    b"CS", b"G", b"J", b"K", b"M", b"RG", b"SC", b"SCN", b"W", b"W*", b"cs", b"g", b"i", b"j",
+    b"k", b"rg", b"ri", b"sc", b"scn", b"sh", b"w",
+];
+
+/// Finds the one font of a page, from its resources, if the page is simple enough to be scanned.
+///
+/// That means it has exactly one font, which is not a composite or Type 3 font, and no XObjects which could draw
+/// text of their own.
+pub fn simple_font<'a, 'b>(
+    document: &'a Document,
+    resources: &'b Resources<'a>,
+) -> Option<(&'b [u8], &'a Object)> {
+    if !resources.xobjects.is_empty() {
+        return None;
+    }
+    let mut fonts = resources.fonts.iter();
+    let (Some((name, &font)), None) = (fonts.next(), fonts.next()) else {
+        return None;
+    };
+    let subtype = document
+        .dereference(font)
+        .and_then(|(_, font)| font.as_dict())
+        .and_then(|font| font.get(b"Subtype"))
+        .and_then(Object::as_name);
+    if !matches!(subtype, Ok(b"Type1" | b"MMType1" | b"TrueType")) {
+        return None;
+    }
+    Some((name, font))
+}
+
+/// Scans a page's content for text drawn in its one font, giving `None` if the page turns out to need the normal
+/// path after all.
+pub fn scan(
+    content: &[u8],
+    page_number: u32,
+    font_name: &[u8],
+    font: &Font,
+    budget: PageBudget,
+    options: &TextOptions,
+) -> Result<Option<PageText>, BudgetExceeded> {
+    let mut scanner = Scanner {
+        content,
+        position: 0,
+    };
+    let mut operands = Vec::new();
+    let mut state = TextState::new(page_number, options.clone());
+    let mut font_selected = false;
+
+    let mut operator_count = 0;
+    loop {
+        let operator = match scanner.next_token() {
+            Some(Token::End) => break,
+            Some(Token::Operator(operator)) => operator,
+            Some(Token::Operand(operand)) => {
+                operands.push(operand);
+                continue;
+            }
+            None => return Ok(None),
+        };
+        operator_count += 1;
+        if operator_count > budget.max_operators {
+            return Err(BudgetExceeded::Operators(budget.max_operators));
+        }
+        match (operator, operands.as_slice()) {
+            (b"BT", []) => state.begin_text(),
+            (b"ET", []) => state.end_text(),
+            (b"Tf", [Operand::Name(name), Operand::Number(size)]) if *name == font_name => {
+                font_selected = true;
+                state.set_font_size(*size);
+                state.set_vertical(font.is_vertical());
+                state.set_font(font.family.as_deref(), font.bold, font.italic);
+                state.set_font_metrics(font.space_width(), font.height());
+            }
+            (b"Tj", [Operand::String(text)]) if font_selected => {
+                if state.in_text() {
+                    state.show(&font.decode(text), font.measure(text));
+                }
+            }
+            (b"'", [Operand::String(text)]) if font_selected => {
+                if state.in_text() {
+                    state.next_line();
+                    state.show(&font.decode(text), font.measure(text));
+                }
+            }
+            (
+                b"\"",
+                [Operand::Number(word_spacing), Operand::Number(character_spacing), Operand::String(text)],
+            ) if font_selected => {
+                if state.in_text() {
+                    state.set_word_spacing(*word_spacing);
+                    state.set_character_spacing(*character_spacing);
+                    state.next_line();
+                    state.show(&font.decode(text), font.measure(text));
+                }
+            }
+            (b"TJ", [Operand::Array(elements)]) if font_selected => {
+                if state.in_text() {
+                    for element in elements {
+                        match element {
+                            Operand::String(text) => {
+                                state.show(&font.decode(text), font.measure(text))
+                            }
+                            Operand::Number(adjustment) => state.adjust(*adjustment),
+                            Operand::Name(_) | Operand::Array(_) => return Ok(None),
+                        }
+                    }
+                }
+            }
+            (b"q", []) => state.save(),
+            (b"Q", []) => state.restore(),
+            (b"cm", operands) => match numbers(operands) {
+                Some(matrix) => state.transform(matrix),
+                None => return Ok(None),
+            },
+            (b"Tm", operands) => match numbers(operands) {
+                Some(matrix) => state.set_matrix(matrix),
+                None => return Ok(None),
+            },
+            (b"Td", [Operand::Number(x), Operand::Number(y)]) => state.move_line(*x, *y),
+            (b"TD", [Operand::Number(x), Operand::Number(y)]) => {
+                state.move_line_setting_leading(*x, *y)
+            }
+            (b"T*", []) => state.next_line(),
+            (b"Tc", [Operand::Number(spacing)]) => state.set_character_spacing(*spacing),
+            (b"Tw", [Operand::Number(spacing)]) => state.set_word_spacing(*spacing),
+            (b"Tz", [Operand::Number(percentage)]) => state.set_horizontal_scaling(*percentage),
+            (b"Ts", [Operand::Number(rise)]) => state.set_rise(*rise),
+            (b"Tr", [Operand::Number(mode)]) => state.set_render_mode(*mode as i64),
+            (b"TL", [Operand::Number(leading)]) => state.set_leading(*leading),
+            (b"m", [Operand::Number(x), Operand::Number(y)]) => state.move_to(*x, *y),
+            (b"l", [Operand::Number(x), Operand::Number(y)]) => state.line_to(*x, *y),
+            (b"c" | b"v" | b"y", [.., Operand::Number(x), Operand::Number(y)]) => {
+                state.curve_to(*x, *y)
+            }
+            (b"re", operands) => match numbers(operands) {
+                Some([x, y, width, height]) => state.rectangle(x, y, width, height),
+                None => return Ok(None),
+            },
+            (b"h", []) => state.close_path(),
+            (b"S", []) => state.paint_path(true, false),
+            (b"s", []) => {
+                state.close_path();
+                state.paint_path(true, false);
+            }
+            (b"f" | b"F" | b"f*", []) => state.paint_path(false, true),
+            (b"B" | b"B*", []) => state.paint_path(true, true),
+            (b"b" | b"b*", []) => {
+                state.close_path();
+                state.paint_path(true, true);
+            }
+            (b"n", []) => state.paint_path(false, false),
+            (operator, _) if GRAPHICS_OPERATORS.contains(&operator) => {}
+            _ => return Ok(None),
+        }
+        operands.clear();
+    }
+    Ok(Some(state.finish()))
+}
+
+/// The operands of an operator which takes only numbers, if they are all numbers and there are the right number of
+/// them.
+fn numbers<const N: usize>(operands: &[Operand]) -> Option<[f32; N]> {
+    let numbers: Vec<f32> = operands
+        .iter()
+        .map(|operand| match operand {
+            Operand::Number(number) => Some(*number),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    numbers.try_into().ok()
+}
+
+enum Token<'a> {
+    Operand(Operand<'a>),
+    Operator(&'a [u8]),
+    End,
+}
+
+enum Operand<'a> {
+    Number(f32),
+    Name(&'a [u8]),
+    String(Vec<u8>),
+    /// An array of strings and numbers, as `TJ` takes.
+    Array(Vec<Operand<'a>>),
+}
+
+struct Scanner<'a> {
+    content: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    /// Reads the next token, or `None` if it is something the scan doesn't handle, such as a dictionary.
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        loop {
+            let Some(&byte) = self.content.get(self.position) else {
+                return Some(Token::End);
+            };
+            match byte {
+                _ if is_whitespace(byte) => self.position += 1,
+                b'%' => {
+                    while self
+                        .content
+                        .get(self.position)
+                        .is_some_and(|&byte| byte != b'\n' && byte != b'\r')
+                    {
+                        self.position += 1;
+                    }
+                }
+                b'(' => {
+                    self.position += 1;
+                    return self
+                        .literal_string()
+                        .map(|text| Token::Operand(Operand::String(text)));
+                }
+                b'<' if self.content.get(self.position + 1) != Some(&b'<') => {
+                    self.position += 1;
+                    return self
+                        .hex_string()
+                        .map(|text| Token::Operand(Operand::String(text)));
+                }
+                b'/' => {
+                    self.position += 1;
+                    let name = self.regular_characters();
+                    // Names with escapes in them would need decoding, and never turn up in simple pages anyway.
+                    if name.contains(&b'#') {
+                        return None;
+                    }
+                    return Some(Token::Operand(Operand::Name(name)));
+                }
+                b'[' => {
+                    self.position += 1;
+                    return self
+                        .array()
+                        .map(|elements| Token::Operand(Operand::Array(elements)));
+                }
+                _ if is_delimiter(byte) => return None,
+                _ => {
+                    let word = self.regular_characters();
+                    if !matches!(word[0], b'0'..=b'9' | b'+' | b'-' | b'.') {
+                        return Some(Token::Operator(word));
+                    }
+                    let word = std::str::from_utf8(word).ok()?;
+                    return Some(Token::Operand(Operand::Number(word.parse().ok()?)));
+                }
+            }
+        }
+    }
+
+    fn regular_characters(&mut self) -> &'a [u8] {
+        let start = self.position;
+        while self
+            .content
+            .get(self.position)
+            .is_some_and(|&byte| !is_whitespace(byte) && !is_delimiter(byte))
+        {
+            self.position += 1;
+        }
+        &self.content[start..self.position]
+    }
+
+    /// Reads the rest of an array of strings and numbers, after the opening bracket.
+    fn array(&mut self) -> Option<Vec<Operand<'a>>> {
+        let mut elements = Vec::new();
+        loop {
+            while self
+                .content
+                .get(self.position)
+                .is_some_and(|&byte| is_whitespace(byte))
+            {
+                self.position += 1;
+            }
+            if self.content.get(self.position) == Some(&b']') {
+                self.position += 1;
+                return Some(elements);
+            }
+            match self.next_token()? {
+                Token::Operand(element @ (Operand::String(_) | Operand::Number(_))) => {
+                    elements.push(element)
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Reads the rest of a string in parentheses, after the opening one.
+    fn literal_string(&mut self) -> Option<Vec<u8>> {
+        let mut text = Vec::new();
+        // Balanced parentheses are allowed inside the string without escaping them.
+        let mut depth = 1;
+        loop {
+            let byte = *self.content.get(self.position)?;
+            self.position += 1;
+            match byte {
+                b'\\' => {
+                    let escaped = *self.content.get(self.position)?;
+                    self.position += 1;
+                    match escaped {
+                        b'n' => text.push(b'\n'),
+                        b'r' => text.push(b'\r'),
+                        b't' => text.push(b'\t'),
+                        b'b' => text.push(0x08),
+                        b'f' => text.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut code = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.content.get(self.position) {
+                                    Some(&digit @ b'0'..=b'7') => {
+                                        code = code * 8 + u32::from(digit - b'0');
+                                        self.position += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            text.push(code as u8);
+                        }
+                        // A backslash at the end of a line continues the string onto the next line.
+                        b'\r' => {
+                            if self.content.get(self.position) == Some(&b'\n') {
+                                self.position += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        _ => text.push(escaped),
+                    }
+                }
+                b'(' => {
+                    depth += 1;
+                    text.push(byte);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(text);
+                    }
+                    text.push(byte);
+                }
+                _ => text.push(byte),
+            }
+        }
+    }
+
+    /// Reads the rest of a string in angle brackets, after the opening one.
+    fn hex_string(&mut self) -> Option<Vec<u8>> {
+        let mut digits = Vec::new();
+        loop {
+            let byte = *self.content.get(self.position)?;
+            self.position += 1;
+            match byte {
+                b'>' => break,
+                _ if is_whitespace(byte) => {}
+                _ => digits.push(char::from(byte).to_digit(16)? as u8),
+            }
+        }
+        // A missing final digit is taken to be zero.
+        Some(
+            digits
+                .chunks(2)
+                .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::dictionary;
+
+    use super::*;
+    use crate::{cmap::CMapLibrary, text::ExtractOptions};
+
+    fn scan_content(
+        content: &[u8],
+        budget: PageBudget,
+    ) -> Result<Option<Vec<String>>, BudgetExceeded> {
+        let font_data = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        };
+        let document = Document::with_version("1.7");
+        let font = Font::load(&document, &font_data, &mut CMapLibrary::new(None)).unwrap();
+        let options = ExtractOptions::default();
+        let page_text = scan(content, 1, b"F1", &font, budget, &options.text_options())?;
+        Ok(page_text.map(|page_text| {
+            page_text
+                .chunks
+                .into_iter()
+                .map(|chunk| chunk.text)
+                .collect()
+        }))
+    }
+
+    fn scan_all(content: &[u8]) -> Option<Vec<String>> {
+        scan_content(content, ExtractOptions::default().budget).unwrap()
+    }
+
+    #[test]
+    fn scans_plain_text() {
+        let texts = scan_all(
+            b"% a comment\n0.5 g 1 0 0 1 0 0 cm BT /F1 12 Tf 72 700 Td (Hello \\(there\\)) Tj \
+              [(wor) -20 <6C64>] TJ 0 -14 Td q 2 Tc (again) ' Q ET 10 10 50 50 re f",
+        );
+        assert_eq!(texts.unwrap().concat(), "Hello (there)worldagain");
+    }
+
+    #[test]
+    fn gives_up_on_content_it_does_not_handle() {
+        for content in [
+            // Other fonts, and text before any font.
+            &b"BT /F2 12 Tf 72 700 Td (text) Tj ET"[..],
+            b"BT 72 700 Td (text) Tj ET",
+            // Dictionaries, as marked content properties and inline images have.
+            b"/Span << /ActualText (x) >> BDC BT /F1 12 Tf (text) Tj ET EMC",
+            b"BI /W 1 /H 1 ID x EI",
+            // Operators which could draw text some other way, or which the scan knows nothing about.
+            b"/Im1 Do",
+            b"BT /F1 12 Tf 3 Tr (text) Tj ET 1 d0",
+            // Operands which aren't what the operator takes.
+            b"BT /F1 12 Tf 1 0 0 Tm (text) Tj ET",
+            b"BT /F1 12 Tf [(text) /Name] TJ ET",
+            b"BT /F1 12 Tf (text) 5 Tj ET",
+            // Tokens which can't be read, or need decoding first.
+            b"BT /F1 12 Tf (never closed Tj ET",
+            b"BT /F1 12 Tf <4G> Tj ET",
+            b"BT /F#31 12 Tf (text) Tj ET",
+            b"BT /F1 1.2.3 Tf (text) Tj ET",
+            b"BT /F1 12 Tf [(text) [1]] TJ ET",
+            b"BT /F1 12 Tf {(text)} Tj ET",
+        ] {
+            assert_eq!(
+                scan_all(content),
+                None,
+                "{:?}",
+                String::from_utf8_lossy(content)
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_to_operator_budget() {
+        let budget = PageBudget {
+            max_operators: 4,
+            ..ExtractOptions::default().budget
+        };
+        let content = b"BT /F1 12 Tf (one) Tj (two) Tj ET";
+        assert!(matches!(
+            scan_content(content, budget),
+            Err(BudgetExceeded::Operators(4))
+        ));
+        assert!(scan_content(b"BT /F1 12 Tf (one) Tj ET", budget)
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,63 @@
+//! Output meant to be read aloud, by a screen reader or text to speech, rather than looked at.
+//!
+//! The rows of each page become flowing paragraphs, found the same way as for `--paragraphs`, and superscript and
+//! subscript markup is dropped in favour of a space, so that footnote markers and exponents are read as words of
+//! their own instead of running into the word before them.
+//!
+//! Running headers, footers and page numbers, whether tagged documents mark them as artifacts or they only repeat
+//! from page to page, have already been left out of the pages, unless they are kept, so they don't interrupt the
+//! paragraphs that run from one page onto the next.
+
+use std::io::{self, Write};
+
+use crate::{
+    hyphenation::Hyphenation,
+    output::OutputRenderer,
+    paragraphs::{self, ParagraphRenderer},
+    text::{self, Page},
+};
+
+pub struct SpeechRenderer<W: Write> {
+    paragraphs: ParagraphRenderer<W>,
+}
+
+impl<W: Write> SpeechRenderer<W> {
+    pub fn new(writer: W, hyphenation: Hyphenation) -> Self {
+        Self {
+            paragraphs: ParagraphRenderer::with_text(writer, spoken_text, hyphenation),
+        }
+    }
+}
+
+/// Drops the superscript and subscript markup from a row, joining the pieces either side with a space, or as one
+/// word if the markup is where a hyphenated word was broken.
+fn spoken_text(text: &str) -> String {
+    let text = ["<sup>", "</sup>", "<sub>", "</sub>"]
+        .iter()
+        .fold(text.to_owned(), |text, tag| text.replace(tag, "\n"));
+    let mut spoken = String::new();
+    for piece in text.split('\n') {
+        let piece = piece.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !piece.is_empty() {
+            text::join_line(&mut spoken, &piece);
+        }
+    }
+    spoken
+}
+
+/// Splits the rows of a page into paragraphs, as they would be read aloud.
+///
+/// The last paragraph ends in a hyphen if its last word carries on over the page break.
+pub fn paragraphs(page: &Page, hyphenation: &Hyphenation) -> Vec<String> {
+    paragraphs::split(page, spoken_text, hyphenation)
+}
+
+impl<W: Write> OutputRenderer for SpeechRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.paragraphs.render_page(page)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.paragraphs.finish()
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::PathBuf,
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    outline::Bookmark,
+    output::{OutputRenderer, PlainTextRenderer},
+    text::Page,
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SplitBy {
+    /// Writes one file per page.
+    Pages,
+    /// Writes one file per bookmarked section, cutting the document at the page each bookmark points to.
+    Bookmarks,
+}
+
+/// Makes the renderer a file of split output is written with.
+pub type FileRenderer = Box<dyn Fn(BufWriter<File>) -> Box<dyn OutputRenderer>>;
+
+/// Writes each page to its own file in a directory.
+pub struct PageSplitter {
+    directory: PathBuf,
+    /// How many digits the page numbers in file names are padded to, so that the files sort in page order.
+    width: usize,
+    extension: &'static str,
+    renderer: FileRenderer,
+}
+
+impl PageSplitter {
+    /// Writes each page as plain text.
+    pub fn new(directory: PathBuf, page_count: usize) -> io::Result<Self> {
+        Self::with_renderer(
+            directory,
+            page_count,
+            "txt",
+            Box::new(|file| Box::new(PlainTextRenderer::new(file))),
+        )
+    }
+
+    /// Writes each page with a renderer of its own, to a file with the given extension.
+    pub fn with_renderer(
+        directory: PathBuf,
+        page_count: usize,
+        extension: &'static str,
+        renderer: FileRenderer,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+        Ok(Self {
+            directory,
+            width: page_count.to_string().len(),
+            extension,
+            renderer,
+        })
+    }
+}
+
+impl OutputRenderer for PageSplitter {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let name = format!(
+            "page-{:0width$}.{}",
+            page.number,
+            self.extension,
+            width = self.width
+        );
+        let file = BufWriter::new(File::create(self.directory.join(name))?);
+        let mut renderer = (self.renderer)(file);
+        renderer.render_page(page)?;
+        renderer.finish()
+    }
+}
+
+/// Writes each section of the document to its own file in a directory, named after its bookmark.
+///
+/// Any text before the first bookmark is written to a front matter file, and sections without any text are skipped.
+pub struct BookmarkSplitter {
+    directory: PathBuf,
+    /// The title and first page of each section, in page order.
+    sections: Vec<(String, u32)>,
+    width: usize,
+    /// The index of the section being written, and the file it is being written to.
+    current: Option<(usize, PlainTextRenderer<BufWriter<File>>)>,
+    written: usize,
+}
+
+impl BookmarkSplitter {
+    /// Only bookmarks nested at most `depth` levels deep start a new section.
+    pub fn new(directory: PathBuf, bookmarks: &[Bookmark], depth: usize) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+        let mut sections = vec![("front matter".to_owned(), 0)];
+        for bookmark in bookmarks {
+            let Some(page) = bookmark.page else {
+                continue;
+            };
+            if bookmark.level > depth {
+                continue;
+            }
+            // A section that ends on the page it starts has no pages of its own, so the later bookmark replaces it.
+            match sections.last_mut() {
+                Some(last) if last.1 == page => *last = (bookmark.title.clone(), page),
+                _ => sections.push((bookmark.title.clone(), page)),
+            }
+            // Bookmarks pointing backwards would make the sections overlap, so they can't start a new section.
+            if sections.len() > 1 && sections[sections.len() - 2].1 > page {
+                sections.pop();
+            }
+        }
+        Ok(Self {
+            directory,
+            width: sections.len().to_string().len().max(2),
+            sections,
+            current: None,
+            written: 0,
+        })
+    }
+}
+
+impl OutputRenderer for BookmarkSplitter {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        if page.rows.is_empty() {
+            return Ok(());
+        }
+        let section = self
+            .sections
+            .partition_point(|&(_, first_page)| first_page <= page.number)
+            - 1;
+        if self.current.as_ref().map(|(index, _)| *index) != Some(section) {
+            self.finish()?;
+            self.written += 1;
+            let name = format!(
+                "{:0width$}-{}.txt",
+                self.written,
+                file_name_from_title(&self.sections[section].0),
+                width = self.width
+            );
+            let file = BufWriter::new(File::create(self.directory.join(name))?);
+            self.current = Some((section, PlainTextRenderer::new(file)));
+        }
+        let (_, renderer) = self.current.as_mut().unwrap();
+        renderer.render_page(page)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some((_, renderer)) = self.current.as_mut() {
+            renderer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes a title safe to use in a file name on any platform.
+fn file_name_from_title(title: &str) -> String {
+    let mut name = String::new();
+    for c in title.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name = name.trim_end_matches('-');
+    if name.is_empty() {
+        return "untitled".to_owned();
+    }
+    name.chars().take(80).collect()
+}
@@ -0,0 +1,417 @@
+//! The built-in encodings of the standard fonts which aren't Latin text fonts, Symbol and ZapfDingbats, which are
+//! what their codes mean when a document uses them without an encoding or a `ToUnicode` map. The rest of the 14
+//! standard fonts use the standard encoding.
+
+/// The built-in encoding of a font, from its `BaseFont`, if it is one of the standard fonts with an encoding of its
+/// own.
+pub fn built_in_encoding(base_font: &str) -> Option<&'static [(u8, char)]> {
+    // Subsets have a tag of six letters and a plus sign before the name, and styles come after a comma.
+    let name = match base_font.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 => name,
+        _ => base_font,
+    };
+    let name = name.split(',').next().unwrap_or(name);
+    match name {
+        "Symbol" | "SymbolMT" => Some(&SYMBOL_ENCODING),
+        "ZapfDingbats" | "ZapfDingbatsITC" | "Dingbats" => Some(&ZAPF_DINGBATS_ENCODING),
+        _ => None,
+    }
+}
+
+/// The built-in encoding of Symbol.
+static SYMBOL_ENCODING: [(u8, char); 188] = [
+    (0x20, '\u{0020}'),
+    (0x21, '\u{0021}'),
+    (0x22, '\u{2200}'),
+    (0x23, '\u{0023}'),
+    (0x24, '\u{2203}'),
+    (0x25, '\u{0025}'),
+    (0x26, '\u{0026}'),
+    (0x27, '\u{220b}'),
+    (0x28, '\u{0028}'),
+    (0x29, '\u{0029}'),
+    (0x2a, '\u{2217}'),
+    (0x2b, '\u{002b}'),
+    (0x2c, '\u{002c}'),
+    (0x2d, '\u{2212}'),
+    (0x2e, '\u{002e}'),
+    (0x2f, '\u{002f}'),
+    (0x30, '\u{0030}'),
+    (0x31, '\u{0031}'),
+    (0x32, '\u{0032}'),
+    (0x33, '\u{0033}'),
+    (0x34, '\u{0034}'),
+    (0x35, '\u{0035}'),
+    (0x36, '\u{0036}'),
+    (0x37, '\u{0037}'),
+    (0x38, '\u{0038}'),
+    (0x39, '\u{0039}'),
+    (0x3a, '\u{003a}'),
+    (0x3b, '\u{003b}'),
+    (0x3c, '\u{003c}'),
+    (0x3d, '\u{003d}'),
+    (0x3e, '\u{003e}'),
+    (0x3f, '\u{003f}'),
+    (0x40, '\u{2245}'),
+    (0x41, '\u{0391}'),
+    (0x42, '\u{0392}'),
+    (0x43, '\u{03a7}'),
+    (0x44, '\u{2206}'),
+    (0x45, '\u{0395}'),
+    (0x46, '\u{03a6}'),
+    (0x47, '\u{0393}'),
+    (0x48, '\u{0397}'),
+    (0x49, '\u{0399}'),
+    (0x4a, '\u{03d1}'),
+    (0x4b, '\u{039a}'),
+    (0x4c, '\u{039b}'),
+    (0x4d, '\u{039c}'),
+    (0x4e, '\u{039d}'),
+    (0x4f, '\u{039f}'),
+    (0x50, '\u{03a0}'),
+    (0x51, '\u{0398}'),
+    (0x52, '\u{03a1}'),
+    (0x53, '\u{03a3}'),
+    (0x54, '\u{03a4}'),
+    (0x55, '\u{03a5}'),
+    (0x56, '\u{03c2}'),
+    (0x57, '\u{2126}'),
+    (0x58, '\u{039e}'),
+    (0x59, '\u{03a8}'),
+    (0x5a, '\u{0396}'),
+    (0x5b, '\u{005b}'),
+    (0x5c, '\u{2234}'),
+    (0x5d, '\u{005d}'),
+    (0x5e, '\u{22a5}'),
+    (0x5f, '\u{005f}'),
+    (0x60, '\u{f8e5}'),
+    (0x61, '\u{03b1}'),
+    (0x62, '\u{03b2}'),
+    (0x63, '\u{03c7}'),
+    (0x64, '\u{03b4}'),
+    (0x65, '\u{03b5}'),
+    (0x66, '\u{03c6}'),
+    (0x67, '\u{03b3}'),
+    (0x68, '\u{03b7}'),
+    (0x69, '\u{03b9}'),
+    (0x6a, '\u{03d5}'),
+    (0x6b, '\u{03ba}'),
+    (0x6c, '\u{03bb}'),
+    (0x6d, '\u{00b5}'),
+    (0x6e, '\u{03bd}'),
+    (0x6f, '\u{03bf}'),
+    (0x70, '\u{03c0}'),
+    (0x71, '\u{03b8}'),
+    (0x72, '\u{03c1}'),
+    (0x73, '\u{03c3}'),
+    (0x74, '\u{03c4}'),
+    (0x75, '\u{03c5}'),
+    (0x76, '\u{03d6}'),
+    (0x77, '\u{03c9}'),
+    (0x78, '\u{03be}'),
+    (0x79, '\u{03c8}'),
+    (0x7a, '\u{03b6}'),
+    (0x7b, '\u{007b}'),
+    (0x7c, '\u{007c}'),
+    (0x7d, '\u{007d}'),
+    (0x7e, '\u{223c}'),
+    (0xa1, '\u{03d2}'),
+    (0xa2, '\u{2032}'),
+    (0xa3, '\u{2264}'),
+    (0xa4, '\u{2044}'),
+    (0xa5, '\u{221e}'),
+    (0xa6, '\u{0192}'),
+    (0xa7, '\u{2663}'),
+    (0xa8, '\u{2662}'),
+    (0xa9, '\u{2661}'),
+    (0xaa, '\u{2660}'),
+    (0xab, '\u{2194}'),
+    (0xac, '\u{2190}'),
+    (0xad, '\u{2191}'),
+    (0xae, '\u{2192}'),
+    (0xaf, '\u{2193}'),
+    (0xb0, '\u{00b0}'),
+    (0xb1, '\u{00b1}'),
+    (0xb2, '\u{2033}'),
+    (0xb3, '\u{2265}'),
+    (0xb4, '\u{00d7}'),
+    (0xb5, '\u{221d}'),
+    (0xb6, '\u{2202}'),
+    (0xb7, '\u{2022}'),
+    (0xb8, '\u{00f7}'),
+    (0xb9, '\u{2260}'),
+    (0xba, '\u{2261}'),
+    (0xbb, '\u{2248}'),
+    (0xbc, '\u{2026}'),
+    (0xbd, '\u{2195}'),
+    (0xbe, '\u{f8e7}'),
+    (0xbf, '\u{21b5}'),
+    (0xc0, '\u{2135}'),
+    (0xc1, '\u{2111}'),
+    (0xc2, '\u{211c}'),
+    (0xc3, '\u{2118}'),
+    (0xc4, '\u{2297}'),
+    (0xc5, '\u{2295}'),
+    (0xc6, '\u{2205}'),
+    (0xc7, '\u{2229}'),
+    (0xc8, '\u{222a}'),
+    (0xc9, '\u{2283}'),
+    (0xca, '\u{2287}'),
+    (0xcb, '\u{2284}'),
+    (0xcc, '\u{2282}'),
+    (0xcd, '\u{2286}'),
+    (0xce, '\u{2208}'),
+    (0xcf, '\u{2209}'),
+    (0xd0, '\u{2220}'),
+    (0xd1, '\u{2207}'),
+    (0xd2, '\u{f6da}'),
+    (0xd3, '\u{f6d9}'),
+    (0xd4, '\u{f6db}'),
+    (0xd5, '\u{220f}'),
+    (0xd6, '\u{221a}'),
+    (0xd7, '\u{22c5}'),
+    (0xd8, '\u{00ac}'),
+    (0xd9, '\u{2227}'),
+    (0xda, '\u{2228}'),
+    (0xdb, '\u{21d4}'),
+    (0xdc, '\u{21d0}'),
+    (0xdd, '\u{21d1}'),
+    (0xde, '\u{21d2}'),
+    (0xdf, '\u{21d3}'),
+    (0xe0, '\u{25ca}'),
+    (0xe1, '\u{2329}'),
+    (0xe2, '\u{f8e8}'),
+    (0xe3, '\u{f8e9}'),
+    (0xe4, '\u{f8ea}'),
+    (0xe5, '\u{2211}'),
+    (0xe6, '\u{f8eb}'),
+    (0xe7, '\u{007c}'),
+    (0xe8, '\u{f8ed}'),
+    (0xe9, '\u{f8ee}'),
+    (0xea, '\u{f8ef}'),
+    (0xeb, '\u{f8f0}'),
+    (0xec, '\u{f8f1}'),
+    (0xed, '\u{007c}'),
+    (0xee, '\u{f8f3}'),
+    (0xef, '\u{007c}'),
+    (0xf1, '\u{232a}'),
+    (0xf2, '\u{222b}'),
+    (0xf3, '\u{2320}'),
+    (0xf4, '\u{f8f5}'),
+    (0xf5, '\u{2321}'),
+    (0xf6, '\u{f8f6}'),
+    (0xf7, '\u{007c}'),
+    (0xf8, '\u{f8f8}'),
+    (0xf9, '\u{f8f9}'),
+    (0xfa, '\u{f8fa}'),
+    (0xfb, '\u{f8fb}'),
+    (0xfc, '\u{f8fc}'),
+    (0xfd, '\u{2016}'),
+    (0xfe, '\u{f8fe}'),
+];
+
+/// The built-in encoding of ZapfDingbats.
+static ZAPF_DINGBATS_ENCODING: [(u8, char); 202] = [
+    (0x20, '\u{0020}'),
+    (0x21, '\u{2701}'),
+    (0x22, '\u{2702}'),
+    (0x23, '\u{2703}'),
+    (0x24, '\u{2704}'),
+    (0x25, '\u{260e}'),
+    (0x26, '\u{2706}'),
+    (0x27, '\u{2707}'),
+    (0x28, '\u{2708}'),
+    (0x29, '\u{2709}'),
+    (0x2a, '\u{261b}'),
+    (0x2b, '\u{261e}'),
+    (0x2c, '\u{270c}'),
+    (0x2d, '\u{270d}'),
+    (0x2e, '\u{270e}'),
+    (0x2f, '\u{270f}'),
+    (0x30, '\u{2710}'),
+    (0x31, '\u{2711}'),
+    (0x32, '\u{2712}'),
+    (0x33, '\u{2713}'),
+    (0x34, '\u{2714}'),
+    (0x35, '\u{2715}'),
+    (0x36, '\u{2716}'),
+    (0x37, '\u{2717}'),
+    (0x38, '\u{2718}'),
+    (0x39, '\u{2719}'),
+    (0x3a, '\u{271a}'),
+    (0x3b, '\u{271b}'),
+    (0x3c, '\u{271c}'),
+    (0x3d, '\u{271d}'),
+    (0x3e, '\u{271e}'),
+    (0x3f, '\u{271f}'),
+    (0x40, '\u{2720}'),
+    (0x41, '\u{2721}'),
+    (0x42, '\u{2722}'),
+    (0x43, '\u{2723}'),
+    (0x44, '\u{2724}'),
+    (0x45, '\u{2725}'),
+    (0x46, '\u{2726}'),
+    (0x47, '\u{2727}'),
+    (0x48, '\u{2605}'),
+    (0x49, '\u{2729}'),
+    (0x4a, '\u{272a}'),
+    (0x4b, '\u{272b}'),
+    (0x4c, '\u{272c}'),
+    (0x4d, '\u{272d}'),
+    (0x4e, '\u{272e}'),
+    (0x4f, '\u{272f}'),
+    (0x50, '\u{2730}'),
+    (0x51, '\u{2731}'),
+    (0x52, '\u{2732}'),
+    (0x53, '\u{2733}'),
+    (0x54, '\u{2734}'),
+    (0x55, '\u{2735}'),
+    (0x56, '\u{2736}'),
+    (0x57, '\u{2737}'),
+    (0x58, '\u{2738}'),
+    (0x59, '\u{2739}'),
+    (0x5a, '\u{273a}'),
+    (0x5b, '\u{273b}'),
+    (0x5c, '\u{273c}'),
+    (0x5d, '\u{273d}'),
+    (0x5e, '\u{273e}'),
+    (0x5f, '\u{273f}'),
+    (0x60, '\u{2740}'),
+    (0x61, '\u{2741}'),
+    (0x62, '\u{2742}'),
+    (0x63, '\u{2743}'),
+    (0x64, '\u{2744}'),
+    (0x65, '\u{2745}'),
+    (0x66, '\u{2746}'),
+    (0x67, '\u{2747}'),
+    (0x68, '\u{2748}'),
+    (0x69, '\u{2749}'),
+    (0x6a, '\u{274a}'),
+    (0x6b, '\u{274b}'),
+    (0x6c, '\u{25cf}'),
+    (0x6d, '\u{274d}'),
+    (0x6e, '\u{25a0}'),
+    (0x6f, '\u{274f}'),
+    (0x70, '\u{2750}'),
+    (0x71, '\u{2751}'),
+    (0x72, '\u{2752}'),
+    (0x73, '\u{25b2}'),
+    (0x74, '\u{25bc}'),
+    (0x75, '\u{25c6}'),
+    (0x76, '\u{2756}'),
+    (0x77, '\u{25d7}'),
+    (0x78, '\u{2758}'),
+    (0x79, '\u{2759}'),
+    (0x7a, '\u{275a}'),
+    (0x7b, '\u{275b}'),
+    (0x7c, '\u{275c}'),
+    (0x7d, '\u{275d}'),
+    (0x7e, '\u{275e}'),
+    (0x80, '\u{2768}'),
+    (0x81, '\u{2769}'),
+    (0x82, '\u{276a}'),
+    (0x83, '\u{276b}'),
+    (0x84, '\u{276c}'),
+    (0x85, '\u{276d}'),
+    (0x86, '\u{276e}'),
+    (0x87, '\u{276f}'),
+    (0x88, '\u{2770}'),
+    (0x89, '\u{2771}'),
+    (0x8a, '\u{2772}'),
+    (0x8b, '\u{2773}'),
+    (0x8c, '\u{2774}'),
+    (0x8d, '\u{2775}'),
+    (0xa1, '\u{2761}'),
+    (0xa2, '\u{2762}'),
+    (0xa3, '\u{2763}'),
+    (0xa4, '\u{2764}'),
+    (0xa5, '\u{2765}'),
+    (0xa6, '\u{2766}'),
+    (0xa7, '\u{2767}'),
+    (0xa8, '\u{2663}'),
+    (0xa9, '\u{2666}'),
+    (0xaa, '\u{2665}'),
+    (0xab, '\u{2660}'),
+    (0xac, '\u{2460}'),
+    (0xad, '\u{2461}'),
+    (0xae, '\u{2462}'),
+    (0xaf, '\u{2463}'),
+    (0xb0, '\u{2464}'),
+    (0xb1, '\u{2465}'),
+    (0xb2, '\u{2466}'),
+    (0xb3, '\u{2467}'),
+    (0xb4, '\u{2468}'),
+    (0xb5, '\u{2469}'),
+    (0xb6, '\u{2776}'),
+    (0xb7, '\u{2777}'),
+    (0xb8, '\u{2778}'),
+    (0xb9, '\u{2779}'),
+    (0xba, '\u{277a}'),
+    (0xbb, '\u{277b}'),
+    (0xbc, '\u{277c}'),
+    (0xbd, '\u{277d}'),
+    (0xbe, '\u{277e}'),
+    (0xbf, '\u{277f}'),
+    (0xc0, '\u{2780}'),
+    (0xc1, '\u{2781}'),
+    (0xc2, '\u{2782}'),
+    (0xc3, '\u{2783}'),
+    (0xc4, '\u{2784}'),
+    (0xc5, '\u{2785}'),
+    (0xc6, '\u{2786}'),
+    (0xc7, '\u{2787}'),
+    (0xc8, '\u{2788}'),
+    (0xc9, '\u{2789}'),
+    (0xca, '\u{278a}'),
+    (0xcb, '\u{278b}'),
+    (0xcc, '\u{278c}'),
+    (0xcd, '\u{278d}'),
+    (0xce, '\u{278e}'),
+    (0xcf, '\u{278f}'),
+    (0xd0, '\u{2790}'),
+    (0xd1, '\u{2791}'),
+    (0xd2, '\u{2792}'),
+    (0xd3, '\u{2793}'),
+    (0xd4, '\u{2794}'),
+    (0xd5, '\u{2192}'),
+    (0xd6, '\u{2194}'),
+    (0xd7, '\u{2195}'),
+    (0xd8, '\u{2798}'),
+    (0xd9, '\u{2799}'),
+    (0xda, '\u{279a}'),
+    (0xdb, '\u{279b}'),
+    (0xdc, '\u{279c}'),
+    (0xdd, '\u{279d}'),
+    (0xde, '\u{279e}'),
+    (0xdf, '\u{279f}'),
+    (0xe0, '\u{27a0}'),
+    (0xe1, '\u{27a1}'),
+    (0xe2, '\u{27a2}'),
+    (0xe3, '\u{27a3}'),
+    (0xe4, '\u{27a4}'),
+    (0xe5, '\u{27a5}'),
+    (0xe6, '\u{27a6}'),
+    (0xe7, '\u{27a7}'),
+    (0xe8, '\u{27a8}'),
+    (0xe9, '\u{27a9}'),
+    (0xea, '\u{27aa}'),
+    (0xeb, '\u{27ab}'),
+    (0xec, '\u{27ac}'),
+    (0xed, '\u{27ad}'),
+    (0xee, '\u{27ae}'),
+    (0xef, '\u{27af}'),
+    (0xf1, '\u{27b1}'),
+    (0xf2, '\u{27b2}'),
+    (0xf3, '\u{27b3}'),
+    (0xf4, '\u{27b4}'),
+    (0xf5, '\u{27b5}'),
+    (0xf6, '\u{27b6}'),
+    (0xf7, '\u{27b7}'),
+    (0xf8, '\u{27b8}'),
+    (0xf9, '\u{27b9}'),
+    (0xfa, '\u{27ba}'),
+    (0xfb, '\u{27bb}'),
+    (0xfc, '\u{27bc}'),
+    (0xfd, '\u{27bd}'),
+    (0xfe, '\u{27be}'),
+];
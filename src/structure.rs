@@ -0,0 +1,187 @@
+//! Reads the structure tree of tagged documents, which says what each part of the content is (a paragraph, a heading,
+//! a formula and so on) independently of how it is drawn, and what order its parts are read in.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use crate::traversal::{self, TraversalError, MAX_DEPTH};
+
+/// An element of the structure tree.
+pub struct StructElement<'a> {
+    /// The element's standard structure type, such as `P` or `Formula`, once any custom type has been mapped to one.
+    pub role: Vec<u8>,
+    pub dictionary: &'a Dictionary,
+    /// The page the element's content is on, which it may inherit from its parent.
+    pub page: Option<u32>,
+    /// How deeply the element is nested, where the children of the structure tree root have depth 1.
+    pub depth: usize,
+}
+
+/// Whether the document is tagged, with a structure tree saying what its content is.
+pub fn is_tagged(document: &Document) -> bool {
+    document
+        .catalog()
+        .is_ok_and(|catalog| catalog.has(b"StructTreeRoot"))
+}
+
+/// Finds every element in the structure tree, in the order of the tree, so each element comes before its children.
+///
+/// Untagged documents have no elements at all.
+pub fn elements(document: &Document) -> Result<Vec<StructElement<'_>>, TraversalError> {
+    let mut elements = Vec::new();
+    walk(document, |node| {
+        if let Node::Element(element) = node {
+            elements.push(element);
+        }
+    })?;
+    Ok(elements)
+}
+
+/// The marked content identifiers of each page's content, by page number, in the order the structure tree reads them
+/// in, which for tagged documents is the order the text is meant to be read in whatever the layout of the page.
+///
+/// Marked content in the content of forms rather than of the page itself is left out, since its identifiers are the
+/// form's own. Untagged documents have no marked content in their structure at all.
+pub fn reading_order(document: &Document) -> Result<BTreeMap<u32, Vec<i64>>, TraversalError> {
+    let mut order = BTreeMap::<u32, Vec<i64>>::new();
+    walk(document, |node| {
+        if let Node::MarkedContent {
+            page: Some(page),
+            identifier,
+            ..
+        } = node
+        {
+            order.entry(page).or_default().push(identifier);
+        }
+    })?;
+    Ok(order)
+}
+
+/// Something the structure tree refers to.
+pub enum Node<'a> {
+    Element(StructElement<'a>),
+    /// A sequence of marked content in the content of a page, by its identifier, nested as deeply as an element
+    /// would be in the same place.
+    MarkedContent {
+        page: Option<u32>,
+        identifier: i64,
+        depth: usize,
+    },
+}
+
+/// Visits every element of the structure tree and the marked content they contain, in the order of the tree, so each
+/// element comes before what it contains.
+pub fn walk<'a>(
+    document: &'a Document,
+    mut visit: impl FnMut(Node<'a>),
+) -> Result<(), TraversalError> {
+    let Some(root) = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| resolve(document, catalog.get(b"StructTreeRoot").ok()))
+        .and_then(|root| root.as_dict().ok())
+    else {
+        return Ok(());
+    };
+    let role_map = resolve(document, root.get(b"RoleMap").ok()).and_then(|map| map.as_dict().ok());
+    let page_numbers = traversal::pages(document)?
+        .into_iter()
+        .map(|(number, id)| (id, number))
+        .collect::<BTreeMap<ObjectId, u32>>();
+    let page_of = |dictionary: &Dictionary| {
+        dictionary
+            .get(b"Pg")
+            .and_then(Object::as_reference)
+            .ok()
+            .and_then(|id| page_numbers.get(&id).copied())
+    };
+
+    // Each entry is a kid still to be visited, along with its depth and the page of its parent.
+    let mut pending = kids(root.get(b"K").ok(), 1, None);
+    let mut visited = BTreeSet::new();
+    while let Some((kid, depth, parent_page)) = pending.pop() {
+        if depth > MAX_DEPTH {
+            return Err(TraversalError::TooDeep);
+        }
+        let element = match kid {
+            Object::Reference(id) => {
+                if !visited.insert(*id) {
+                    return Err(TraversalError::Revisited(*id));
+                }
+                match document.get_dictionary(*id) {
+                    Ok(element) => element,
+                    Err(_) => continue,
+                }
+            }
+            Object::Dictionary(element) => element,
+            // A marked content identifier on its own is in the content of the parent's page.
+            Object::Integer(identifier) => {
+                visit(Node::MarkedContent {
+                    page: parent_page,
+                    identifier: *identifier,
+                    depth,
+                });
+                continue;
+            }
+            _ => continue,
+        };
+        // Marked content and object references are dictionaries too, but have no structure type.
+        let Ok(role) = element.get(b"S").and_then(Object::as_name) else {
+            if let (Ok(identifier), Err(_)) = (
+                element.get(b"MCID").and_then(Object::as_i64),
+                element.get(b"Stm"),
+            ) {
+                visit(Node::MarkedContent {
+                    page: page_of(element).or(parent_page),
+                    identifier,
+                    depth,
+                });
+            }
+            continue;
+        };
+        let page = page_of(element).or(parent_page);
+        visit(Node::Element(StructElement {
+            role: standard_role(document, role_map, role),
+            dictionary: element,
+            page,
+            depth,
+        }));
+        pending.extend(kids(element.get(b"K").ok(), depth + 1, page));
+    }
+    Ok(())
+}
+
+/// Lists an element's kids in reverse, so that popping them off the end visits them in order.
+fn kids(
+    kids: Option<&Object>,
+    depth: usize,
+    page: Option<u32>,
+) -> Vec<(&Object, usize, Option<u32>)> {
+    match kids {
+        Some(Object::Array(kids)) => kids.iter().rev().map(|kid| (kid, depth, page)).collect(),
+        Some(kid) => vec![(kid, depth, page)],
+        None => Vec::new(),
+    }
+}
+
+/// Follows the role map from a custom structure type to the standard one it stands for.
+fn standard_role(document: &Document, role_map: Option<&Dictionary>, role: &[u8]) -> Vec<u8> {
+    let mut role = role;
+    let mut seen = BTreeSet::new();
+    while let Some(mapped) = role_map
+        .and_then(|map| resolve(document, map.get(role).ok()))
+        .and_then(|mapped| mapped.as_name().ok())
+    {
+        // Role maps can map types to each other in a circle, in which case the last one reached has to do.
+        if !seen.insert(role) {
+            break;
+        }
+        role = mapped;
+    }
+    role.to_vec()
+}
+
+fn resolve<'a>(document: &'a Document, object: Option<&'a Object>) -> Option<&'a Object> {
+    document.dereference(object?).ok().map(|(_, object)| object)
+}
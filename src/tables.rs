@@ -0,0 +1,747 @@
+//! Finds the tables drawn with ruling lines around their cells, and puts the text of each cell together.
+//!
+//! The lines are the straight parts of the paths a page strokes which run straight across or straight up the page,
+//! and filled rectangles thin enough to be lines, which is how many generators draw them. Lines which cross or touch
+//! are part of the same grid, and a grid with at least two lines each way, and at least three one way, is a table:
+//! its lines across the page are the boundaries between its rows, and its lines up the page the boundaries between its
+//! columns. A box around a single cell, as forms and callouts draw, isn't a table.
+//!
+//! Tables without any lines are found from how their text lines up instead. A run of lines, one under the other,
+//! which are each split into pieces by gaps of more than a couple of spaces, and whose gaps line up so that they leave
+//! at least two gutters running all the way down the run with no text in them, is a table with a column between each
+//! pair of gutters and a row for each line. Pages set in several columns of prose line up the same way, so a table has
+//! to have at least three columns, most of which are short pieces of text rather than lines of sentences.
+//!
+//! Each piece of text inside a table goes in the cell its middle is in, and the text of each cell is laid out on its
+//! own, so that text in one cell never runs into the next one along. Cells which span several rows or columns have
+//! their text in whichever of them it is drawn in.
+//!
+//! The tables can also be written out as CSV, either all to one file, with a blank line between each one and the
+//! next, or each to a file of its own in a directory.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+
+use serde::Serialize;
+
+use crate::{
+    bates::csv_field,
+    output::OutputRenderer,
+    text::{self, Page, TextChunk, COLUMN_GAP},
+};
+
+/// Filled rectangles at most this thick are lines.
+const MAX_RULING_WIDTH: f32 = 3.0;
+
+/// Lines shorter than this are too short to be the edge of a cell.
+const MIN_RULING_LENGTH: f32 = 3.0;
+
+/// Lines whose ends are this close, in the units of the page, are straight, and lines which come this close to each
+/// other meet.
+const TOLERANCE: f32 = 2.0;
+
+/// The rows of a table start at its left edge and are as wide as it to within this much, so that they are still
+/// recognised after their positions have been worked out again or have picked up rounding errors along the way.
+const EDGE_TOLERANCE: f32 = 0.01;
+
+/// Pages drawn with more lines than this are drawings rather than tables, and are too slow to look through.
+const MAX_RULINGS: usize = 5000;
+
+/// Tables without lines have at least this many rows and columns.
+const MIN_BORDERLESS_ROWS: usize = 3;
+const MIN_BORDERLESS_COLUMNS: usize = 3;
+
+/// The rows of a table without lines are at most this many times the height of their text below the row before.
+const MAX_ROW_SPACING: f32 = 2.5;
+
+/// Columns whose pieces have more words than this on average are prose rather than cells.
+const MAX_CELL_WORDS: f32 = 3.0;
+
+/// A straight line drawn across or up the page.
+#[derive(Clone, Copy, Debug)]
+pub struct Ruling {
+    /// Whether the line runs across the page, rather than up it.
+    pub horizontal: bool,
+    /// How far up the page a line across it is, or how far across the page a line up it is.
+    pub position: f32,
+    /// Where the line starts and ends along its length, with the start before the end.
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Ruling {
+    /// The line between two points, if it runs straight across or up the page and is long enough to be a ruling.
+    pub fn between((x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> Option<Self> {
+        let (horizontal, position, start, end) = if (y1 - y2).abs() <= TOLERANCE {
+            (true, (y1 + y2) / 2.0, x1.min(x2), x1.max(x2))
+        } else if (x1 - x2).abs() <= TOLERANCE {
+            (false, (x1 + x2) / 2.0, y1.min(y2), y1.max(y2))
+        } else {
+            return None;
+        };
+        (end - start >= MIN_RULING_LENGTH).then_some(Self {
+            horizontal,
+            position,
+            start,
+            end,
+        })
+    }
+
+    /// Where the ends of the line are on the page.
+    pub fn ends(&self) -> [(f32, f32); 2] {
+        if self.horizontal {
+            [(self.start, self.position), (self.end, self.position)]
+        } else {
+            [(self.position, self.start), (self.position, self.end)]
+        }
+    }
+
+    /// Whether a line across the page and a line up it cross or touch.
+    fn meets(&self, other: &Ruling) -> bool {
+        self.horizontal != other.horizontal
+            && other.position >= self.start - TOLERANCE
+            && other.position <= self.end + TOLERANCE
+            && self.position >= other.start - TOLERANCE
+            && self.position <= other.end + TOLERANCE
+    }
+}
+
+/// The rulings a painted path draws, from its pieces, each of which is a list of points and whether it is closed.
+pub fn path_rulings(path: &[(Vec<(f32, f32)>, bool)], stroke: bool, fill: bool) -> Vec<Ruling> {
+    let mut rulings = Vec::new();
+    for (points, closed) in path {
+        let mut lines: Vec<_> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        // Filling closes every piece of the path.
+        if (*closed || fill) && points.len() > 2 {
+            lines.push((points[points.len() - 1], points[0]));
+        }
+        if stroke {
+            rulings.extend(
+                lines
+                    .iter()
+                    .filter_map(|&(start, end)| Ruling::between(start, end)),
+            );
+        } else if fill {
+            // A filled piece which is a thin rectangle is a line along the middle of it.
+            let (xs, ys): (Vec<f32>, Vec<f32>) = points.iter().copied().unzip();
+            let left = xs.iter().copied().fold(f32::INFINITY, f32::min);
+            let right = xs.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let bottom = ys.iter().copied().fold(f32::INFINITY, f32::min);
+            let top = ys.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let is_rectangle = lines.iter().all(|&((x1, y1), (x2, y2))| {
+                (x1 - x2).abs() <= TOLERANCE || (y1 - y2).abs() <= TOLERANCE
+            });
+            if is_rectangle && top - bottom <= MAX_RULING_WIDTH {
+                let middle = (top + bottom) / 2.0;
+                rulings.extend(Ruling::between((left, middle), (right, middle)));
+            } else if is_rectangle && right - left <= MAX_RULING_WIDTH {
+                let middle = (left + right) / 2.0;
+                rulings.extend(Ruling::between((middle, bottom), (middle, top)));
+            }
+        }
+    }
+    rulings
+}
+
+/// The lines of a table, from the top of it down and from the left of it across.
+pub struct Grid {
+    pub row_boundaries: Vec<f32>,
+    pub column_boundaries: Vec<f32>,
+}
+
+impl Grid {
+    /// Which cell a point on the page is in, as its row and column, if it is inside the table.
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        let row = self
+            .row_boundaries
+            .windows(2)
+            .position(|pair| y <= pair[0] && y > pair[1])?;
+        let column = self
+            .column_boundaries
+            .windows(2)
+            .position(|pair| x >= pair[0] && x < pair[1])?;
+        Some((row, column))
+    }
+
+    pub fn rows(&self) -> usize {
+        self.row_boundaries.len() - 1
+    }
+
+    pub fn columns(&self) -> usize {
+        self.column_boundaries.len() - 1
+    }
+}
+
+/// Finds the grids of lines on a page which are tables.
+pub fn find_grids(rulings: &[Ruling]) -> Vec<Grid> {
+    if rulings.len() > MAX_RULINGS {
+        return Vec::new();
+    }
+    // The lines are joined into groups that meet, keeping the smallest index in each group as its name.
+    let mut groups: Vec<usize> = (0..rulings.len()).collect();
+    fn group(groups: &mut [usize], mut index: usize) -> usize {
+        while groups[index] != index {
+            groups[index] = groups[groups[index]];
+            index = groups[index];
+        }
+        index
+    }
+    for first in 0..rulings.len() {
+        for second in first + 1..rulings.len() {
+            if rulings[first].meets(&rulings[second]) {
+                let (a, b) = (group(&mut groups, first), group(&mut groups, second));
+                groups[a.max(b)] = a.min(b);
+            }
+        }
+    }
+    let mut grids = Vec::new();
+    for name in 0..rulings.len() {
+        if group(&mut groups, name) != name {
+            continue;
+        }
+        let lines: Vec<&Ruling> = (0..rulings.len())
+            .filter(|&index| group(&mut groups, index) == name)
+            .map(|index| &rulings[index])
+            .collect();
+        let positions = |horizontal: bool| {
+            lines
+                .iter()
+                .filter(|line| line.horizontal == horizontal)
+                .map(|line| line.position)
+                .collect()
+        };
+        let mut row_boundaries = boundaries(positions(true));
+        row_boundaries.reverse();
+        let column_boundaries = boundaries(positions(false));
+        if row_boundaries.len() >= 2
+            && column_boundaries.len() >= 2
+            && row_boundaries.len() + column_boundaries.len() >= 5
+        {
+            grids.push(Grid {
+                row_boundaries,
+                column_boundaries,
+            });
+        }
+    }
+    grids
+}
+
+/// A line of text on the page, split into pieces at the gaps between its columns.
+struct Line {
+    /// Where the baseline of the line is, and how tall its text is.
+    y: f32,
+    height: f32,
+    /// How wide a space is on the line, which gutters have to be wider than.
+    space_width: f32,
+    /// Where each piece starts and ends across the page, from left to right, with how many words it has.
+    pieces: Vec<(f32, f32, usize)>,
+}
+
+/// Finds the tables drawn without lines among chunks which haven't been laid out yet, from how their text lines up.
+/// Chunks this close up or down the page are on the same line.
+pub fn find_borderless_grids(chunks: &[TextChunk], line_tolerance: f32) -> Vec<Grid> {
+    let mut chunks: Vec<&TextChunk> = chunks
+        .iter()
+        .filter(|chunk| {
+            !chunk.vertical && chunk.angle.abs() <= 1.0 && !chunk.text.trim().is_empty()
+        })
+        .collect();
+    chunks.sort_by(|a, b| b.y.total_cmp(&a.y));
+    let mut lines: Vec<Line> = Vec::new();
+    for line_chunks in chunks.chunk_by(|a, b| a.y - b.y <= line_tolerance) {
+        let mut line_chunks = line_chunks.to_vec();
+        line_chunks.sort_by(|a, b| a.x.total_cmp(&b.x));
+        let mut pieces: Vec<(f32, f32, usize)> = Vec::new();
+        for chunk in &line_chunks {
+            let words = chunk.text.split_whitespace().count();
+            match pieces.last_mut() {
+                Some((_, end, piece_words)) if chunk.x - *end <= chunk.space_width * COLUMN_GAP => {
+                    *end = end.max(chunk.x + chunk.width);
+                    *piece_words += words;
+                }
+                _ => pieces.push((chunk.x, chunk.x + chunk.width, words)),
+            }
+        }
+        lines.push(Line {
+            y: line_chunks
+                .iter()
+                .map(|chunk| chunk.y)
+                .fold(f32::NEG_INFINITY, f32::max),
+            height: line_chunks
+                .iter()
+                .map(|chunk| chunk.height)
+                .fold(0.0, f32::max),
+            space_width: line_chunks
+                .iter()
+                .map(|chunk| chunk.space_width)
+                .fold(0.0, f32::max),
+            pieces,
+        });
+    }
+
+    let mut grids = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        if lines[start].pieces.len() < MIN_BORDERLESS_COLUMNS {
+            start += 1;
+            continue;
+        }
+        // The run carries on for as long as its lines leave enough gutters between them.
+        let mut end = start + 1;
+        while end < lines.len()
+            && lines[end].pieces.len() >= 2
+            && lines[end - 1].y - lines[end].y <= lines[end - 1].height * MAX_ROW_SPACING
+            && columns(&lines[start..=end]).len() >= MIN_BORDERLESS_COLUMNS
+        {
+            end += 1;
+        }
+        let run = &lines[start..end];
+        let columns = columns(run);
+        if run.len() < MIN_BORDERLESS_ROWS || columns.len() < MIN_BORDERLESS_COLUMNS {
+            start += 1;
+            continue;
+        }
+        let prose_columns = columns
+            .iter()
+            .filter(|&&(left, right)| {
+                let pieces: Vec<usize> = run
+                    .iter()
+                    .flat_map(|line| &line.pieces)
+                    .filter(|&&(start, _, _)| start >= left && start <= right)
+                    .map(|&(_, _, words)| words)
+                    .collect();
+                pieces.iter().sum::<usize>() as f32 > pieces.len() as f32 * MAX_CELL_WORDS
+            })
+            .count();
+        if prose_columns * 2 > columns.len() {
+            start = end;
+            continue;
+        }
+        // The boundaries between columns are in the middle of the gutters, and those between rows are half way
+        // between the baselines, a little above so that they stay clear of where chunks are counted as being.
+        let mut column_boundaries = vec![columns[0].0];
+        column_boundaries.extend(columns.windows(2).map(|pair| (pair[0].1 + pair[1].0) / 2.0));
+        column_boundaries.push(columns[columns.len() - 1].1 + 1.0);
+        let mut row_boundaries = vec![run[0].y + run[0].height];
+        row_boundaries.extend(
+            run.windows(2)
+                .map(|pair| (pair[0].y + pair[1].y) / 2.0 + pair[1].height * 0.3),
+        );
+        row_boundaries.push(run[run.len() - 1].y - run[run.len() - 1].height);
+        grids.push(Grid {
+            row_boundaries,
+            column_boundaries,
+        });
+        start = end;
+    }
+    grids
+}
+
+/// Where the columns of a run of lines are across the page: the stretches covered by their pieces, between the
+/// gutters that none of them cross.
+fn columns(lines: &[Line]) -> Vec<(f32, f32)> {
+    let space_width = lines
+        .iter()
+        .map(|line| line.space_width)
+        .fold(0.0, f32::max);
+    let mut pieces: Vec<(f32, f32)> = lines
+        .iter()
+        .flat_map(|line| line.pieces.iter().map(|&(start, end, _)| (start, end)))
+        .collect();
+    pieces.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f32, f32)> = Vec::new();
+    for (start, end) in pieces {
+        match columns.last_mut() {
+            Some((_, column_end)) if start <= *column_end + space_width => {
+                *column_end = column_end.max(end)
+            }
+            _ => columns.push((start, end)),
+        }
+    }
+    columns
+}
+
+/// Where some parallel lines are, in order, with lines close to each other counting as the same one.
+fn boundaries(mut positions: Vec<f32>) -> Vec<f32> {
+    positions.sort_by(f32::total_cmp);
+    positions.dedup_by(|later, earlier| *later - *earlier <= TOLERANCE);
+    positions
+}
+
+/// A table found on a page, with the text of each of its cells.
+#[derive(Clone, Serialize)]
+pub struct Table {
+    /// Where the top left corner of the table is.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// The text of each cell, a row at a time from the top of the table down.
+    pub cells: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Whether a row of a page is one of the rows of the table, which start at its left edge and go right across it.
+    pub fn contains(&self, row: &TextChunk) -> bool {
+        (row.x - self.x).abs() <= EDGE_TOLERANCE
+            && (row.width - self.width).abs() <= EDGE_TOLERANCE
+            && row.y <= self.y
+            && row.y >= self.y - self.height
+    }
+
+    /// The rows and columns of the table which have text in any of their cells.
+    fn filled(&self) -> (Vec<usize>, Vec<usize>) {
+        let columns = self.cells.first().map_or(0, Vec::len);
+        let rows = (0..self.cells.len())
+            .filter(|&row| self.cells[row].iter().any(|cell| !cell.is_empty()))
+            .collect();
+        let columns = (0..columns)
+            .filter(|&column| self.cells.iter().any(|row| !row[column].is_empty()))
+            .collect();
+        (rows, columns)
+    }
+
+    /// The table as CSV, a line for each row, with the markup taken out of the text of the cells.
+    pub fn to_csv(&self) -> String {
+        let (rows, columns) = self.filled();
+        let mut csv = String::new();
+        for row in rows {
+            let fields: Vec<String> = columns
+                .iter()
+                .map(|&column| csv_field(&text::plain_text(&self.cells[row][column])))
+                .collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// The table as a Markdown table, with its first row as the header, as Markdown tables have to have one. The text
+    /// of the cells is put through `escape`, and the bars between them are escaped after that.
+    pub fn to_markdown(&self, escape: impl Fn(&str) -> String) -> String {
+        let (rows, columns) = self.filled();
+        let mut markdown = String::new();
+        for (index, &row) in rows.iter().enumerate() {
+            markdown.push('|');
+            for &column in &columns {
+                let cell = escape(&self.cells[row][column]).replace('|', "\\|");
+                markdown.push_str(&format!(" {} |", cell));
+            }
+            markdown.push('\n');
+            if index == 0 {
+                markdown.push('|');
+                markdown.push_str(&" --- |".repeat(columns.len()));
+                markdown.push('\n');
+            }
+        }
+        markdown
+    }
+
+    /// The table as an HTML table, with the text of the cells put through `escape`. Rows and columns with no text in
+    /// any of their cells are left out, as they are from Markdown tables.
+    pub fn to_html(&self, escape: impl Fn(&str) -> String) -> String {
+        let (rows, columns) = self.filled();
+        let mut html = String::from("<table>\n");
+        for row in rows {
+            html.push_str("<tr>");
+            for &column in &columns {
+                html.push_str(&format!("<td>{}</td>", escape(&self.cells[row][column])));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// The rows of the table as lines of text, each with the index of its row, and with the cells of each column lined
+    /// up under each other. Rows and columns with no text in any of their cells are left out.
+    pub fn aligned_rows(&self) -> Vec<(usize, String)> {
+        let columns = self.cells.first().map_or(0, Vec::len);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                self.cells
+                    .iter()
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.iter().any(|cell| !cell.is_empty()))
+            .map(|(index, row)| {
+                let mut line = String::new();
+                for (cell, &width) in row.iter().zip(&widths).filter(|&(_, &width)| width > 0) {
+                    if !line.is_empty() {
+                        line.push_str("  ");
+                    }
+                    line.push_str(cell);
+                    line.extend(std::iter::repeat_n(' ', width - cell.chars().count()));
+                }
+                (index, line.trim_end().to_owned())
+            })
+            .collect()
+    }
+}
+
+/// Writes the tables of each page as CSV before passing the pages on to another renderer.
+pub struct TableExporter {
+    inner: Box<dyn OutputRenderer>,
+    destination: TableDestination,
+}
+
+enum TableDestination {
+    /// One file for every table, with a blank line between each table and the next.
+    File {
+        writer: BufWriter<File>,
+        started: bool,
+    },
+    /// A file of its own for each table in a directory.
+    Directory(PathBuf),
+}
+
+impl TableExporter {
+    /// Writes the tables to `path`, or to files of their own in it if it is a directory.
+    pub fn new(inner: Box<dyn OutputRenderer>, path: PathBuf) -> io::Result<Self> {
+        let destination = if path.is_dir() {
+            TableDestination::Directory(path)
+        } else {
+            TableDestination::File {
+                writer: BufWriter::new(File::create(path)?),
+                started: false,
+            }
+        };
+        Ok(Self { inner, destination })
+    }
+}
+
+impl OutputRenderer for TableExporter {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        for (index, table) in page.tables.iter().enumerate() {
+            match &mut self.destination {
+                TableDestination::File { writer, started } => {
+                    if *started {
+                        writeln!(writer)?;
+                    }
+                    writer.write_all(table.to_csv().as_bytes())?;
+                    *started = true;
+                }
+                TableDestination::Directory(directory) => {
+                    let name = format!("page-{}-table-{}.csv", page.number, index + 1);
+                    let mut writer = BufWriter::new(File::create(directory.join(name))?);
+                    writer.write_all(table.to_csv().as_bytes())?;
+                    writer.flush()?;
+                }
+            }
+        }
+        self.inner.render_page(page)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let TableDestination::File { writer, .. } = &mut self.destination {
+            writer.flush()?;
+        }
+        self.inner.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn horizontal(y: f32, start: f32, end: f32) -> Ruling {
+        Ruling {
+            horizontal: true,
+            position: y,
+            start,
+            end,
+        }
+    }
+
+    fn vertical(x: f32, start: f32, end: f32) -> Ruling {
+        Ruling {
+            horizontal: false,
+            position: x,
+            start,
+            end,
+        }
+    }
+
+    /// The lines of a grid with a line across at each of `ys` and a line up at each of `xs`.
+    fn grid_lines(xs: &[f32], ys: &[f32]) -> Vec<Ruling> {
+        let (left, right) = (xs[0], xs[xs.len() - 1]);
+        let (bottom, top) = (ys[ys.len() - 1], ys[0]);
+        let mut rulings: Vec<Ruling> = ys.iter().map(|&y| horizontal(y, left, right)).collect();
+        rulings.extend(xs.iter().map(|&x| vertical(x, bottom, top)));
+        rulings
+    }
+
+    fn chunk(text: &str, x: f32, y: f32) -> TextChunk {
+        TextChunk {
+            text: text.to_owned(),
+            x,
+            y,
+            width: 6.0 * text.len() as f32,
+            height: 10.0,
+            space_width: 3.0,
+            font_size: 10.0,
+            ..TextChunk::default()
+        }
+    }
+
+    #[test]
+    fn finds_grids_of_lines() {
+        let grids = find_grids(&grid_lines(&[100.0, 200.0, 300.0], &[500.0, 480.0, 460.0]));
+        assert_eq!(grids.len(), 1);
+        assert_eq!(grids[0].row_boundaries, [500.0, 480.0, 460.0]);
+        assert_eq!(grids[0].column_boundaries, [100.0, 200.0, 300.0]);
+        assert_eq!((grids[0].rows(), grids[0].columns()), (2, 2));
+    }
+
+    #[test]
+    fn joins_lines_which_nearly_meet() {
+        // The lines up are a little short of the lines across, and one line across is drawn twice, slightly apart.
+        let mut rulings = vec![
+            horizontal(500.0, 100.0, 300.0),
+            horizontal(480.0, 100.0, 300.0),
+            horizontal(479.0, 100.0, 300.0),
+            horizontal(460.0, 100.0, 300.0),
+        ];
+        rulings.extend([100.0, 200.0, 300.0].map(|x| vertical(x, 461.5, 498.5)));
+        let grids = find_grids(&rulings);
+        assert_eq!(grids.len(), 1);
+        assert_eq!(grids[0].rows(), 2);
+    }
+
+    #[test]
+    fn leaves_out_boxes_and_lines_on_their_own() {
+        let mut rulings = grid_lines(&[100.0, 300.0], &[500.0, 400.0]);
+        rulings.push(horizontal(200.0, 100.0, 300.0));
+        assert!(find_grids(&rulings).is_empty());
+    }
+
+    #[test]
+    fn finds_each_grid_apart() {
+        let mut rulings = grid_lines(&[100.0, 200.0, 300.0], &[700.0, 680.0]);
+        rulings.extend(grid_lines(&[100.0, 150.0], &[300.0, 280.0, 260.0]));
+        let grids = find_grids(&rulings);
+        assert_eq!(grids.len(), 2);
+        assert_eq!((grids[0].rows(), grids[0].columns()), (1, 2));
+        assert_eq!((grids[1].rows(), grids[1].columns()), (2, 1));
+    }
+
+    #[test]
+    fn gives_up_on_drawings() {
+        let rulings: Vec<Ruling> = (0..=MAX_RULINGS)
+            .map(|index| horizontal(index as f32 * 10.0, 0.0, 100.0))
+            .collect();
+        assert!(find_grids(&rulings).is_empty());
+    }
+
+    #[test]
+    fn puts_points_in_cells() {
+        let grid = Grid {
+            row_boundaries: vec![500.0, 480.0, 460.0],
+            column_boundaries: vec![100.0, 200.0, 300.0],
+        };
+        assert_eq!(grid.cell_at(150.0, 490.0), Some((0, 0)));
+        assert_eq!(grid.cell_at(250.0, 470.0), Some((1, 1)));
+        // A point on a line between cells is in the cell below it or to the right of it.
+        assert_eq!(grid.cell_at(200.0, 480.0), Some((1, 1)));
+        assert_eq!(grid.cell_at(99.0, 490.0), None);
+        assert_eq!(grid.cell_at(150.0, 501.0), None);
+        assert_eq!(grid.cell_at(300.0, 460.0), None);
+    }
+
+    #[test]
+    fn finds_tables_without_lines() {
+        let mut chunks = Vec::new();
+        for (row, y) in [700.0, 686.0, 672.0, 658.0].into_iter().enumerate() {
+            chunks.push(chunk(&format!("Item {}", row), 72.0, y));
+            chunks.push(chunk(&format!("{}", row * 10), 200.0, y));
+            chunks.push(chunk("yes", 300.0, y));
+        }
+        let grids = find_borderless_grids(&chunks, 1.0);
+        assert_eq!(grids.len(), 1);
+        assert_eq!((grids[0].rows(), grids[0].columns()), (4, 3));
+        assert_eq!(grids[0].cell_at(210.0, 690.0), Some((1, 1)));
+        assert_eq!(grids[0].cell_at(310.0, 661.0), Some((3, 2)));
+    }
+
+    #[test]
+    fn leaves_columns_of_prose_alone() {
+        let sentence = "words of a sentence running on";
+        let mut chunks = Vec::new();
+        for y in [700.0, 686.0, 672.0, 658.0] {
+            for x in [72.0, 272.0, 472.0] {
+                chunks.push(chunk(sentence, x, y));
+            }
+        }
+        assert!(find_borderless_grids(&chunks, 1.0).is_empty());
+    }
+
+    #[test]
+    fn needs_three_rows_and_columns_without_lines() {
+        let mut chunks = Vec::new();
+        for y in [700.0, 686.0] {
+            chunks.extend([
+                chunk("a", 72.0, y),
+                chunk("b", 200.0, y),
+                chunk("c", 300.0, y),
+            ]);
+        }
+        for y in [500.0, 486.0, 472.0] {
+            chunks.extend([chunk("a", 72.0, y), chunk("b", 200.0, y)]);
+        }
+        assert!(find_borderless_grids(&chunks, 1.0).is_empty());
+    }
+
+    fn table(cells: &[&[&str]]) -> Table {
+        Table {
+            x: 100.0,
+            y: 500.0,
+            width: 200.0,
+            height: 40.0,
+            cells: cells
+                .iter()
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn writes_csv_without_empty_rows_and_columns() {
+        let table = table(&[
+            &["Name", "", "Note"],
+            &["", "", ""],
+            &["Smith, J", "", "says \"hi\""],
+            &["x<sup>2</sup>", "", ""],
+        ]);
+        assert_eq!(
+            table.to_csv(),
+            "Name,Note\n\"Smith, J\",\"says \"\"hi\"\"\"\nx2,\n"
+        );
+    }
+
+    #[test]
+    fn knows_its_own_rows() {
+        let table = table(&[&["a"]]);
+        let row = |x: f32, y: f32, width: f32| TextChunk {
+            x,
+            y,
+            width,
+            ..TextChunk::default()
+        };
+        assert!(table.contains(&row(100.0, 480.0, 200.0)));
+        // Positions worked out in another way can be a rounding error off.
+        assert!(table.contains(&row(100.0 + 1e-4, 480.0, 200.0 - 1e-4)));
+        assert!(!table.contains(&row(100.0, 480.0, 150.0)));
+        assert!(!table.contains(&row(120.0, 480.0, 200.0)));
+        assert!(!table.contains(&row(100.0, 520.0, 200.0)));
+        assert!(!table.contains(&row(100.0, 450.0, 200.0)));
+    }
+}
@@ -0,0 +1,213 @@
+//! Writes the text as TEI P5, the XML of the Text Encoding Initiative which the digital humanities keep texts in.
+//!
+//! The rows of each page are put back together into paragraphs, as they are for HTML, and each heading found by the
+//! size of its text starts a `div` of the section it heads, nested in the sections of the headings bigger than it.
+//! Every page starts with a page break, numbered by the page number printed on it if it has been found, and the
+//! footnotes found at the bottom of it become notes at the markers which refer to them, or at the end of the page if
+//! nothing refers to them. Tables become TEI tables, and bold, italic, underlined and struck through text, superscripts,
+//! subscripts and links are marked up with `hi` and `ref`.
+
+use std::io::{self, Write};
+
+use crate::{
+    footnotes::{self, Footnote},
+    html,
+    hyphenation::Hyphenation,
+    output::{self, OutputRenderer, PagePart},
+    paragraphs,
+    tables::Table,
+    text::{self, Page, TextChunk},
+};
+
+/// The TEI for each tag of the markup of extracted text, other than the start of a link, which has its address in it.
+const MARKUP: [(&str, &str); 13] = [
+    ("<sup>", "<hi rend=\"superscript\">"),
+    ("</sup>", "</hi>"),
+    ("<sub>", "<hi rend=\"subscript\">"),
+    ("</sub>", "</hi>"),
+    ("<u>", "<hi rend=\"underline\">"),
+    ("</u>", "</hi>"),
+    ("<s>", "<hi rend=\"strikethrough\">"),
+    ("</s>", "</hi>"),
+    ("<strong>", "<hi rend=\"bold\">"),
+    ("</strong>", "</hi>"),
+    ("<em>", "<hi rend=\"italic\">"),
+    ("</em>", "</hi>"),
+    ("</a>", "</ref>"),
+];
+
+const FOOTER: &str = "</body>\n</text>\n</TEI>\n";
+
+pub struct TeiRenderer<W: Write> {
+    writer: W,
+    /// The title of the text, for its header.
+    title: String,
+    hyphenation: Hyphenation,
+    started: bool,
+    /// The levels of the headings of the sections which are open, from the outermost in.
+    open_divs: Vec<u8>,
+}
+
+impl<W: Write> TeiRenderer<W> {
+    pub fn new(writer: W, title: String, hyphenation: Hyphenation) -> Self {
+        Self {
+            writer,
+            title,
+            hyphenation,
+            started: false,
+            open_divs: Vec::new(),
+        }
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        let title = html::escape_plain(&self.title);
+        writeln!(self.writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(self.writer, "<TEI xmlns=\"http://www.tei-c.org/ns/1.0\">")?;
+        writeln!(
+            self.writer,
+            "<teiHeader>\n<fileDesc>\n<titleStmt>\n<title>{}</title>\n</titleStmt>",
+            title
+        )?;
+        writeln!(
+            self.writer,
+            "<publicationStmt>\n<p>Extracted by rust-pdf-test {}.</p>\n</publicationStmt>",
+            env!("CARGO_PKG_VERSION")
+        )?;
+        writeln!(
+            self.writer,
+            "<sourceDesc>\n<p>{}</p>\n</sourceDesc>\n</fileDesc>\n</teiHeader>\n<text>\n<body>",
+            title
+        )
+    }
+
+    /// Starts the section of a heading, after closing the sections it isn't inside.
+    fn write_heading(&mut self, level: u8, text: &str) -> io::Result<()> {
+        while self.open_divs.last().is_some_and(|&open| open >= level) {
+            self.open_divs.pop();
+            writeln!(self.writer, "</div>")?;
+        }
+        self.open_divs.push(level);
+        writeln!(self.writer, "<div>\n<head>{}</head>", escape(text))
+    }
+
+    /// Writes some rows of a page which aren't in a table or a heading as paragraphs, with the notes referred to in
+    /// them where they are referred to.
+    fn write_paragraphs(
+        &mut self,
+        page: &Page,
+        rows: Vec<TextChunk>,
+        notes: &mut [Option<Footnote>],
+    ) -> io::Result<()> {
+        let rows = Page {
+            number: page.number,
+            label: None,
+            rows,
+            tables: Vec::new(),
+            bounds: page.bounds,
+        };
+        for mut paragraph in paragraphs::split(&rows, escape, &self.hyphenation) {
+            for note in notes.iter_mut() {
+                let Some(footnote) = note else {
+                    continue;
+                };
+                let reference = escape(&format!("<sup>{}</sup>", footnote.marker));
+                if let Some(start) = paragraph.find(&reference) {
+                    paragraph
+                        .replace_range(start..start + reference.len(), &note_element(footnote));
+                    *note = None;
+                }
+            }
+            writeln!(self.writer, "<p>{}</p>", paragraph)?;
+        }
+        Ok(())
+    }
+
+    fn write_table(&mut self, table: &Table) -> io::Result<()> {
+        writeln!(self.writer, "<table>")?;
+        for row in &table.cells {
+            write!(self.writer, "<row>")?;
+            for cell in row {
+                write!(self.writer, "<cell>{}</cell>", escape(cell))?;
+            }
+            writeln!(self.writer, "</row>")?;
+        }
+        writeln!(self.writer, "</table>")
+    }
+}
+
+impl<W: Write> OutputRenderer for TeiRenderer<W> {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.start()?;
+        let mut page = Page {
+            number: page.number,
+            label: page.label.clone(),
+            rows: page.rows.clone(),
+            tables: page.tables.clone(),
+            bounds: page.bounds,
+        };
+        let mut notes: Vec<Option<Footnote>> = footnotes::take_footnotes(&mut page)
+            .into_iter()
+            .map(|(footnote, _)| Some(footnote))
+            .collect();
+        let number = page
+            .label
+            .clone()
+            .unwrap_or_else(|| page.number.to_string());
+        writeln!(self.writer, "<pb n=\"{}\"/>", html::escape_plain(&number))?;
+        for part in output::page_parts(&page) {
+            match part {
+                PagePart::Rows(rows) => self.write_paragraphs(&page, rows.to_vec(), &mut notes)?,
+                PagePart::Heading(level, text) => self.write_heading(level, &text)?,
+                PagePart::Table(table) => self.write_table(table)?,
+            }
+        }
+        for footnote in notes.into_iter().flatten() {
+            writeln!(self.writer, "{}", note_element(&footnote))?;
+        }
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.start()?;
+        for _ in self.open_divs.drain(..) {
+            writeln!(self.writer, "</div>")?;
+        }
+        self.writer.write_all(FOOTER.as_bytes())?;
+        self.writer.flush()
+    }
+}
+
+fn note_element(footnote: &Footnote) -> String {
+    format!(
+        "<note place=\"foot\" n=\"{}\">{}</note>",
+        html::escape_plain(&footnote.marker),
+        html::escape_plain(&footnote.text)
+    )
+}
+
+/// Escapes the characters which mean something in XML, turning the markup of extracted text into TEI.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, tag)) = text::find_markup(rest) {
+        escaped.push_str(&html::escape_plain(&rest[..start]));
+        match tag.strip_prefix(text::LINK_START) {
+            Some(address) => {
+                let address = address.strip_suffix("\">").unwrap_or(address);
+                escaped.push_str(&format!("<ref target=\"{}\">", html::escape_plain(address)));
+            }
+            None => {
+                if let Some((_, tei)) = MARKUP.iter().find(|(markup, _)| *markup == tag) {
+                    escaped.push_str(tei);
+                }
+            }
+        }
+        rest = &rest[start + tag.len()..];
+    }
+    escaped.push_str(&html::escape_plain(rest));
+    escaped
+}
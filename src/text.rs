@@ -0,0 +1,1715 @@
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Display,
+    io, mem,
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use clap::ValueEnum;
+use lopdf::{
+    content::{Content, Operation},
+    Document, Object, ObjectId,
+};
+use serde::Serialize;
+
+use crate::{
+    bidi,
+    content::{self, BudgetExceeded, PageBudget},
+    filters::{self, DecodeError},
+    font::{DecodingCheck, Font, FontCache},
+    hyphenation::Hyphenation,
+    links, outline,
+    output::OutputRenderer,
+    page_range::PageSelection,
+    resources::{self, Resources},
+    simple_page, structure,
+    tables::{self, Grid, Ruling, Table},
+    text_state::{MarkedContent, PageText, TextOptions, TextState},
+    traversal::{self, TraversalError},
+};
+
+#[derive(PartialEq, Clone, Default, Serialize)]
+pub struct TextChunk {
+    pub text: String,
+    pub page: u32,
+    pub x: f32,
+    pub y: f32,
+    /// How far along the line the text goes from where it starts.
+    pub width: f32,
+    /// How tall the text's font is, from the lowest of its descenders to the highest of its ascenders.
+    pub height: f32,
+    /// How wide a space is in the text's font, on the page, which the gaps between chunks are measured against.
+    #[serde(skip)]
+    pub space_width: f32,
+    /// Whether the text was written from top to bottom, in which case it is a piece of a column rather than a row.
+    #[serde(skip)]
+    pub vertical: bool,
+    /// How far the direction the text runs in is turned anticlockwise from left to right across the page, in
+    /// degrees, such as 45 for a diagonal watermark.
+    #[serde(skip)]
+    pub angle: f32,
+    /// The identifier of the marked content the text is in, which the structure tree of tagged documents refers to
+    /// it by.
+    #[serde(skip)]
+    pub marked_content: Option<i64>,
+    /// The level of the heading the row is, from 1 for the biggest, once the headings of the document have been found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<u8>,
+    /// The family of the font the text is in, such as `Helvetica`, if the font has a name. A row made of chunks in
+    /// more than one font has the font of the chunk it starts with, as it does for the size and style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    /// The size the text is drawn in on the page, which is the size its font was chosen in, scaled by any transform.
+    pub font_size: f32,
+    /// Whether the text's font is bold, and whether it is italic.
+    pub bold: bool,
+    pub italic: bool,
+    /// The chunks a row was joined from, in the order they were joined in, if they are kept and there was more than
+    /// one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<TextChunk>,
+    /// The baseline of the row a superscript or subscript belongs to, which it is laid out on while it keeps the
+    /// position it is drawn at.
+    #[serde(skip)]
+    pub baseline: Option<f32>,
+}
+
+impl PartialOrd for TextChunk {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(
+            self.page
+                .cmp(&other.page)
+                .then_with(|| self.y.total_cmp(&other.y))
+                .then_with(|| self.x.total_cmp(&other.x)),
+        )
+    }
+}
+
+impl TextChunk {
+    /// How far up the page the row the text is on is, which is its baseline if it has been moved onto a row.
+    pub fn row_y(&self) -> f32 {
+        self.baseline.unwrap_or(self.y)
+    }
+
+    /// Roughly how many bytes the chunk takes up in memory.
+    pub fn approximate_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.text.capacity()
+            + self.font_family.as_ref().map_or(0, String::capacity)
+            + self
+                .chunks
+                .iter()
+                .map(TextChunk::approximate_size)
+                .sum::<usize>()
+    }
+}
+
+impl Display for TextChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.text.fmt(f)
+    }
+}
+
+/// The rows of text on a single page.
+#[derive(Clone, Serialize)]
+pub struct Page {
+    pub number: u32,
+    /// The page number printed on the page, such as "iv" or "12", once it has been found and taken out of the rows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub rows: Vec<TextChunk>,
+    /// The tables drawn with ruling lines on the page, each of whose rows is also one of the page's rows.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<Table>,
+    /// The corners of the part of the page that is shown, left, bottom, right and top, in the same units and from the
+    /// same origin as its rows.
+    pub bounds: [f32; 4],
+}
+
+/// What order the text of a page comes out in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Order {
+    /// Every piece of text on a row of its own, in exactly the order the page's content draws it, for debugging the
+    /// software that made the file or comparing against other extractors.
+    Raw,
+    /// Text on the same line joined into rows, with superscripts and subscripts marked up and moved onto their row,
+    /// and the rows put in reading order, from the top of the page down and from left to right along each line.
+    #[default]
+    Layout,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExtractOptions {
+    /// How many parsed fonts to keep around for later pages.
+    pub font_cache_size: NonZeroUsize,
+    /// The pages to extract, or `None` for all of them.
+    pub pages: Option<PageSelection>,
+    /// Pages which need more work than this are skipped with a warning.
+    pub budget: PageBudget,
+    /// Roughly how much memory extraction may use, including the loaded document itself.
+    pub max_memory: Option<usize>,
+    pub order: Order,
+    /// How far, in thousandths of the font size, a `TJ` array has to move its text along for a space to go there.
+    pub space_threshold: f32,
+    /// Whether to keep text drawn invisibly, as the OCR layers of scans and some watermarks are.
+    pub include_invisible: bool,
+    /// The layers to extract the text of, or all of the text, whatever layer it is in, if there are none.
+    pub layers: Vec<String>,
+    /// The layers to leave the text of out, such as a watermark.
+    pub excluded_layers: Vec<String>,
+    /// Whether to use the `ActualText` of tagged content in place of the text drawn inside it.
+    pub actual_text: bool,
+    /// Whether to keep the running headers, footers, page numbers and other artifacts that tagged documents mark as
+    /// not being part of their real content.
+    pub include_artifacts: bool,
+    /// A directory of Adobe's CMap resources, for the predefined CMaps of Chinese, Japanese and Korean fonts.
+    pub cmap_dir: Option<PathBuf>,
+    /// Whether to keep right to left text, such as Arabic and Hebrew, in the order it is drawn in rather than putting
+    /// it back into the order it is read in.
+    pub visual_order: bool,
+    /// Whether to put the text of tagged documents in the order their structure tree reads it in, rather than the
+    /// order it is drawn in, when laying it out.
+    pub use_structure: bool,
+    /// How far apart, in the units of the page, text can be up or down and still be on the same line.
+    pub line_tolerance: f32,
+    /// How much of the width of a space the gap between two pieces of text on the same line has to be for a space to
+    /// go between them.
+    pub word_gap: f32,
+    /// Whether to keep text on the same line apart where there is a wide gap between it, as there is between the
+    /// columns of a table, so that it can be put back where it was on the page.
+    pub keep_columns: bool,
+    /// Whether to keep the chunks each row is joined from, for the formats which write them.
+    pub keep_chunks: bool,
+    /// Whether to guess which text is superscripts and subscripts from how far it moves up or down along its row,
+    /// rather than leaving it where it is drawn.
+    pub detect_scripts: bool,
+    /// How far text can move up or down from the text before it along its row and still be a superscript or
+    /// subscript.
+    pub script_offset: ScriptOffset,
+    /// How far after the text before it, in times its own height, a superscript or subscript can start.
+    pub script_gap: f32,
+    /// Whether to mark up bold, italic, underlined and struck through text, for the formats which keep the markup.
+    pub mark_styles: bool,
+    /// Whether to mark up the text of links with their addresses, for the formats which keep the markup.
+    pub mark_links: bool,
+}
+
+impl ExtractOptions {
+    pub fn layout_options(&self) -> LayoutOptions {
+        LayoutOptions {
+            line_tolerance: self.line_tolerance,
+            word_gap: self.word_gap,
+            keep_columns: self.keep_columns,
+            keep_chunks: self.keep_chunks,
+            detect_scripts: self.detect_scripts,
+            script_offset: self.script_offset,
+            script_gap: self.script_gap,
+        }
+    }
+
+    pub fn text_options(&self) -> TextOptions {
+        TextOptions {
+            space_threshold: self.space_threshold,
+            include_invisible: self.include_invisible,
+            layers: self.layers.clone(),
+            excluded_layers: self.excluded_layers.clone(),
+            use_actual_text: self.actual_text,
+            include_artifacts: self.include_artifacts,
+            mark_styles: self.mark_styles,
+            mark_links: self.mark_links,
+        }
+    }
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            font_cache_size: NonZeroUsize::new(64).unwrap(),
+            pages: None,
+            budget: PageBudget {
+                max_operators: 5_000_000,
+                max_bytes: 256 * 1024 * 1024,
+            },
+            max_memory: None,
+            order: Order::Layout,
+            space_threshold: 200.0,
+            include_invisible: true,
+            layers: Vec::new(),
+            excluded_layers: Vec::new(),
+            actual_text: true,
+            include_artifacts: false,
+            cmap_dir: None,
+            visual_order: false,
+            use_structure: false,
+            line_tolerance: 1.0,
+            word_gap: 0.5,
+            keep_columns: false,
+            keep_chunks: false,
+            detect_scripts: true,
+            script_offset: ScriptOffset::Auto,
+            script_gap: 1.0,
+            mark_styles: false,
+            mark_links: false,
+        }
+    }
+}
+
+/// Roughly how many times more memory a content stream takes up once it has been parsed into operations.
+const PARSED_CONTENT_OVERHEAD: usize = 4;
+
+/// Extracts the text of a document one page at a time, so that each page can be output as soon as it is done.
+///
+/// The extractor usually borrows the document, but can own it instead for when it needs to be kept around along with
+/// the fonts and statistics gathered from it so far.
+pub struct TextExtractor<D: Borrow<Document>> {
+    document: D,
+    pages: Option<PageSelection>,
+    budget: PageBudget,
+    max_memory: usize,
+    /// Roughly how much of the memory the loaded document takes up.
+    document_size: usize,
+    order: Order,
+    text_options: TextOptions,
+    visual_order: bool,
+    use_structure: bool,
+    layout_options: LayoutOptions,
+    /// The order the structure tree reads each page's marked content in, once it has been read.
+    reading_order: Option<BTreeMap<u32, Vec<i64>>>,
+    fonts: FontCache,
+    scripts: ScriptDetector,
+}
+
+impl<D: Borrow<Document>> TextExtractor<D> {
+    pub fn new(document: D, options: &ExtractOptions) -> Self {
+        Self {
+            document_size: document_size(document.borrow()),
+            document,
+            pages: options.pages.clone(),
+            budget: options.budget,
+            max_memory: options.max_memory.unwrap_or(usize::MAX),
+            order: options.order,
+            text_options: options.text_options(),
+            visual_order: options.visual_order,
+            use_structure: options.use_structure,
+            layout_options: options.layout_options(),
+            reading_order: None,
+            fonts: FontCache::new(options.font_cache_size, options.cmap_dir.clone()),
+            scripts: ScriptDetector::default(),
+        }
+    }
+
+    /// Extracts every selected page of the document in order.
+    pub fn pages(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<Page, Box<dyn Error>>> + use<'_, D>, TraversalError>
+    {
+        Ok(self
+            .selected_pages()?
+            .into_iter()
+            .map(move |(number, page_id)| self.extract_page(number, page_id)))
+    }
+
+    /// The numbers and IDs of the pages which were asked for.
+    pub fn selected_pages(&self) -> Result<BTreeMap<u32, ObjectId>, TraversalError> {
+        let mut pages = traversal::pages(self.document.borrow())?;
+        // Pages which weren't asked for are dropped before anything else happens, so their content streams and fonts
+        // are never touched.
+        if let Some(selection) = &self.pages {
+            pages.retain(|&number, _| selection.contains(number));
+        }
+        Ok(pages)
+    }
+
+    /// Extracts a page as a list of rows in reading order, with superscripts and subscripts marked up and right to left
+    /// text put into reading order too, unless the text is to come out in raw order.
+    ///
+    /// Pages that go over the budget come out empty, with a warning, rather than failing the whole document.
+    ///
+    /// Tables drawn with ruling lines come out as a row for each of their rows, with the text of each cell laid out on
+    /// its own and the cells lined up in columns.
+    pub fn extract_page(&mut self, number: u32, page_id: ObjectId) -> Result<Page, Box<dyn Error>> {
+        let page_text = self.extract_chunks(number, page_id)?;
+        let mut rows = page_text.chunks;
+        let mut tables = Vec::new();
+        self.scripts.uses_rise |= page_text.uses_rise;
+        if self.order == Order::Layout {
+            let structured = self.use_structure && self.sort_by_structure(number, &mut rows);
+            // The structure tree's order is the order the text is meant to be read in, so tables aren't looked for
+            // when it is followed, as their rows would have nowhere to go in it. Nor are they when columns are kept,
+            // since the pieces of each of their rows then stay as far across as they are drawn anyway.
+            let table_rows = if structured || self.layout_options.keep_columns {
+                Vec::new()
+            } else {
+                self.take_tables(&page_text.rulings, &mut rows, &mut tables)
+            };
+            lay_out_rows(&mut rows, &mut self.scripts, &self.layout_options);
+            if !self.visual_order {
+                for row in &mut rows {
+                    bidi::logical_order(&mut row.text);
+                }
+            }
+            rows.extend(table_rows);
+            if !structured {
+                sort_into_reading_order(&mut rows, self.layout_options.line_tolerance);
+            }
+        }
+        Ok(Page {
+            number,
+            label: None,
+            rows,
+            tables,
+            bounds: page_bounds(self.document.borrow(), page_id)?,
+        })
+    }
+
+    /// Takes the chunks inside the tables on the page out of the rest, adding the tables with the text of their cells,
+    /// and gives back a row for each row of the tables. Tables drawn with ruling lines are found first, and then
+    /// tables without lines among the chunks left over.
+    fn take_tables(
+        &mut self,
+        rulings: &[Ruling],
+        chunks: &mut Vec<TextChunk>,
+        tables: &mut Vec<Table>,
+    ) -> Vec<TextChunk> {
+        let mut table_rows = Vec::new();
+        for grid in tables::find_grids(rulings) {
+            self.take_table(&grid, chunks, tables, &mut table_rows);
+        }
+        for grid in tables::find_borderless_grids(chunks, self.layout_options.line_tolerance) {
+            self.take_table(&grid, chunks, tables, &mut table_rows);
+        }
+        table_rows
+    }
+
+    /// Takes the chunks inside a grid out of the rest, adding the table they make, and a row for each of its rows.
+    fn take_table(
+        &mut self,
+        grid: &Grid,
+        chunks: &mut Vec<TextChunk>,
+        tables: &mut Vec<Table>,
+        table_rows: &mut Vec<TextChunk>,
+    ) {
+        // Each chunk goes in the cell the middle of it is in, a little above its baseline so that descenders hanging
+        // below a ruling don't move it to the next row.
+        let mut cell_chunks = vec![vec![Vec::new(); grid.columns()]; grid.rows()];
+        let mut index = 0;
+        while index < chunks.len() {
+            let chunk = &chunks[index];
+            let cell = if chunk.vertical || chunk.angle.abs() > 1.0 {
+                None
+            } else {
+                grid.cell_at(chunk.x + chunk.width / 2.0, chunk.y + chunk.height * 0.3)
+            };
+            match cell {
+                Some((row, column)) => cell_chunks[row][column].push(chunks.remove(index)),
+                None => index += 1,
+            }
+        }
+        if cell_chunks.iter().flatten().all(Vec::is_empty) {
+            return;
+        }
+        let mut cells = Vec::new();
+        // The chunk each row of the table is made from, which is the highest line in any of its cells.
+        let mut row_chunks: Vec<Option<TextChunk>> = Vec::new();
+        for row in cell_chunks {
+            let mut texts = Vec::new();
+            let mut row_chunk: Option<TextChunk> = None;
+            for mut cell in row {
+                lay_out_rows(&mut cell, &mut self.scripts, &self.layout_options);
+                sort_into_reading_order(&mut cell, self.layout_options.line_tolerance);
+                let mut text = String::new();
+                for mut line in cell {
+                    if !self.visual_order {
+                        bidi::logical_order(&mut line.text);
+                    }
+                    join_line(&mut text, &line.text);
+                    if row_chunk.as_ref().is_none_or(|chunk| line.y > chunk.y) {
+                        row_chunk = Some(line);
+                    }
+                }
+                texts.push(text);
+            }
+            cells.push(texts);
+            row_chunks.push(row_chunk);
+        }
+        let left = grid.column_boundaries[0];
+        let top = grid.row_boundaries[0];
+        let table = Table {
+            x: left,
+            y: top,
+            width: grid.column_boundaries[grid.columns()] - left,
+            height: top - grid.row_boundaries[grid.rows()],
+            cells,
+        };
+        for (row, text) in table.aligned_rows() {
+            if let Some(chunk) = row_chunks[row].take() {
+                // The row's text is now the text of the whole row of the table, which its chunks no longer add up to.
+                table_rows.push(TextChunk {
+                    text,
+                    x: left,
+                    width: table.width,
+                    chunks: Vec::new(),
+                    ..chunk
+                });
+            }
+        }
+        tables.push(table);
+    }
+
+    /// Extracts the text of each piece of marked content on a page, by its identifier, for following the structure
+    /// tree of a tagged document. Each piece is laid out on its own and its rows joined into one line, and text
+    /// outside of any marked content with an identifier is left out.
+    pub fn extract_marked_content(
+        &mut self,
+        number: u32,
+        page_id: ObjectId,
+    ) -> Result<BTreeMap<i64, String>, Box<dyn Error>> {
+        let page_text = self.extract_chunks(number, page_id)?;
+        self.scripts.uses_rise |= page_text.uses_rise;
+        let mut pieces = BTreeMap::<i64, Vec<TextChunk>>::new();
+        for chunk in page_text.chunks {
+            if let Some(identifier) = chunk.marked_content {
+                pieces.entry(identifier).or_default().push(chunk);
+            }
+        }
+        Ok(pieces
+            .into_iter()
+            .map(|(identifier, mut rows)| {
+                lay_out_rows(&mut rows, &mut self.scripts, &self.layout_options);
+                sort_into_reading_order(&mut rows, self.layout_options.line_tolerance);
+                let mut text = String::new();
+                for mut row in rows {
+                    if !self.visual_order {
+                        bidi::logical_order(&mut row.text);
+                    }
+                    join_line(&mut text, &row.text);
+                }
+                (identifier, text)
+            })
+            .collect())
+    }
+
+    /// Extracts the chunks of a page in the order they are drawn in, before they are laid out into rows, within the
+    /// budget and the memory limit in the same way as `extract_page`.
+    pub fn extract_chunks(
+        &mut self,
+        number: u32,
+        page_id: ObjectId,
+    ) -> Result<PageText, Box<dyn Error>> {
+        let mut budget = self.budget;
+        // Once parsed, content takes up several times as much memory as its bytes do.
+        if self.available_memory() / PARSED_CONTENT_OVERHEAD < budget.max_bytes {
+            // Emptying the font cache is the cheapest way to make room, since fonts can always be loaded again.
+            self.fonts.clear();
+            budget.max_bytes = budget
+                .max_bytes
+                .min(self.available_memory() / PARSED_CONTENT_OVERHEAD);
+        }
+        let result = extract_text_chunks(
+            self.document.borrow(),
+            number,
+            page_id,
+            budget,
+            &self.text_options,
+            &mut self.fonts,
+        );
+        match result {
+            Ok(page_text) => Ok(page_text),
+            Err(error) => match error.downcast_ref::<BudgetExceeded>() {
+                Some(BudgetExceeded::Bytes(_)) if budget.max_bytes < self.budget.max_bytes => {
+                    eprintln!(
+                        "warning: skipping page {} because its content doesn't fit in the memory limit",
+                        number
+                    );
+                    Ok(PageText::default())
+                }
+                Some(exceeded) => {
+                    eprintln!("warning: skipping page {} because {}", number, exceeded);
+                    Ok(PageText::default())
+                }
+                None => Err(error),
+            },
+        }
+    }
+
+    pub fn document(&self) -> &Document {
+        self.document.borrow()
+    }
+
+    /// Puts the chunks of a page in the order the structure tree reads their marked content in, with any the tree
+    /// doesn't mention after the rest, in the order they were drawn in. Pages the tree has nothing of are left as
+    /// they are, and give false.
+    fn sort_by_structure(&mut self, number: u32, rows: &mut [TextChunk]) -> bool {
+        let reading_order = self.reading_order.get_or_insert_with(|| {
+            structure::reading_order(self.document.borrow()).unwrap_or_else(|error| {
+                eprintln!("warning: not using the structure tree, because {}", error);
+                BTreeMap::new()
+            })
+        });
+        let Some(identifiers) = reading_order.get(&number) else {
+            return false;
+        };
+        let mut positions = HashMap::new();
+        for (position, &identifier) in identifiers.iter().enumerate() {
+            positions.entry(identifier).or_insert(position);
+        }
+        rows.sort_by_key(|row| {
+            row.marked_content
+                .and_then(|identifier| positions.get(&identifier).copied())
+                .unwrap_or(usize::MAX)
+        });
+        true
+    }
+
+    /// Roughly how much memory is left for extracting a page, once the document and the cached fonts are counted.
+    fn available_memory(&self) -> usize {
+        self.max_memory
+            .saturating_sub(self.document_size + self.fonts.approximate_size())
+    }
+}
+
+/// Runs some extraction, giving the message of any panic in it as an error instead, for the modes which keep running
+/// after one document turns out to be too broken to extract.
+///
+/// Whatever the extraction was using is left as the panic left it, which at worst is a font cache missing the font it
+/// was loading.
+pub fn catching_panics<T>(extract: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(extract)).map_err(|payload| {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "no message".to_owned()),
+        };
+        format!("extraction failed unexpectedly: {}", message)
+    })
+}
+
+/// Extracts the text of the whole document as a list of rows, for uses which need all of it at once.
+///
+/// The rows count against the memory limit, and extraction stops with an error once they no longer fit.
+pub fn extract_text(
+    document: &Document,
+    options: &ExtractOptions,
+) -> Result<Vec<TextChunk>, Box<dyn Error>> {
+    let mut extractor = TextExtractor::new(document, options);
+    let mut collector = RowCollector::new(
+        options
+            .max_memory
+            .map(|max_memory| max_memory.saturating_sub(extractor.document_size)),
+    );
+    for page in extractor.pages()? {
+        collector.render_page(&page?)?;
+    }
+    Ok(collector.rows)
+}
+
+/// Collects the rows of every page into one list, for uses which need all of the text at once.
+pub struct RowCollector {
+    pub rows: Vec<TextChunk>,
+    /// Roughly how much memory the rows may take up.
+    max_memory: Option<usize>,
+    used_memory: usize,
+}
+
+impl RowCollector {
+    pub fn new(max_memory: Option<usize>) -> Self {
+        Self {
+            rows: Vec::new(),
+            max_memory,
+            used_memory: 0,
+        }
+    }
+}
+
+impl OutputRenderer for RowCollector {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.used_memory += page
+            .rows
+            .iter()
+            .map(TextChunk::approximate_size)
+            .sum::<usize>();
+        if let Some(max_memory) = self.max_memory {
+            if self.used_memory > max_memory {
+                return Err(io::Error::other(format!(
+                    "the document's text doesn't fit in the memory limit of {} bytes",
+                    max_memory
+                )));
+            }
+        }
+        self.rows.extend(page.rows.iter().cloned());
+        Ok(())
+    }
+}
+
+/// Roughly how many bytes the document takes up in memory, which is mostly the data in its streams.
+pub fn document_size(document: &Document) -> usize {
+    document
+        .objects
+        .values()
+        .filter_map(|object| object.as_stream().ok())
+        .map(|stream| stream.content.len())
+        .sum()
+}
+
+/// The markup text can have in it: superscripts and subscripts, and when styles are marked up, underlined, struck
+/// through, bold and italic text, and the ends of links when links are. Links start with `LINK_START` instead, which
+/// has the address of the link after it.
+pub const MARKUP: [&str; 13] = [
+    "<sup>",
+    "</sup>",
+    "<sub>",
+    "</sub>",
+    "<u>",
+    "</u>",
+    "<s>",
+    "</s>",
+    "<strong>",
+    "</strong>",
+    "<em>",
+    "</em>",
+    "</a>",
+];
+
+/// The start of the markup of a link, which is followed by the address of the link and `">`.
+pub const LINK_START: &str = "<a href=\"";
+
+/// The markup for the styles of text, outermost first, as each tag to start it and the tag to end it.
+const STYLE_TAGS: [(&str, &str); 4] = [
+    ("<u>", "</u>"),
+    ("<s>", "</s>"),
+    ("<strong>", "</strong>"),
+    ("<em>", "</em>"),
+];
+
+/// Finds the first tag of markup in some text, with where it starts.
+pub fn find_markup(text: &str) -> Option<(usize, &str)> {
+    let tag = MARKUP
+        .iter()
+        .filter_map(|&tag| Some((text.find(tag)?, tag)))
+        .min();
+    let link = text.find(LINK_START).and_then(|start| {
+        let end = start + text[start..].find("\">")? + 2;
+        Some((start, &text[start..end]))
+    });
+    match (tag, link) {
+        (Some(tag), Some(link)) => Some(tag.min(link)),
+        (tag, link) => tag.or(link),
+    }
+}
+
+/// Removes the markup from a row's text.
+pub fn plain_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, tag)) = find_markup(rest) {
+        plain.push_str(&rest[..start]);
+        rest = &rest[start + tag.len()..];
+    }
+    plain.push_str(rest);
+    plain
+}
+
+/// Adds the text of a chunk to the end of a row, carrying on the style markup of the row into it if it starts
+/// in the same style, rather than ending the markup and starting it again, along with any space between them.
+fn push_styled(row: &mut String, text: &str) {
+    let spaces = row.len() - row.trim_end_matches(' ').len();
+    let mut end = row.len() - spaces;
+    let mut text = text;
+    for (start_tag, end_tag) in STYLE_TAGS {
+        if !row[..end].ends_with(end_tag) {
+            continue;
+        }
+        // Markup nested inside another style can only carry on if the other style does.
+        let Some(rest) = text.strip_prefix(start_tag) else {
+            break;
+        };
+        end -= end_tag.len();
+        text = rest;
+    }
+    let spaces = row[row.len() - spaces..].to_owned();
+    row.truncate(end);
+    row.push_str(&spaces);
+    row.push_str(text);
+}
+
+/// Adds some text to the end of a paragraph, putting back together any word hyphenated across the break between them.
+pub fn join_line(paragraph: &mut String, text: &str) {
+    Hyphenation::default().join_line(paragraph, text);
+}
+
+/// Walks a page's content, returning each piece of text drawn on it along with where it was drawn.
+pub fn extract_text_chunks(
+    document: &Document,
+    page_number: u32,
+    page_id: ObjectId,
+    budget: PageBudget,
+    options: &TextOptions,
+    fonts: &mut FontCache,
+) -> Result<PageText, Box<dyn Error>> {
+    let resources = Resources::of_page(document, page_id)?;
+    let content = content::page_content(document, page_id, budget)?;
+    let mut decoding = DecodingCheck::default();
+    // Most pages are simple enough to be scanned directly, which is much faster than parsing them first.
+    let simple_chunks = match simple_page::simple_font(document, &resources) {
+        Some((font_name, font)) => {
+            let font = fonts.get(document, font)?;
+            let chunks =
+                simple_page::scan(&content, page_number, font_name, &font, budget, options)?;
+            for chunk in chunks.iter().flat_map(|page_text| &page_text.chunks) {
+                decoding.check(&font, &chunk.text);
+            }
+            chunks
+        }
+        None => None,
+    };
+    let mut chunks = match simple_chunks {
+        Some(chunks) => chunks,
+        None => {
+            let bytes = content.len();
+            let content = Content::decode(&content::without_inline_images(&content))?;
+            if content.operations.len() > budget.max_operators {
+                return Err(BudgetExceeded::Operators(budget.max_operators).into());
+            }
+            let mut walker = ContentWalker {
+                document,
+                page_number,
+                fonts,
+                decoding: &mut decoding,
+                page_resources: &resources,
+                state: TextState::new(page_number, options.clone()),
+                current_font: None,
+                budget,
+                operators: content.operations.len(),
+                bytes,
+                forms: Vec::new(),
+                missing_fonts: Vec::new(),
+            };
+            walker.walk(content, &resources)?;
+            for font_name in &walker.missing_fonts {
+                eprintln!(
+                    "warning: page {} uses a font called {:?} which it doesn't have, so the text in it is left out",
+                    page_number, font_name
+                );
+            }
+            walker.state.finish()
+        }
+    };
+    for font_name in decoding.garbled_fonts() {
+        eprintln!(
+            "warning: the text in {} on page {} looks wrongly decoded, so the font's encoding or ToUnicode map is \
+             probably missing or wrong",
+            font_name, page_number
+        );
+    }
+    // Links are where they are on the page before it is turned, as the text is until now.
+    if options.mark_links {
+        links::mark(&mut chunks.chunks, &links::page_links(document, page_id));
+    }
+    rotate_page_text(document, page_id, &mut chunks)?;
+    Ok(chunks)
+}
+
+/// The size of a page which doesn't say how big it is, which is US Letter.
+pub const DEFAULT_PAGE_BOX: [f32; 4] = [0.0, 0.0, 612.0, 792.0];
+
+/// Turns the positions of a page's text by the page's `Rotate`, so that they are where the text is on the page as it
+/// is shown, which is what rows and their order go by. The bottom left corner of a rotated page as it is shown becomes
+/// the origin.
+fn rotate_page_text(
+    document: &Document,
+    page_id: ObjectId,
+    page_text: &mut PageText,
+) -> Result<(), TraversalError> {
+    let rotation = page_rotation(document, page_id)?;
+    if rotation == 0 {
+        return Ok(());
+    }
+    let [left, bottom, right, top] = shown_box(document, page_id)?;
+    // Pages are turned clockwise.
+    let turn = |(x, y): (f32, f32)| match rotation {
+        90 => (y - bottom, right - x),
+        180 => (right - x, top - y),
+        270 => (top - y, x - left),
+        // Rotations which aren't a multiple of 90 degrees aren't allowed, so the page is left as it is.
+        _ => (x, y),
+    };
+    for chunk in &mut page_text.chunks {
+        (chunk.x, chunk.y) = turn((chunk.x, chunk.y));
+    }
+    for ruling in &mut page_text.rulings {
+        let [start, end] = ruling.ends().map(turn);
+        if let Some(turned) = Ruling::between(start, end) {
+            *ruling = turned;
+        }
+    }
+    Ok(())
+}
+
+/// The corners of the part of a page that is shown, left, bottom, right and top, once its text has been turned by
+/// `rotate_page_text`.
+pub fn page_bounds(document: &Document, page_id: ObjectId) -> Result<[f32; 4], TraversalError> {
+    let [left, bottom, right, top] = shown_box(document, page_id)?;
+    Ok(match page_rotation(document, page_id)? {
+        90 | 270 => [0.0, 0.0, top - bottom, right - left],
+        180 => [0.0, 0.0, right - left, top - bottom],
+        _ => [left, bottom, right, top],
+    })
+}
+
+/// How far a page is turned clockwise when it is shown, in degrees from 0 up to 360.
+fn page_rotation(document: &Document, page_id: ObjectId) -> Result<i64, TraversalError> {
+    Ok(
+        traversal::inherited_attribute(document, page_id, b"Rotate")?
+            .and_then(|rotation| rotation.as_i64().ok())
+            .unwrap_or(0)
+            .rem_euclid(360),
+    )
+}
+
+/// The corners of the part of a page that is shown, left, bottom, right and top, in the default coordinates of the page.
+fn shown_box(document: &Document, page_id: ObjectId) -> Result<[f32; 4], TraversalError> {
+    // The part of the page that is shown is the crop box, which is the media box unless it says otherwise.
+    let page_box = match traversal::inherited_attribute(document, page_id, b"CropBox")? {
+        Some(page_box) => Some(page_box),
+        None => traversal::inherited_attribute(document, page_id, b"MediaBox")?,
+    };
+    let page_box = page_box
+        .and_then(|page_box| page_box.as_array().ok())
+        .and_then(|page_box| {
+            let numbers = page_box
+                .iter()
+                .map(Object::as_float)
+                .collect::<Result<Vec<_>, _>>()
+                .ok()?;
+            <[f32; 4]>::try_from(numbers).ok()
+        })
+        .unwrap_or(DEFAULT_PAGE_BOX);
+    // The corners can be given either way round.
+    Ok([
+        page_box[0].min(page_box[2]),
+        page_box[1].min(page_box[3]),
+        page_box[0].max(page_box[2]),
+        page_box[1].max(page_box[3]),
+    ])
+}
+
+/// How deeply Form XObjects can draw other forms, which is far deeper than any real document goes.
+const MAX_FORM_DEPTH: usize = 16;
+
+/// Follows the text state through a page's parsed content, and through the content of the Form XObjects it draws with
+/// `Do`, which letterheads, stamps and whole pages from some generators are drawn in.
+struct ContentWalker<'a, 'b> {
+    document: &'a Document,
+    page_number: u32,
+    fonts: &'b mut FontCache,
+    decoding: &'b mut DecodingCheck,
+    /// The page's resources, which forms without resources of their own use.
+    page_resources: &'b Resources<'a>,
+    state: TextState,
+    current_font: Option<Rc<Font>>,
+    /// The page's budget, which the content of its forms counts against along with its own.
+    budget: PageBudget,
+    operators: usize,
+    bytes: usize,
+    /// The forms being drawn, innermost last, so that a form which draws itself isn't followed forever.
+    forms: Vec<ObjectId>,
+    /// The names of the fonts chosen with `Tf` which the page doesn't have.
+    missing_fonts: Vec<String>,
+}
+
+impl<'a> ContentWalker<'a, '_> {
+    fn walk(&mut self, content: Content, resources: &Resources<'a>) -> Result<(), Box<dyn Error>> {
+        for operation in content.operations {
+            let operands = &operation.operands;
+            match operation.operator.as_str() {
+                "BT" => self.state.begin_text(),
+                "ET" => self.state.end_text(),
+                "Tf" => {
+                    if let Some(size) = operands.get(1).and_then(|size| size.as_float().ok()) {
+                        self.state.set_font_size(size);
+                    }
+                    // Text in a font which isn't named, or which the page doesn't have, can't be decoded, so it is left
+                    // out until the next font is chosen.
+                    self.current_font = None;
+                    let Some(font_id) = operands.first().and_then(|name| name.as_name().ok()) else {
+                        continue;
+                    };
+                    let Some(font) = resources.fonts.get(font_id) else {
+                        let name = String::from_utf8_lossy(font_id).into_owned();
+                        if !self.missing_fonts.contains(&name) {
+                            self.missing_fonts.push(name);
+                        }
+                        continue;
+                    };
+                    let font = self.fonts.get(self.document, font)?;
+                    self.state.set_vertical(font.is_vertical());
+                    self.state
+                        .set_font(font.family.as_deref(), font.bold, font.italic);
+                    self.state.set_font_metrics(font.space_width(), font.height());
+                    self.current_font = Some(font);
+                }
+                "Tj" | "'" | "\"" if self.state.in_text() => {
+                    // The quote operators move to the next line first, and `"` sets the word and character spacing as
+                    // well.
+                    if let [word_spacing, character_spacing, _] = operands.as_slice() {
+                        self.state.set_word_spacing(word_spacing.as_float()?);
+                        self.state
+                            .set_character_spacing(character_spacing.as_float()?);
+                    }
+                    if operation.operator != "Tj" {
+                        self.state.next_line();
+                    }
+                    // Text shown before a font is chosen, or without a string to show, is left out.
+                    let (Some(font), Some(Ok(text))) = (
+                        self.current_font.as_ref(),
+                        operands.last().map(Object::as_str),
+                    ) else {
+                        continue;
+                    };
+                    let decoded = font.decode(text);
+                    self.decoding.check(font, &decoded);
+                    self.state.show(&decoded, font.measure(text));
+                }
+                "TJ" if self.state.in_text() => {
+                    let (Some(font), Some(Ok(elements))) = (
+                        self.current_font.as_ref(),
+                        operands.first().map(Object::as_array),
+                    ) else {
+                        continue;
+                    };
+                    for element in elements {
+                        match element {
+                            Object::String(text, _) => {
+                                let decoded = font.decode(text);
+                                self.decoding.check(font, &decoded);
+                                self.state.show(&decoded, font.measure(text));
+                            }
+                            _ => self.state.adjust(element.as_float()?),
+                        }
+                    }
+                }
+                "q" => self.state.save(),
+                "Q" => self.state.restore(),
+                "cm" => self.state.transform(numbers(&operation)?),
+                "Tm" => self.state.set_matrix(numbers(&operation)?),
+                "Td" => {
+                    let [x, y] = numbers(&operation)?;
+                    self.state.move_line(x, y);
+                }
+                "TD" => {
+                    let [x, y] = numbers(&operation)?;
+                    self.state.move_line_setting_leading(x, y);
+                }
+                "T*" => self.state.next_line(),
+                "Tc" => {
+                    let [spacing] = numbers(&operation)?;
+                    self.state.set_character_spacing(spacing);
+                }
+                "Tw" => {
+                    let [spacing] = numbers(&operation)?;
+                    self.state.set_word_spacing(spacing);
+                }
+                "Tz" => {
+                    let [percentage] = numbers(&operation)?;
+                    self.state.set_horizontal_scaling(percentage);
+                }
+                "Tr" => {
+                    let [mode] = numbers(&operation)?;
+                    self.state.set_render_mode(mode as i64);
+                }
+                "Ts" => {
+                    let [rise] = numbers(&operation)?;
+                    self.state.set_rise(rise);
+                }
+                "TL" => {
+                    let [leading] = numbers(&operation)?;
+                    self.state.set_leading(leading);
+                }
+                "BMC" => self.state.begin_marked_content(MarkedContent {
+                    artifact: matches!(operands.as_slice(), [Object::Name(tag)] if tag == b"Artifact"),
+                    ..MarkedContent::default()
+                }),
+                "BDC" => {
+                    let marked_content = match operands.as_slice() {
+                        [Object::Name(tag), properties] => {
+                            self.marked_content(tag, properties, resources)
+                        }
+                        _ => MarkedContent::default(),
+                    };
+                    self.state.begin_marked_content(marked_content);
+                }
+                "EMC" => self.state.end_marked_content(),
+                // Paths only matter for the rulings of tables, so ones with the wrong operands are skipped rather
+                // than failing the page.
+                "m" => {
+                    if let Ok([x, y]) = numbers(&operation) {
+                        self.state.move_to(x, y);
+                    }
+                }
+                "l" => {
+                    if let Ok([x, y]) = numbers(&operation) {
+                        self.state.line_to(x, y);
+                    }
+                }
+                "c" | "v" | "y" => {
+                    if let [.., x, y] = operands.as_slice() {
+                        if let (Ok(x), Ok(y)) = (x.as_float(), y.as_float()) {
+                            self.state.curve_to(x, y);
+                        }
+                    }
+                }
+                "re" => {
+                    if let Ok([x, y, width, height]) = numbers(&operation) {
+                        self.state.rectangle(x, y, width, height);
+                    }
+                }
+                "h" => self.state.close_path(),
+                "S" => self.state.paint_path(true, false),
+                "s" => {
+                    self.state.close_path();
+                    self.state.paint_path(true, false);
+                }
+                "f" | "F" | "f*" => self.state.paint_path(false, true),
+                "B" | "B*" => self.state.paint_path(true, true),
+                "b" | "b*" => {
+                    self.state.close_path();
+                    self.state.paint_path(true, true);
+                }
+                "n" => self.state.paint_path(false, false),
+                "Do" => {
+                    if let Some(name) = operands.first().and_then(|name| name.as_name().ok()) {
+                        self.draw_form(name, resources)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the properties of marked content started by `BDC`, which are either given there or named in the
+    /// resources.
+    fn marked_content(
+        &self,
+        tag: &[u8],
+        properties: &'a Object,
+        resources: &Resources<'a>,
+    ) -> MarkedContent {
+        let properties = match properties {
+            Object::Name(name) => match resources.properties.get(name) {
+                Some(properties) => properties,
+                None => return MarkedContent::default(),
+            },
+            properties => properties,
+        };
+        let layers = if tag == b"OC" {
+            resources::layer_names(self.document, properties)
+        } else {
+            Vec::new()
+        };
+        let dictionary = self
+            .document
+            .dereference(properties)
+            .and_then(|(_, properties)| properties.as_dict())
+            .ok();
+        let actual_text = dictionary
+            .and_then(|properties| properties.get(b"ActualText").ok())
+            .and_then(|actual_text| self.document.dereference(actual_text).ok())
+            .and_then(|(_, actual_text)| actual_text.as_str().ok())
+            .map(outline::decode_text_string);
+        MarkedContent {
+            layers,
+            actual_text,
+            artifact: tag == b"Artifact",
+            // The identifiers of marked content in a form are the form's own, and refer to something else.
+            identifier: if self.forms.is_empty() {
+                dictionary
+                    .and_then(|properties| properties.get(b"MCID").ok())
+                    .and_then(|identifier| identifier.as_i64().ok())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Handles `Do`, which draws an XObject. Only forms can have text in them, and their content is walked as if it
+    /// were part of the page, transformed by the form's matrix.
+    fn draw_form(&mut self, name: &[u8], resources: &Resources<'a>) -> Result<(), Box<dyn Error>> {
+        let Some(&xobject) = resources.xobjects.get(name) else {
+            return Ok(());
+        };
+        let Ok((id, Object::Stream(form))) = self.document.dereference(xobject) else {
+            return Ok(());
+        };
+        if !matches!(
+            form.dict.get(b"Subtype").and_then(Object::as_name),
+            Ok(b"Form")
+        ) {
+            return Ok(());
+        }
+        if let Some(id) = id {
+            if self.forms.contains(&id) || self.forms.len() >= MAX_FORM_DEPTH {
+                eprintln!(
+                    "warning: skipping a form on page {} which draws itself or is nested too deeply",
+                    self.page_number
+                );
+                return Ok(());
+            }
+        }
+
+        let data = match filters::decode(form, self.budget.max_bytes - self.bytes) {
+            Ok(data) => data,
+            Err(DecodeError::TooBig(_)) => {
+                return Err(BudgetExceeded::Bytes(self.budget.max_bytes).into())
+            }
+            Err(error) => {
+                eprintln!(
+                    "warning: skipping a form on page {} because {}",
+                    self.page_number, error
+                );
+                return Ok(());
+            }
+        };
+        self.bytes += data.len();
+        let content = Content::decode(&content::without_inline_images(&data))?;
+        self.operators += content.operations.len();
+        if self.operators > self.budget.max_operators {
+            return Err(BudgetExceeded::Operators(self.budget.max_operators).into());
+        }
+        let own_resources = form
+            .dict
+            .get(b"Resources")
+            .and_then(|resources| self.document.dereference(resources))
+            .and_then(|(_, resources)| resources.as_dict())
+            .ok()
+            .map(|resources| Resources::new(self.document, resources));
+        let matrix = form
+            .dict
+            .get(b"Matrix")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|matrix| {
+                let numbers = matrix
+                    .iter()
+                    .map(Object::as_float)
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()?;
+                <[f32; 6]>::try_from(numbers).ok()
+            })
+            .unwrap_or([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+        // A form can belong to layers as a whole, as if it were marked content.
+        let layers = form.dict.get(b"OC").map_or_else(
+            |_| Vec::new(),
+            |optional_content| resources::layer_names(self.document, optional_content),
+        );
+        self.state.begin_marked_content(MarkedContent {
+            layers,
+            actual_text: None,
+            artifact: false,
+            identifier: None,
+        });
+        // Drawing a form saves and restores the graphics state around it, font included.
+        self.state.save();
+        self.state.transform(matrix);
+        let font = self.current_font.clone();
+        self.forms.extend(id);
+        let page_resources = self.page_resources;
+        let result = self.walk(content, own_resources.as_ref().unwrap_or(page_resources));
+        if id.is_some() {
+            self.forms.pop();
+        }
+        self.current_font = font;
+        self.state.restore();
+        self.state.end_marked_content();
+        result
+    }
+}
+
+/// The operands of an operation which takes only numbers.
+fn numbers<const N: usize>(operation: &Operation) -> Result<[f32; N], String> {
+    let error = || {
+        format!(
+            "{} takes {} numbers, but was given {:?}",
+            operation.operator, N, operation.operands
+        )
+    };
+    let numbers = operation
+        .operands
+        .iter()
+        .map(Object::as_float)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| error())?;
+    numbers.try_into().map_err(|_| error())
+}
+
+/// Puts a space in text shown by `TJ` where the array moves the text along by more than the threshold, since many
+/// generators leave out the spaces between words and kern them apart instead.
+///
+/// Adjustments are in thousandths of the font size, and negative ones move the text to the right.
+pub fn push_adjustment(text: &mut String, adjustment: f32, space_threshold: f32) {
+    if -adjustment > space_threshold && !text.is_empty() && !text.ends_with(' ') {
+        text.push(' ');
+    }
+}
+
+/// How chunks are joined into rows.
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutOptions {
+    /// How far apart, in the units of the page, chunks can be up or down and still be on the same row, or across the
+    /// page and still be in the same column of vertical text.
+    pub line_tolerance: f32,
+    /// How much of the width of a space the gap between two chunks on the same row has to be for a space to go
+    /// between them.
+    pub word_gap: f32,
+    /// Whether chunks on the same row with a gap of more than [`COLUMN_GAP`] spaces between them stay apart.
+    pub keep_columns: bool,
+    /// Whether rows keep the chunks they are joined from.
+    pub keep_chunks: bool,
+    /// Whether to look for superscripts and subscripts by how far text moves up or down along its row.
+    pub detect_scripts: bool,
+    /// How far text can move up or down from the text before it and still be a superscript or subscript.
+    pub script_offset: ScriptOffset,
+    /// Superscripts and subscripts start at most this many times the height of their text after the text before
+    /// them, so that text further along is in another column instead.
+    pub script_gap: f32,
+}
+
+/// Chunks on the same row with a gap of more than this many spaces between them are in different columns, when
+/// columns are kept.
+pub const COLUMN_GAP: f32 = 2.0;
+
+/// Chunks whose text runs in directions more than this many degrees apart are never on the same row, so that text
+/// drawn across the page at an angle, as watermarks are, isn't joined onto the row it happens to start level with.
+const ANGLE_TOLERANCE: f32 = 1.0;
+
+/// Joins the chunks of a page into rows, with superscripts and subscripts marked up and moved onto their row, and
+/// text drawn twice over to look bold only once.
+pub fn lay_out_rows(
+    rows: &mut Vec<TextChunk>,
+    scripts: &mut ScriptDetector,
+    options: &LayoutOptions,
+) {
+    remove_shadows(rows);
+    merge_text_rows(rows, options);
+    // Documents which raise and lower text with `Ts` have their superscripts and subscripts marked up already, and
+    // guessing at more would only pick out lines which are meant to be where they are. Text laid out in columns stays
+    // where it is drawn, raised or not.
+    if options.detect_scripts && !scripts.uses_rise && !options.keep_columns {
+        scripts.learn(rows);
+        scripts.mark(rows, options);
+        merge_text_rows(rows, options);
+        // A row which starts with a script is on the baseline of the rest of it, while the chunks it was joined from
+        // keep where they are drawn.
+        for row in rows.iter_mut() {
+            if let Some(baseline) = row.baseline.take() {
+                row.y = baseline;
+            }
+        }
+    }
+}
+
+/// Puts the rows of a page in the order they are read in: from the top of the page down, and from left to right
+/// along each line, with any columns of vertical text after them from right to left. Rows up to `line_tolerance`
+/// above or below each other are on the same line.
+///
+/// Rows come from a page which has already been turned the way up it is shown, so the order follows the page's
+/// rotation.
+pub fn sort_into_reading_order(rows: &mut Vec<TextChunk>, line_tolerance: f32) {
+    let (mut horizontal, mut vertical): (Vec<TextChunk>, Vec<TextChunk>) =
+        mem::take(rows).into_iter().partition(|row| !row.vertical);
+    // The sort is stable, so rows at exactly the same place stay in the order they were drawn in.
+    horizontal.sort_by(|a, b| b.y.total_cmp(&a.y));
+    for line in horizontal.chunk_by_mut(|above, below| above.y - below.y <= line_tolerance) {
+        line.sort_by(|a, b| a.x.total_cmp(&b.x));
+    }
+    vertical.sort_by(|a, b| b.x.total_cmp(&a.x).then_with(|| b.y.total_cmp(&a.y)));
+    horizontal.append(&mut vertical);
+    *rows = horizontal;
+}
+
+/// How far text can move up or down from the text before it along its row and still be a superscript or subscript.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptOffset {
+    /// However far text most often moves up along its rows in the document so far, which is usually how far its
+    /// superscripts are raised.
+    Auto,
+    /// A distance in the units of the page.
+    Fixed(f32),
+    /// A fraction of the size of the text before it.
+    FontSize(f32),
+}
+
+/// Detects superscripts and subscripts from how far the text moves up or down within a line, for documents which
+/// don't use `Ts` for them.
+#[derive(Default)]
+pub struct ScriptDetector {
+    /// How often each upward offset between consecutive rows, to the nearest whole unit, has been seen so far in the
+    /// document. The y axis of the page points up, so these are the positive ones.
+    upward_offsets: BTreeMap<i32, usize>,
+    /// Whether any page so far has raised or lowered text with `Ts`.
+    uses_rise: bool,
+}
+
+impl ScriptDetector {
+    /// Adds the offsets between the rows of a page to the statistics, so that later pages benefit from earlier ones.
+    fn learn(&mut self, text_chunks: &[TextChunk]) {
+        // When doing superscripts, the general pattern is that the y position moves upwards rather than downwards, as it
+        // does from one line to the next. We manipulate this to try to find the superscript offset, which we assume is
+        // the most common of these.
+        for pair in text_chunks.windows(2) {
+            // Vertical text moves down its column, which says nothing about scripts.
+            if pair[0].vertical || pair[1].vertical {
+                continue;
+            }
+            let offset = (pair[1].y - pair[0].y).round() as i32;
+            // We are only interested in positive offsets, which mean that it moved upwards.
+            if offset > 0 {
+                *self.upward_offsets.entry(offset).or_insert(0) += 1;
+            }
+        }
+    }
+
+    fn superscript_offset(&self) -> Option<i32> {
+        self.upward_offsets
+            .iter()
+            .max_by_key(|(_, &count)| count)
+            .map(|(&offset, _)| offset)
+    }
+
+    /// Marks up superscripts and subscripts in place, moving them onto the row they belong to.
+    fn mark(&self, text_chunks: &mut [TextChunk], options: &LayoutOptions) {
+        // Until some text has moved upwards there is nothing to compare against, so the page is left as it is.
+        let superscript_offset = match options.script_offset {
+            ScriptOffset::Auto => match self.superscript_offset() {
+                Some(offset) => offset as f32,
+                None => return,
+            },
+            ScriptOffset::Fixed(offset) => offset,
+            ScriptOffset::FontSize(_) => 0.0,
+        };
+        // We assume that if the difference between consecutive chunks is less than or equal to the superscript offset, it is probably a superscript or subscript.
+        let max_offset = |font_size: f32| match options.script_offset {
+            ScriptOffset::FontSize(fraction) => font_size * fraction,
+            _ => superscript_offset,
+        };
+        // The baseline of the row, which is kept when text is moved up or down from it and then left where it is.
+        let mut last_y = 0.0;
+        let mut last_x = 0.0;
+        let mut last_width = 0.0;
+        // The size of the text the next script would belong to.
+        let mut last_font_size = 0.0;
+        // The last chunk, if it was left where it is but off the baseline it was compared with, or started a new one,
+        // since it can be a script in front of the text after it, such as the marker at the start of a footnote.
+        let mut set_apart: Option<usize> = None;
+        for index in 0..text_chunks.len() {
+            let text_chunk = &text_chunks[index];
+            if text_chunk.vertical {
+                continue;
+            }
+            // Scripts come after the text they belong to, so text which starts before the middle of the last chunk is
+            // on a new line. Half of the width leaves room for widths which aren't quite right.
+            if text_chunk.x < last_x + last_width / 2.0 {
+                last_x = text_chunk.x;
+                last_width = text_chunk.width;
+                last_y = text_chunk.y;
+                last_font_size = text_chunk.font_size;
+                set_apart = Some(index);
+                continue;
+            }
+            let offset = text_chunk.y - last_y;
+            let gap = text_chunk.x - (last_x + last_width);
+            last_x = text_chunk.x;
+            last_width = text_chunk.width;
+            // Smaller text just in front of this a little above or below it is a script of this text, rather than this
+            // being a script of it.
+            if let Some(before) = set_apart.take() {
+                let script = &text_chunks[before];
+                let offset = script.y - text_chunk.y;
+                if script.font_size < text_chunk.font_size
+                    && offset.abs() <= max_offset(text_chunk.font_size)
+                    && offset != 0.0
+                    && gap <= script.height * options.script_gap
+                {
+                    last_y = text_chunk.y;
+                    last_font_size = text_chunk.font_size;
+                    mark_script(&mut text_chunks[before], offset, last_y);
+                    continue;
+                }
+            }
+            if offset.abs() <= max_offset(last_font_size) && offset != 0.0 {
+                // Text far along the row is in another column, or apart from the row, and leaves the row's baseline
+                // where it is.
+                if gap <= text_chunk.height * options.script_gap {
+                    mark_script(&mut text_chunks[index], offset, last_y);
+                } else {
+                    set_apart = Some(index);
+                }
+            } else {
+                last_y = text_chunk.y;
+                last_font_size = text_chunk.font_size;
+                if offset != 0.0 {
+                    set_apart = Some(index);
+                }
+            }
+        }
+    }
+}
+
+/// Marks up a chunk as a superscript if it is `offset` above the baseline of its row, or a subscript if it is below,
+/// and puts it on the row.
+fn mark_script(text_chunk: &mut TextChunk, offset: f32, baseline: f32) {
+    let html_tag_name = if offset > 0.0 { "sup" } else { "sub" };
+    text_chunk
+        .text
+        .insert_str(0, &format!("<{}>", html_tag_name));
+    text_chunk.text.push_str(&format!("</{}>", html_tag_name));
+    text_chunk.baseline = Some(baseline);
+}
+
+/// Copies of a chunk this close to it, in the units of the page both across and up, are drawn over it.
+const SHADOW_OFFSET: f32 = 1.0;
+
+/// Leaves out the copies of chunks drawn over them, or a fraction of a point away from them, which some software uses
+/// to make text look bold rather than using a bold font, so that every word doesn't come out twice.
+fn remove_shadows(text_chunks: &mut Vec<TextChunk>) {
+    // Where each piece of text has been drawn so far.
+    let mut drawn: HashMap<String, Vec<(f32, f32)>> = HashMap::new();
+    text_chunks.retain(|text_chunk| {
+        let places = drawn.entry(text_chunk.text.clone()).or_default();
+        let is_shadow = places.iter().any(|&(x, y)| {
+            (text_chunk.x - x).abs() <= SHADOW_OFFSET && (text_chunk.y - y).abs() <= SHADOW_OFFSET
+        });
+        if !is_shadow {
+            places.push((text_chunk.x, text_chunk.y));
+        }
+        !is_shadow
+    });
+}
+
+/// Joins consecutive chunks on the same row, or in the same column of vertical text, together.
+///
+/// Baselines are rarely exactly level, since positions are worked out with fractions, so chunks a little way from each
+/// other still count as being on the same row or in the same column. Documents which draw each word on its own
+/// often leave out the spaces between them, so a space goes wherever the gap between two chunks of a row is wide
+/// enough for one. Chunks with a much wider gap between them are kept apart instead, if columns are being kept.
+///
+/// If chunks are being kept, each row made of more than one keeps copies of them, which are the only copies made.
+fn merge_text_rows(text_chunks: &mut Vec<TextChunk>, options: &LayoutOptions) {
+    let tolerance = options.line_tolerance;
+    // `dedup_by` passes the later chunk first, and removes it if the closure returns true.
+    text_chunks.dedup_by(|text_chunk, last_text_chunk| {
+        // Rows never continue onto the next page, even if the y position happens to line up.
+        if last_text_chunk.page != text_chunk.page {
+            return false;
+        }
+        if (last_text_chunk.angle - text_chunk.angle).abs() > ANGLE_TOLERANCE {
+            return false;
+        }
+        // Vertical text is joined into columns instead, from each chunk to the one below it in the same place across
+        // the page. Columns which happen to start at the same height are still separate.
+        let joins = if last_text_chunk.vertical || text_chunk.vertical {
+            last_text_chunk.vertical
+                && text_chunk.vertical
+                && (last_text_chunk.x - text_chunk.x).abs() <= tolerance
+                && text_chunk.y < last_text_chunk.y
+        } else {
+            (last_text_chunk.row_y() - text_chunk.row_y()).abs() <= tolerance
+        };
+        if joins {
+            let gap = text_chunk.x - (last_text_chunk.x + last_text_chunk.width);
+            if !last_text_chunk.vertical
+                && options.keep_columns
+                && gap > last_text_chunk.space_width * COLUMN_GAP
+            {
+                return false;
+            }
+            // A row which is a single chunk is its own only chunk, until something is joined onto it.
+            if options.keep_chunks {
+                if last_text_chunk.chunks.is_empty() {
+                    let first_chunk = last_text_chunk.clone();
+                    last_text_chunk.chunks.push(first_chunk);
+                }
+                if text_chunk.chunks.is_empty() {
+                    last_text_chunk.chunks.push(text_chunk.clone());
+                } else {
+                    last_text_chunk.chunks.append(&mut text_chunk.chunks);
+                }
+            }
+            if !last_text_chunk.vertical {
+                // Text which already has a space, or has no text at all, needs no more.
+                if gap > last_text_chunk.space_width * options.word_gap
+                    && !plain_text(&last_text_chunk.text).ends_with(char::is_whitespace)
+                    && !plain_text(&text_chunk.text)
+                        .chars()
+                        .next()
+                        .is_none_or(char::is_whitespace)
+                {
+                    last_text_chunk.text.push(' ');
+                }
+                last_text_chunk.width = last_text_chunk
+                    .width
+                    .max(text_chunk.x + text_chunk.width - last_text_chunk.x);
+            } else {
+                last_text_chunk.width = last_text_chunk
+                    .width
+                    .max(last_text_chunk.y - text_chunk.y + text_chunk.width);
+            }
+            last_text_chunk.height = last_text_chunk.height.max(text_chunk.height);
+            push_styled(&mut last_text_chunk.text, &text_chunk.text);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(text: &str, x: f32, y: f32, width: f32, font_size: f32) -> TextChunk {
+        TextChunk {
+            text: text.to_owned(),
+            page: 1,
+            x,
+            y,
+            width,
+            height: font_size,
+            space_width: font_size / 4.0,
+            vertical: false,
+            angle: 0.0,
+            marked_content: None,
+            heading: None,
+            font_family: None,
+            font_size,
+            bold: false,
+            italic: false,
+            chunks: Vec::new(),
+            baseline: None,
+        }
+    }
+
+    fn lay_out(mut rows: Vec<TextChunk>) -> Vec<String> {
+        let options = ExtractOptions {
+            script_offset: ScriptOffset::Fixed(4.0),
+            ..ExtractOptions::default()
+        }
+        .layout_options();
+        lay_out_rows(&mut rows, &mut ScriptDetector::default(), &options);
+        sort_into_reading_order(&mut rows, options.line_tolerance);
+        rows.into_iter().map(|row| row.text).collect()
+    }
+
+    #[test]
+    fn marks_superscript_after_its_text() {
+        let rows = lay_out(vec![
+            chunk("E = mc", 72.0, 686.0, 40.0, 12.0),
+            chunk("2", 112.0, 690.0, 4.0, 8.0),
+            chunk(" is famous.", 116.0, 686.0, 50.0, 12.0),
+        ]);
+        assert_eq!(rows, ["E = mc<sup>2</sup> is famous."]);
+    }
+
+    #[test]
+    fn keeps_baseline_when_raised_text_is_too_far_along() {
+        // A footnote marker in the margin, well past the end of the line before it and just in front of the text it
+        // belongs to, drawn before the rest of its own line.
+        let rows = lay_out(vec![
+            chunk("used used these sample", 72.0, 668.0, 306.0, 12.0),
+            chunk("4", 400.0, 658.0, 4.0, 8.0),
+            chunk("by more or", 410.0, 654.0, 60.0, 12.0),
+            chunk("has as value", 72.0, 654.0, 300.0, 12.0),
+        ]);
+        assert_eq!(rows[0], "used used these sample");
+        assert!(rows[1..].iter().all(|row| !row.contains("<sub>")));
+        assert!(rows[1..].iter().any(|row| row.contains("<sup>4</sup>")));
+    }
+
+    #[test]
+    fn keeps_where_scripts_are_drawn() {
+        let options = ExtractOptions {
+            script_offset: ScriptOffset::Fixed(5.0),
+            keep_chunks: true,
+            ..ExtractOptions::default()
+        }
+        .layout_options();
+        let mut rows = vec![
+            chunk("H", 72.0, 700.0, 8.0, 12.0),
+            chunk("2", 80.0, 696.0, 4.0, 8.0),
+            chunk("O", 84.0, 700.0, 8.0, 12.0),
+            chunk("2", 92.0, 705.0, 4.0, 8.0),
+        ];
+        lay_out_rows(&mut rows, &mut ScriptDetector::default(), &options);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].text, "H<sub>2</sub>O<sup>2</sup>");
+        assert_eq!(rows[0].y, 700.0);
+        let heights: Vec<f32> = rows[0].chunks.iter().map(|chunk| chunk.y).collect();
+        assert_eq!(heights, [700.0, 696.0, 700.0, 705.0]);
+    }
+
+    /// Extracts the text of a page drawn with `content`, with fonts called `F1` and `F2`. Having two fonts keeps the
+    /// page off the fast path for pages in one font, so that the content is walked.
+    fn extract_content(content: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        use lopdf::{dictionary, Stream};
+
+        let mut document = Document::with_version("1.7");
+        let font = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let content = document.add_object(Stream::new(dictionary! {}, content.to_vec()));
+        let pages = document.new_object_id();
+        let page = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font, "F2" => font } },
+            "Contents" => content,
+        });
+        document.objects.insert(
+            pages,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages });
+        document.trailer.set("Root", catalog);
+        let mut extractor = TextExtractor::new(&document, &ExtractOptions::default());
+        let page = extractor.extract_page(1, page)?;
+        Ok(page.rows.into_iter().map(|row| row.text).collect())
+    }
+
+    #[test]
+    fn skips_malformed_text_operators() {
+        for content in [
+            &b"BT 72 700 Td (no font) Tj /F1 12 Tf (after) Tj ET"[..],
+            b"BT /F1 12 Tf 72 700 Td Tj (after) Tj ET",
+            b"BT /F1 12 Tf 72 700 Td 5 Tj (after) Tj ET",
+            b"BT Tf 72 700 Td (hidden) Tj /F1 12 Tf (after) Tj ET",
+            b"BT /F1 12 Tf 72 700 Td TJ [(after)] TJ ET",
+            b"BT /F9 12 Tf 72 700 Td (hidden) Tj /F1 12 Tf (after) Tj ET",
+        ] {
+            assert_eq!(extract_content(content).unwrap(), ["after"]);
+        }
+    }
+
+    #[test]
+    fn leaves_text_in_the_next_column_alone() {
+        let rows = lay_out(vec![
+            chunk("left column", 72.0, 700.0, 100.0, 12.0),
+            chunk("right column", 300.0, 703.0, 100.0, 12.0),
+        ]);
+        assert!(rows.iter().all(|row| !row.contains("<su")));
+    }
+}
@@ -0,0 +1,616 @@
+//! Follows where a page's content puts its text, and gathers the text it shows into chunks.
+//!
+//! Text is positioned by the text matrix, which `Tm` sets outright and `Td`, `TD` and `T*` move on from the start of
+//! the current line, so that a whole paragraph can be drawn inside one `BT` and `ET` with only the distance from one
+//! line to the next. Each time the text is moved, what was shown since the last move becomes a chunk of its own, at
+//! the place it was drawn.
+//!
+//! Text space is then mapped onto the page by the current transformation matrix, which `cm` changes and `q` and `Q`
+//! save and restore along with the rest of the graphics state, so that text in scaled, moved or rotated content ends
+//! up where it is actually drawn.
+//!
+//! Showing text moves the text matrix along by the width of the glyphs, with the character and word spacing from
+//! `Tc` and `Tw` added and the whole scaled by `Tz`, so that text shown after other text on the same line starts where
+//! the other text ended.
+//!
+//! Text drawn with text rendering mode 3, set by `Tr`, is invisible, as in the OCR layers of scans, and can be left out.
+//! So can the text in marked content which belongs to an optional content group, or layer, that isn't wanted.
+//!
+//! Marked content tagged as an `Artifact` is not part of the document's real content, such as the running headers,
+//! footers and page numbers of tagged documents, and is left out too unless artifacts are kept.
+//!
+//! Marked content with an `ActualText` says what the text drawn inside it really is, such as the whole of a word that
+//! is drawn hyphenated or a drop cap drawn as a picture, and that text is used in place of what was drawn.
+//!
+//! Each piece of marked content with an identifier, which is how the structure tree of a tagged document refers to
+//! the content, gets chunks of its own, so that the text can be put in the order the structure reads it in.
+//!
+//! Text raised or lowered with `Ts` is marked up as a superscript or subscript, and kept on the line it is part of.
+//!
+//! Fonts with a vertical CMap write downwards instead, so their text moves down the page by the heights of the glyphs,
+//! and their chunks are marked as vertical so that the layout can join them into columns rather than rows.
+//!
+//! Paths are followed too, as far as the straight lines they stroke and the thin rectangles they fill, which are the
+//! rulings tables are drawn with.
+
+use std::{mem, rc::Rc};
+
+use crate::{
+    decorations,
+    font::Measurement,
+    tables::{self, Ruling},
+    text::{self, TextChunk},
+};
+
+/// A matrix in the form PDF uses, `[a b c d e f]`, where the last two numbers are the translation.
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Marks up text with a tag, leaving any spaces at either end of it outside, since Markdown doesn't mark up text which
+/// starts or ends with a space.
+pub fn wrap(text: &str, tag: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_owned();
+    }
+    let start = text.len() - text.trim_start().len();
+    format!(
+        "{}<{}>{}</{}>{}",
+        &text[..start],
+        tag,
+        trimmed,
+        tag,
+        &text[start + trimmed.len()..]
+    )
+}
+
+/// Applies `first` and then `second`.
+fn multiply(first: Matrix, second: Matrix) -> Matrix {
+    let [a, b, c, d, e, f] = first;
+    let [a2, b2, c2, d2, e2, f2] = second;
+    [
+        a * a2 + b * c2,
+        a * b2 + b * d2,
+        c * a2 + d * c2,
+        c * b2 + d * d2,
+        e * a2 + f * c2 + e2,
+        e * b2 + f * d2 + f2,
+    ]
+}
+
+/// The text drawn on a page.
+#[derive(Default)]
+pub struct PageText {
+    pub chunks: Vec<TextChunk>,
+    /// The lines drawn straight across or up the page.
+    pub rulings: Vec<Ruling>,
+    /// Whether any of the text was raised or lowered with `Ts`, in which case superscripts and subscripts are already
+    /// marked up.
+    pub uses_rise: bool,
+}
+
+/// What to pick out of the text shown on a page.
+#[derive(Clone, Debug)]
+pub struct TextOptions {
+    /// How far, in thousandths of the font size, a `TJ` array has to move its text along for a space to go there.
+    pub space_threshold: f32,
+    /// Whether to keep text which is drawn invisibly.
+    pub include_invisible: bool,
+    /// The names of the layers to keep the text of, leaving out everything else, or all text if there are none.
+    pub layers: Vec<String>,
+    /// The names of the layers to leave the text of out.
+    pub excluded_layers: Vec<String>,
+    /// Whether to use the `ActualText` of marked content instead of the text drawn inside it.
+    pub use_actual_text: bool,
+    /// Whether to keep the text of marked content tagged as an artifact, such as running headers and page numbers.
+    pub include_artifacts: bool,
+    /// Whether to mark up text in bold and italic fonts with `<strong>` and `<em>`, and text with lines drawn under or
+    /// through it with `<u>` and `<s>`, as superscripts and subscripts are marked up.
+    pub mark_styles: bool,
+    /// Whether to mark up the text inside links with `<a href="...">`.
+    pub mark_links: bool,
+}
+
+/// What the properties of a level of marked content say about the text inside it.
+#[derive(Default)]
+pub struct MarkedContent {
+    /// The layers it belongs to, if it is optional content.
+    pub layers: Vec<String>,
+    /// The text that stands in for what it draws.
+    pub actual_text: Option<String>,
+    /// Whether it is tagged as an artifact, which isn't part of the document's real content.
+    pub artifact: bool,
+    /// The marked content identifier, or MCID, that the structure tree refers to it by.
+    pub identifier: Option<i64>,
+}
+
+/// The text rendering mode which neither fills nor strokes the glyphs, so that the text is there but can't be seen.
+const INVISIBLE_RENDER_MODE: i64 = 3;
+
+/// The parts of the graphics state which affect where text goes and what it looks like, all of which `q` saves and `Q`
+/// restores.
+#[derive(Clone)]
+struct GraphicsState {
+    /// The current transformation matrix, from user space to the page.
+    transformation: Matrix,
+    /// How far apart lines are, which `T*` moves down by.
+    leading: f32,
+    font_size: f32,
+    /// Added after every character, and set by `Tc`.
+    character_spacing: f32,
+    /// Added after every space, and set by `Tw`.
+    word_spacing: f32,
+    /// How much wider the text is drawn than its glyphs are, set as a percentage by `Tz`.
+    horizontal_scaling: f32,
+    /// How far `Ts` has moved the text up from the line, or down if it is negative.
+    rise: f32,
+    /// Whether the current font writes from top to bottom.
+    vertical: bool,
+    /// The family of the current font, if it has one.
+    font_family: Option<Rc<str>>,
+    /// Whether the current font is bold, and whether it is italic.
+    bold: bool,
+    italic: bool,
+    /// How wide a space is in the current font, in thousandths of the font size.
+    space_width: f32,
+    /// How tall the glyphs of the current font are, in thousandths of the font size.
+    font_height: f32,
+    /// How glyphs are drawn, set by `Tr`.
+    render_mode: i64,
+}
+
+pub struct TextState {
+    page_number: u32,
+    options: TextOptions,
+    /// Whether the content is between `BT` and `ET`, since text shown outside of them isn't drawn.
+    in_text: bool,
+    graphics: GraphicsState,
+    saved_graphics: Vec<GraphicsState>,
+    matrix: Matrix,
+    /// Where the current line started, which the line moving operators go from.
+    line_matrix: Matrix,
+    uses_rise: bool,
+    /// Each level of marked content the content is in, innermost last.
+    marked_content: Vec<MarkedContent>,
+    current_text: String,
+    /// Where the text shown since the last move starts, if anything has been shown.
+    start: Option<(f32, f32)>,
+    chunks: Vec<TextChunk>,
+    /// The pieces of the path being built, each a list of points on the page and whether it has been closed.
+    path: Vec<(Vec<(f32, f32)>, bool)>,
+    rulings: Vec<Ruling>,
+}
+
+impl TextState {
+    pub fn new(page_number: u32, options: TextOptions) -> Self {
+        Self {
+            page_number,
+            options,
+            in_text: false,
+            graphics: GraphicsState {
+                transformation: IDENTITY,
+                leading: 0.0,
+                font_size: 0.0,
+                character_spacing: 0.0,
+                word_spacing: 0.0,
+                horizontal_scaling: 1.0,
+                rise: 0.0,
+                vertical: false,
+                font_family: None,
+                bold: false,
+                italic: false,
+                space_width: 0.0,
+                font_height: 0.0,
+                render_mode: 0,
+            },
+            saved_graphics: Vec::new(),
+            matrix: IDENTITY,
+            line_matrix: IDENTITY,
+            uses_rise: false,
+            marked_content: Vec::new(),
+            current_text: String::new(),
+            start: None,
+            chunks: Vec::new(),
+            path: Vec::new(),
+            rulings: Vec::new(),
+        }
+    }
+
+    /// Handles `BT`, which starts the text again from the origin.
+    pub fn begin_text(&mut self) {
+        self.end_chunk();
+        self.in_text = true;
+        self.matrix = IDENTITY;
+        self.line_matrix = IDENTITY;
+    }
+
+    /// Handles `ET`.
+    pub fn end_text(&mut self) {
+        self.end_chunk();
+        self.in_text = false;
+    }
+
+    pub fn in_text(&self) -> bool {
+        self.in_text
+    }
+
+    /// Handles `q`.
+    pub fn save(&mut self) {
+        self.saved_graphics.push(self.graphics.clone());
+    }
+
+    /// Handles `Q`, which is ignored if nothing was saved, as happens in content that has been cut and pasted together.
+    pub fn restore(&mut self) {
+        if let Some(graphics) = self.saved_graphics.pop() {
+            if graphics.rise != self.graphics.rise || graphics.vertical != self.graphics.vertical {
+                self.end_chunk();
+            }
+            self.graphics = graphics;
+        }
+    }
+
+    /// Handles `cm`, which transforms everything drawn after it by a matrix on top of whatever transform is in place.
+    pub fn transform(&mut self, matrix: Matrix) {
+        self.graphics.transformation = multiply(matrix, self.graphics.transformation);
+    }
+
+    /// Handles `Tm`.
+    pub fn set_matrix(&mut self, matrix: Matrix) {
+        self.end_chunk();
+        self.matrix = matrix;
+        self.line_matrix = matrix;
+    }
+
+    /// Handles `Td`, which moves to the start of a line this far from the start of the current one.
+    pub fn move_line(&mut self, x: f32, y: f32) {
+        self.end_chunk();
+        self.line_matrix = multiply([1.0, 0.0, 0.0, 1.0, x, y], self.line_matrix);
+        self.matrix = self.line_matrix;
+    }
+
+    /// Handles `TD`, which moves like `Td` and makes the distance down it moved the leading for later lines.
+    pub fn move_line_setting_leading(&mut self, x: f32, y: f32) {
+        self.graphics.leading = -y;
+        self.move_line(x, y);
+    }
+
+    /// Handles `T*`.
+    pub fn next_line(&mut self) {
+        self.move_line(0.0, -self.graphics.leading);
+    }
+
+    /// Handles `TL`.
+    pub fn set_leading(&mut self, leading: f32) {
+        self.graphics.leading = leading;
+    }
+
+    /// Handles the size given to `Tf`. Text in another size is a chunk of its own, so that each chunk has the size it
+    /// is in.
+    pub fn set_font_size(&mut self, font_size: f32) {
+        if font_size != self.graphics.font_size {
+            self.end_chunk();
+            self.graphics.font_size = font_size;
+        }
+    }
+
+    /// Handles the writing mode of the font chosen with `Tf`.
+    pub fn set_vertical(&mut self, vertical: bool) {
+        if vertical != self.graphics.vertical {
+            self.end_chunk();
+            self.graphics.vertical = vertical;
+        }
+    }
+
+    /// Handles the family and style of the font chosen with `Tf`. Text in another font is a chunk of its own, so that
+    /// each chunk has the font it is in and only the text in a style is marked up as being in it.
+    pub fn set_font(&mut self, family: Option<&str>, bold: bool, italic: bool) {
+        if family != self.graphics.font_family.as_deref()
+            || (bold, italic) != (self.graphics.bold, self.graphics.italic)
+        {
+            self.end_chunk();
+            self.graphics.font_family = family.map(Rc::from);
+            self.graphics.bold = bold;
+            self.graphics.italic = italic;
+        }
+    }
+
+    /// Handles the width of a space and the height of the glyphs in the font chosen with `Tf`, both in thousandths of
+    /// the font size.
+    pub fn set_font_metrics(&mut self, space_width: f32, height: f32) {
+        self.graphics.space_width = space_width;
+        self.graphics.font_height = height;
+    }
+
+    /// Handles `Tc`.
+    pub fn set_character_spacing(&mut self, spacing: f32) {
+        self.graphics.character_spacing = spacing;
+    }
+
+    /// Handles `Tw`.
+    pub fn set_word_spacing(&mut self, spacing: f32) {
+        self.graphics.word_spacing = spacing;
+    }
+
+    /// Handles `Tz`.
+    pub fn set_horizontal_scaling(&mut self, percentage: f32) {
+        self.graphics.horizontal_scaling = percentage / 100.0;
+    }
+
+    /// Handles `Tr`.
+    pub fn set_render_mode(&mut self, mode: i64) {
+        self.graphics.render_mode = mode;
+    }
+
+    /// Handles `BMC` and `BDC`, which start marked content.
+    pub fn begin_marked_content(&mut self, mut marked_content: MarkedContent) {
+        if !self.options.use_actual_text {
+            marked_content.actual_text = None;
+        }
+        if marked_content.identifier.is_some() {
+            self.end_chunk();
+        }
+        self.marked_content.push(marked_content);
+    }
+
+    /// Handles `EMC`, which is ignored if no marked content was started. The actual text of the marked content, if it
+    /// has any, goes in the chunk the text drawn inside it started in.
+    pub fn end_marked_content(&mut self) {
+        let actual_text = self
+            .marked_content
+            .last_mut()
+            .and_then(|marked_content| marked_content.actual_text.take());
+        if let Some(actual_text) = actual_text {
+            // Marked content with actual text of its own around this takes the place of it as well.
+            if !self.in_actual_text() && self.is_kept() {
+                if self.start.is_none() {
+                    self.start = Some(self.position());
+                }
+                self.current_text.push_str(&actual_text);
+            }
+        }
+        if self
+            .marked_content
+            .last()
+            .is_some_and(|marked_content| marked_content.identifier.is_some())
+        {
+            self.end_chunk();
+        }
+        self.marked_content.pop();
+    }
+
+    /// Handles `Ts`.
+    pub fn set_rise(&mut self, rise: f32) {
+        self.end_chunk();
+        self.graphics.rise = rise;
+        if rise != 0.0 {
+            self.uses_rise = true;
+        }
+    }
+
+    /// Handles `m`, which starts a new piece of the path.
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        let point = self.user_to_page(x, y);
+        self.path.push((vec![point], false));
+    }
+
+    /// Handles `l`.
+    pub fn line_to(&mut self, x: f32, y: f32) {
+        let point = self.user_to_page(x, y);
+        match self.path.last_mut() {
+            Some((points, _)) => points.push(point),
+            None => self.path.push((vec![point], false)),
+        }
+    }
+
+    /// Handles `c`, `v` and `y`, which draw curves ending at a point. Curves are never rulings, so the path carries on
+    /// from the end of the curve as if it started there.
+    pub fn curve_to(&mut self, x: f32, y: f32) {
+        self.move_to(x, y);
+    }
+
+    /// Handles `re`, which adds a closed rectangle to the path.
+    pub fn rectangle(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let corners = [
+            (x, y),
+            (x + width, y),
+            (x + width, y + height),
+            (x, y + height),
+        ];
+        let points = corners.map(|(x, y)| self.user_to_page(x, y)).to_vec();
+        self.path.push((points, true));
+    }
+
+    /// Handles `h`.
+    pub fn close_path(&mut self) {
+        if let Some((_, closed)) = self.path.last_mut() {
+            *closed = true;
+        }
+    }
+
+    /// Handles the operators which end the path, stroking it, filling it, both or neither, as `n` does.
+    pub fn paint_path(&mut self, stroke: bool, fill: bool) {
+        let path = mem::take(&mut self.path);
+        self.rulings
+            .extend(tables::path_rulings(&path, stroke, fill));
+    }
+
+    /// Where a point in user space is on the page.
+    fn user_to_page(&self, x: f32, y: f32) -> (f32, f32) {
+        let [.., x, y] = multiply([1.0, 0.0, 0.0, 1.0, x, y], self.graphics.transformation);
+        (x, y)
+    }
+
+    /// Adds decoded text to the current chunk, and moves past it.
+    pub fn show(&mut self, text: &str, measurement: Measurement) {
+        // Text which is left out or replaced still moves the text after it along.
+        if self.in_actual_text() {
+            if self.start.is_none() && self.is_kept() {
+                self.start = Some(self.position());
+            }
+        } else if self.is_kept() {
+            if self.start.is_none() {
+                self.start = Some(self.position());
+            }
+            self.current_text.push_str(text);
+        }
+        let spacing = measurement.codes as f32 * self.graphics.character_spacing
+            + measurement.spaces as f32 * self.graphics.word_spacing;
+        if self.graphics.vertical {
+            // Horizontal scaling doesn't apply to text written downwards.
+            self.advance_down(measurement.height / 1000.0 * self.graphics.font_size + spacing);
+        } else {
+            let advance = measurement.width / 1000.0 * self.graphics.font_size + spacing;
+            self.advance(advance * self.graphics.horizontal_scaling);
+        }
+    }
+
+    /// Handles a number in a `TJ` array, which moves the text back by that many thousandths of the font size, or, in
+    /// vertical text, down by that many.
+    pub fn adjust(&mut self, adjustment: f32) {
+        let space_threshold = self.options.space_threshold;
+        let distance = adjustment / 1000.0 * self.graphics.font_size;
+        if self.graphics.vertical {
+            text::push_adjustment(&mut self.current_text, -adjustment, space_threshold);
+            self.advance_down(distance);
+        } else {
+            text::push_adjustment(&mut self.current_text, adjustment, space_threshold);
+            self.advance(-distance * self.graphics.horizontal_scaling);
+        }
+    }
+
+    /// The chunks of text shown on the page, in the order they were drawn.
+    pub fn finish(mut self) -> PageText {
+        // Marked content left open at the end of the page still has its actual text.
+        while !self.marked_content.is_empty() {
+            self.end_marked_content();
+        }
+        self.end_chunk();
+        if self.options.mark_styles {
+            decorations::mark(&mut self.chunks, &self.rulings);
+        }
+        PageText {
+            chunks: self.chunks,
+            rulings: self.rulings,
+            uses_rise: self.uses_rise,
+        }
+    }
+
+    /// Whether text shown now is wanted, which it isn't if it is invisible, an artifact or in layers which are left
+    /// out.
+    fn is_kept(&self) -> bool {
+        if !self.options.include_invisible && self.graphics.render_mode == INVISIBLE_RENDER_MODE {
+            return false;
+        }
+        if !self.options.include_artifacts
+            && self
+                .marked_content
+                .iter()
+                .any(|marked_content| marked_content.artifact)
+        {
+            return false;
+        }
+        let mut layers = self
+            .marked_content
+            .iter()
+            .flat_map(|marked_content| &marked_content.layers);
+        if layers
+            .clone()
+            .any(|layer| self.options.excluded_layers.contains(layer))
+        {
+            return false;
+        }
+        self.options.layers.is_empty() || layers.any(|layer| self.options.layers.contains(layer))
+    }
+
+    /// Whether the text shown now is inside marked content with actual text, which takes its place.
+    fn in_actual_text(&self) -> bool {
+        self.marked_content
+            .iter()
+            .any(|marked_content| marked_content.actual_text.is_some())
+    }
+
+    /// Moves the text along the line, leaving the start of the line where it is.
+    fn advance(&mut self, distance: f32) {
+        self.matrix = multiply([1.0, 0.0, 0.0, 1.0, distance, 0.0], self.matrix);
+    }
+
+    /// Moves vertical text down its column.
+    fn advance_down(&mut self, distance: f32) {
+        self.matrix = multiply([1.0, 0.0, 0.0, 1.0, 0.0, -distance], self.matrix);
+    }
+
+    /// How long a distance along the line in text space is on the page.
+    fn page_distance(&self, distance: f32) -> f32 {
+        let [a, b, ..] = multiply(self.matrix, self.graphics.transformation);
+        (distance * a).hypot(distance * b)
+    }
+
+    /// Where the text would be drawn next, in the units of the page.
+    fn position(&self) -> (f32, f32) {
+        let [.., x, y] = multiply(self.matrix, self.graphics.transformation);
+        (x, y)
+    }
+
+    fn end_chunk(&mut self) {
+        // Text replaced by actual text is all one piece, however it was drawn, so it stays in the chunk it started in.
+        if self.in_actual_text() {
+            return;
+        }
+        if let Some((x, y)) = self.start.take() {
+            let (end_x, end_y) = self.position();
+            let [a, b, ..] = multiply(self.matrix, self.graphics.transformation);
+            let space_width = self.page_distance(
+                self.graphics.space_width / 1000.0
+                    * self.graphics.font_size
+                    * self.graphics.horizontal_scaling,
+            );
+            let height =
+                self.page_distance(self.graphics.font_height / 1000.0 * self.graphics.font_size);
+            let mut text = mem::take(&mut self.current_text);
+            // Showing an empty string draws nothing, and isn't a row of its own.
+            if text.is_empty() {
+                return;
+            }
+            if self.graphics.rise != 0.0 {
+                let html_tag_name = if self.graphics.rise > 0.0 {
+                    "sup"
+                } else {
+                    "sub"
+                };
+                text = format!("<{}>{}</{}>", html_tag_name, text, html_tag_name);
+            }
+            if self.options.mark_styles {
+                if self.graphics.italic {
+                    text = wrap(&text, "em");
+                }
+                if self.graphics.bold {
+                    text = wrap(&text, "strong");
+                }
+            }
+            self.chunks.push(TextChunk {
+                text,
+                page: self.page_number,
+                x,
+                y,
+                width: (end_x - x).hypot(end_y - y),
+                height,
+                space_width,
+                vertical: self.graphics.vertical,
+                angle: b.atan2(a).to_degrees(),
+                marked_content: self
+                    .marked_content
+                    .iter()
+                    .rev()
+                    .find_map(|marked_content| marked_content.identifier),
+                heading: None,
+                font_family: self.graphics.font_family.as_deref().map(str::to_owned),
+                font_size: self.page_distance(self.graphics.font_size),
+                bold: self.graphics.bold,
+                italic: self.graphics.italic,
+                chunks: Vec::new(),
+                baseline: None,
+            });
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Makes a table of contents for documents which don't come with an outline, from the headings found in their text.
+//!
+//! The headings are the ones found by the size of their text, at the levels they are found at, with a heading which
+//! wraps onto a second row as one entry. Without bigger text to go on, a heading is taken to be a short row at the top
+//! of its page or after a bigger gap than usual, which starts with a capital letter or a number and doesn't end like a
+//! sentence does. Rows which turn up on many pages, such as running heads, are left out. Headings numbered like `2.1`
+//! are nested by their numbering, and all the others are at the top level.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::{
+    headings::{self, MAX_HEADING_LENGTH},
+    outline::Bookmark,
+    text::{self, TextChunk},
+};
+
+/// Rows longer than this fraction of the page's longest row are taken to be part of a paragraph.
+const SHORT_ROW: f32 = 0.6;
+
+/// Gaps this many times bigger than the usual distance between rows set off a heading.
+const HEADING_GAP: f32 = 1.5;
+
+/// Uses the outline as the table of contents if there is one, and otherwise finds the headings in the rows.
+pub fn table_of_contents(outline: Vec<Bookmark>, rows: &[TextChunk]) -> Vec<Bookmark> {
+    if outline.is_empty() {
+        let mut rows = rows.to_vec();
+        if rows.iter().all(|row| row.heading.is_none()) {
+            headings::mark_heading_rows(&mut rows);
+        }
+        detect_headings(&rows)
+    } else {
+        outline
+    }
+}
+
+/// Finds the headings in the rows, in document order: the rows marked as headings if there are any, and otherwise the
+/// rows which look like headings.
+pub fn detect_headings(rows: &[TextChunk]) -> Vec<Bookmark> {
+    if rows.iter().any(|row| row.heading.is_some()) {
+        return marked_headings(rows);
+    }
+    let numbering = Regex::new(r"^(\d+(?:\.\d+)*)\.?\s+\S").unwrap();
+    let mut candidates = Vec::new();
+    let mut page_count = 0;
+    for page_rows in rows.chunk_by(|a, b| a.page == b.page) {
+        page_count += 1;
+        let texts: Vec<String> = page_rows
+            .iter()
+            .map(|row| text::plain_text(&row.text).trim().to_owned())
+            .collect();
+        let longest_row = texts
+            .iter()
+            .map(|text| text.chars().count())
+            .max()
+            .unwrap_or(0);
+        let usual_gap = page_rows
+            .windows(2)
+            .map(|pair| (pair[1].y - pair[0].y).abs())
+            .filter(|&gap| gap > 0.0)
+            .min_by(f32::total_cmp)
+            .unwrap_or(0.0);
+        for (index, text) in texts.iter().enumerate() {
+            let length = text.chars().count();
+            let is_short =
+                length <= MAX_HEADING_LENGTH && (length as f32) < longest_row as f32 * SHORT_ROW;
+            let is_set_off = index == 0
+                || (page_rows[index].y - page_rows[index - 1].y).abs() > usual_gap * HEADING_GAP;
+            let looks_like_heading = text
+                .chars()
+                .next()
+                .is_some_and(|first| first.is_uppercase() || first.is_ascii_digit())
+                && text.chars().any(char::is_alphabetic)
+                && !text.ends_with(['.', ',', ';', ':', '!', '?']);
+            // The last row of a page has nothing after it to be the heading of.
+            if is_short && is_set_off && looks_like_heading && index + 1 < texts.len() {
+                candidates.push((text.clone(), page_rows[index].page));
+            }
+        }
+    }
+
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    for (title, _) in &candidates {
+        *occurrences.entry(title).or_default() += 1;
+    }
+    // Running heads and footers repeat on every page or so, while real headings are rarely repeated that often.
+    let is_repeated = |title: &str| page_count >= 3 && occurrences[title] * 2 > page_count;
+    candidates
+        .iter()
+        .filter(|(title, _)| !is_repeated(title))
+        .map(|(title, page)| Bookmark {
+            title: title.clone(),
+            level: numbering
+                .captures(title)
+                .map_or(1, |captures| captures[1].split('.').count()),
+            page: Some(*page),
+        })
+        .collect()
+}
+
+/// The rows marked as headings, with the rows of a heading which wraps joined into one.
+fn marked_headings(rows: &[TextChunk]) -> Vec<Bookmark> {
+    let mut bookmarks: Vec<Bookmark> = Vec::new();
+    let mut last_level = None;
+    for row in rows {
+        let title = text::plain_text(headings::heading_text(&row.text))
+            .trim()
+            .to_owned();
+        match (row.heading, bookmarks.last_mut()) {
+            (Some(level), Some(bookmark))
+                if last_level == Some(level) && bookmark.page == Some(row.page) =>
+            {
+                text::join_line(&mut bookmark.title, &title);
+            }
+            (Some(level), _) => bookmarks.push(Bookmark {
+                title,
+                level: usize::from(level),
+                page: Some(row.page),
+            }),
+            (None, _) => {}
+        }
+        last_level = row.heading;
+    }
+    bookmarks
+}
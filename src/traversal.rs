@@ -0,0 +1,117 @@
+//! Walks over the trees and chains of objects in a document without trusting them to be well formed.
+//!
+//! Malicious files can link these objects into loops, or nest them deeply enough to exhaust the stack, so everything
+//! here keeps track of what it has visited and how deep it is, and fails with an error rather than hanging or
+//! crashing.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt::Display,
+};
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// How deeply trees of objects may be nested before the document is rejected.
+pub const MAX_DEPTH: usize = 256;
+
+#[derive(Debug)]
+pub enum TraversalError {
+    /// The object was reached twice, so following it again could loop forever.
+    Revisited(ObjectId),
+    TooDeep,
+}
+
+impl Display for TraversalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraversalError::Revisited((number, generation)) => write!(
+                f,
+                "object {} {} R was reached more than once, which would loop forever",
+                number, generation
+            ),
+            TraversalError::TooDeep => write!(f, "objects are nested more than {} deep", MAX_DEPTH),
+        }
+    }
+}
+
+impl Error for TraversalError {}
+
+/// Walks the page tree, returning the id of each page keyed by its page number.
+pub fn pages(document: &Document) -> Result<BTreeMap<u32, ObjectId>, TraversalError> {
+    let mut pages = BTreeMap::new();
+    let Ok(root) = document
+        .catalog()
+        .and_then(|catalog| catalog.get(b"Pages"))
+        .and_then(Object::as_reference)
+    else {
+        return Ok(pages);
+    };
+    let mut visited = BTreeSet::new();
+    // Each entry is a node still to be visited, along with how deep in the tree it is.
+    let mut pending = vec![(root, 0)];
+    while let Some((id, depth)) = pending.pop() {
+        if depth > MAX_DEPTH {
+            return Err(TraversalError::TooDeep);
+        }
+        if !visited.insert(id) {
+            return Err(TraversalError::Revisited(id));
+        }
+        let Ok(node) = document.get_dictionary(id) else {
+            continue;
+        };
+        // Some writers leave out the type, so anything with kids is treated as an intermediate node.
+        match node.get(b"Kids").and_then(Object::as_array) {
+            Ok(kids) => {
+                // The kids are pushed in reverse so that they are visited in order.
+                for kid in kids.iter().rev() {
+                    if let Ok(kid) = kid.as_reference() {
+                        pending.push((kid, depth + 1));
+                    }
+                }
+            }
+            Err(_) => {
+                pages.insert(pages.len() as u32 + 1, id);
+            }
+        }
+    }
+    Ok(pages)
+}
+
+/// Finds a page and each of its ancestors in the page tree, starting with the page itself.
+pub fn page_ancestors(
+    document: &Document,
+    page_id: ObjectId,
+) -> Result<Vec<&Dictionary>, TraversalError> {
+    let mut ancestors = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut next = Some(page_id);
+    while let Some(id) = next {
+        if ancestors.len() > MAX_DEPTH {
+            return Err(TraversalError::TooDeep);
+        }
+        if !visited.insert(id) {
+            return Err(TraversalError::Revisited(id));
+        }
+        let Ok(node) = document.get_dictionary(id) else {
+            break;
+        };
+        ancestors.push(node);
+        next = node.get(b"Parent").and_then(Object::as_reference).ok();
+    }
+    Ok(ancestors)
+}
+
+/// Looks up an attribute a page can inherit from the page tree, such as `Rotate` or `MediaBox`, on the page itself or
+/// else on the nearest of its ancestors which has it.
+pub fn inherited_attribute<'a>(
+    document: &'a Document,
+    page_id: ObjectId,
+    key: &[u8],
+) -> Result<Option<&'a Object>, TraversalError> {
+    Ok(page_ancestors(document, page_id)?
+        .into_iter()
+        .find_map(|node| node.get(key).ok())
+        .and_then(|value| document.dereference(value).ok())
+        .map(|(_, value)| value))
+}
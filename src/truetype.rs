@@ -0,0 +1,268 @@
+//! Reads the `cmap` table of embedded TrueType fonts, to work out the text of fonts which have no `ToUnicode` map.
+//!
+//! Symbolic TrueType fonts, which most subsets made by word processors are, pick their glyphs with a `cmap` subtable
+//! for the symbol encoding or for Mac Roman rather than by any encoding in the document, so the codes in the content
+//! say nothing about the text by themselves. Many of these fonts have a Unicode subtable as well, though, which maps
+//! the same glyphs to their characters, and going from the code to the glyph and back from the glyph to the
+//! character recovers the text.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// The most characters read from a `cmap` subtable, which is every character there is, so that a hostile font can't
+/// make ranges of billions of them.
+const MAX_CHARACTERS: usize = 0x110000;
+
+/// The text of each single byte code of a simple font, from its font program, for the codes it can be worked out for.
+pub fn unicode_by_code(program: &[u8]) -> BTreeMap<u32, char> {
+    let mut result = BTreeMap::new();
+    let Some(cmap) = table(program, b"cmap") else {
+        return result;
+    };
+    let mut unicode_glyphs = Vec::new();
+    let mut symbol_glyphs = Vec::new();
+    let mut mac_glyphs = Vec::new();
+    let table_count = read_u16(cmap, 2).unwrap_or(0) as usize;
+    for index in 0..table_count {
+        let record = 4 + index * 8;
+        let (Some(platform), Some(encoding), Some(offset)) = (
+            read_u16(cmap, record),
+            read_u16(cmap, record + 2),
+            read_u32(cmap, record + 4),
+        ) else {
+            break;
+        };
+        let Some(subtable) = cmap.get(offset as usize..) else {
+            continue;
+        };
+        match (platform, encoding) {
+            (0, _) | (3, 1) | (3, 10) if unicode_glyphs.is_empty() => {
+                unicode_glyphs = read_subtable(subtable)
+            }
+            (3, 0) => symbol_glyphs = read_subtable(subtable),
+            (1, 0) => mac_glyphs = read_subtable(subtable),
+            _ => {}
+        }
+    }
+
+    let mut characters = HashMap::new();
+    for (character, glyph) in unicode_glyphs {
+        if let Some(character) = char::from_u32(character) {
+            characters.entry(glyph).or_insert(character);
+        }
+    }
+    let symbol_glyphs: HashMap<u32, u16> = symbol_glyphs.into_iter().collect();
+    let mac_glyphs: HashMap<u32, u16> = mac_glyphs.into_iter().collect();
+    for code in 0..=255 {
+        // Symbol subtables usually put the codes at 0xF000 onwards, so that they are in the private use area, but
+        // some have them as they are.
+        let glyph = symbol_glyphs
+            .get(&(0xF000 + code))
+            .or_else(|| symbol_glyphs.get(&code))
+            .or_else(|| mac_glyphs.get(&code));
+        if let Some(character) = glyph.and_then(|glyph| characters.get(glyph)) {
+            result.insert(code, *character);
+        }
+    }
+    result
+}
+
+/// Finds a table in the font's table directory.
+fn table<'a>(program: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let table_count = read_u16(program, 4)? as usize;
+    (0..table_count).find_map(|index| {
+        let record = 12 + index * 16;
+        if program.get(record..record + 4)? != tag {
+            return None;
+        }
+        let offset = read_u32(program, record + 8)? as usize;
+        let length = read_u32(program, record + 12)? as usize;
+        program.get(offset..offset.checked_add(length)?)
+    })
+}
+
+/// Reads the characters and glyphs of a `cmap` subtable, in any of the formats fonts embedded in documents use.
+fn read_subtable(subtable: &[u8]) -> Vec<(u32, u16)> {
+    let mut glyphs = Vec::new();
+    let mut remaining = MAX_CHARACTERS;
+    match read_u16(subtable, 0) {
+        // A byte for each of the 256 codes.
+        Some(0) => {
+            if let Some(glyph_ids) = subtable.get(6..6 + 256) {
+                glyphs.extend((0..).zip(glyph_ids.iter().map(|&glyph| glyph.into())));
+            }
+        }
+        // Segments of consecutive characters, whose glyphs are either offset from the characters or listed.
+        Some(4) => {
+            let segment_count = read_u16(subtable, 6).unwrap_or(0) as usize / 2;
+            let end_codes = 14;
+            let start_codes = end_codes + segment_count * 2 + 2;
+            let deltas = start_codes + segment_count * 2;
+            let range_offsets = deltas + segment_count * 2;
+            for segment in 0..segment_count {
+                let (Some(end), Some(start), Some(delta), Some(range_offset)) = (
+                    read_u16(subtable, end_codes + segment * 2),
+                    read_u16(subtable, start_codes + segment * 2),
+                    read_u16(subtable, deltas + segment * 2),
+                    read_u16(subtable, range_offsets + segment * 2),
+                ) else {
+                    break;
+                };
+                for character in start..=end {
+                    remaining = match remaining.checked_sub(1) {
+                        Some(remaining) => remaining,
+                        None => return glyphs,
+                    };
+                    let glyph = if range_offset == 0 {
+                        character.wrapping_add(delta)
+                    } else {
+                        let address = range_offsets
+                            + segment * 2
+                            + range_offset as usize
+                            + (character - start) as usize * 2;
+                        match read_u16(subtable, address) {
+                            Some(0) | None => continue,
+                            Some(glyph) => glyph.wrapping_add(delta),
+                        }
+                    };
+                    if glyph != 0 {
+                        glyphs.push((character.into(), glyph));
+                    }
+                }
+            }
+        }
+        // One run of consecutive characters.
+        Some(6) => {
+            let (Some(first), Some(count)) = (read_u16(subtable, 6), read_u16(subtable, 8)) else {
+                return glyphs;
+            };
+            for index in 0..count {
+                match read_u16(subtable, 10 + index as usize * 2) {
+                    Some(glyph) => glyphs.push((u32::from(first) + u32::from(index), glyph)),
+                    None => break,
+                }
+            }
+        }
+        // Groups of consecutive characters with consecutive glyphs, for fonts with characters beyond 0xFFFF.
+        Some(12) => {
+            let group_count = read_u32(subtable, 12).unwrap_or(0) as usize;
+            for group in 0..group_count {
+                let record = 16 + group * 12;
+                let (Some(start), Some(end), Some(first_glyph)) = (
+                    read_u32(subtable, record),
+                    read_u32(subtable, record + 4),
+                    read_u32(subtable, record + 8),
+                ) else {
+                    break;
+                };
+                if start > end {
+                    continue;
+                }
+                if remaining == 0 {
+                    return glyphs;
+                }
+                let end = end.min(start.saturating_add(remaining as u32 - 1));
+                for character in start..=end {
+                    glyphs.push((
+                        character,
+                        first_glyph.wrapping_add(character - start) as u16,
+                    ));
+                }
+                remaining -= (end - start) as usize + 1;
+            }
+        }
+        _ => {}
+    }
+    glyphs
+}
+
+/// Reads a big-endian number, as all the numbers in font programs are.
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().unwrap(),
+    ))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().unwrap(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A font program with only a `cmap` table, of the subtables given with their platform and encoding.
+    fn font_program(subtables: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut cmap = [0u16.to_be_bytes(), (subtables.len() as u16).to_be_bytes()].concat();
+        let mut offset = 4 + subtables.len() * 8;
+        for (platform, encoding, subtable) in subtables {
+            cmap.extend(platform.to_be_bytes());
+            cmap.extend(encoding.to_be_bytes());
+            cmap.extend((offset as u32).to_be_bytes());
+            offset += subtable.len();
+        }
+        for (_, _, subtable) in subtables {
+            cmap.extend(subtable);
+        }
+        let mut program = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+        program.extend(b"cmap");
+        program.extend([0; 4]);
+        program.extend(28u32.to_be_bytes());
+        program.extend((cmap.len() as u32).to_be_bytes());
+        program.extend(cmap);
+        program
+    }
+
+    /// A subtable in format 6, of consecutive characters from `first`.
+    fn trimmed_subtable(first: u16, glyphs: &[u16]) -> Vec<u8> {
+        let mut subtable = [
+            6,
+            10 + glyphs.len() as u16 * 2,
+            0,
+            first,
+            glyphs.len() as u16,
+        ]
+        .map(u16::to_be_bytes)
+        .concat();
+        subtable.extend(glyphs.iter().flat_map(|glyph| glyph.to_be_bytes()));
+        subtable
+    }
+
+    #[test]
+    fn maps_symbol_codes_through_unicode_subtable() {
+        let program = font_program(&[
+            (3, 0, trimmed_subtable(0xF041, &[7, 3])),
+            (3, 1, trimmed_subtable(0x61, &[3, 5, 7])),
+        ]);
+        assert_eq!(
+            unicode_by_code(&program),
+            BTreeMap::from([(0x41, 'c'), (0x42, 'a')])
+        );
+    }
+
+    #[test]
+    fn reads_no_more_than_is_there_from_truncated_programs() {
+        let program = font_program(&[
+            (1, 0, trimmed_subtable(0x41, &[3, 5])),
+            (3, 1, trimmed_subtable(0x61, &[3, 5])),
+        ]);
+        let whole = unicode_by_code(&program);
+        assert_eq!(whole.len(), 2);
+        for length in 0..program.len() {
+            let truncated = unicode_by_code(&program[..length]);
+            assert!(truncated
+                .iter()
+                .all(|(code, character)| whole.get(code) == Some(character)));
+        }
+        // The table directory says the `cmap` table goes on past the end, so none of it is read.
+        assert!(unicode_by_code(&program[..program.len() - 1]).is_empty());
+    }
+
+    #[test]
+    fn cuts_groups_short_at_every_character() {
+        let mut subtable = [12u16, 0].map(u16::to_be_bytes).concat();
+        subtable.extend([28, 0, 1, 0, 0xFFFF_FFFF, 1].map(u32::to_be_bytes).concat());
+        assert_eq!(read_subtable(&subtable).len(), MAX_CHARACTERS);
+    }
+}
@@ -0,0 +1,349 @@
+//! An interactive browser for the extracted text of a document in the terminal, for quickly checking how well a new
+//! kind of document comes out.
+//!
+//! The text is shown a page at a time, with the outline in a sidebar, and can be searched. Showing where each row is
+//! on the page makes it easy to see why rows were joined or split the way they were.
+
+use std::io;
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use regex::{Regex, RegexBuilder};
+
+use crate::{outline::Bookmark, output::OutputRenderer, text::Page};
+
+/// How wide the outline sidebar is, in columns.
+const OUTLINE_WIDTH: u16 = 32;
+
+const HELP: &str =
+    "←/→ pages  ↑/↓ scroll  g go to page  / search  n/N next/previous match  c coordinates  o outline  q quit";
+
+#[derive(PartialEq, Eq)]
+enum Focus {
+    Text,
+    Outline,
+}
+
+/// What is being typed into the status bar.
+enum Prompt {
+    GoTo(String),
+    Search(String),
+}
+
+/// Collects the pages of a document, and then lets the user browse them with [`Viewer::run`].
+pub struct Viewer {
+    pages: Vec<Page>,
+    outline: Vec<Bookmark>,
+    /// The index into `pages` of the page being shown.
+    current: usize,
+    scroll: u16,
+    show_coordinates: bool,
+    show_outline: bool,
+    focus: Focus,
+    outline_state: ListState,
+    prompt: Option<Prompt>,
+    search: Option<Regex>,
+    /// The page index and row of the match that was last jumped to.
+    current_match: Option<(usize, usize)>,
+    /// Shown in the status bar in place of the help until the next key press.
+    message: Option<String>,
+}
+
+impl Viewer {
+    pub fn new(outline: Vec<Bookmark>) -> Self {
+        Self {
+            pages: Vec::new(),
+            show_outline: !outline.is_empty(),
+            outline,
+            current: 0,
+            scroll: 0,
+            show_coordinates: false,
+            focus: Focus::Text,
+            outline_state: ListState::default().with_selected(Some(0)),
+            prompt: None,
+            search: None,
+            current_match: None,
+            message: None,
+        }
+    }
+
+    /// Takes over the terminal until the user quits.
+    pub fn run(&mut self) -> io::Result<()> {
+        if self.pages.is_empty() {
+            return Err(io::Error::other("the document has no pages to show"));
+        }
+        let mut terminal = ratatui::try_init()?;
+        let result = self.event_loop(&mut terminal);
+        ratatui::try_restore()?;
+        result
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [body, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let text_area = if self.show_outline {
+            let [sidebar, text_area] =
+                Layout::horizontal([Constraint::Length(OUTLINE_WIDTH), Constraint::Min(1)])
+                    .areas(body);
+            let items = self.outline.iter().map(|bookmark| {
+                format!(
+                    "{}{}",
+                    "  ".repeat(bookmark.level.saturating_sub(1)),
+                    bookmark.title
+                )
+            });
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title("Outline"))
+                .highlight_style(if self.focus == Focus::Outline {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().add_modifier(Modifier::BOLD)
+                });
+            frame.render_stateful_widget(list, sidebar, &mut self.outline_state);
+            text_area
+        } else {
+            body
+        };
+
+        let page = &self.pages[self.current];
+        let lines: Vec<Line> = page
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let mut spans = Vec::new();
+                if self.show_coordinates {
+                    spans.push(Span::styled(
+                        format!("({}, {}) ", row.x, row.y),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                let is_current_match = self.current_match == Some((self.current, index));
+                spans.extend(highlight(&row.text, self.search.as_ref(), is_current_match));
+                Line::from(spans)
+            })
+            .collect();
+        let title = format!("Page {} of {}", page.number, self.pages.len());
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .scroll((self.scroll, 0)),
+            text_area,
+        );
+
+        let status_text = match &self.prompt {
+            Some(Prompt::GoTo(number)) => format!("Go to page: {}", number),
+            Some(Prompt::Search(pattern)) => format!("Search: {}", pattern),
+            None => self.message.clone().unwrap_or_else(|| HELP.to_owned()),
+        };
+        frame.render_widget(
+            Paragraph::new(status_text).style(Style::default().add_modifier(Modifier::REVERSED)),
+            status,
+        );
+    }
+
+    /// Acts on a key press, returning whether to keep going.
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        self.message = None;
+        if let Some(prompt) = &mut self.prompt {
+            let input = match prompt {
+                Prompt::GoTo(input) | Prompt::Search(input) => input,
+            };
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Enter => match self.prompt.take() {
+                    Some(Prompt::GoTo(number)) => self.go_to_page(&number),
+                    Some(Prompt::Search(pattern)) => self.start_search(&pattern),
+                    None => {}
+                },
+                _ => {}
+            }
+            return true;
+        }
+        if self.focus == Focus::Outline {
+            match code {
+                KeyCode::Up | KeyCode::Char('k') => self.outline_state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.outline_state.select_next(),
+                KeyCode::Enter => {
+                    let page = self
+                        .outline_state
+                        .selected()
+                        .and_then(|selected| self.outline.get(selected))
+                        .and_then(|bookmark| bookmark.page);
+                    match page {
+                        Some(page) => self.go_to_page(&page.to_string()),
+                        None => {
+                            self.message = Some("That bookmark doesn't point to a page".to_owned())
+                        }
+                    }
+                }
+                KeyCode::Tab | KeyCode::Esc => self.focus = Focus::Text,
+                KeyCode::Char('q') => return false,
+                _ => {}
+            }
+            return true;
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Right | KeyCode::PageDown | KeyCode::Char(' ') => {
+                self.show(self.current + 1);
+            }
+            KeyCode::Left | KeyCode::PageUp => self.show(self.current.saturating_sub(1)),
+            KeyCode::Home => self.show(0),
+            KeyCode::End => self.show(self.pages.len() - 1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Char('g') => self.prompt = Some(Prompt::GoTo(String::new())),
+            KeyCode::Char('/') => self.prompt = Some(Prompt::Search(String::new())),
+            KeyCode::Char('n') => self.find(true),
+            KeyCode::Char('N') => self.find(false),
+            KeyCode::Char('c') => self.show_coordinates = !self.show_coordinates,
+            KeyCode::Char('o') => {
+                self.show_outline = !self.show_outline;
+                if self.outline.is_empty() {
+                    self.message = Some("The document has no outline".to_owned());
+                }
+            }
+            KeyCode::Tab if self.show_outline && !self.outline.is_empty() => {
+                self.focus = Focus::Outline;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn show(&mut self, index: usize) {
+        if index < self.pages.len() {
+            self.current = index;
+            self.scroll = 0;
+        }
+    }
+
+    fn go_to_page(&mut self, number: &str) {
+        let index = number
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .and_then(|number| self.pages.iter().position(|page| page.number == number));
+        match index {
+            Some(index) => self.show(index),
+            None => self.message = Some(format!("There is no page {}", number.trim())),
+        }
+    }
+
+    fn start_search(&mut self, pattern: &str) {
+        if pattern.is_empty() {
+            self.search = None;
+            return;
+        }
+        // The pattern is looked for literally, whatever its case.
+        self.search = RegexBuilder::new(&regex::escape(pattern))
+            .case_insensitive(true)
+            .build()
+            .ok();
+        // Searching starts from the top of the page being shown.
+        self.current_match = self
+            .current
+            .checked_sub(1)
+            .map(|previous| (previous, usize::MAX));
+        self.find(true);
+    }
+
+    /// Jumps to the next or previous row with a match in it, going round to the other end of the document if need be.
+    fn find(&mut self, forwards: bool) {
+        let Some(search) = &self.search else {
+            self.message = Some("Press / to search".to_owned());
+            return;
+        };
+        let matches: Vec<(usize, usize)> = self
+            .pages
+            .iter()
+            .enumerate()
+            .flat_map(|(page_index, page)| {
+                page.rows
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, row)| search.is_match(&row.text))
+                    .map(move |(row_index, _)| (page_index, row_index))
+            })
+            .collect();
+        let found = if forwards {
+            matches
+                .iter()
+                .find(|&&position| Some(position) > self.current_match)
+                .or(matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|&&position| self.current_match.is_none_or(|current| position < current))
+                .or(matches.last())
+        };
+        match found.copied() {
+            Some((page_index, row_index)) => {
+                self.current_match = Some((page_index, row_index));
+                self.current = page_index;
+                self.scroll = row_index.saturating_sub(2).try_into().unwrap_or(u16::MAX);
+            }
+            None => {
+                self.current_match = None;
+                self.message = Some("No matches".to_owned());
+            }
+        }
+    }
+}
+
+impl OutputRenderer for Viewer {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        self.pages.push(Page {
+            number: page.number,
+            label: page.label.clone(),
+            rows: page.rows.clone(),
+            tables: page.tables.clone(),
+            bounds: page.bounds,
+        });
+        Ok(())
+    }
+}
+
+/// Splits a row into spans with the matches of the search picked out.
+fn highlight<'a>(text: &'a str, search: Option<&Regex>, is_current_match: bool) -> Vec<Span<'a>> {
+    let Some(search) = search else {
+        return vec![Span::raw(text)];
+    };
+    let style = if is_current_match {
+        Style::default().fg(Color::Black).bg(Color::Yellow)
+    } else {
+        Style::default().add_modifier(Modifier::REVERSED)
+    };
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for found in search.find_iter(text) {
+        spans.push(Span::raw(&text[end..found.start()]));
+        spans.push(Span::styled(found.as_str(), style));
+        end = found.end();
+    }
+    spans.push(Span::raw(&text[end..]));
+    spans
+}
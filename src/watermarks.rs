@@ -0,0 +1,110 @@
+//! Leaves out watermarks, such as a big "DRAFT" or "CONFIDENTIAL" drawn diagonally across every page.
+//!
+//! A watermark is text drawn at an angle rather than straight across the page, and bigger than the rest of the page's
+//! text, which is on at least half of the pages with the same words. Text at an angle on only a few pages, such as
+//! the labels of a chart's axes, is kept, and so are notes turned to run up the margin in the same size as the rest
+//! of the text, and everything in documents with only one page, where there is nothing to compare it with.
+//!
+//! Tagged documents usually mark their watermarks as artifacts, which are left out before this.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+use crate::{
+    output::{OutputRenderer, PageWindow},
+    text::{self, Page, TextChunk},
+};
+
+/// Text turned more than this many degrees from running straight across the page is at an angle.
+const MIN_ANGLE: f32 = 10.0;
+
+/// Watermarks are at least this many times taller than most of the rows on their page.
+const MIN_SIZE: f32 = 1.5;
+
+/// Removes the watermarks from the pages before passing them on to another renderer.
+pub struct WatermarkRemover {
+    inner: Box<dyn OutputRenderer>,
+    window: PageWindow,
+}
+
+impl WatermarkRemover {
+    /// Passes each page on once `lookahead` pages after it have been extracted to compare it with.
+    pub fn new(inner: Box<dyn OutputRenderer>, lookahead: usize) -> Self {
+        Self {
+            inner,
+            window: PageWindow::new(lookahead),
+        }
+    }
+}
+
+impl OutputRenderer for WatermarkRemover {
+    fn render_page(&mut self, page: &Page) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.window.push(page, |pages, index| {
+            inner.render_page(&without_watermarks(pages, index))
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.window
+            .finish(|pages, index| inner.render_page(&without_watermarks(pages, index)))?;
+        self.inner.finish()
+    }
+}
+
+/// The page at `index` without the rows drawn at an angle whose text is on at least half of the pages.
+pub fn without_watermarks(pages: &[Page], index: usize) -> Page {
+    let mut page = pages[index].clone();
+    if pages.len() < 2 {
+        return page;
+    }
+    // Which pages each piece of text that could be a watermark is on.
+    let mut pages_with: HashMap<String, HashSet<usize>> = HashMap::new();
+    for (page_index, other_page) in pages.iter().enumerate() {
+        let usual_height = usual_height(other_page);
+        for row in other_page
+            .rows
+            .iter()
+            .filter(|row| could_be_watermark(row, usual_height))
+        {
+            pages_with
+                .entry(watermark_key(&row.text))
+                .or_default()
+                .insert(page_index);
+        }
+    }
+    let needed = pages.len().div_ceil(2).max(2);
+    let usual_height = usual_height(&page);
+    page.rows.retain(|row| {
+        !could_be_watermark(row, usual_height)
+            || pages_with
+                .get(&watermark_key(&row.text))
+                .is_none_or(|pages| pages.len() < needed)
+    });
+    page
+}
+
+/// Whether a row is drawn at an angle, and big enough for its page to be a watermark.
+fn could_be_watermark(row: &TextChunk, usual_height: f32) -> bool {
+    // The angle is anywhere from -180 to 180 degrees, and text running right to left upside down is at 180.
+    let is_at_an_angle = row.angle.abs().min(180.0 - row.angle.abs()) > MIN_ANGLE;
+    is_at_an_angle && row.height >= usual_height * MIN_SIZE
+}
+
+/// How tall most of the rows of a page are, going by the middle of their heights.
+fn usual_height(page: &Page) -> f32 {
+    let mut heights: Vec<f32> = page.rows.iter().map(|row| row.height).collect();
+    heights.sort_by(f32::total_cmp);
+    heights.get(heights.len() / 2).copied().unwrap_or(0.0)
+}
+
+/// The text that has to be the same for rows to be the same watermark, whatever spaces it was drawn with.
+fn watermark_key(text: &str) -> String {
+    text::plain_text(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,97 @@
+//! Splits the rows of a page into words, with the box around each one, for the formats which say where every word is.
+//!
+//! PDFs say where each piece of text starts and how far it goes, but not where the words in it are, so every character
+//! of a piece is taken to be as wide as the others. A word carries on from one of the chunks a row was joined from
+//! into the next unless there is a space between them, or a gap wide enough for a space to have gone between them,
+//! so that words drawn in more than one font or in pieces stay whole. The text of columns of vertical text isn't split
+//! up, since each character of it is below the last rather than beside it.
+
+use crate::{
+    json::TextBlock,
+    text::{self, TextChunk},
+};
+
+/// A word of a row, without any markup, and the box around it, going up from the baseline of its text.
+pub struct Word {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// The lines of a block which have any words in them, each with its words, split as `split` splits them.
+pub fn lines(block: &TextBlock, word_gap: f32) -> Vec<(&TextChunk, Vec<Word>)> {
+    block
+        .lines
+        .iter()
+        .map(|line| (line, split(line, word_gap)))
+        .filter(|(_, words)| !words.is_empty())
+        .collect()
+}
+
+/// Splits a row into its words, from the chunks it was joined from if they were kept, with a gap of `word_gap` times
+/// the width of a space between two chunks separating the words they are in.
+pub fn split(row: &TextChunk, word_gap: f32) -> Vec<Word> {
+    let chunks = if row.chunks.is_empty() {
+        std::slice::from_ref(row)
+    } else {
+        &row.chunks
+    };
+    let mut words: Vec<Word> = Vec::new();
+    // Whether the next character starts a new word.
+    let mut separate = true;
+    let mut last_end = f32::NEG_INFINITY;
+    for chunk in chunks {
+        let text = text::plain_text(&chunk.text);
+        if chunk.vertical {
+            // A column goes down from where it starts, and is as wide as its text is tall.
+            let text = text.trim();
+            if !text.is_empty() {
+                words.push(Word {
+                    text: text.to_owned(),
+                    x: chunk.x,
+                    y: chunk.y - chunk.width,
+                    width: chunk.height,
+                    height: chunk.width,
+                });
+            }
+            separate = true;
+            continue;
+        }
+        let count = text.chars().count();
+        if count == 0 {
+            continue;
+        }
+        if chunk.x - last_end > chunk.space_width * word_gap {
+            separate = true;
+        }
+        let advance = chunk.width / count as f32;
+        for (index, character) in text.chars().enumerate() {
+            if character.is_whitespace() {
+                separate = true;
+                continue;
+            }
+            let left = chunk.x + advance * index as f32;
+            match words.last_mut() {
+                Some(word) if !separate => {
+                    word.text.push(character);
+                    word.width = left + advance - word.x;
+                    let top = (word.y + word.height).max(chunk.y + chunk.height);
+                    word.y = word.y.min(chunk.y);
+                    word.height = top - word.y;
+                }
+                _ => words.push(Word {
+                    text: character.to_string(),
+                    x: left,
+                    y: chunk.y,
+                    width: advance,
+                    height: chunk.height,
+                }),
+            }
+            separate = false;
+        }
+        last_end = chunk.x + chunk.width;
+    }
+    words
+}